@@ -24,6 +24,32 @@ enum Commands {
         /// Path to the disk image
         #[arg(long)]
         disk: Option<PathBuf>,
+
+        /// Path to an initrd image (produced by ferrous-mkfs) to mount as
+        /// the root filesystem instead of `--disk`
+        #[arg(long)]
+        initrd: Option<PathBuf>,
+
+        /// Host directory to make available under /host via the 9P
+        /// passthrough transport
+        #[arg(long)]
+        share: Option<PathBuf>,
+
+        /// Kernel command line string, surfaced to the guest through the
+        /// boot-info device instead of being baked into the ELF
+        #[arg(long, default_value = "")]
+        append: String,
+
+        /// Serve a GDB Remote Serial Protocol session on this TCP port
+        /// instead of running freestanding -- connect with
+        /// `riscv32-elf-gdb` and `target remote 127.0.0.1:<port>`
+        #[arg(long)]
+        gdb: Option<u16>,
+
+        /// Capture every frame the NIC sends/receives to this path as a
+        /// standard pcap file, openable in Wireshark
+        #[arg(long)]
+        pcap: Option<PathBuf>,
     },
 }
 
@@ -33,16 +59,53 @@ fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run { file, memory, disk } => {
+        Commands::Run {
+            file,
+            memory,
+            disk,
+            initrd,
+            share,
+            append,
+            gdb,
+            pcap,
+        } => {
             println!("Starting Ferrous VM with {} bytes memory...", memory);
             println!("Loading binary: {:?}", file);
             if let Some(d) = &disk {
                 println!("Mounting disk image: {:?}", d);
             }
+            if let Some(dir) = &share {
+                println!("Sharing host directory under /host: {:?}", dir);
+            }
+            if !append.is_empty() {
+                println!("Kernel command line: {:?}", append);
+            }
+            let initrd_data = match &initrd {
+                Some(path) => {
+                    println!("Loading initrd: {:?}", path);
+                    Some(std::fs::read(path)?)
+                }
+                None => None,
+            };
 
-            let mut runtime = Runtime::new(memory, disk.as_deref())?;
+            if let Some(p) = &pcap {
+                println!("Capturing network traffic to: {:?}", p);
+            }
+
+            let mut runtime = Runtime::new(
+                memory,
+                disk.as_deref(),
+                initrd_data,
+                share,
+                append,
+                pcap.as_deref(),
+            )?;
             runtime.load_program(&file)?;
-            runtime.run()?;
+
+            match gdb {
+                Some(port) => runtime.run_with_gdbstub(port)?,
+                None => runtime.run()?,
+            }
 
             println!("Execution completed.");
         }
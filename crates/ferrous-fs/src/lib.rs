@@ -34,7 +34,9 @@ pub struct Inode {
     pub file_type: FileType,
     pub size: u32,
     pub direct_ptrs: [u32; INODE_DIRECT_POINTERS],
-    pub indirect_ptr: u32, // Points to a block containing more pointers
+    pub indirect_ptr: u32,        // Points to a block containing more pointers
+    pub double_indirect_ptr: u32, // Points to a block of single-indirect pointers
+    pub triple_indirect_ptr: u32, // Points to a block of double-indirect pointers
 }
 
 impl Inode {
@@ -45,6 +47,8 @@ impl Inode {
             size: 0,
             direct_ptrs: [0; INODE_DIRECT_POINTERS],
             indirect_ptr: 0,
+            double_indirect_ptr: 0,
+            triple_indirect_ptr: 0,
         }
     }
 }
@@ -73,3 +77,29 @@ impl DirEntry {
         core::str::from_utf8(&self.name[0..end]).unwrap_or("<invalid>")
     }
 }
+
+/// `Stat::mode`'s type bits, POSIX's `S_IFREG`/`S_IFIFO`/`S_IFCHR` values so
+/// a userspace `fstat` wrapper can tell a disk/9P file apart from a pipe or
+/// a scheme like `console:`/`null:`/`rand:` the same way a real libc would.
+pub const S_IFREG: u32 = 0o100000;
+pub const S_IFIFO: u32 = 0o010000;
+pub const S_IFCHR: u32 = 0o020000;
+
+/// What `Syscall::Fstat` copies back to userspace. Not every field means
+/// something for every descriptor kind -- `block_size`/`links` are disk-file
+/// concepts that a pipe or a scheme descriptor reports as `0`/`1`
+/// respectively -- but giving every kind the same shape lets one `fstat`
+/// syscall cover all of them instead of one per `FileDescriptor` variant.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Stat {
+    /// Inode number for a disk file, the 9P fid for a host file, or `0` for
+    /// a descriptor with no backing inode (console/null/rand/pipe).
+    pub inode: u32,
+    /// `S_IFREG`/`S_IFIFO`/`S_IFCHR`, telling userspace what kind of
+    /// descriptor this is.
+    pub mode: u32,
+    pub size: u32,
+    pub block_size: u32,
+    pub links: u32,
+}
@@ -0,0 +1,314 @@
+//! Mounts a `ferrous_fs` disk image as a read-only FUSE filesystem, so a
+//! student can `ls`/`cat`/copy files out of an image `ferrous-mkfs` just
+//! produced without booting the VM. Reads the image the same way
+//! `ferrous_kernel::fs::FileSystem` does -- `SuperBlock` and `Inode` are
+//! reinterpreted straight out of their block's bytes, `DirEntry`s are a
+//! flat array of the same raw layout, and file contents are resolved by
+//! walking `direct_ptrs` through the indirect/double-indirect/
+//! triple-indirect tree -- just against a host `File` via `seek`/
+//! `read_exact` instead of the kernel's `Memory` trait.
+
+use clap::Parser;
+use ferrous_fs::{DirEntry, FileType as FsFileType, Inode, SuperBlock, BLOCK_SIZE, INODE_DIRECT_POINTERS, MAGIC};
+use fuser::{
+    FileAttr, FileType as FuseFileType, Filesystem, MountOption, ReplyAttr, ReplyData,
+    ReplyDirectory, ReplyEntry, Request,
+};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    /// Path to the ferrous_fs disk image to mount
+    image: PathBuf,
+
+    /// Where to mount it
+    mountpoint: PathBuf,
+}
+
+/// How long the kernel's FUSE client may cache an answer before asking
+/// again -- the image is opened read-only and never changes underneath
+/// us, so there's no reason to keep this short.
+const TTL: Duration = Duration::from_secs(60);
+
+/// Ferrous inode `0` is the root directory, same as every other inode id;
+/// FUSE reserves inode `1` for the mount's root, so every ferrous inode id
+/// is shifted up by one to become a FUSE inode number.
+fn fuse_ino(ferrous_id: u32) -> u64 {
+    ferrous_id as u64 + 1
+}
+
+fn ferrous_id(fuse_ino: u64) -> u32 {
+    (fuse_ino - 1) as u32
+}
+
+struct FerrousImage {
+    file: File,
+    superblock: SuperBlock,
+}
+
+impl FerrousImage {
+    fn open(path: &std::path::Path) -> std::io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut buffer = [0u8; BLOCK_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut buffer)?;
+
+        let superblock = unsafe { (buffer.as_ptr() as *const SuperBlock).read_unaligned() };
+        if superblock.magic != MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("bad ferrous_fs magic: {:#x} != {:#x}", superblock.magic, MAGIC),
+            ));
+        }
+
+        Ok(Self { file, superblock })
+    }
+
+    fn read_block(&mut self, block_id: u32) -> std::io::Result<[u8; BLOCK_SIZE]> {
+        let mut buf = [0u8; BLOCK_SIZE];
+        self.file
+            .seek(SeekFrom::Start(block_id as u64 * BLOCK_SIZE as u64))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_inode(&mut self, inode_id: u32) -> std::io::Result<Inode> {
+        let inode_size = std::mem::size_of::<Inode>() as u32;
+        let inodes_per_block = BLOCK_SIZE as u32 / inode_size;
+        let block_id = self.superblock.inode_table_block + inode_id / inodes_per_block;
+        let index_in_block = inode_id % inodes_per_block;
+
+        let buf = self.read_block(block_id)?;
+        let inode = unsafe {
+            (buf.as_ptr().add((index_in_block * inode_size) as usize) as *const Inode)
+                .read_unaligned()
+        };
+        Ok(inode)
+    }
+
+    /// Return the `index`th little-endian pointer stored in indirect block
+    /// `block_id`, or `0` (a hole) if `block_id` itself is `0`.
+    fn read_pointer(&mut self, block_id: u32, index: u32) -> std::io::Result<u32> {
+        if block_id == 0 {
+            return Ok(0);
+        }
+        let buf = self.read_block(block_id)?;
+        let offset = (index * 4) as usize;
+        Ok(u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()))
+    }
+
+    /// Resolve the data block backing `inode`'s `block_index`'th block,
+    /// walking direct pointers, then single-, double-, and
+    /// triple-indirect trees in turn. `0` means a sparse hole.
+    fn resolve_block(&mut self, inode: &Inode, block_index: u32) -> std::io::Result<u32> {
+        let pointers_per_block = (BLOCK_SIZE / 4) as u32;
+        let single_indirect_cap = INODE_DIRECT_POINTERS as u32 + pointers_per_block;
+        let double_indirect_cap = single_indirect_cap + pointers_per_block * pointers_per_block;
+
+        if (block_index as usize) < INODE_DIRECT_POINTERS {
+            Ok(inode.direct_ptrs[block_index as usize])
+        } else if block_index < single_indirect_cap {
+            let indirect_index = block_index - INODE_DIRECT_POINTERS as u32;
+            self.read_pointer(inode.indirect_ptr, indirect_index)
+        } else if block_index < double_indirect_cap {
+            let indirect_index = block_index - single_indirect_cap;
+            let outer_index = indirect_index / pointers_per_block;
+            let inner_index = indirect_index % pointers_per_block;
+
+            let single_indirect_block = self.read_pointer(inode.double_indirect_ptr, outer_index)?;
+            self.read_pointer(single_indirect_block, inner_index)
+        } else {
+            let indirect_index = block_index - double_indirect_cap;
+            let outer_index = indirect_index / (pointers_per_block * pointers_per_block);
+            let middle_index = (indirect_index / pointers_per_block) % pointers_per_block;
+            let inner_index = indirect_index % pointers_per_block;
+
+            let double_indirect_block = self.read_pointer(inode.triple_indirect_ptr, outer_index)?;
+            let single_indirect_block = self.read_pointer(double_indirect_block, middle_index)?;
+            self.read_pointer(single_indirect_block, inner_index)
+        }
+    }
+
+    /// Read all of `inode`'s data, up to its recorded `size`.
+    fn read_file(&mut self, inode: &Inode) -> std::io::Result<Vec<u8>> {
+        let mut out = vec![0u8; inode.size as usize];
+        let total_blocks = (inode.size as usize).div_ceil(BLOCK_SIZE);
+        for block_index in 0..total_blocks as u32 {
+            let block_id = self.resolve_block(inode, block_index)?;
+            let start = block_index as usize * BLOCK_SIZE;
+            let end = (start + BLOCK_SIZE).min(out.len());
+            if block_id == 0 {
+                continue; // sparse hole, `out` is already zeroed
+            }
+            let block = self.read_block(block_id)?;
+            out[start..end].copy_from_slice(&block[..end - start]);
+        }
+        Ok(out)
+    }
+
+    /// Parse `inode`'s data as a flat array of `DirEntry`, skipping unused
+    /// (zero-named) slots -- the same layout `FileSystem::find_entry`
+    /// expects in the kernel.
+    fn read_dir(&mut self, inode: &Inode) -> std::io::Result<Vec<DirEntry>> {
+        let data = self.read_file(inode)?;
+        let entry_size = std::mem::size_of::<DirEntry>();
+        Ok(data
+            .chunks_exact(entry_size)
+            .map(|chunk| unsafe { (chunk.as_ptr() as *const DirEntry).read_unaligned() })
+            .filter(|entry| entry.name[0] != 0)
+            .collect())
+    }
+
+    fn attr(&self, ferrous_id: u32, inode: &Inode) -> FileAttr {
+        let kind = match inode.file_type {
+            FsFileType::Directory => FuseFileType::Directory,
+            FsFileType::File => FuseFileType::RegularFile,
+        };
+        FileAttr {
+            ino: fuse_ino(ferrous_id),
+            size: inode.size as u64,
+            blocks: (inode.size as u64).div_ceil(BLOCK_SIZE as u64),
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm: if kind == FuseFileType::Directory { 0o555 } else { 0o444 },
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            blksize: BLOCK_SIZE as u32,
+            flags: 0,
+        }
+    }
+}
+
+impl Filesystem for FerrousImage {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let Ok(parent_inode) = self.read_inode(ferrous_id(parent)) else {
+            reply.error(libc::EIO);
+            return;
+        };
+        let Some(name) = name.to_str() else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let Ok(entries) = self.read_dir(&parent_inode) else {
+            reply.error(libc::EIO);
+            return;
+        };
+        match entries.iter().find(|e| e.name_as_str() == name) {
+            Some(entry) => match self.read_inode(entry.inode_id) {
+                Ok(inode) => reply.entry(&TTL, &self.attr(entry.inode_id, &inode), 0),
+                Err(_) => reply.error(libc::EIO),
+            },
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.read_inode(ferrous_id(ino)) {
+            Ok(inode) => reply.attr(&TTL, &self.attr(ferrous_id(ino), &inode)),
+            Err(_) => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Ok(inode) = self.read_inode(ferrous_id(ino)) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        if inode.file_type != FsFileType::Directory {
+            reply.error(libc::ENOTDIR);
+            return;
+        }
+
+        let mut entries = vec![
+            (ino, FuseFileType::Directory, ".".to_string()),
+            (ino, FuseFileType::Directory, "..".to_string()),
+        ];
+        match self.read_dir(&inode) {
+            Ok(dir_entries) => {
+                for entry in dir_entries {
+                    let Ok(child) = self.read_inode(entry.inode_id) else {
+                        continue;
+                    };
+                    let kind = match child.file_type {
+                        FsFileType::Directory => FuseFileType::Directory,
+                        FsFileType::File => FuseFileType::RegularFile,
+                    };
+                    entries.push((fuse_ino(entry.inode_id), kind, entry.name_as_str().to_string()));
+                }
+            }
+            Err(_) => {
+                reply.error(libc::EIO);
+                return;
+            }
+        }
+
+        for (i, (entry_ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(entry_ino, (i + 1) as i64, kind, name) {
+                break; // kernel's reply buffer is full; it'll ask again with a later offset
+            }
+        }
+        reply.ok();
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Ok(inode) = self.read_inode(ferrous_id(ino)) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        match self.read_file(&inode) {
+            Ok(data) => {
+                let start = (offset as usize).min(data.len());
+                let end = (start + size as usize).min(data.len());
+                reply.data(&data[start..end]);
+            }
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let image = FerrousImage::open(&cli.image).unwrap_or_else(|e| {
+        eprintln!("Failed to open {}: {}", cli.image.display(), e);
+        std::process::exit(1);
+    });
+
+    let options = vec![MountOption::RO, MountOption::FSName("ferrous_fs".to_string())];
+    println!(
+        "Mounting {} at {} (read-only, Ctrl-C to unmount)",
+        cli.image.display(),
+        cli.mountpoint.display()
+    );
+    if let Err(e) = fuser::mount2(image, &cli.mountpoint, &options) {
+        eprintln!("Mount failed: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,175 @@
+//! A small seL4-style capability model: physical frames are handed out as
+//! `Untyped` regions, `retype` carves typed objects out of an `Untyped`,
+//! and the resulting authority is held in a per-thread `CapabilityTable`
+//! rather than as a bare integer. Syscalls that act on a kernel object
+//! (a `Mutex`, eventually a `FileDescriptor`) take a `CapSlot` and must
+//! have it checked against the rights they need before acting.
+//!
+//! `ThreadControlBlock`/`Mutex` are ordinary host-side Rust objects in this
+//! kernel rather than guest-resident structures, so retyping one doesn't
+//! hand back raw bytes the way retyping a `Frame` does — it still debits
+//! the `Untyped`'s frame budget (mirroring seL4's accounting, where every
+//! kernel object costs some backing memory) but the actual
+//! `ThreadControlBlock`/`Mutex` continues to live in `ThreadManager`'s /
+//! the kernel's own tables, with the capability just naming it.
+
+use crate::types::ThreadHandle;
+
+pub const PAGE_SIZE: u32 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+    Frame,
+    Thread,
+    Mutex,
+    Condvar,
+    Endpoint,
+    Server,
+    Semaphore,
+}
+
+/// What a capability refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapObject {
+    Frame(u32),
+    Thread(ThreadHandle),
+    Mutex(u32),
+    Condvar(u32),
+    Endpoint(u32),
+    /// A Xous-style `Server`/`Connection` IPC endpoint. `CreateServer` grants
+    /// `READ | WRITE` over the id it creates; `Connect` grants a second,
+    /// distinct capability over the same id with only `WRITE`, so a client
+    /// can `SendMessage` but not `ReceiveMessage`/`ReturnMemory` on a server
+    /// it didn't create.
+    Server(u32),
+    Semaphore(u32),
+}
+
+/// The set of operations a capability authorizes on its object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapRights(u8);
+
+impl CapRights {
+    pub const READ: CapRights = CapRights(1 << 0);
+    pub const WRITE: CapRights = CapRights(1 << 1);
+    pub const GRANT: CapRights = CapRights(1 << 2);
+
+    pub const fn contains(self, required: CapRights) -> bool {
+        self.0 & required.0 == required.0
+    }
+}
+
+impl core::ops::BitOr for CapRights {
+    type Output = CapRights;
+    fn bitor(self, rhs: CapRights) -> CapRights {
+        CapRights(self.0 | rhs.0)
+    }
+}
+
+/// A size-aligned region of physical frames that hasn't been turned into
+/// anything yet. `retype_frames` carves raw frames out of it directly;
+/// higher-level callers that want a `Thread` or `Mutex` object create the
+/// real thing through the normal kernel tables and call `debit` to charge
+/// the untyped for the frame(s) it conceptually cost.
+pub struct Untyped {
+    base: u32,
+    frame_count: u32,
+    allocated: u32,
+}
+
+impl Untyped {
+    pub fn new(base: u32, frame_count: u32) -> Self {
+        Self {
+            base,
+            frame_count,
+            allocated: 0,
+        }
+    }
+
+    pub fn frames_remaining(&self) -> u32 {
+        self.frame_count - self.allocated
+    }
+
+    fn take(&mut self, count: u32) -> Result<u32, &'static str> {
+        if count == 0 || self.allocated + count > self.frame_count {
+            return Err("Untyped: not enough frames to retype");
+        }
+        let base = self.base + self.allocated * PAGE_SIZE;
+        self.allocated += count;
+        Ok(base)
+    }
+
+    /// Carve `count` contiguous raw physical frames out of this untyped.
+    pub fn retype_frames(&mut self, count: u32) -> Result<alloc::vec::Vec<u32>, &'static str> {
+        let base = self.take(count)?;
+        Ok((0..count).map(|i| base + i * PAGE_SIZE).collect())
+    }
+
+    /// Charge this untyped for one frame's worth of budget without handing
+    /// any bytes back, used when retyping a `Thread` or `Mutex` whose real
+    /// storage lives in the kernel's own tables rather than guest memory.
+    pub fn debit(&mut self, obj_type: ObjectType) -> Result<(), &'static str> {
+        match obj_type {
+            ObjectType::Frame => Err("Untyped: use retype_frames for Frame objects"),
+            ObjectType::Thread
+            | ObjectType::Mutex
+            | ObjectType::Condvar
+            | ObjectType::Endpoint
+            | ObjectType::Server
+            | ObjectType::Semaphore => self.take(1).map(|_| ()),
+        }
+    }
+}
+
+/// A single entry in a thread's capability table: an object plus the
+/// rights this particular capability grants over it.
+#[derive(Debug, Clone, Copy)]
+pub struct Capability {
+    pub object: CapObject,
+    pub rights: CapRights,
+}
+
+/// A handle into a thread's own `CapabilityTable`. Capability tables are
+/// per-thread, so a `CapSlot` is only meaningful together with the thread
+/// that holds it — unlike a `ThreadHandle`, it carries no global identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapSlot(u32);
+
+impl CapSlot {
+    /// Wrap a raw slot index as decoded from a syscall argument register.
+    pub fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    pub fn val(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A thread's private table of capability slots.
+#[derive(Debug, Default)]
+pub struct CapabilityTable {
+    slots: alloc::vec::Vec<Capability>,
+}
+
+impl CapabilityTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn grant(&mut self, object: CapObject, rights: CapRights) -> CapSlot {
+        self.slots.push(Capability { object, rights });
+        CapSlot((self.slots.len() - 1) as u32)
+    }
+
+    pub fn lookup(&self, slot: CapSlot) -> Option<&Capability> {
+        self.slots.get(slot.0 as usize)
+    }
+
+    /// Look up `slot` and confirm it grants every bit set in `required`,
+    /// returning `None` if the slot is empty or lacks the rights.
+    pub fn check(&self, slot: CapSlot, required: CapRights) -> Option<&Capability> {
+        self.lookup(slot)
+            .filter(|cap| cap.rights.contains(required))
+    }
+}
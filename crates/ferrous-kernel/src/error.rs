@@ -16,4 +16,13 @@ pub enum SyscallError {
 
     #[error("invalid argument")]
     InvalidArgument,
+
+    #[error("9p error: {0}")]
+    NineP(String),
+}
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("truncated device state blob: expected at least {expected} bytes, got {actual}")]
+    Truncated { expected: usize, actual: usize },
 }
@@ -2,11 +2,49 @@ use ferrous_vm::{Memory, PhysAddr};
 
 pub const BLOCK_DEVICE_BASE: u32 = 0x2000_0000;
 // Register Offsets
-const _REG_STATUS: u32 = 0x00;
+const REG_STATUS: u32 = 0x00;
 const REG_COMMAND: u32 = 0x04;
 const REG_SECTOR: u32 = 0x08;
 const REG_BUFFER_START: u32 = 0x100;
 
+// The DMA engine's own MMIO window, mirroring `devices::dma`'s private
+// register offsets the same way the constants above mirror
+// `devices::block`'s -- this module only ever pokes the protocol through
+// `Memory`, never the device structs themselves.
+pub const DMA_DEVICE_BASE: u32 = 0x0C00_1000;
+const DMA_REG_HEAD: u32 = 0x04;
+const DMA_REG_COMMAND: u32 = 0x08;
+
+/// Program the DMA engine with the descriptor chain rooted at `desc_ptr`
+/// and kick it off. The engine runs the whole chain before this returns
+/// (see `SystemBus::run_dma_chain`), copying each descriptor's sector
+/// straight into its own `dest_addr` rather than through a buffer this
+/// syscall would otherwise have to relay -- so unlike `read_sector`, there's
+/// nothing to hand the caller back beyond whether programming the engine
+/// itself succeeded.
+pub fn start_dma(memory: &mut dyn Memory, desc_ptr: u32) -> Result<(), String> {
+    memory
+        .write_word(PhysAddr::new(DMA_DEVICE_BASE + DMA_REG_HEAD), desc_ptr)
+        .map_err(|e| format!("Failed to write DMA head: {:?}", e))?;
+    memory
+        .write_word(PhysAddr::new(DMA_DEVICE_BASE + DMA_REG_COMMAND), 1)
+        .map_err(|e| format!("Failed to start DMA: {:?}", e))?;
+    Ok(())
+}
+
+/// `REG_STATUS` reads 1 while the command issued by the last `REG_COMMAND`
+/// write is still within its simulated latency window -- the actual host
+/// I/O behind it has already completed by the time this is ever observed
+/// true (see `SimpleBlockDevice::busy_ticks`), so this only answers whether
+/// a caller waiting on the device's completion IRQ can stop waiting, not
+/// whether `read_sector`/`write_sector`'s buffer is populated yet.
+pub fn is_busy(memory: &mut dyn Memory) -> Result<bool, String> {
+    memory
+        .read_word(PhysAddr::new(BLOCK_DEVICE_BASE + REG_STATUS))
+        .map(|status| status != 0)
+        .map_err(|e| format!("Failed to read status: {:?}", e))
+}
+
 pub fn read_sector(memory: &mut dyn Memory, sector: u32, buffer: &mut [u8]) -> Result<(), String> {
     if buffer.len() != 512 {
         return Err("Buffer must be 512 bytes".to_string());
@@ -22,8 +60,8 @@ pub fn read_sector(memory: &mut dyn Memory, sector: u32, buffer: &mut [u8]) -> R
         .write_word(PhysAddr::new(BLOCK_DEVICE_BASE + REG_COMMAND), 1)
         .map_err(|e| format!("Failed to write command: {:?}", e))?;
 
-    // 3. Read Data from Device Buffer
-    // In a real device, we might poll status, but SimpleBlockDevice is synchronous.
+    // 3. Read Data from Device Buffer. The command above already ran
+    // synchronously against the host file, so there's nothing to poll here.
     for i in (0..512).step_by(4) {
         let val = memory
             .read_word(PhysAddr::new(
@@ -0,0 +1,150 @@
+//! A tiny durable key/value store for boot-time settings (e.g. a static IP,
+//! a MAC override, a default boot path) -- analogous to a flash config
+//! partition, laid out in a fixed range of sectors past the mkfs filesystem
+//! so it doesn't collide with the superblock/bitmap/inode/data regions
+//! `FileSystem::mount` expects, the same way `fs::block`'s `DMA_DEVICE_BASE`
+//! sits in its own address window rather than sharing `BLOCK_DEVICE_BASE`'s.
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use ferrous_vm::Memory;
+
+/// First sector of the reserved region. Chosen well past any mkfs test
+/// image's own blocks rather than derived from `SuperBlock::total_blocks`,
+/// since `fs::block` has no handle on the superblock -- a disk that grows
+/// into this range would need it moved.
+const CONFIG_STORE_START_SECTOR: u32 = 4096;
+/// 16 sectors (8 KiB) of records -- enough for a handful of boot settings.
+const CONFIG_STORE_SECTOR_COUNT: u32 = 16;
+const SECTOR_SIZE: usize = 512;
+const CONFIG_STORE_SIZE: usize = CONFIG_STORE_SECTOR_COUNT as usize * SECTOR_SIZE;
+
+fn load(memory: &mut dyn Memory) -> Result<Vec<u8>, String> {
+    let mut region = vec![0u8; CONFIG_STORE_SIZE];
+    for i in 0..CONFIG_STORE_SECTOR_COUNT {
+        let mut sector = [0u8; SECTOR_SIZE];
+        super::block::read_sector(memory, CONFIG_STORE_START_SECTOR + i, &mut sector)?;
+        let start = i as usize * SECTOR_SIZE;
+        region[start..start + SECTOR_SIZE].copy_from_slice(&sector);
+    }
+    Ok(region)
+}
+
+fn store(memory: &mut dyn Memory, region: &[u8]) -> Result<(), String> {
+    for i in 0..CONFIG_STORE_SECTOR_COUNT {
+        let start = i as usize * SECTOR_SIZE;
+        super::block::write_sector(
+            memory,
+            CONFIG_STORE_START_SECTOR + i,
+            &region[start..start + SECTOR_SIZE],
+        )?;
+    }
+    Ok(())
+}
+
+/// Walk `region`'s length-prefixed `(key_len u16, key, val_len u32, val)`
+/// records, calling `f(key, val)` for each one until it returns `false` or
+/// a zero `key_len` sentinel marks the end.
+fn for_each_record(region: &[u8], mut f: impl FnMut(&[u8], &[u8]) -> bool) {
+    let mut offset = 0;
+    while offset + 2 <= region.len() {
+        let key_len = u16::from_le_bytes([region[offset], region[offset + 1]]) as usize;
+        if key_len == 0 {
+            break;
+        }
+        let key_start = offset + 2;
+        let val_len_pos = key_start + key_len;
+        if val_len_pos + 4 > region.len() {
+            break; // Corrupt/truncated tail; stop rather than reading garbage.
+        }
+        let val_len = u32::from_le_bytes(
+            region[val_len_pos..val_len_pos + 4].try_into().unwrap(),
+        ) as usize;
+        let val_start = val_len_pos + 4;
+        let val_end = val_start + val_len;
+        if val_end > region.len() {
+            break;
+        }
+        if !f(&region[key_start..val_len_pos], &region[val_start..val_end]) {
+            return;
+        }
+        offset = val_end;
+    }
+}
+
+fn append_record(out: &mut Vec<u8>, key: &[u8], val: &[u8]) {
+    out.extend_from_slice(&(key.len() as u16).to_le_bytes());
+    out.extend_from_slice(key);
+    out.extend_from_slice(&(val.len() as u32).to_le_bytes());
+    out.extend_from_slice(val);
+}
+
+/// Look up `key`, copying its value into `buf` (truncated if `buf` is
+/// shorter) and returning the value's full length -- the same
+/// longer-than-`buf`-is-fine, return-the-real-length convention `GetRandom`
+/// and `ConsoleRead` use.
+pub fn read(memory: &mut dyn Memory, key: &[u8], buf: &mut [u8]) -> Result<usize, String> {
+    let region = load(memory)?;
+    let mut found = None;
+    for_each_record(&region, |k, v| {
+        if k == key {
+            found = Some(v.to_vec());
+            false
+        } else {
+            true
+        }
+    });
+    match found {
+        Some(val) => {
+            let n = val.len().min(buf.len());
+            buf[..n].copy_from_slice(&val[..n]);
+            Ok(val.len())
+        }
+        None => Err("key not found".to_string()),
+    }
+}
+
+/// Write `key` = `val`, replacing any existing record for `key` -- erase is
+/// just a rewrite of the whole region with that key's record dropped, same
+/// as `remove` below, rather than patching a differently-sized value in place.
+pub fn write(memory: &mut dyn Memory, key: &[u8], val: &[u8]) -> Result<(), String> {
+    if key.len() > u16::MAX as usize {
+        return Err("key too long".to_string());
+    }
+    let region = load(memory)?;
+    let mut rebuilt = Vec::with_capacity(region.len());
+    for_each_record(&region, |k, v| {
+        if k != key {
+            append_record(&mut rebuilt, k, v);
+        }
+        true
+    });
+    append_record(&mut rebuilt, key, val);
+
+    // +2 leaves room for the zero key_len sentinel that marks the end.
+    if rebuilt.len() + 2 > CONFIG_STORE_SIZE {
+        return Err("config store full".to_string());
+    }
+    rebuilt.resize(CONFIG_STORE_SIZE, 0);
+    store(memory, &rebuilt)
+}
+
+/// Drop `key`'s record, erasing it by rewriting the region without it.
+pub fn remove(memory: &mut dyn Memory, key: &[u8]) -> Result<(), String> {
+    let region = load(memory)?;
+    let mut rebuilt = Vec::with_capacity(region.len());
+    let mut found = false;
+    for_each_record(&region, |k, v| {
+        if k == key {
+            found = true;
+        } else {
+            append_record(&mut rebuilt, k, v);
+        }
+        true
+    });
+    if !found {
+        return Err("key not found".to_string());
+    }
+    rebuilt.resize(CONFIG_STORE_SIZE, 0);
+    store(memory, &rebuilt)
+}
@@ -1,10 +1,17 @@
 use crate::error::KernelError;
 use alloc::format;
-use ferrous_fs::{DirEntry, Inode, SuperBlock, BLOCK_SIZE, INODE_DIRECT_POINTERS, MAGIC};
+use alloc::vec;
+use alloc::vec::Vec;
+use ferrous_fs::{
+    DirEntry, FileType, Inode, SuperBlock, BLOCK_SIZE, INODE_DIRECT_POINTERS, MAGIC,
+};
 use ferrous_vm::Memory;
 use log::{error, info};
 
 pub mod block;
+pub mod config;
+pub mod ninep;
+pub mod scheme;
 
 pub struct FileSystem {
     pub superblock: SuperBlock,
@@ -71,50 +78,68 @@ impl FileSystem {
         Ok(inode)
     }
 
-    pub fn find_inode(&self, memory: &mut dyn Memory, name: &str) -> Result<u32, KernelError> {
-        // Special case for root directory
-        if name == "/" {
+    /// Resolve `path` to an inode id, walking it component by component from
+    /// the root directory (inode 0) instead of only scanning a single flat
+    /// directory -- `path` may start with `/` or not, and repeated/trailing
+    /// slashes are ignored the same way.  Every component but the last must
+    /// name a directory to descend into; the last component's inode is
+    /// returned whatever type it is.
+    pub fn find_inode(&self, memory: &mut dyn Memory, path: &str) -> Result<u32, KernelError> {
+        let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+        if components.is_empty() {
+            // "/" or "" both mean the root directory itself.
             return Ok(0);
         }
 
-        // Read root inode (ID 0)
-        let root_inode = self.read_inode(memory, 0)?;
-
-        // Scan direct pointers
-        for &block_id in root_inode.direct_ptrs.iter() {
-            if block_id == 0 {
-                continue;
-            }
-
-            let mut buffer = [0u8; BLOCK_SIZE];
-            if let Err(e) = block::read_sector(memory, block_id, &mut buffer) {
+        let mut current_id = 0u32;
+        let last = components.len() - 1;
+        for (i, component) in components.iter().enumerate() {
+            let dir_inode = self.read_inode(memory, current_id)?;
+            if dir_inode.file_type != FileType::Directory {
                 return Err(KernelError::InitializationError(format!(
-                    "Dir Read Error: {}",
-                    e
+                    "{} is not a directory",
+                    component
                 )));
             }
 
-            // Iterate entries in block
-            let entry_size = core::mem::size_of::<DirEntry>();
-            let num_entries = BLOCK_SIZE / entry_size;
-
-            for i in 0..num_entries {
-                let entry_offset = i * entry_size;
-                let entry_ptr = unsafe { buffer.as_ptr().add(entry_offset) as *const DirEntry };
-                let entry = unsafe { entry_ptr.read_unaligned() };
+            current_id = self.find_entry(memory, &dir_inode, component)?;
+            if i == last {
+                return Ok(current_id);
+            }
+        }
 
-                // Skip if name is empty (first char is 0)
-                if entry.name[0] == 0 {
-                    continue;
-                }
+        unreachable!("components is non-empty, so the loop above always returns")
+    }
 
-                if entry.name_as_str() == name {
-                    return Ok(entry.inode_id);
-                }
+    /// Scan `dir_inode`'s directory entries for one named `name`, returning
+    /// its inode id. Reads the whole directory through `read_data` rather
+    /// than only its direct pointers, so a directory big enough to need
+    /// indirect blocks is handled the same as any other file's data.
+    fn find_entry(
+        &self,
+        memory: &mut dyn Memory,
+        dir_inode: &Inode,
+        name: &str,
+    ) -> Result<u32, KernelError> {
+        let entry_size = core::mem::size_of::<DirEntry>();
+        let mut buffer = vec![0u8; dir_inode.size as usize];
+        self.read_data(memory, dir_inode, 0, &mut buffer)?;
+
+        for chunk in buffer.chunks_exact(entry_size) {
+            let entry = unsafe { (chunk.as_ptr() as *const DirEntry).read_unaligned() };
+            // Skip if name is empty (first char is 0)
+            if entry.name[0] == 0 {
+                continue;
+            }
+            if entry.name_as_str() == name {
+                return Ok(entry.inode_id);
             }
         }
 
-        Err(KernelError::InitializationError("File not found".into()))
+        Err(KernelError::InitializationError(format!(
+            "File not found: {}",
+            name
+        )))
     }
 
     pub fn read_data(
@@ -128,10 +153,24 @@ impl FileSystem {
             return Ok(0); // EOF
         }
 
+        let pointers_per_block = (BLOCK_SIZE / 4) as u32;
+        let single_indirect_cap = INODE_DIRECT_POINTERS as u32 + pointers_per_block;
+        let double_indirect_cap = single_indirect_cap + pointers_per_block * pointers_per_block;
+
         let mut bytes_read = 0;
         let mut current_offset = offset;
         let end_offset = (offset + buffer.len() as u32).min(inode.size);
 
+        // Each cache holds the last indirect block read plus its id, so a
+        // multi-block read that stays within the same indirect block's
+        // span only fetches it once instead of once per data block.
+        let mut indirect_cache: Option<(u32, [u8; BLOCK_SIZE])> = None;
+        let mut dbl_indirect_cache: Option<(u32, [u8; BLOCK_SIZE])> = None;
+        let mut dbl_inner_cache: Option<(u32, [u8; BLOCK_SIZE])> = None;
+        let mut tpl_indirect_cache: Option<(u32, [u8; BLOCK_SIZE])> = None;
+        let mut tpl_middle_cache: Option<(u32, [u8; BLOCK_SIZE])> = None;
+        let mut tpl_inner_cache: Option<(u32, [u8; BLOCK_SIZE])> = None;
+
         // While we have bytes to read
         while current_offset < end_offset {
             let block_index = current_offset / BLOCK_SIZE as u32;
@@ -142,38 +181,55 @@ impl FileSystem {
             // Resolve block ID
             let block_id = if (block_index as usize) < INODE_DIRECT_POINTERS {
                 inode.direct_ptrs[block_index as usize]
-            } else {
+            } else if block_index < single_indirect_cap {
                 let indirect_index = block_index - INODE_DIRECT_POINTERS as u32;
-                let pointers_per_block = (BLOCK_SIZE / 4) as u32;
-
-                if indirect_index < pointers_per_block {
-                    let indirect_ptr_block = inode.indirect_ptr;
-                    if indirect_ptr_block == 0 {
-                        0
-                    } else {
-                        // Read the indirect block
-                        let mut indirect_buf = [0u8; BLOCK_SIZE];
-                        if let Err(e) =
-                            block::read_sector(memory, indirect_ptr_block, &mut indirect_buf)
-                        {
-                            return Err(KernelError::InitializationError(format!(
-                                "Indirect Block Read Error: {}",
-                                e
-                            )));
-                        }
-
-                        // Read u32 from buffer
-                        unsafe {
-                            let ptr = indirect_buf.as_ptr().add((indirect_index * 4) as usize)
-                                as *const u32;
-                            ptr.read_unaligned()
-                        }
-                    }
-                } else {
-                    return Err(KernelError::InitializationError(
-                        "Double indirect pointers not supported yet".into(),
-                    ));
-                }
+                Self::read_pointer(
+                    memory,
+                    inode.indirect_ptr,
+                    indirect_index,
+                    &mut indirect_cache,
+                )?
+            } else if block_index < double_indirect_cap {
+                let indirect_index = block_index - single_indirect_cap;
+                let outer_index = indirect_index / pointers_per_block;
+                let inner_index = indirect_index % pointers_per_block;
+
+                let single_indirect_block = Self::read_pointer(
+                    memory,
+                    inode.double_indirect_ptr,
+                    outer_index,
+                    &mut dbl_indirect_cache,
+                )?;
+                Self::read_pointer(
+                    memory,
+                    single_indirect_block,
+                    inner_index,
+                    &mut dbl_inner_cache,
+                )?
+            } else {
+                let indirect_index = block_index - double_indirect_cap;
+                let outer_index = indirect_index / (pointers_per_block * pointers_per_block);
+                let middle_index = (indirect_index / pointers_per_block) % pointers_per_block;
+                let inner_index = indirect_index % pointers_per_block;
+
+                let double_indirect_block = Self::read_pointer(
+                    memory,
+                    inode.triple_indirect_ptr,
+                    outer_index,
+                    &mut tpl_indirect_cache,
+                )?;
+                let single_indirect_block = Self::read_pointer(
+                    memory,
+                    double_indirect_block,
+                    middle_index,
+                    &mut tpl_middle_cache,
+                )?;
+                Self::read_pointer(
+                    memory,
+                    single_indirect_block,
+                    inner_index,
+                    &mut tpl_inner_cache,
+                )?
             };
 
             if block_id == 0 {
@@ -202,4 +258,356 @@ impl FileSystem {
 
         Ok(bytes_read)
     }
+
+    /// Write `buffer` into `inode`'s data starting at `offset`, allocating
+    /// data and indirect blocks on demand (filling any hole up to `offset`
+    /// with sparse zero blocks) and growing `inode.size` as needed. The
+    /// inode is persisted back to its table slot once all of `buffer` has
+    /// landed.
+    pub fn write_data(
+        &mut self,
+        memory: &mut dyn Memory,
+        inode: &mut Inode,
+        offset: u32,
+        buffer: &[u8],
+    ) -> Result<usize, KernelError> {
+        let pointers_per_block = (BLOCK_SIZE / 4) as u32;
+        let single_indirect_cap = INODE_DIRECT_POINTERS as u32 + pointers_per_block;
+        let double_indirect_cap = single_indirect_cap + pointers_per_block * pointers_per_block;
+
+        let mut bytes_written = 0;
+        let mut current_offset = offset;
+        let end_offset = offset + buffer.len() as u32;
+
+        while current_offset < end_offset {
+            let block_index = current_offset / BLOCK_SIZE as u32;
+            let offset_in_block = (current_offset % BLOCK_SIZE as u32) as usize;
+            let bytes_to_write =
+                (BLOCK_SIZE - offset_in_block).min((end_offset - current_offset) as usize);
+
+            let block_id = if (block_index as usize) < INODE_DIRECT_POINTERS {
+                if inode.direct_ptrs[block_index as usize] == 0 {
+                    inode.direct_ptrs[block_index as usize] = self.alloc_block(memory)?;
+                }
+                inode.direct_ptrs[block_index as usize]
+            } else if block_index < single_indirect_cap {
+                if inode.indirect_ptr == 0 {
+                    inode.indirect_ptr = self.alloc_block(memory)?;
+                }
+                let indirect_index = block_index - INODE_DIRECT_POINTERS as u32;
+                self.ensure_pointer(memory, inode.indirect_ptr, indirect_index)?
+            } else if block_index < double_indirect_cap {
+                if inode.double_indirect_ptr == 0 {
+                    inode.double_indirect_ptr = self.alloc_block(memory)?;
+                }
+                let indirect_index = block_index - single_indirect_cap;
+                let outer_index = indirect_index / pointers_per_block;
+                let inner_index = indirect_index % pointers_per_block;
+
+                let single_indirect_block =
+                    self.ensure_pointer(memory, inode.double_indirect_ptr, outer_index)?;
+                self.ensure_pointer(memory, single_indirect_block, inner_index)?
+            } else {
+                if inode.triple_indirect_ptr == 0 {
+                    inode.triple_indirect_ptr = self.alloc_block(memory)?;
+                }
+                let indirect_index = block_index - double_indirect_cap;
+                let outer_index = indirect_index / (pointers_per_block * pointers_per_block);
+                let middle_index = (indirect_index / pointers_per_block) % pointers_per_block;
+                let inner_index = indirect_index % pointers_per_block;
+
+                let double_indirect_block =
+                    self.ensure_pointer(memory, inode.triple_indirect_ptr, outer_index)?;
+                let single_indirect_block =
+                    self.ensure_pointer(memory, double_indirect_block, middle_index)?;
+                self.ensure_pointer(memory, single_indirect_block, inner_index)?
+            };
+
+            // A partial-block write has to preserve the rest of the
+            // block's existing contents, so read it first unless we're
+            // about to overwrite the whole thing anyway.
+            let mut block_buf = [0u8; BLOCK_SIZE];
+            if bytes_to_write < BLOCK_SIZE {
+                if let Err(e) = block::read_sector(memory, block_id, &mut block_buf) {
+                    return Err(KernelError::InitializationError(format!(
+                        "Data Read Error: {}",
+                        e
+                    )));
+                }
+            }
+            block_buf[offset_in_block..(offset_in_block + bytes_to_write)]
+                .copy_from_slice(&buffer[bytes_written..(bytes_written + bytes_to_write)]);
+            block::write_sector(memory, block_id, &block_buf).map_err(|e| {
+                KernelError::InitializationError(format!("Data Write Error: {}", e))
+            })?;
+
+            bytes_written += bytes_to_write;
+            current_offset += bytes_to_write as u32;
+        }
+
+        if current_offset > inode.size {
+            inode.size = current_offset;
+        }
+        self.write_inode(memory, inode)?;
+
+        Ok(bytes_written)
+    }
+
+    /// Write `inode` back to its slot in the on-disk inode table.
+    pub fn write_inode(&self, memory: &mut dyn Memory, inode: &Inode) -> Result<(), KernelError> {
+        let inode_size = core::mem::size_of::<Inode>() as u32;
+        let inodes_per_block = BLOCK_SIZE as u32 / inode_size;
+
+        let block_offset = inode.id / inodes_per_block;
+        let index_in_block = inode.id % inodes_per_block;
+        let block_id = self.superblock.inode_table_block + block_offset;
+
+        let mut buffer = [0u8; BLOCK_SIZE];
+        block::read_sector(memory, block_id, &mut buffer)
+            .map_err(|e| KernelError::InitializationError(format!("Inode Read Error: {}", e)))?;
+
+        unsafe {
+            let ptr =
+                buffer.as_mut_ptr().add((index_in_block * inode_size) as usize) as *mut Inode;
+            ptr.write_unaligned(*inode);
+        }
+
+        block::write_sector(memory, block_id, &buffer)
+            .map_err(|e| KernelError::InitializationError(format!("Inode Write Error: {}", e)))
+    }
+
+    /// Read the `index`th pointer out of the indirect block `block_id`,
+    /// reusing `cache` (keyed by block id) instead of re-reading the same
+    /// indirect block once per pointer it's consulted for. Returns 0
+    /// (sparse) without touching disk if `block_id` itself is unallocated.
+    fn read_pointer(
+        memory: &mut dyn Memory,
+        block_id: u32,
+        index: u32,
+        cache: &mut Option<(u32, [u8; BLOCK_SIZE])>,
+    ) -> Result<u32, KernelError> {
+        if block_id == 0 {
+            return Ok(0);
+        }
+
+        if cache.as_ref().map(|(id, _)| *id) != Some(block_id) {
+            let mut buf = [0u8; BLOCK_SIZE];
+            block::read_sector(memory, block_id, &mut buf).map_err(|e| {
+                KernelError::InitializationError(format!("Indirect Block Read Error: {}", e))
+            })?;
+            *cache = Some((block_id, buf));
+        }
+
+        let buf = &cache.as_ref().unwrap().1;
+        let ptr = unsafe { (buf.as_ptr().add((index * 4) as usize) as *const u32).read_unaligned() };
+        Ok(ptr)
+    }
+
+    /// Return the `index`th pointer stored in indirect block `parent_block`,
+    /// allocating a fresh data block and writing it into that slot first if
+    /// it's still a hole.
+    fn ensure_pointer(
+        &mut self,
+        memory: &mut dyn Memory,
+        parent_block: u32,
+        index: u32,
+    ) -> Result<u32, KernelError> {
+        let mut buf = [0u8; BLOCK_SIZE];
+        block::read_sector(memory, parent_block, &mut buf).map_err(|e| {
+            KernelError::InitializationError(format!("Indirect Block Read Error: {}", e))
+        })?;
+
+        let existing =
+            unsafe { (buf.as_ptr().add((index * 4) as usize) as *const u32).read_unaligned() };
+        if existing != 0 {
+            return Ok(existing);
+        }
+
+        let new_block = self.alloc_block(memory)?;
+        unsafe {
+            (buf.as_mut_ptr().add((index * 4) as usize) as *mut u32).write_unaligned(new_block);
+        }
+        block::write_sector(memory, parent_block, &buf).map_err(|e| {
+            KernelError::InitializationError(format!("Indirect Block Write Error: {}", e))
+        })?;
+
+        Ok(new_block)
+    }
+
+    /// Allocate a free data block from the on-disk free-block bitmap,
+    /// marking the first clear bit and persisting both the bitmap and the
+    /// superblock's updated `free_blocks` count. The returned block is
+    /// zeroed so callers never hand back a previous owner's stale data.
+    fn alloc_block(&mut self, memory: &mut dyn Memory) -> Result<u32, KernelError> {
+        let mut bitmap = [0u8; BLOCK_SIZE];
+        block::read_sector(memory, self.superblock.data_bitmap_block, &mut bitmap).map_err(
+            |e| KernelError::InitializationError(format!("Data Bitmap Read Error: {}", e)),
+        )?;
+
+        let free_bit = bitmap
+            .iter()
+            .enumerate()
+            .find(|(_, &byte)| byte != 0xFF)
+            .and_then(|(byte_index, &byte)| {
+                (0..8u32)
+                    .find(|bit| byte & (1 << bit) == 0)
+                    .map(|bit| byte_index * 8 + bit as usize)
+            })
+            .ok_or_else(|| KernelError::InitializationError("No free data blocks".into()))?;
+
+        bitmap[free_bit / 8] |= 1 << (free_bit % 8);
+        block::write_sector(memory, self.superblock.data_bitmap_block, &bitmap).map_err(
+            |e| KernelError::InitializationError(format!("Data Bitmap Write Error: {}", e)),
+        )?;
+
+        self.superblock.free_blocks -= 1;
+        self.write_superblock(memory)?;
+
+        let block_id = self.superblock.data_blocks_start + free_bit as u32;
+        let zeroed = [0u8; BLOCK_SIZE];
+        block::write_sector(memory, block_id, &zeroed)
+            .map_err(|e| KernelError::InitializationError(format!("Data Block Zero Error: {}", e)))?;
+
+        Ok(block_id)
+    }
+
+    /// Allocate a free inode id from the on-disk inode bitmap, mirroring
+    /// `alloc_block`'s bitmap-scan-and-flip shape over the inode bitmap
+    /// instead of the data bitmap, and persist a freshly zeroed `Inode` of
+    /// `file_type` into its inode-table slot.
+    pub fn alloc_inode(
+        &mut self,
+        memory: &mut dyn Memory,
+        file_type: FileType,
+    ) -> Result<Inode, KernelError> {
+        let mut bitmap = [0u8; BLOCK_SIZE];
+        block::read_sector(memory, self.superblock.inode_bitmap_block, &mut bitmap).map_err(
+            |e| KernelError::InitializationError(format!("Inode Bitmap Read Error: {}", e)),
+        )?;
+
+        let free_bit = bitmap
+            .iter()
+            .enumerate()
+            .find(|(_, &byte)| byte != 0xFF)
+            .and_then(|(byte_index, &byte)| {
+                (0..8u32)
+                    .find(|bit| byte & (1 << bit) == 0)
+                    .map(|bit| byte_index * 8 + bit as usize)
+            })
+            .ok_or_else(|| KernelError::InitializationError("No free inodes".into()))?;
+
+        bitmap[free_bit / 8] |= 1 << (free_bit % 8);
+        block::write_sector(memory, self.superblock.inode_bitmap_block, &bitmap).map_err(
+            |e| KernelError::InitializationError(format!("Inode Bitmap Write Error: {}", e)),
+        )?;
+
+        self.superblock.free_inodes -= 1;
+        self.write_superblock(memory)?;
+
+        let inode = Inode::new(free_bit as u32, file_type);
+        self.write_inode(memory, &inode)?;
+
+        Ok(inode)
+    }
+
+    /// Clear `inode_id`'s bit in the on-disk inode bitmap and give its slot
+    /// back to `SuperBlock::free_inodes`. Does not reclaim the inode's data
+    /// blocks -- a caller that wants those freed too must do so itself
+    /// before calling this.
+    pub fn free_inode(&mut self, memory: &mut dyn Memory, inode_id: u32) -> Result<(), KernelError> {
+        let mut bitmap = [0u8; BLOCK_SIZE];
+        block::read_sector(memory, self.superblock.inode_bitmap_block, &mut bitmap).map_err(
+            |e| KernelError::InitializationError(format!("Inode Bitmap Read Error: {}", e)),
+        )?;
+
+        bitmap[(inode_id / 8) as usize] &= !(1 << (inode_id % 8));
+        block::write_sector(memory, self.superblock.inode_bitmap_block, &bitmap).map_err(
+            |e| KernelError::InitializationError(format!("Inode Bitmap Write Error: {}", e)),
+        )?;
+
+        self.superblock.free_inodes += 1;
+        self.write_superblock(memory)
+    }
+
+    /// Append a `name` -> new-inode `DirEntry` to `dir_inode`'s directory
+    /// data: reuses a zeroed slot in an already-allocated block if one is
+    /// free, otherwise appends past the directory's current end (growing
+    /// it by a block through `write_data`'s on-demand allocation). Returns
+    /// the new entry's inode id.
+    pub fn create(
+        &mut self,
+        memory: &mut dyn Memory,
+        dir_inode: &mut Inode,
+        name: &str,
+        file_type: FileType,
+    ) -> Result<u32, KernelError> {
+        if name.len() > 28 {
+            return Err(KernelError::InitializationError(
+                "File name too long".into(),
+            ));
+        }
+
+        let entry_size = core::mem::size_of::<DirEntry>() as u32;
+        let entries_per_block = BLOCK_SIZE as u32 / entry_size;
+        let used_blocks = dir_inode.size.div_ceil(BLOCK_SIZE as u32);
+
+        for block_index in 0..used_blocks.min(INODE_DIRECT_POINTERS as u32) {
+            let block_id = dir_inode.direct_ptrs[block_index as usize];
+            if block_id == 0 {
+                continue;
+            }
+
+            let mut buffer = [0u8; BLOCK_SIZE];
+            block::read_sector(memory, block_id, &mut buffer)
+                .map_err(|e| KernelError::InitializationError(format!("Dir Read Error: {}", e)))?;
+
+            for i in 0..entries_per_block {
+                let entry_offset = (i * entry_size) as usize;
+                // A free slot has an empty name, the same test `find_entry`
+                // uses; `name` follows the 4-byte `inode_id`.
+                if buffer[entry_offset + core::mem::size_of::<u32>()] == 0 {
+                    let new_inode = self.alloc_inode(memory, file_type)?;
+                    let entry = DirEntry::new(new_inode.id, name);
+                    unsafe {
+                        let ptr = buffer.as_mut_ptr().add(entry_offset) as *mut DirEntry;
+                        ptr.write_unaligned(entry);
+                    }
+                    block::write_sector(memory, block_id, &buffer).map_err(|e| {
+                        KernelError::InitializationError(format!("Dir Write Error: {}", e))
+                    })?;
+                    // A slot past the directory's current end has to grow
+                    // `size`, or `find_entry` would never read it.
+                    let entry_end = block_index * BLOCK_SIZE as u32 + entry_offset as u32 + entry_size;
+                    if entry_end > dir_inode.size {
+                        dir_inode.size = entry_end;
+                        self.write_inode(memory, dir_inode)?;
+                    }
+                    return Ok(new_inode.id);
+                }
+            }
+        }
+
+        let new_inode = self.alloc_inode(memory, file_type)?;
+        let entry = DirEntry::new(new_inode.id, name);
+        let entry_bytes = unsafe {
+            core::slice::from_raw_parts(
+                &entry as *const DirEntry as *const u8,
+                entry_size as usize,
+            )
+        };
+        self.write_data(memory, dir_inode, dir_inode.size, entry_bytes)?;
+
+        Ok(new_inode.id)
+    }
+
+    /// Persist `self.superblock` back to sector 0.
+    fn write_superblock(&self, memory: &mut dyn Memory) -> Result<(), KernelError> {
+        let mut buffer = [0u8; BLOCK_SIZE];
+        unsafe {
+            let ptr = buffer.as_mut_ptr() as *mut SuperBlock;
+            ptr.write_unaligned(self.superblock);
+        }
+        block::write_sector(memory, 0, &buffer)
+            .map_err(|e| KernelError::InitializationError(format!("Superblock Write Error: {}", e)))
+    }
 }
@@ -0,0 +1,271 @@
+//! A minimal 9P2000.L-style transport for host-directory passthrough.
+//!
+//! Structured the same way as [`crate::net::driver::NetDriver`]: a pair of
+//! fixed guest-memory-resident buffers the guest writes a request message
+//! into and reads a reply back out of, rather than a real host socket.
+//! [`NineTransport`] itself exposes a plain Rust API (`open`/`read`/
+//! `write`/`clunk`) that the `FileOpen`/`FileRead`/`FileWrite`/`FileClose`
+//! syscall handlers call directly for a path under the `/host` mount
+//! prefix; [`NineTransport::dispatch`] is the guest-memory-wire-format
+//! side of the same operations, present as the transport device itself
+//! (mirroring `NetDriver`'s not-yet-bus-attached virtqueues) rather than
+//! something the syscall path round-trips through today.
+
+use ferrous_vm::{Memory, PhysAddr};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::error::SyscallError;
+
+/// Base address of the 9P transport's guest-memory-resident message
+/// buffers, past the networking virtqueues and NIC feature-config region.
+const NINEP_BASE: u32 = 0x3000_4000;
+const REQUEST_BASE: u32 = NINEP_BASE;
+const REPLY_BASE: u32 = NINEP_BASE + 0x1000;
+/// Header (7 bytes) plus payload; large enough for a `Tread`/`Rread` at
+/// the chunk sizes the rest of the FS layer already reads in.
+const MAX_MESSAGE: usize = 0x1000;
+const HEADER_SIZE: usize = 7; // {size: u32, type: u8, tag: u16}
+
+// 9P2000.L message type numbers. T* is guest -> host, R* is the paired
+// reply; `RLERROR` is .L's numeric-errno error reply used for all of them.
+const TVERSION: u8 = 100;
+const RVERSION: u8 = 101;
+const RLERROR: u8 = 7;
+const TWALK: u8 = 110;
+const TOPEN: u8 = 112;
+const ROPEN: u8 = 113;
+const TREAD: u8 = 116;
+const RREAD: u8 = 117;
+const TWRITE: u8 = 118;
+const RWRITE: u8 = 119;
+const TCLUNK: u8 = 120;
+const RCLUNK: u8 = 121;
+
+fn read_bytes(memory: &mut dyn Memory, base: u32, buf: &mut [u8]) {
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = memory
+            .read_byte(PhysAddr::new(base + i as u32))
+            .unwrap_or(0);
+    }
+}
+
+fn write_bytes(memory: &mut dyn Memory, base: u32, data: &[u8]) {
+    for (i, byte) in data.iter().enumerate() {
+        let _ = memory.write_byte(PhysAddr::new(base + i as u32), *byte);
+    }
+}
+
+struct Header {
+    size: u32,
+    msg_type: u8,
+    tag: u16,
+}
+
+fn read_header(memory: &mut dyn Memory, base: u32) -> Header {
+    let mut raw = [0u8; HEADER_SIZE];
+    read_bytes(memory, base, &mut raw);
+    Header {
+        size: u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]),
+        msg_type: raw[4],
+        tag: u16::from_le_bytes([raw[5], raw[6]]),
+    }
+}
+
+fn write_message(memory: &mut dyn Memory, base: u32, msg_type: u8, tag: u16, payload: &[u8]) {
+    let size = (HEADER_SIZE + payload.len()) as u32;
+    let mut header = [0u8; HEADER_SIZE];
+    header[0..4].copy_from_slice(&size.to_le_bytes());
+    header[4] = msg_type;
+    header[5..7].copy_from_slice(&tag.to_le_bytes());
+    write_bytes(memory, base, &header);
+    write_bytes(memory, base + HEADER_SIZE as u32, payload);
+}
+
+fn write_error(memory: &mut dyn Memory, tag: u16, err: &SyscallError) {
+    let message = err.to_string();
+    let bytes = message.as_bytes();
+    let len = bytes.len().min(MAX_MESSAGE - HEADER_SIZE);
+    write_message(memory, REPLY_BASE, RLERROR, tag, &bytes[..len]);
+}
+
+/// One fid the guest has walked/opened, keyed the same way a `Twalk`'s
+/// `newfid` would be. `path` is already resolved to an absolute host path
+/// under `share_root`.
+struct FidEntry {
+    #[allow(dead_code)]
+    path: PathBuf,
+    file: File,
+}
+
+/// Host-directory passthrough transport. `share_root` is the real host
+/// directory `--share` mounts under `/host` in the guest path namespace;
+/// every fid is resolved relative to it, and a `..` path component is
+/// rejected outright so a guest can never walk outside of it.
+pub struct NineTransport {
+    share_root: PathBuf,
+    fids: BTreeMap<u32, FidEntry>,
+    next_fid: u32,
+}
+
+impl NineTransport {
+    pub fn new(share_root: PathBuf) -> Self {
+        Self {
+            share_root,
+            fids: BTreeMap::new(),
+            next_fid: 0,
+        }
+    }
+
+    fn resolve(&self, path: &str) -> Result<PathBuf, SyscallError> {
+        let relative = path.trim_start_matches('/');
+        if relative.split('/').any(|part| part == "..") {
+            return Err(SyscallError::NineP(format!(
+                "path escapes share root: {}",
+                path
+            )));
+        }
+        Ok(self.share_root.join(relative))
+    }
+
+    /// Walk `path` (already stripped of its `/host` mount prefix) and open
+    /// it on the host, collapsing what would be a `Twalk` followed by a
+    /// `Topen` into one call since nothing here needs the intermediate
+    /// fid a bare `Twalk` alone would produce.
+    pub fn open(&mut self, path: &str) -> Result<u32, SyscallError> {
+        let resolved = self.resolve(path)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&resolved)
+            .map_err(|e| SyscallError::NineP(format!("open {}: {}", path, e)))?;
+
+        let fid = self.next_fid;
+        self.next_fid += 1;
+        self.fids.insert(fid, FidEntry { path: resolved, file });
+        Ok(fid)
+    }
+
+    pub fn read(&mut self, fid: u32, offset: u64, buf: &mut [u8]) -> Result<usize, SyscallError> {
+        let entry = self
+            .fids
+            .get_mut(&fid)
+            .ok_or_else(|| SyscallError::NineP(format!("unknown fid {}", fid)))?;
+        entry
+            .file
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| SyscallError::NineP(format!("seek: {}", e)))?;
+        entry
+            .file
+            .read(buf)
+            .map_err(|e| SyscallError::NineP(format!("read: {}", e)))
+    }
+
+    pub fn write(&mut self, fid: u32, offset: u64, buf: &[u8]) -> Result<usize, SyscallError> {
+        let entry = self
+            .fids
+            .get_mut(&fid)
+            .ok_or_else(|| SyscallError::NineP(format!("unknown fid {}", fid)))?;
+        entry
+            .file
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| SyscallError::NineP(format!("seek: {}", e)))?;
+        entry
+            .file
+            .write(buf)
+            .map_err(|e| SyscallError::NineP(format!("write: {}", e)))
+    }
+
+    pub fn clunk(&mut self, fid: u32) {
+        self.fids.remove(&fid);
+    }
+
+    /// Current length of `fid`'s file, used to seek an `O_APPEND` write to
+    /// end-of-file before it lands.
+    pub fn size(&self, fid: u32) -> Result<u64, SyscallError> {
+        let entry = self
+            .fids
+            .get(&fid)
+            .ok_or_else(|| SyscallError::NineP(format!("unknown fid {}", fid)))?;
+        entry
+            .file
+            .metadata()
+            .map(|meta| meta.len())
+            .map_err(|e| SyscallError::NineP(format!("metadata: {}", e)))
+    }
+
+    /// Decode one request out of `REQUEST_BASE`, perform it, and encode the
+    /// reply into `REPLY_BASE` — the guest-memory-wire-format path the
+    /// `Tversion`/`Twalk`+`Topen`/`Tread`/`Twrite`/`Tclunk` messages take
+    /// when something drives this transport as an actual MMIO device
+    /// rather than calling the Rust methods above directly.
+    pub fn dispatch(&mut self, memory: &mut dyn Memory) {
+        let header = read_header(memory, REQUEST_BASE);
+        let payload_len = (header.size as usize)
+            .saturating_sub(HEADER_SIZE)
+            .min(MAX_MESSAGE - HEADER_SIZE);
+        let mut payload = vec![0u8; payload_len];
+        read_bytes(memory, REQUEST_BASE + HEADER_SIZE as u32, &mut payload);
+
+        match header.msg_type {
+            TVERSION => {
+                let mut body = Vec::new();
+                body.extend_from_slice(&(MAX_MESSAGE as u32).to_le_bytes());
+                body.extend_from_slice(b"9P2000.L");
+                write_message(memory, REPLY_BASE, RVERSION, header.tag, &body);
+            }
+            TWALK | TOPEN => {
+                let path = String::from_utf8_lossy(&payload).into_owned();
+                match self.open(&path) {
+                    Ok(fid) => {
+                        write_message(memory, REPLY_BASE, ROPEN, header.tag, &fid.to_le_bytes())
+                    }
+                    Err(e) => write_error(memory, header.tag, &e),
+                }
+            }
+            TREAD if payload.len() >= 16 => {
+                let fid = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+                let offset = u64::from_le_bytes(payload[4..12].try_into().unwrap());
+                let count = u32::from_le_bytes(payload[12..16].try_into().unwrap()) as usize;
+                let mut buf = vec![0u8; count.min(MAX_MESSAGE - HEADER_SIZE - 4)];
+                match self.read(fid, offset, &mut buf) {
+                    Ok(n) => {
+                        let mut body = Vec::with_capacity(4 + n);
+                        body.extend_from_slice(&(n as u32).to_le_bytes());
+                        body.extend_from_slice(&buf[..n]);
+                        write_message(memory, REPLY_BASE, RREAD, header.tag, &body);
+                    }
+                    Err(e) => write_error(memory, header.tag, &e),
+                }
+            }
+            TWRITE if payload.len() >= 16 => {
+                let fid = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+                let offset = u64::from_le_bytes(payload[4..12].try_into().unwrap());
+                let count = u32::from_le_bytes(payload[12..16].try_into().unwrap()) as usize;
+                let count = count.min(payload.len() - 16);
+                match self.write(fid, offset, &payload[16..16 + count]) {
+                    Ok(n) => write_message(
+                        memory,
+                        REPLY_BASE,
+                        RWRITE,
+                        header.tag,
+                        &(n as u32).to_le_bytes(),
+                    ),
+                    Err(e) => write_error(memory, header.tag, &e),
+                }
+            }
+            TCLUNK if payload.len() >= 4 => {
+                let fid = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+                self.clunk(fid);
+                write_message(memory, REPLY_BASE, RCLUNK, header.tag, &[]);
+            }
+            _ => write_error(
+                memory,
+                header.tag,
+                &SyscallError::NineP("malformed or unsupported 9p message".into()),
+            ),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/// In-kernel schemes `FileOpen` can resolve a path against before falling
+/// back to the mkfs disk image, Redox-style: the path's prefix alone picks
+/// the backend, so `FileRead`/`FileWrite`/`FileClose` never parse a path
+/// themselves -- they just match on whatever `FileDescriptor` variant the
+/// lookup here produced. `/host/`-prefixed paths route to the 9P
+/// passthrough instead and aren't part of this registry, since that's a
+/// mount point rather than a scheme a path names directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Console,
+    Null,
+    Pipe,
+    Rand,
+    /// Explicit spelling of the same mkfs-disk lookup an unprefixed path
+    /// already falls back to -- `"disk:/foo"` and `"/foo"` resolve to the
+    /// same inode, so scripts that want to name every path unambiguously
+    /// don't have to special-case the disk as "whatever has no scheme".
+    Disk,
+}
+
+/// `(prefix, scheme)` pairs `resolve` checks in order. Adding a provider is
+/// just adding an entry here and a matching `FileDescriptor` variant --
+/// no other `FileOpen` plumbing changes.
+const REGISTRY: &[(&str, Scheme)] = &[
+    ("console:", Scheme::Console),
+    ("null:", Scheme::Null),
+    ("pipe:", Scheme::Pipe),
+    ("rand:", Scheme::Rand),
+    ("disk:", Scheme::Disk),
+];
+
+/// Resolve `path` against the registry, returning the matching scheme and
+/// whatever follows its prefix (e.g. `"pipe:foo"` -> `(Pipe, "foo")`).
+/// `None` means `path` doesn't name a registered scheme, and the caller
+/// should fall back to a disk lookup.
+pub fn resolve(path: &str) -> Option<(Scheme, &str)> {
+    REGISTRY
+        .iter()
+        .find_map(|&(prefix, scheme)| path.strip_prefix(prefix).map(|rest| (scheme, rest)))
+}
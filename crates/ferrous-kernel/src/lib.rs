@@ -1,42 +1,136 @@
+pub mod capability;
 pub mod error;
 pub mod fs;
 pub mod memory;
+pub mod net;
+pub mod plic;
+pub mod snapshot;
 pub mod sync;
 pub mod syscall;
 pub mod thread;
 pub mod types;
 
+use crate::capability::{CapObject, CapRights, ObjectType, Untyped};
 use crate::error::KernelError;
-use crate::sync::Mutex;
-use crate::thread::tcb::FileDescriptor;
+use crate::sync::{
+    Condvar, Endpoint, EndpointWaiter, Message, Mutex, Semaphore, Server, ServerWaiter,
+    ENDPOINT_MAX_MSG, SERVER_MAX_MSG,
+};
+use crate::thread::tcb::{FileDescriptor, Vma};
 use ferrous_vm::{Cpu, Memory, PhysAddr, TrapCause, TrapError, TrapHandler, VirtAddr};
 use goblin::elf;
 use log::{debug, info, warn};
 use std::collections::HashMap;
 use thread::ThreadManager;
+use zerocopy::{AsBytes, FromBytes};
+
+/// Frames reserved up front for `capability::retype`-carved objects (1 MiB
+/// at the default 4 KiB page size).
+const CAPABILITY_POOL_FRAMES: u32 = 256;
 
 pub struct Kernel {
     thread_manager: ThreadManager,
     mutexes: HashMap<u32, Mutex>,
     next_mutex_id: u32,
+    condvars: HashMap<u32, Condvar>,
+    next_condvar_id: u32,
+    semaphores: HashMap<u32, Semaphore>,
+    next_semaphore_id: u32,
+    endpoints: HashMap<u32, Endpoint>,
+    next_endpoint_id: u32,
+    servers: HashMap<u32, Server>,
+    next_server_id: u32,
     file_system: Option<fs::FileSystem>,
+    capability_pool: Untyped,
+    /// 9P host-directory passthrough, present when `--share` named a
+    /// directory to mount under `/host`.
+    ninep: Option<fs::ninep::NineTransport>,
+    /// Ring buffers backing `pipe:<name>` descriptors, keyed by the id
+    /// `pipe_names` assigns the name on first open.
+    pipes: HashMap<u32, sync::Pipe>,
+    /// Maps a `pipe:<name>` to the `pipes` id every descriptor opened
+    /// against that name shares, so a second `FileOpen` of the same name
+    /// connects to the first instead of creating an unconnected pipe.
+    pipe_names: HashMap<String, u32>,
+    next_pipe_id: u32,
+    /// xorshift32 state backing the `rand:` scheme. Seeded to a fixed
+    /// nonzero constant rather than any real entropy source -- there isn't
+    /// one available to the emulator -- so this is good enough to unblock
+    /// code that wants *some* bytes, not for anything security-sensitive.
+    rand_state: u32,
+    /// The thread blocked on an in-flight async `BlockRead`, if any --
+    /// `SimpleBlockDevice` only ever has one command in flight at a time,
+    /// so a single slot is enough rather than a queue of them.
+    block_read_waiter: Option<BlockReadWaiter>,
+}
+
+/// A `BlockRead` parked until the block device's simulated latency window
+/// (`SimpleBlockDevice::busy_ticks`) elapses -- `buffer` is already filled,
+/// since the device's host I/O runs synchronously inside `fs::block::
+/// read_sector`, but `buf_ptr` isn't written until `TimerInterrupt` observes
+/// the command retired, the same `Copy`-on-wake shape `ConnectOutcome::
+/// Delivered`'s waiter uses.
+struct BlockReadWaiter {
+    thread: crate::types::ThreadHandle,
+    buf_ptr: VirtAddr,
+    buffer: [u8; 512],
 }
 
+/// Path prefix that routes `FileOpen`/`FileRead`/`FileWrite`/`FileClose`
+/// through the 9P passthrough instead of the mkfs disk image.
+const HOST_MOUNT_PREFIX: &str = "/host/";
+
 const UART_BASE: u32 = 0x1000_0000;
 const UART_THR_OFFSET: u32 = 0x00;
 const UART_RBR_OFFSET: u32 = 0x00;
 const UART_LSR_OFFSET: u32 = 0x05;
 
 impl Kernel {
-    pub fn new() -> Result<Self, KernelError> {
+    pub fn new(share_dir: Option<std::path::PathBuf>) -> Result<Self, KernelError> {
+        // Reserve a contiguous run of frames for the capability pool up
+        // front, via the same frame allocator page tables use, so retyped
+        // objects never collide with frames handed out elsewhere.
+        let pool_base = memory::alloc_frame()
+            .ok_or_else(|| KernelError::InitializationError("out of physical memory for capability pool".into()))?;
+        for _ in 1..CAPABILITY_POOL_FRAMES {
+            memory::alloc_frame()
+                .ok_or_else(|| KernelError::InitializationError("out of physical memory for capability pool".into()))?;
+        }
+
         Ok(Self {
             thread_manager: ThreadManager::new(),
             mutexes: HashMap::new(),
             next_mutex_id: 1,
+            condvars: HashMap::new(),
+            next_condvar_id: 1,
+            semaphores: HashMap::new(),
+            next_semaphore_id: 1,
+            endpoints: HashMap::new(),
+            next_endpoint_id: 1,
+            servers: HashMap::new(),
+            next_server_id: 1,
             file_system: None,
+            capability_pool: Untyped::new(pool_base, CAPABILITY_POOL_FRAMES),
+            ninep: share_dir.map(fs::ninep::NineTransport::new),
+            pipes: HashMap::new(),
+            pipe_names: HashMap::new(),
+            next_pipe_id: 1,
+            rand_state: 0x9E37_79B9,
+            block_read_waiter: None,
         })
     }
 
+    /// Advances the `rand:` scheme's xorshift32 generator and fills `buf`
+    /// with its output, four bytes at a time.
+    fn fill_random(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(4) {
+            self.rand_state ^= self.rand_state << 13;
+            self.rand_state ^= self.rand_state >> 17;
+            self.rand_state ^= self.rand_state << 5;
+            chunk.copy_from_slice(&self.rand_state.to_le_bytes()[..chunk.len()]);
+        }
+    }
+
     pub fn init_memory(&mut self, memory: &mut dyn Memory) -> Result<u32, KernelError> {
         let satp =
             memory::setup_kernel_address_space(memory).map_err(KernelError::InitializationError)?;
@@ -69,131 +163,22 @@ impl Kernel {
             memory::create_user_address_space(memory).map_err(TrapError::HandlerPanic)?;
         let root_ppn = satp_val & 0x003F_FFFF;
 
-        // 2. Load Segments
-        let mut max_vaddr = 0;
-        for ph in elf.program_headers.iter() {
-            if ph.p_type == elf::program_header::PT_LOAD {
-                let file_start = ph.p_offset as usize;
-                let file_len = ph.p_filesz as usize;
-                let segment_data = &elf_data[file_start..(file_start + file_len)];
-
-                let vaddr_start = ph.p_vaddr as u32;
-                let mem_len = ph.p_memsz as u32;
-
-                let mut current_vaddr = vaddr_start;
-                let end_vaddr = vaddr_start + mem_len;
-
-                if end_vaddr > max_vaddr {
-                    max_vaddr = end_vaddr;
-                }
-
-                while current_vaddr < end_vaddr {
-                    let page_base = current_vaddr & !(memory::PAGE_SIZE - 1);
-                    // Check if already mapped (segment overlap?) or alloc new
-                    let paddr_base = match translate_vaddr(memory, satp_val, page_base) {
-                        Ok(p) => p & !(memory::PAGE_SIZE - 1),
-                        Err(_) => {
-                            let frame = memory::alloc_frame();
-                            let flags =
-                                memory::PTE_R | memory::PTE_W | memory::PTE_U | memory::PTE_X;
-                            memory::map_page(memory, root_ppn, page_base, frame, flags)
-                                .map_err(|e| TrapError::HandlerPanic(e))?;
-                            // Zero fill
-                            for i in 0..memory::PAGE_SIZE {
-                                memory.write_byte(PhysAddr::new(frame + i), 0).unwrap();
-                            }
-                            frame
-                        }
-                    };
-
-                    let page_offset = current_vaddr & (memory::PAGE_SIZE - 1);
-                    let bytes_available_in_page = memory::PAGE_SIZE - page_offset;
-                    let bytes_to_end = end_vaddr - current_vaddr;
-                    let chunk_size = bytes_available_in_page.min(bytes_to_end);
-
-                    let segment_offset = (current_vaddr - vaddr_start) as usize;
-
-                    if segment_offset < file_len {
-                        let data_remaining = file_len - segment_offset;
-                        let copy_size = (chunk_size as usize).min(data_remaining);
-
-                        for i in 0..copy_size {
-                            let b = segment_data[segment_offset + i];
-                            memory
-                                .write_byte(PhysAddr::new(paddr_base + page_offset + i as u32), b)
-                                .map_err(|e| {
-                                    TrapError::HandlerPanic(format!(
-                                        "Bootstrap write error: {:?}",
-                                        e
-                                    ))
-                                })?;
-                        }
-                    }
-                    current_vaddr += chunk_size;
-                }
-            }
-        }
+        // 2. Load Segments (lazily -- reserved now, backed on first fault)
+        let (segments, max_vaddr) = reserve_elf_segments(memory, root_ppn, &elf)?;
 
-        // 3. Setup Stack
+        // 3. Setup Stack (eager top pages + lazy grow-down reservation below)
         let stack_top = 0xF000_0000u32;
-        let stack_pages = 4;
-        for i in 0..stack_pages {
-            let vaddr = stack_top - ((i + 1) * memory::PAGE_SIZE);
-            let frame = memory::alloc_frame();
-            memory::map_page(
-                memory,
-                root_ppn,
-                vaddr,
-                frame,
-                memory::PTE_R | memory::PTE_W | memory::PTE_U,
-            )
-            .map_err(TrapError::HandlerPanic)?;
-        }
+        reserve_user_stack(memory, root_ppn, stack_top)?;
 
-        // 4. Push Arguments
-        let mut current_sp = stack_top;
-
-        // 4a. Push String Data
-        let mut arg_vaddrs = Vec::with_capacity(args.len());
-        for arg in args {
-            let arg_bytes = arg.as_bytes();
-            current_sp -= arg_bytes.len() as u32; // No null terminator needed for slice access, but standard is null-term?
-                                                  // Shell expects &str parts, but Exec passes bytes.
-                                                  // Let's stick to simple copy.
-            let dest = VirtAddr::new(current_sp);
-            copy_to_user(memory, satp_val, arg_bytes, dest)?;
-            arg_vaddrs.push(current_sp);
-        }
-
-        // 4b. Push Argv Array (ptr, len) for Rust-style args
-        let argv_size = (args.len() * 8) as u32;
-        current_sp -= argv_size;
-        current_sp &= !3;
-        let argv_base = current_sp;
-
-        for (i, vaddr) in arg_vaddrs.iter().enumerate() {
-            let len = args[i].len() as u32;
-            let desc_addr = argv_base + (i * 8) as u32;
-
-            // Write ptr
-            let paddr_ptr = translate_vaddr(memory, satp_val, desc_addr)?;
-            memory
-                .write_word(PhysAddr::new(paddr_ptr), *vaddr)
-                .map_err(|e| {
-                    TrapError::HandlerPanic(format!("Bootstrap arg ptr write error: {:?}", e))
-                })?;
-
-            // Write len
-            let paddr_len = translate_vaddr(memory, satp_val, desc_addr + 4)?;
-            memory
-                .write_word(PhysAddr::new(paddr_len), len)
-                .map_err(|e| {
-                    TrapError::HandlerPanic(format!("Bootstrap arg len write error: {:?}", e))
-                })?;
-        }
-
-        // Align Stack
-        current_sp &= !15;
+        // 4. Push argv/envp/auxv onto the stack, SysV-style. There's no
+        // separate executable path here, only `args`, so `argv[0]` (if any)
+        // doubles as `AT_EXECFN` the same way a shell's own argv[0] does.
+        let arg_bytes: Vec<Vec<u8>> = args.iter().map(|a| a.as_bytes().to_vec()).collect();
+        let execfn = args.first().map(|a| a.as_bytes()).unwrap_or(&[]);
+        let current_sp = self.build_initial_stack(
+            memory, satp_val, stack_top, &arg_bytes, execfn, &elf, &segments,
+        )?;
+        let argv_base = current_sp + 4;
 
         // 5. Create Thread
         let entry_point = VirtAddr::new(elf.entry as u32);
@@ -216,6 +201,8 @@ impl Kernel {
             // Align to next page boundary for cleanliness, though not strictly required
             let heap_start = (max_vaddr + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
             tcb.program_break = heap_start;
+            tcb.segments = segments;
+            tcb.image = elf_data.to_vec();
             info!(
                 "Bootstrap: Heap starts at {:#x} (Segment end: {:#x})",
                 heap_start, max_vaddr
@@ -233,6 +220,85 @@ impl Kernel {
         ))
     }
 
+    /// Lay out a SysV-style initial stack below `stack_top`: null-terminated
+    /// `args` strings and `exec_path`, 16 fresh `AT_RANDOM` bytes, then the
+    /// word-sized `argc` / `argv[]` (NULL-terminated) / `envp[]` (just NULL,
+    /// no environment exists yet) / auxv `(type, value)` pairs (terminated by
+    /// `AT_NULL`) region a libc's `_start` expects, replacing the old
+    /// `{ptr, len}` descriptor array `bootstrap_process` and `Syscall::Exec`
+    /// used to build independently. Returns the address of `argc`, which
+    /// doubles as the stack pointer the new thread starts with.
+    fn build_initial_stack(
+        &mut self,
+        memory: &mut dyn Memory,
+        satp_val: u32,
+        stack_top: u32,
+        args: &[Vec<u8>],
+        exec_path: &[u8],
+        elf: &elf::Elf,
+        segments: &[memory::Segment],
+    ) -> Result<u32, TrapError> {
+        let mut sp = stack_top;
+
+        let mut arg_vaddrs = Vec::with_capacity(args.len());
+        for arg in args {
+            sp -= arg.len() as u32 + 1;
+            copy_to_user(memory, satp_val, arg, VirtAddr::new(sp))?;
+            copy_to_user(memory, satp_val, &[0u8], VirtAddr::new(sp + arg.len() as u32))?;
+            arg_vaddrs.push(sp);
+        }
+
+        sp -= exec_path.len() as u32 + 1;
+        let execfn_vaddr = sp;
+        copy_to_user(memory, satp_val, exec_path, VirtAddr::new(sp))?;
+        copy_to_user(
+            memory,
+            satp_val,
+            &[0u8],
+            VirtAddr::new(sp + exec_path.len() as u32),
+        )?;
+
+        sp -= 16;
+        let random_vaddr = sp;
+        let mut random_bytes = [0u8; 16];
+        self.fill_random(&mut random_bytes);
+        copy_to_user(memory, satp_val, &random_bytes, VirtAddr::new(sp))?;
+
+        let auxv: [(u32, u32); 7] = [
+            (AT_PHDR, phdr_vaddr(elf, segments)),
+            (AT_PHENT, elf.header.e_phentsize as u32),
+            (AT_PHNUM, elf.header.e_phnum as u32),
+            (AT_PAGESZ, memory::PAGE_SIZE),
+            (AT_ENTRY, elf.entry as u32),
+            (AT_RANDOM, random_vaddr),
+            (AT_EXECFN, execfn_vaddr),
+        ];
+
+        let mut words = Vec::with_capacity(1 + args.len() + 1 + 1 + auxv.len() * 2 + 2);
+        words.push(args.len() as u32); // argc
+        words.extend(arg_vaddrs.iter().copied()); // argv[]
+        words.push(0); // argv NULL terminator
+        words.push(0); // envp: just the NULL terminator
+        for &(ty, value) in &auxv {
+            words.push(ty);
+            words.push(value);
+        }
+        words.push(AT_NULL);
+        words.push(0);
+
+        let region_bytes = (words.len() * 4) as u32;
+        sp -= region_bytes;
+        sp &= !15; // argc itself must sit on a 16-byte boundary
+
+        let mut bytes = Vec::with_capacity(words.len() * 4);
+        for word in &words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        copy_to_user(memory, satp_val, &bytes, VirtAddr::new(sp))?;
+
+        Ok(sp)
+    }
+
     pub fn handle_syscall(
         &mut self,
         cpu: &mut Cpu,
@@ -263,18 +329,7 @@ impl Kernel {
             } => {
                 let mut buf = vec![0u8; len];
                 copy_from_user(memory, satp, buf_ptr, &mut buf)?;
-
-                for byte in buf {
-                    // Driver: Write to UART
-                    memory
-                        .write_word(
-                            ferrous_vm::PhysAddr::new(UART_BASE + UART_THR_OFFSET),
-                            byte as u32,
-                        )
-                        .map_err(|e| {
-                            TrapError::HandlerPanic(format!("UART write error: {:?}", e))
-                        })?;
-                }
+                uart_write(memory, &buf)?;
 
                 syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
                 Ok(VirtAddr::new(cpu.pc + 4))
@@ -284,56 +339,8 @@ impl Kernel {
                 buf_ptr,
                 len,
             } => {
-                if len == 0 {
-                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Value(0)), cpu);
-                    return Ok(VirtAddr::new(cpu.pc + 4));
-                }
-
-                let mut read_buf = Vec::new();
+                let read_buf = uart_read(memory, len)?;
 
-                // 1. Blocking read for the first byte
-                // Accessing memory at UART_BASE triggers the device read
-                let val = memory
-                    .read_word(ferrous_vm::PhysAddr::new(UART_BASE + UART_RBR_OFFSET))
-                    .map_err(|e| TrapError::HandlerPanic(format!("UART read error: {:?}", e)))?;
-
-                if val == 0 {
-                    // EOF on first byte
-                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Value(0)), cpu);
-                    return Ok(VirtAddr::new(cpu.pc + 4));
-                }
-                read_buf.push(val as u8);
-
-                // 2. Non-blocking read for subsequent bytes
-                let limit = len.min(1024);
-                while read_buf.len() < limit {
-                    let lsr = memory
-                        .read_word(ferrous_vm::PhysAddr::new(UART_BASE + UART_LSR_OFFSET))
-                        .map_err(|e| {
-                            TrapError::HandlerPanic(format!("UART LSR read error: {:?}", e))
-                        })?;
-
-                    if (lsr & 0x01) == 0 {
-                        break; // No more data
-                    }
-
-                    let val = memory
-                        .read_word(ferrous_vm::PhysAddr::new(UART_BASE + UART_RBR_OFFSET))
-                        .map_err(|e| {
-                            TrapError::HandlerPanic(format!("UART read error: {:?}", e))
-                        })?;
-
-                    if val == 0 {
-                        break; // EOF
-                    }
-                    read_buf.push(val as u8);
-
-                    if val == 10 || val == 13 {
-                        break; // Newline
-                    }
-                }
-
-                // 3. Copy to user
                 let current_handle = self
                     .thread_manager
                     .current_thread
@@ -357,7 +364,14 @@ impl Kernel {
             }
             syscall::Syscall::Exit { code } => {
                 info!("Thread/Process Exit: {}", code);
-                self.thread_manager.exit_current_thread(code);
+                if let Some(current) = self.thread_manager.current_thread {
+                    self.remove_endpoint_waiter(current);
+                    self.remove_server_waiter(current);
+                }
+                if let Some(root_ppn) = self.thread_manager.exit_current_thread(code) {
+                    memory::release_address_space(memory, root_ppn)
+                        .map_err(TrapError::HandlerPanic)?;
+                }
                 self.thread_manager.yield_thread(cpu);
 
                 if self.thread_manager.current_thread.is_none() {
@@ -391,15 +405,51 @@ impl Kernel {
                 self.thread_manager.yield_thread(cpu);
                 Ok(VirtAddr::new(cpu.pc))
             }
+            syscall::Syscall::ThreadStats { handle } => {
+                let ticks = crate::types::ThreadHandle::new(handle)
+                    .and_then(|h| self.thread_manager.threads.get(&h))
+                    .map(|tcb| tcb.cpu_ticks_total)
+                    .unwrap_or(0);
+                debug!("ThreadStats: handle={}, cpu_ticks_total={}", handle, ticks);
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Value(ticks as i64)),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
             syscall::Syscall::MutexCreate => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "MutexCreate called without current thread".into(),
+                        ))?;
+
                 let id = self.next_mutex_id;
                 self.next_mutex_id += 1;
                 let mutex = Mutex::new(id);
                 self.mutexes.insert(id, mutex);
-                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Handle(id)), cpu);
+
+                self.capability_pool
+                    .debit(ObjectType::Mutex)
+                    .map_err(|e| TrapError::HandlerPanic(e.into()))?;
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .ok_or(TrapError::HandlerPanic("MutexCreate: unknown thread".into()))?;
+                let slot = tcb
+                    .capabilities
+                    .grant(CapObject::Mutex(id), CapRights::READ | CapRights::WRITE);
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(slot.val())),
+                    cpu,
+                );
                 Ok(VirtAddr::new(cpu.pc + 4))
             }
-            syscall::Syscall::MutexAcquire { id } => {
+            syscall::Syscall::MutexAcquire { slot } => {
                 let current_handle =
                     self.thread_manager
                         .current_thread
@@ -407,13 +457,27 @@ impl Kernel {
                             "MutexAcquire called without current thread".into(),
                         ))?;
 
-                if let Some(mutex) = self.mutexes.get_mut(&id) {
-                    if mutex.owner.is_none() {
-                        mutex.owner = Some(current_handle);
+                let id = self.resolve_mutex_slot(current_handle, slot, CapRights::WRITE)?;
+
+                if let Some(id) = id {
+                    let owner = self.mutexes.get(&id).unwrap().owner;
+                    if owner.is_none() {
+                        self.mutexes.get_mut(&id).unwrap().owner = Some(current_handle);
+                        if let Some(tcb) = self.thread_manager.threads.get_mut(&current_handle) {
+                            tcb.held_mutexes.push(id);
+                        }
                         syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
                         Ok(VirtAddr::new(cpu.pc + 4))
                     } else {
-                        mutex.wait_queue.push_back(current_handle);
+                        // Contended: boost the owner (and whatever it's in
+                        // turn blocked on) up to our own priority so it
+                        // can't be starved by lower-priority threads while
+                        // we wait on it -- classic priority inheritance.
+                        let waiter_level = self.thread_manager.scheduler.priority_level(current_handle);
+                        if let Some(owner) = owner {
+                            self.propagate_priority_boost(owner, waiter_level);
+                        }
+                        self.mutexes.get_mut(&id).unwrap().wait_queue.push_back(current_handle);
                         self.thread_manager.block_current_thread();
                         syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
                         cpu.pc += 4;
@@ -428,7 +492,7 @@ impl Kernel {
                     Ok(VirtAddr::new(cpu.pc + 4))
                 }
             }
-            syscall::Syscall::MutexRelease { id } => {
+            syscall::Syscall::MutexRelease { slot } => {
                 let current_handle =
                     self.thread_manager
                         .current_thread
@@ -436,13 +500,15 @@ impl Kernel {
                             "MutexRelease called without current thread".into(),
                         ))?;
 
-                if let Some(mutex) = self.mutexes.get_mut(&id) {
-                    if mutex.owner == Some(current_handle) {
-                        mutex.owner = None;
-                        if let Some(next_owner) = mutex.wait_queue.pop_front() {
-                            mutex.owner = Some(next_owner);
-                            self.thread_manager.wake_thread(next_owner);
-                        }
+                let id = self.resolve_mutex_slot(current_handle, slot, CapRights::WRITE)?;
+
+                if let Some(mutex_id) = id {
+                    let is_owner = self
+                        .mutexes
+                        .get(&mutex_id)
+                        .is_some_and(|m| m.owner == Some(current_handle));
+                    if is_owner {
+                        self.release_mutex(mutex_id, current_handle);
                         syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
                     } else {
                         syscall::Syscall::encode_result(
@@ -458,193 +524,1979 @@ impl Kernel {
                 }
                 Ok(VirtAddr::new(cpu.pc + 4))
             }
-            syscall::Syscall::Sbrk { increment } => {
+            syscall::Syscall::MutexTryAcquire { slot } => {
                 let current_handle =
                     self.thread_manager
                         .current_thread
                         .ok_or(TrapError::HandlerPanic(
-                            "Sbrk called without current thread".into(),
+                            "MutexTryAcquire called without current thread".into(),
                         ))?;
 
-                // Get current program break
-                let mut current_break = 0;
-                let mut root_ppn = 0;
-
-                if let Some(tcb) = self.thread_manager.threads.get(&current_handle) {
-                    current_break = tcb.program_break;
-                    root_ppn = tcb.context.satp & 0x003F_FFFF; // Extract PPN from SATP
-                }
+                let id = self.resolve_mutex_slot(current_handle, slot, CapRights::WRITE)?;
 
-                if increment == 0 {
+                if let Some(id) = id {
+                    let owner = self.mutexes.get(&id).unwrap().owner;
+                    if owner.is_none() {
+                        self.mutexes.get_mut(&id).unwrap().owner = Some(current_handle);
+                        if let Some(tcb) = self.thread_manager.threads.get_mut(&current_handle) {
+                            tcb.held_mutexes.push(id);
+                        }
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Value(1)), cpu);
+                    } else {
+                        // Already held, by us or anyone else -- never blocks,
+                        // unlike `MutexAcquire`.
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Value(0)), cpu);
+                    }
+                } else {
                     syscall::Syscall::encode_result(
-                        Ok(syscall::SyscallReturn::Value(current_break as i64)),
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
                         cpu,
                     );
-                    return Ok(VirtAddr::new(cpu.pc + 4));
                 }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::CondvarCreate => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "CondvarCreate called without current thread".into(),
+                        ))?;
 
-                let new_break = (current_break as i32 + increment) as u32;
-
-                // Align to page boundary for mapping check
-                let old_page_end =
-                    (current_break + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
-                let new_page_end = (new_break + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
-
-                if increment > 0 {
-                    // Growing
-                    if new_page_end > old_page_end {
-                        // Need to allocate new pages
-                        let start_page = old_page_end;
-                        let end_page = new_page_end;
-                        let mut page_addr = start_page;
-
-                        debug!("Sbrk: Allocating {} bytes. Old break: {:#x}. Mapping pages from {:#x} to {:#x}", increment, current_break, start_page, end_page);
-
-                        while page_addr < end_page {
-                            // Alloc frame
-                            let frame = memory::alloc_frame();
-                            // Map
-                            memory::map_page(
-                                memory,
-                                root_ppn,
-                                page_addr,
-                                frame,
-                                memory::PTE_R | memory::PTE_W | memory::PTE_U, // User RW
-                            )
-                            .map_err(TrapError::HandlerPanic)?;
+                let id = self.next_condvar_id;
+                self.next_condvar_id += 1;
+                self.condvars.insert(id, Condvar::new(id));
 
-                            page_addr += memory::PAGE_SIZE;
-                        }
-                    }
-                } else {
-                    // Shrinking (Not implemented yet for safety/simplicity, just update break)
-                }
+                self.capability_pool
+                    .debit(ObjectType::Condvar)
+                    .map_err(|e| TrapError::HandlerPanic(e.into()))?;
 
-                // Update TCB
-                if let Some(tcb) = self.thread_manager.threads.get_mut(&current_handle) {
-                    tcb.program_break = new_break;
-                }
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .ok_or(TrapError::HandlerPanic("CondvarCreate: unknown thread".into()))?;
+                let slot = tcb
+                    .capabilities
+                    .grant(CapObject::Condvar(id), CapRights::READ | CapRights::WRITE);
 
                 syscall::Syscall::encode_result(
-                    Ok(syscall::SyscallReturn::Value(current_break as i64)),
+                    Ok(syscall::SyscallReturn::Handle(slot.val())),
                     cpu,
                 );
                 Ok(VirtAddr::new(cpu.pc + 4))
             }
-            syscall::Syscall::BlockRead { sector, buf_ptr } => {
-                let mut buffer = [0u8; 512];
-                match crate::fs::block::read_sector(memory, sector, &mut buffer) {
-                    Ok(_) => {
-                        let current_handle = self
-                            .thread_manager
-                            .current_thread
-                            .ok_or(TrapError::HandlerPanic("No current thread".into()))?;
-                        let satp = self
-                            .thread_manager
-                            .threads
-                            .get(&current_handle)
-                            .unwrap()
-                            .context
-                            .satp;
+            syscall::Syscall::CondvarWait {
+                condvar_slot,
+                mutex_slot,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "CondvarWait called without current thread".into(),
+                        ))?;
 
-                        copy_to_user(memory, satp, &buffer, buf_ptr)?;
-                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                let condvar_id = self.resolve_condvar_slot(current_handle, condvar_slot, CapRights::WRITE)?;
+                let mutex_id = self.resolve_mutex_slot(current_handle, mutex_slot, CapRights::WRITE)?;
+
+                match (condvar_id, mutex_id) {
+                    (Some(condvar_id), Some(mutex_id)) => {
+                        let is_owner = self
+                            .mutexes
+                            .get(&mutex_id)
+                            .is_some_and(|m| m.owner == Some(current_handle));
+                        if is_owner {
+                            // Release and enqueue under the same syscall --
+                            // the caller never runs between the two, so a
+                            // concurrent `CondvarNotify*` can't land in a gap
+                            // that doesn't exist.
+                            self.release_mutex(mutex_id, current_handle);
+                            self.condvars
+                                .get_mut(&condvar_id)
+                                .unwrap()
+                                .wait_queue
+                                .push_back((current_handle, mutex_id));
+                            self.thread_manager.block_current_thread();
+                            syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                            cpu.pc += 4;
+                            self.thread_manager.yield_thread(cpu);
+                            Ok(VirtAddr::new(cpu.pc))
+                        } else {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                            Ok(VirtAddr::new(cpu.pc + 4))
+                        }
                     }
-                    Err(_) => {
+                    _ => {
                         syscall::Syscall::encode_result(
                             Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
                             cpu,
                         );
+                        Ok(VirtAddr::new(cpu.pc + 4))
                     }
                 }
-                Ok(VirtAddr::new(cpu.pc + 4))
             }
-            syscall::Syscall::FileOpen { path_ptr, path_len } => {
+            syscall::Syscall::CondvarNotifyOne { slot } => {
                 let current_handle =
                     self.thread_manager
                         .current_thread
                         .ok_or(TrapError::HandlerPanic(
-                            "FileOpen: No current thread".into(),
+                            "CondvarNotifyOne called without current thread".into(),
                         ))?;
 
-                let satp = self
-                    .thread_manager
-                    .threads
-                    .get(&current_handle)
-                    .unwrap()
-                    .context
-                    .satp;
-
-                let mut path_bytes = vec![0u8; path_len];
-                copy_from_user(memory, satp, path_ptr, &mut path_bytes)?;
-
-                let path_str = String::from_utf8(path_bytes)
-                    .map_err(|_| TrapError::HandlerPanic("Invalid UTF-8 path".into()))?;
-
-                let inode_id = if let Some(fs) = &self.file_system {
-                    fs.find_inode(memory, &path_str)
-                        .map_err(|_| crate::error::SyscallError::InvalidSyscallNumber(0))
-                } else {
-                    Err(crate::error::SyscallError::InvalidSyscallNumber(0))
-                };
-
-                match inode_id {
-                    Ok(id) => {
-                        let tcb = self
-                            .thread_manager
-                            .threads
-                            .get_mut(&current_handle)
-                            .unwrap();
-                        // Find free FD
-                        let fd_idx = tcb.file_descriptors.len();
-                        tcb.file_descriptors.push(Some(FileDescriptor {
-                            inode_id: id,
-                            offset: 0,
-                            flags: 0,
-                        }));
-                        syscall::Syscall::encode_result(
-                            Ok(syscall::SyscallReturn::Handle(fd_idx as u32)),
-                            cpu,
-                        );
-                    }
-                    Err(_) => {
-                        syscall::Syscall::encode_result(
-                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
-                            cpu,
-                        );
+                let id = self.resolve_condvar_slot(current_handle, slot, CapRights::WRITE)?;
+                if let Some(id) = id {
+                    let waiter = self
+                        .condvars
+                        .get_mut(&id)
+                        .and_then(|c| c.wait_queue.pop_front());
+                    if let Some((thread, mutex_id)) = waiter {
+                        self.acquire_or_enqueue_mutex(thread, mutex_id);
                     }
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
                 }
                 Ok(VirtAddr::new(cpu.pc + 4))
             }
-            syscall::Syscall::WaitPid { pid } => {
-                let target = crate::types::ThreadHandle::new(pid)
-                    .ok_or(TrapError::HandlerPanic("Invalid pid 0".into()))?;
+            syscall::Syscall::CondvarNotifyAll { slot } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "CondvarNotifyAll called without current thread".into(),
+                        ))?;
 
-                match self.thread_manager.wait_current_thread(target) {
-                    Ok(Some(exit_code)) => {
-                        // Already terminated
-                        syscall::Syscall::encode_result(
-                            Ok(syscall::SyscallReturn::Value(exit_code as i64)),
-                            cpu,
-                        );
-                        Ok(VirtAddr::new(cpu.pc + 4))
+                let id = self.resolve_condvar_slot(current_handle, slot, CapRights::WRITE)?;
+                if let Some(id) = id {
+                    let waiters = self
+                        .condvars
+                        .get_mut(&id)
+                        .map(|c| core::mem::take(&mut c.wait_queue))
+                        .unwrap_or_default();
+                    for (thread, mutex_id) in waiters {
+                        self.acquire_or_enqueue_mutex(thread, mutex_id);
                     }
-                    Ok(None) => {
-                        // Blocked. Return placeholder (will be overwritten by waker)
-                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
-                        cpu.pc += 4;
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::SemCreate { initial } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "SemCreate called without current thread".into(),
+                        ))?;
+
+                let id = self.next_semaphore_id;
+                self.next_semaphore_id += 1;
+                self.semaphores.insert(id, Semaphore::new(id, initial));
+
+                self.capability_pool
+                    .debit(ObjectType::Semaphore)
+                    .map_err(|e| TrapError::HandlerPanic(e.into()))?;
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .ok_or(TrapError::HandlerPanic("SemCreate: unknown thread".into()))?;
+                let slot = tcb
+                    .capabilities
+                    .grant(CapObject::Semaphore(id), CapRights::READ | CapRights::WRITE);
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(slot.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::SemWait { slot } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "SemWait called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_semaphore_slot(current_handle, slot, CapRights::WRITE)?;
+
+                if let Some(id) = id {
+                    let sem = self.semaphores.get_mut(&id).unwrap();
+                    sem.count -= 1;
+                    if sem.count >= 0 {
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                        Ok(VirtAddr::new(cpu.pc + 4))
+                    } else {
+                        sem.wait_queue.push_back(current_handle);
+                        self.thread_manager.block_current_thread();
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                        cpu.pc += 4;
+                        self.thread_manager.yield_thread(cpu);
+                        Ok(VirtAddr::new(cpu.pc))
+                    }
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    Ok(VirtAddr::new(cpu.pc + 4))
+                }
+            }
+            syscall::Syscall::SemPost { slot } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "SemPost called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_semaphore_slot(current_handle, slot, CapRights::WRITE)?;
+
+                if let Some(id) = id {
+                    let sem = self.semaphores.get_mut(&id).unwrap();
+                    sem.count += 1;
+                    let waiter = if sem.count <= 0 {
+                        sem.wait_queue.pop_front()
+                    } else {
+                        None
+                    };
+                    if let Some(waiter) = waiter {
+                        self.thread_manager.wake_thread(waiter);
+                    }
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::SemDestroy { slot } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "SemDestroy called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_semaphore_slot(current_handle, slot, CapRights::WRITE)?;
+
+                if let Some(id) = id {
+                    if let Some(sem) = self.semaphores.remove(&id) {
+                        for waiter in sem.wait_queue {
+                            if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter) {
+                                tcb.context.regs[10] = u32::MAX;
+                            }
+                            self.thread_manager.wake_thread(waiter);
+                        }
+                    }
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::EndpointCreate => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "EndpointCreate called without current thread".into(),
+                        ))?;
+
+                let id = self.next_endpoint_id;
+                self.next_endpoint_id += 1;
+                self.endpoints.insert(id, Endpoint::new(id));
+
+                self.capability_pool
+                    .debit(ObjectType::Endpoint)
+                    .map_err(|e| TrapError::HandlerPanic(e.into()))?;
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .ok_or(TrapError::HandlerPanic("EndpointCreate: unknown thread".into()))?;
+                let slot = tcb
+                    .capabilities
+                    .grant(CapObject::Endpoint(id), CapRights::READ | CapRights::WRITE);
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(slot.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::EndpointSend {
+                ep,
+                buf_ptr,
+                len,
+                transfer_fd,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "EndpointSend called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_endpoint_slot(current_handle, ep, CapRights::WRITE)?;
+                let Some(id) = id else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                // Snapshot the descriptor `transfer_fd` names (if any) up
+                // front, the same as `Dup` would -- the rendezvous hands
+                // the *receiver* a duplicate of it, not a reference back
+                // into the sender's own table.
+                let transfer = if transfer_fd == sync::NO_TRANSFER_FD {
+                    None
+                } else {
+                    let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                    match tcb.file_descriptors.get(transfer_fd as usize).copied().flatten() {
+                        Some(desc) => Some(desc),
+                        None => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidArgument),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        }
+                    }
+                };
+
+                let len = len.min(ENDPOINT_MAX_MSG);
+                let mut message = vec![0u8; len];
+                copy_from_user(memory, satp, buf_ptr, &mut message)?;
+
+                let endpoint = self
+                    .endpoints
+                    .get_mut(&id)
+                    .ok_or(TrapError::HandlerPanic("EndpointSend: unknown endpoint".into()))?;
+
+                if let Some(waiter) = endpoint.waiting_receivers.pop_front() {
+                    // A receiver is already parked in Recv: deliver the
+                    // message and the duplicated capability atomically,
+                    // then wake it -- so the sender never has to block.
+                    let copy_len = len.min(waiter.cap);
+                    let receiver_satp = self
+                        .thread_manager
+                        .threads
+                        .get(&waiter.thread)
+                        .map(|tcb| tcb.context.satp)
+                        .unwrap_or(0);
+                    copy_to_user(memory, receiver_satp, &message[..copy_len], waiter.buf_ptr)?;
+
+                    let new_fd = match transfer {
+                        Some(desc) => {
+                            let receiver_tcb =
+                                self.thread_manager.threads.get_mut(&waiter.thread).unwrap();
+                            alloc_fd_slot(&mut receiver_tcb.file_descriptors, desc)
+                                .map(|idx| idx as u32)
+                                .unwrap_or(sync::NO_TRANSFER_FD)
+                        }
+                        None => sync::NO_TRANSFER_FD,
+                    };
+                    copy_to_user(
+                        memory,
+                        receiver_satp,
+                        &new_fd.to_le_bytes(),
+                        waiter.cap_out_ptr,
+                    )?;
+
+                    if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                        tcb.context.regs[10] = copy_len as u32;
+                    }
+                    self.thread_manager.wake_thread(waiter.thread);
+
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    Ok(VirtAddr::new(cpu.pc + 4))
+                } else {
+                    // Nobody's receiving yet: queue the message (and the
+                    // snapshotted capability) and block until some later
+                    // Recv drains it and wakes us.
+                    endpoint
+                        .pending_sends
+                        .push_back((current_handle, message, transfer));
+                    self.thread_manager.block_current_thread();
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    cpu.pc += 4;
+                    self.thread_manager.yield_thread(cpu);
+                    Ok(VirtAddr::new(cpu.pc))
+                }
+            }
+            syscall::Syscall::EndpointRecv {
+                ep,
+                buf_ptr,
+                cap,
+                cap_out_ptr,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "EndpointRecv called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_endpoint_slot(current_handle, ep, CapRights::READ)?;
+                let Some(id) = id else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let endpoint = self
+                    .endpoints
+                    .get_mut(&id)
+                    .ok_or(TrapError::HandlerPanic("EndpointRecv: unknown endpoint".into()))?;
+
+                if let Some((sender, message, transfer)) = endpoint.pending_sends.pop_front() {
+                    let copy_len = message.len().min(cap);
+                    copy_to_user(memory, satp, &message[..copy_len], buf_ptr)?;
+
+                    let tcb = self
+                        .thread_manager
+                        .threads
+                        .get_mut(&current_handle)
+                        .unwrap();
+                    let new_fd = match transfer {
+                        Some(desc) => alloc_fd_slot(&mut tcb.file_descriptors, desc)
+                            .map(|idx| idx as u32)
+                            .unwrap_or(sync::NO_TRANSFER_FD),
+                        None => sync::NO_TRANSFER_FD,
+                    };
+                    copy_to_user(memory, satp, &new_fd.to_le_bytes(), cap_out_ptr)?;
+
+                    self.thread_manager.wake_thread(sender);
+
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(copy_len as i64)),
+                        cpu,
+                    );
+                    Ok(VirtAddr::new(cpu.pc + 4))
+                } else {
+                    // No message queued: park ourselves so the next Send
+                    // that finds us delivers straight into buf_ptr.
+                    endpoint.waiting_receivers.push_back(EndpointWaiter {
+                        thread: current_handle,
+                        buf_ptr,
+                        cap,
+                        cap_out_ptr,
+                    });
+                    self.thread_manager.block_current_thread();
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    cpu.pc += 4;
+                    self.thread_manager.yield_thread(cpu);
+                    Ok(VirtAddr::new(cpu.pc))
+                }
+            }
+            syscall::Syscall::CreateServer => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "CreateServer called without current thread".into(),
+                        ))?;
+
+                let id = self.next_server_id;
+                self.next_server_id += 1;
+                self.servers.insert(id, Server::new(id));
+
+                self.capability_pool
+                    .debit(ObjectType::Server)
+                    .map_err(|e| TrapError::HandlerPanic(e.into()))?;
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .ok_or(TrapError::HandlerPanic("CreateServer: unknown thread".into()))?;
+                let slot = tcb
+                    .capabilities
+                    .grant(CapObject::Server(id), CapRights::READ | CapRights::WRITE);
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(slot.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Connect { server_id } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "Connect called without current thread".into(),
+                        ))?;
+
+                // `server_id` is the creator's own capability slot, not the
+                // raw id, the same indirection `resolve_server_slot` checks
+                // everywhere else -- a thread can only `Connect` to a server
+                // it holds a READ|WRITE capability over (what `CreateServer`
+                // grants; a `Connect`ed connection carries WRITE only).
+                let id = self.resolve_server_slot(
+                    current_handle,
+                    server_id,
+                    CapRights::READ | CapRights::WRITE,
+                )?;
+                let Some(id) = id else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .ok_or(TrapError::HandlerPanic("Connect: unknown thread".into()))?;
+                // A connection can only send: `ReceiveMessage`/`ReturnMemory`
+                // stay the server creator's privilege.
+                let slot = tcb
+                    .capabilities
+                    .grant(CapObject::Server(id), CapRights::WRITE);
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(slot.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::SendMessage {
+                conn,
+                opcode,
+                buf_ptr,
+                len,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "SendMessage called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_server_slot(current_handle, conn, CapRights::WRITE)?;
+                let Some(id) = id else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let len = len.min(SERVER_MAX_MSG);
+                let mut data = vec![0u8; len];
+                copy_from_user(memory, satp, buf_ptr, &mut data)?;
+
+                let server = self
+                    .servers
+                    .get_mut(&id)
+                    .ok_or(TrapError::HandlerPanic("SendMessage: unknown server".into()))?;
+
+                if let Some(waiter) = server.waiting_receivers.pop_front() {
+                    let copy_len = len.min(waiter.cap);
+                    let receiver_satp = self
+                        .thread_manager
+                        .threads
+                        .get(&waiter.thread)
+                        .map(|tcb| tcb.context.satp)
+                        .unwrap_or(0);
+                    copy_to_user(memory, receiver_satp, &data[..copy_len], waiter.buf_ptr)?;
+                    copy_to_user(
+                        memory,
+                        receiver_satp,
+                        &message_meta(opcode, current_handle),
+                        waiter.meta_ptr,
+                    )?;
+                    if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                        tcb.context.regs[10] = copy_len as u32;
+                    }
+                    self.thread_manager.wake_thread(waiter.thread);
+                } else {
+                    server.pending.push_back(Message {
+                        sender: current_handle,
+                        opcode,
+                        data,
+                    });
+                }
+
+                // `SendMessage` always blocks, delivered or not -- it's a
+                // request that only completes when `ReturnMemory` replies.
+                server.awaiting_reply.push_back(current_handle);
+                self.thread_manager.block_current_thread();
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                cpu.pc += 4;
+                self.thread_manager.yield_thread(cpu);
+                Ok(VirtAddr::new(cpu.pc))
+            }
+            syscall::Syscall::ReceiveMessage {
+                server_id,
+                buf_ptr,
+                len,
+                meta_ptr,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "ReceiveMessage called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_server_slot(current_handle, server_id, CapRights::READ)?;
+                let Some(id) = id else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let server = self
+                    .servers
+                    .get_mut(&id)
+                    .ok_or(TrapError::HandlerPanic("ReceiveMessage: unknown server".into()))?;
+
+                if let Some(message) = server.pending.pop_front() {
+                    let copy_len = message.data.len().min(len);
+                    copy_to_user(memory, satp, &message.data[..copy_len], buf_ptr)?;
+                    copy_to_user(
+                        memory,
+                        satp,
+                        &message_meta(message.opcode, message.sender),
+                        meta_ptr,
+                    )?;
+
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(copy_len as i64)),
+                        cpu,
+                    );
+                    Ok(VirtAddr::new(cpu.pc + 4))
+                } else {
+                    server.waiting_receivers.push_back(ServerWaiter {
+                        thread: current_handle,
+                        buf_ptr,
+                        cap: len,
+                        meta_ptr,
+                    });
+                    self.thread_manager.block_current_thread();
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    cpu.pc += 4;
+                    self.thread_manager.yield_thread(cpu);
+                    Ok(VirtAddr::new(cpu.pc))
+                }
+            }
+            syscall::Syscall::ReturnMemory { conn } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "ReturnMemory called without current thread".into(),
+                        ))?;
+
+                let id = self.resolve_server_slot(current_handle, conn, CapRights::READ)?;
+                let Some(id) = id else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let server = self
+                    .servers
+                    .get_mut(&id)
+                    .ok_or(TrapError::HandlerPanic("ReturnMemory: unknown server".into()))?;
+
+                let Some(sender) = server.awaiting_reply.pop_front() else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                if let Some(tcb) = self.thread_manager.threads.get_mut(&sender) {
+                    tcb.context.regs[10] = 0;
+                }
+                self.thread_manager.wake_thread(sender);
+
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Sbrk { increment } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "Sbrk called without current thread".into(),
+                        ))?;
+
+                // Get current program break
+                let mut current_break = 0;
+                let mut root_ppn = 0;
+
+                if let Some(tcb) = self.thread_manager.threads.get(&current_handle) {
+                    current_break = tcb.program_break;
+                    root_ppn = tcb.context.satp & 0x003F_FFFF; // Extract PPN from SATP
+                }
+
+                if increment == 0 {
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(current_break as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let new_break = (current_break as i32 + increment) as u32;
+
+                // Align to page boundary for mapping check
+                let old_page_end =
+                    (current_break + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+                let new_page_end = (new_break + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+
+                if increment > 0 {
+                    // Growing
+                    if new_page_end > old_page_end {
+                        // Reserve the new pages as demand-paged rather than
+                        // allocating and mapping a frame for each of them
+                        // up front: most heap growth is never fully
+                        // touched, so the LoadPageFault/StorePageFault
+                        // handler backing each page with a real frame on
+                        // first access is the same lazy-mapping path mmap
+                        // already uses for anonymous regions.
+                        let start_page = old_page_end;
+                        let end_page = new_page_end;
+                        let mut page_addr = start_page;
+
+                        debug!("Sbrk: Reserving {} bytes. Old break: {:#x}. Lazily mapping pages from {:#x} to {:#x}", increment, current_break, start_page, end_page);
+
+                        while page_addr < end_page {
+                            memory::reserve_lazy_page(
+                                memory,
+                                root_ppn,
+                                page_addr,
+                                memory::PTE_R | memory::PTE_W | memory::PTE_U, // User RW
+                            )
+                            .map_err(TrapError::HandlerPanic)?;
+
+                            page_addr += memory::PAGE_SIZE;
+                        }
+                    }
+                } else if new_page_end < old_page_end {
+                    // Shrinking past at least one whole page: clear and
+                    // free every page the new break no longer covers.
+                    let mut page_addr = new_page_end;
+                    while page_addr < old_page_end {
+                        if let Some(frame) =
+                            memory::unmap_page(memory, root_ppn, page_addr)
+                                .map_err(TrapError::HandlerPanic)?
+                        {
+                            memory::release_frame(frame);
+                        }
+                        page_addr += memory::PAGE_SIZE;
+                    }
+                }
+
+                // Update TCB
+                if let Some(tcb) = self.thread_manager.threads.get_mut(&current_handle) {
+                    tcb.program_break = new_break;
+                }
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Value(current_break as i64)),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Mmap {
+                addr,
+                len,
+                prot,
+                flags,
+            } => {
+                const MAP_ANONYMOUS: u32 = 0x20;
+                const PROT_READ: u32 = 0x1;
+                const PROT_WRITE: u32 = 0x2;
+                const PROT_EXEC: u32 = 0x4;
+
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "Mmap called without current thread".into(),
+                        ))?;
+
+                let page_len = (len as u32 + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+
+                // Anonymous mappings are the only kind a device/file-less
+                // heap-style `mmap` can actually serve; anything else is
+                // rejected instead of silently handing back garbage.
+                if page_len == 0 || flags & MAP_ANONYMOUS == 0 {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidArgument),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let mut prot_flags = memory::PTE_U;
+                if prot & PROT_READ != 0 {
+                    prot_flags |= memory::PTE_R;
+                }
+                if prot & PROT_WRITE != 0 {
+                    prot_flags |= memory::PTE_W;
+                }
+                if prot & PROT_EXEC != 0 {
+                    prot_flags |= memory::PTE_X;
+                }
+
+                let (root_ppn, base) = {
+                    let tcb = self
+                        .thread_manager
+                        .threads
+                        .get(&current_handle)
+                        .ok_or(TrapError::HandlerPanic("Mmap: unknown thread".into()))?;
+                    let root_ppn = tcb.context.satp & 0x003F_FFFF;
+                    let base = if addr != 0 {
+                        addr & !(memory::PAGE_SIZE - 1)
+                    } else {
+                        tcb.mmap_top
+                    };
+
+                    // An explicit hint can land on a range this thread
+                    // already mapped; an unhinted request never can, since
+                    // `mmap_top` only ever bumps past the end of the last
+                    // one handed out.
+                    if addr != 0 && tcb.mappings.iter().any(|vma| vma.overlaps(base, page_len)) {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
+                    }
+
+                    (root_ppn, base)
+                };
+                let end = base + page_len;
+
+                let mut page_addr = base;
+                while page_addr < end {
+                    memory::reserve_lazy_page(memory, root_ppn, page_addr, prot_flags)
+                        .map_err(TrapError::HandlerPanic)?;
+                    page_addr += memory::PAGE_SIZE;
+                }
+
+                if let Some(tcb) = self.thread_manager.threads.get_mut(&current_handle) {
+                    if addr == 0 {
+                        tcb.mmap_top = end;
+                    }
+                    tcb.mappings.push(Vma {
+                        base,
+                        len: page_len,
+                        flags: prot_flags,
+                    });
+                }
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Pointer(VirtAddr::new(base))),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Munmap { addr, len } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "Munmap called without current thread".into(),
+                        ))?;
+
+                let root_ppn = {
+                    let tcb = self
+                        .thread_manager
+                        .threads
+                        .get(&current_handle)
+                        .ok_or(TrapError::HandlerPanic("Munmap: unknown thread".into()))?;
+                    tcb.context.satp & 0x003F_FFFF
+                };
+
+                let page_len = (len as u32 + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+                let base = addr & !(memory::PAGE_SIZE - 1);
+                let end = base + page_len;
+
+                let mut page_addr = base;
+                while page_addr < end {
+                    if let Some(frame) =
+                        memory::unmap_page(memory, root_ppn, page_addr)
+                            .map_err(TrapError::HandlerPanic)?
+                    {
+                        memory::release_frame(frame);
+                    }
+                    page_addr += memory::PAGE_SIZE;
+                }
+
+                if let Some(tcb) = self.thread_manager.threads.get_mut(&current_handle) {
+                    // Drop every tracked mapping the unmapped range fully
+                    // covers, and trim the ones it only partially covers
+                    // down to whatever's left outside [base, end) -- the
+                    // same "shrink from either edge" shape `Sbrk` already
+                    // uses for the heap, just applied to an arbitrary range
+                    // instead of one that only ever shrinks from the top.
+                    let mut remaining = Vec::with_capacity(tcb.mappings.len());
+                    for vma in tcb.mappings.drain(..) {
+                        if !vma.overlaps(base, page_len) {
+                            remaining.push(vma);
+                            continue;
+                        }
+                        if vma.base < base {
+                            remaining.push(Vma {
+                                base: vma.base,
+                                len: base - vma.base,
+                                flags: vma.flags,
+                            });
+                        }
+                        if vma.end() > end {
+                            remaining.push(Vma {
+                                base: end,
+                                len: vma.end() - end,
+                                flags: vma.flags,
+                            });
+                        }
+                    }
+                    tcb.mappings = remaining;
+                }
+
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Socket {
+                domain,
+                ty: _,
+                protocol,
+            } => {
+                let id = net::syscalls::create_socket(domain, protocol);
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Handle(id)), cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Bind { fd, ptr, len } => {
+                let mut family_bytes = [0u8; 2];
+                copy_from_user(memory, satp, ptr, &mut family_bytes)?;
+                let family = u16::from_le_bytes(family_bytes) as u32;
+
+                let result = if family == net::AF_UNIX {
+                    if len < core::mem::size_of::<net::SockAddrUn>() {
+                        Err(crate::error::SyscallError::InvalidArgument)
+                    } else {
+                        let mut addr_bytes = vec![0u8; core::mem::size_of::<net::SockAddrUn>()];
+                        copy_from_user(memory, satp, ptr, &mut addr_bytes)?;
+                        let sockaddr = net::SockAddrUn::read_from(&addr_bytes[..]).ok_or(
+                            TrapError::HandlerPanic("Bind: malformed sockaddr_un".into()),
+                        )?;
+                        if net::syscalls::bind_unix(fd as u32, sockaddr.name) {
+                            Ok(syscall::SyscallReturn::Success)
+                        } else {
+                            Err(crate::error::SyscallError::InvalidArgument)
+                        }
+                    }
+                } else if len < core::mem::size_of::<net::SockAddrIn>() {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                } else {
+                    let mut addr_bytes = vec![0u8; core::mem::size_of::<net::SockAddrIn>()];
+                    copy_from_user(memory, satp, ptr, &mut addr_bytes)?;
+                    let sockaddr = net::SockAddrIn::read_from(&addr_bytes[..]).ok_or(
+                        TrapError::HandlerPanic("Bind: malformed sockaddr_in".into()),
+                    )?;
+                    let port = u16::from_be(sockaddr.port);
+                    if net::syscalls::bind(fd as u32, port) {
+                        Ok(syscall::SyscallReturn::Success)
+                    } else {
+                        Err(crate::error::SyscallError::InvalidArgument)
+                    }
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::SendTo {
+                fd,
+                buf_ptr,
+                len,
+                dest_ptr,
+                dest_len,
+            } => {
+                let mut family_bytes = [0u8; 2];
+                copy_from_user(memory, satp, dest_ptr, &mut family_bytes)?;
+                let family = u16::from_le_bytes(family_bytes) as u32;
+
+                let mut payload = vec![0u8; len];
+                copy_from_user(memory, satp, buf_ptr, &mut payload)?;
+
+                let result = if family == net::AF_UNIX {
+                    if dest_len < core::mem::size_of::<net::SockAddrUn>() {
+                        Err(crate::error::SyscallError::InvalidArgument)
+                    } else {
+                        let mut dest_bytes = vec![0u8; core::mem::size_of::<net::SockAddrUn>()];
+                        copy_from_user(memory, satp, dest_ptr, &mut dest_bytes)?;
+                        let dest_addr = net::SockAddrUn::read_from(&dest_bytes[..]).ok_or(
+                            TrapError::HandlerPanic("SendTo: malformed sockaddr_un".into()),
+                        )?;
+                        if let Some(dest_id) =
+                            net::syscalls::send_to_unix(fd as u32, dest_addr.name, &payload)
+                        {
+                            // Loopback delivery is synchronous (no
+                            // `TimerInterrupt` involved the way a real NIC
+                            // packet is), so wake a parked `RecvFrom` on
+                            // the spot instead of leaving it parked until
+                            // the next unrelated tick notices.
+                            if let Some(waiter) = net::syscalls::take_rx_waiter(dest_id) {
+                                self.deliver_rx_waiter(memory, waiter)?;
+                            }
+                            Ok(syscall::SyscallReturn::Value(payload.len() as i64))
+                        } else {
+                            Err(crate::error::SyscallError::InvalidArgument)
+                        }
+                    }
+                } else if dest_len < core::mem::size_of::<net::SockAddrIn>() {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                } else {
+                    let mut dest_bytes = vec![0u8; core::mem::size_of::<net::SockAddrIn>()];
+                    copy_from_user(memory, satp, dest_ptr, &mut dest_bytes)?;
+                    let dest_addr = net::SockAddrIn::read_from(&dest_bytes[..]).ok_or(
+                        TrapError::HandlerPanic("SendTo: malformed sockaddr_in".into()),
+                    )?;
+                    let dest_ip = dest_addr.addr.to_ne_bytes();
+                    let dest_port = u16::from_be(dest_addr.port);
+                    if net::syscalls::send_to(memory, fd as u32, dest_ip, dest_port, &payload) {
+                        Ok(syscall::SyscallReturn::Value(payload.len() as i64))
+                    } else {
+                        Err(crate::error::SyscallError::InvalidArgument)
+                    }
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::RecvFrom {
+                fd,
+                buf_ptr,
+                len,
+                src_ptr,
+                src_len_ptr,
+            } => {
+                match net::syscalls::recv_from(fd as u32) {
+                    Some(packet) => {
+                        let copy_len = packet.payload.len().min(len);
+                        copy_to_user(memory, satp, &packet.payload[..copy_len], buf_ptr)?;
+
+                        let addr_len = if net::syscalls::is_unix(fd as u32) {
+                            let name = match packet.src {
+                                net::socket::SocketAddr::Unix { name } => name,
+                                net::socket::SocketAddr::Inet { .. } => [0; net::UNIX_NAME_MAX],
+                            };
+                            let src_addr = net::SockAddrUn {
+                                family: net::AF_UNIX as u16,
+                                name,
+                            };
+                            copy_to_user(memory, satp, src_addr.as_bytes(), src_ptr)?;
+                            core::mem::size_of::<net::SockAddrUn>() as u32
+                        } else {
+                            let (ip, port) = match packet.src {
+                                net::socket::SocketAddr::Inet { ip, port } => (ip, port),
+                                net::socket::SocketAddr::Unix { .. } => ([0; 4], 0),
+                            };
+                            let src_addr = net::SockAddrIn {
+                                family: net::AF_INET as u16,
+                                port: port.to_be(),
+                                addr: u32::from_ne_bytes(ip),
+                                zero: [0; 8],
+                            };
+                            copy_to_user(memory, satp, src_addr.as_bytes(), src_ptr)?;
+                            core::mem::size_of::<net::SockAddrIn>() as u32
+                        };
+                        copy_to_user(memory, satp, &addr_len.to_le_bytes(), src_len_ptr)?;
+
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Value(copy_len as i64)),
+                            cpu,
+                        );
+                        Ok(VirtAddr::new(cpu.pc + 4))
+                    }
+                    None if net::syscalls::is_non_blocking(fd as u32) => {
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Value(net::EWOULDBLOCK as i64)),
+                            cpu,
+                        );
+                        Ok(VirtAddr::new(cpu.pc + 4))
+                    }
+                    None => {
+                        let current_handle = self.thread_manager.current_thread.ok_or(
+                            TrapError::HandlerPanic("RecvFrom called without current thread".into()),
+                        )?;
+                        let waiter = net::socket::RxWaiter {
+                            thread: current_handle,
+                            fd: fd as u32,
+                            buf_ptr,
+                            len,
+                            src_ptr,
+                            src_len_ptr,
+                        };
+                        if net::syscalls::park_rx_waiter(fd as u32, waiter) {
+                            self.thread_manager.block_current_thread();
+                            syscall::Syscall::encode_result(
+                                Ok(syscall::SyscallReturn::Success),
+                                cpu,
+                            );
+                            cpu.pc += 4;
+                            self.thread_manager.yield_thread(cpu);
+                            Ok(VirtAddr::new(cpu.pc))
+                        } else {
+                            // Not a live socket, or another thread is
+                            // already parked on it: report "nothing
+                            // arrived" immediately rather than blocking
+                            // forever on a request that can never be
+                            // satisfied.
+                            syscall::Syscall::encode_result(
+                                Ok(syscall::SyscallReturn::Value(0)),
+                                cpu,
+                            );
+                            Ok(VirtAddr::new(cpu.pc + 4))
+                        }
+                    }
+                }
+            }
+            syscall::Syscall::Listen { fd, backlog } => {
+                let result = if net::syscalls::listen(fd as u32, backlog) {
+                    Ok(syscall::SyscallReturn::Success)
+                } else {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Accept {
+                fd,
+                addr_ptr,
+                addrlen_ptr,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "Accept called without current thread".into(),
+                        ))?;
+
+                match net::syscalls::accept_pending(fd as u32) {
+                    Some(accepted_id) => {
+                        let port = net::syscalls::peer_port(accepted_id).unwrap_or(0);
+                        let peer_addr = net::SockAddrIn {
+                            family: 2, // AF_INET
+                            port: port.to_be(),
+                            addr: u32::from_ne_bytes(net::syscalls::local_ip()),
+                            zero: [0; 8],
+                        };
+                        copy_to_user(memory, satp, peer_addr.as_bytes(), addr_ptr)?;
+                        let addr_len = core::mem::size_of::<net::SockAddrIn>() as u32;
+                        copy_to_user(memory, satp, &addr_len.to_le_bytes(), addrlen_ptr)?;
+
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Handle(accepted_id)),
+                            cpu,
+                        );
+                        Ok(VirtAddr::new(cpu.pc + 4))
+                    }
+                    None => {
+                        let waiter = net::socket::AcceptWaiter {
+                            thread: current_handle,
+                            addr_ptr,
+                            addrlen_ptr,
+                        };
+                        if net::syscalls::park_accept_waiter(fd as u32, waiter) {
+                            self.thread_manager.block_current_thread();
+                            syscall::Syscall::encode_result(
+                                Ok(syscall::SyscallReturn::Success),
+                                cpu,
+                            );
+                            cpu.pc += 4;
+                            self.thread_manager.yield_thread(cpu);
+                            Ok(VirtAddr::new(cpu.pc))
+                        } else {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidArgument),
+                                cpu,
+                            );
+                            Ok(VirtAddr::new(cpu.pc + 4))
+                        }
+                    }
+                }
+            }
+            syscall::Syscall::SocketConnect { fd, addr_ptr } => {
+                let mut addr_bytes = vec![0u8; core::mem::size_of::<net::SockAddrIn>()];
+                copy_from_user(memory, satp, addr_ptr, &mut addr_bytes)?;
+                let sockaddr = net::SockAddrIn::read_from(&addr_bytes[..]).ok_or(
+                    TrapError::HandlerPanic("SocketConnect: malformed sockaddr_in".into()),
+                )?;
+                let dest_ip = sockaddr.addr.to_ne_bytes();
+                let dest_port = u16::from_be(sockaddr.port);
+
+                if dest_ip != net::syscalls::local_ip() {
+                    // Not this kernel's own address: only a real TCP
+                    // socket can reach it, via an actual SYN/SYN-ACK/ACK
+                    // handshake instead of the loopback pairing below.
+                    if !net::syscalls::is_tcp(fd as u32) {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
+                    }
+
+                    let current_handle =
+                        self.thread_manager
+                            .current_thread
+                            .ok_or(TrapError::HandlerPanic(
+                                "SocketConnect: no current thread".into(),
+                            ))?;
+
+                    if !net::syscalls::tcp_connect(
+                        memory,
+                        fd as u32,
+                        dest_ip,
+                        dest_port,
+                        net::tcp::ConnectWaiter {
+                            thread: current_handle,
+                        },
+                    ) {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
+                    }
+
+                    self.thread_manager.block_current_thread();
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    cpu.pc += 4;
+                    self.thread_manager.yield_thread(cpu);
+                    return Ok(VirtAddr::new(cpu.pc));
+                }
+
+                match net::syscalls::connect_local(fd as u32, dest_port) {
+                    Ok(net::syscalls::ConnectOutcome::Queued) => {
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Success),
+                            cpu,
+                        );
+                    }
+                    Ok(net::syscalls::ConnectOutcome::Delivered {
+                        accepted_id,
+                        waiter,
+                    }) => {
+                        let waiter_satp = self
+                            .thread_manager
+                            .threads
+                            .get(&waiter.thread)
+                            .map(|tcb| tcb.context.satp)
+                            .unwrap_or(0);
+                        let peer_addr = net::SockAddrIn {
+                            family: 2, // AF_INET
+                            port: net::syscalls::peer_port(accepted_id).unwrap_or(0).to_be(),
+                            addr: u32::from_ne_bytes(net::syscalls::local_ip()),
+                            zero: [0; 8],
+                        };
+                        copy_to_user(memory, waiter_satp, peer_addr.as_bytes(), waiter.addr_ptr)?;
+                        let addr_len = core::mem::size_of::<net::SockAddrIn>() as u32;
+                        copy_to_user(
+                            memory,
+                            waiter_satp,
+                            &addr_len.to_le_bytes(),
+                            waiter.addrlen_ptr,
+                        )?;
+
+                        if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                            tcb.context.regs[10] = accepted_id;
+                        }
+                        self.thread_manager.wake_thread(waiter.thread);
+
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Success),
+                            cpu,
+                        );
+                    }
+                    Err(()) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Send { fd, buf_ptr, len } => {
+                let mut payload = vec![0u8; len];
+                copy_from_user(memory, satp, buf_ptr, &mut payload)?;
+
+                let result = match net::syscalls::tcp_send(memory, fd as u32, &payload) {
+                    Ok(sent) => Ok(syscall::SyscallReturn::Value(sent as i64)),
+                    Err(()) => Err(crate::error::SyscallError::InvalidArgument),
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Recv { fd, buf_ptr, len } => {
+                if let Some(data) = net::syscalls::tcp_recv(fd as u32, len) {
+                    copy_to_user(memory, satp, &data, buf_ptr)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(data.len() as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic("Recv: no current thread".into()))?;
+
+                if !net::tcp::park_recv_waiter(
+                    fd as u32,
+                    net::tcp::RecvWaiter {
+                        thread: current_handle,
+                        buf_ptr,
+                        len,
+                    },
+                ) {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidArgument),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                self.thread_manager.block_current_thread();
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                cpu.pc += 4;
+                self.thread_manager.yield_thread(cpu);
+                Ok(VirtAddr::new(cpu.pc))
+            }
+            syscall::Syscall::Poll { fds_ptr, nfds, timeout_ms } => {
+                let mut raw = vec![0u8; nfds * core::mem::size_of::<net::PollFd>()];
+                copy_from_user(memory, satp, fds_ptr, &mut raw)?;
+                let mut fds: Vec<net::PollFd> = raw
+                    .chunks_exact(core::mem::size_of::<net::PollFd>())
+                    .map(|chunk| net::PollFd::read_from(chunk).unwrap())
+                    .collect();
+
+                let mut ready_count = 0usize;
+                for pf in fds.iter_mut() {
+                    pf.revents = net::poll::ready_events(pf.fd, pf.events);
+                    if pf.revents != 0 {
+                        ready_count += 1;
+                    }
+                }
+
+                if ready_count > 0 || timeout_ms == 0 {
+                    let mut out = Vec::with_capacity(raw.len());
+                    for pf in &fds {
+                        out.extend_from_slice(pf.as_bytes());
+                    }
+                    copy_to_user(memory, satp, &out, fds_ptr)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(ready_count as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic("Poll: no current thread".into()))?;
+
+                let ticks_left = if timeout_ms == syscall::POLL_NO_TIMEOUT {
+                    None
+                } else {
+                    let nanos = timeout_ms as u64 * 1_000_000;
+                    Some(nanos.div_ceil(crate::thread::NANOS_PER_TICK).max(1))
+                };
+                net::poll::park(net::poll::PollWaiter {
+                    thread: current_handle,
+                    fds_ptr,
+                    fds: fds.iter().map(|pf| (pf.fd, pf.events)).collect(),
+                    ticks_left,
+                });
+
+                self.thread_manager.block_current_thread();
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                cpu.pc += 4;
+                self.thread_manager.yield_thread(cpu);
+                Ok(VirtAddr::new(cpu.pc))
+            }
+            syscall::Syscall::SetNonBlocking { fd, flag } => {
+                let result = if net::syscalls::set_non_blocking(fd as u32, flag != 0) {
+                    Ok(syscall::SyscallReturn::Success)
+                } else {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::GetRandom { buf_ptr, len, flags: _ } => {
+                let mut buf = vec![0u8; len];
+                self.fill_random(&mut buf);
+                copy_to_user(memory, satp, &buf, buf_ptr)?;
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Value(len as i64)),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::BlockRead { sector, buf_ptr } => {
+                // `read_sector` already ran the host I/O synchronously, but
+                // the result isn't copied to `buf_ptr` or the caller resumed
+                // until `TimerInterrupt` sees `SimpleBlockDevice`'s simulated
+                // latency window close -- the calling thread blocks and
+                // yields the scheduler for that window instead of busy-
+                // waiting, the same way `Sleep` yields for its duration.
+                let mut buffer = [0u8; 512];
+                match crate::fs::block::read_sector(memory, sector, &mut buffer) {
+                    Ok(_) => {
+                        let current_handle = self
+                            .thread_manager
+                            .current_thread
+                            .ok_or(TrapError::HandlerPanic("No current thread".into()))?;
+
+                        self.block_read_waiter = Some(BlockReadWaiter {
+                            thread: current_handle,
+                            buf_ptr,
+                            buffer,
+                        });
+                        self.thread_manager.block_current_thread();
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                        cpu.pc += 4;
+                        self.thread_manager.yield_thread(cpu);
+                        return Ok(VirtAddr::new(cpu.pc));
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::BlockReadDma { desc_ptr } => {
+                match crate::fs::block::start_dma(memory, desc_ptr.val()) {
+                    Ok(_) => {
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::ConfigRead {
+                key_ptr,
+                key_len,
+                buf_ptr,
+                buf_len,
+            } => {
+                let mut key = vec![0u8; key_len];
+                copy_from_user(memory, satp, key_ptr, &mut key)?;
+                let mut buf = vec![0u8; buf_len];
+                match crate::fs::config::read(memory, &key, &mut buf) {
+                    Ok(val_len) => {
+                        copy_to_user(memory, satp, &buf, buf_ptr)?;
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Value(val_len as i64)),
+                            cpu,
+                        );
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::ConfigWrite {
+                key_ptr,
+                key_len,
+                val_ptr,
+                val_len,
+            } => {
+                let mut key = vec![0u8; key_len];
+                copy_from_user(memory, satp, key_ptr, &mut key)?;
+                let mut val = vec![0u8; val_len];
+                copy_from_user(memory, satp, val_ptr, &mut val)?;
+                match crate::fs::config::write(memory, &key, &val) {
+                    Ok(()) => {
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::ConfigRemove { key_ptr, key_len } => {
+                let mut key = vec![0u8; key_len];
+                copy_from_user(memory, satp, key_ptr, &mut key)?;
+                match crate::fs::config::remove(memory, &key) {
+                    Ok(()) => {
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::FileOpen {
+                path_ptr,
+                path_len,
+                flags,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "FileOpen: No current thread".into(),
+                        ))?;
+
+                let satp = self
+                    .thread_manager
+                    .threads
+                    .get(&current_handle)
+                    .unwrap()
+                    .context
+                    .satp;
+
+                let mut path_bytes = vec![0u8; path_len];
+                copy_from_user(memory, satp, path_ptr, &mut path_bytes)?;
+
+                let path_str = String::from_utf8(path_bytes)
+                    .map_err(|_| TrapError::HandlerPanic("Invalid UTF-8 path".into()))?;
+
+                let descriptor = if let Some(host_path) = path_str.strip_prefix(HOST_MOUNT_PREFIX)
+                {
+                    self.ninep
+                        .as_mut()
+                        .ok_or_else(|| crate::error::SyscallError::NineP("no --share mounted".into()))
+                        .and_then(|transport| transport.open(host_path))
+                        .map(|fid| FileDescriptor::Host {
+                            fid,
+                            offset: 0,
+                            flags,
+                        })
+                } else if let Some((scheme, rest)) = fs::scheme::resolve(&path_str) {
+                    match scheme {
+                        fs::scheme::Scheme::Console => Ok(FileDescriptor::Console),
+                        fs::scheme::Scheme::Null => Ok(FileDescriptor::Null),
+                        fs::scheme::Scheme::Rand => Ok(FileDescriptor::Rand),
+                        fs::scheme::Scheme::Pipe => {
+                            let id = if let Some(&id) = self.pipe_names.get(rest) {
+                                id
+                            } else {
+                                let id = self.next_pipe_id;
+                                self.next_pipe_id += 1;
+                                self.pipe_names.insert(rest.to_string(), id);
+                                self.pipes.insert(id, sync::Pipe::new());
+                                id
+                            };
+                            Ok(FileDescriptor::Pipe { id })
+                        }
+                        fs::scheme::Scheme::Disk => self
+                            .file_system
+                            .as_ref()
+                            .ok_or(crate::error::SyscallError::InvalidSyscallNumber(0))
+                            .and_then(|fs| {
+                                fs.find_inode(memory, rest)
+                                    .map_err(|_| crate::error::SyscallError::InvalidSyscallNumber(0))
+                            })
+                            .map(|inode_id| FileDescriptor::Disk {
+                                inode_id,
+                                offset: 0,
+                                flags,
+                            }),
+                    }
+                } else if let Some(fs) = &self.file_system {
+                    fs.find_inode(memory, &path_str)
+                        .map_err(|_| crate::error::SyscallError::InvalidSyscallNumber(0))
+                        .map(|inode_id| FileDescriptor::Disk {
+                            inode_id,
+                            offset: 0,
+                            flags,
+                        })
+                } else {
+                    Err(crate::error::SyscallError::InvalidSyscallNumber(0))
+                };
+
+                match descriptor {
+                    Ok(fd) => {
+                        let tcb = self
+                            .thread_manager
+                            .threads
+                            .get_mut(&current_handle)
+                            .unwrap();
+                        match alloc_fd_slot(&mut tcb.file_descriptors, fd) {
+                            Some(fd_idx) => {
+                                syscall::Syscall::encode_result(
+                                    Ok(syscall::SyscallReturn::Handle(fd_idx as u32)),
+                                    cpu,
+                                );
+                            }
+                            None => {
+                                syscall::Syscall::encode_result(
+                                    Err(crate::error::SyscallError::InvalidArgument),
+                                    cpu,
+                                );
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Sleep { nanos_lo, nanos_hi } => {
+                let nanos = ((nanos_hi as u64) << 32) | nanos_lo as u64;
+                let ticks = nanos.div_ceil(crate::thread::NANOS_PER_TICK).max(1);
+                self.thread_manager.sleep_current_thread(ticks);
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                cpu.pc += 4;
+                self.thread_manager.yield_thread(cpu);
+                Ok(VirtAddr::new(cpu.pc))
+            }
+            syscall::Syscall::WaitPid { pid, timeout_nanos } => {
+                let target = crate::types::ThreadHandle::new(pid)
+                    .ok_or(TrapError::HandlerPanic("Invalid pid 0".into()))?;
+                let timeout_ticks = (timeout_nanos != syscall::WAITPID_NO_TIMEOUT)
+                    .then(|| timeout_nanos.div_ceil(crate::thread::NANOS_PER_TICK).max(1));
+
+                match self.thread_manager.wait_current_thread(target, timeout_ticks) {
+                    Ok(Some(exit_code)) => {
+                        // Already terminated: this is the one collect that
+                        // will ever happen for `target` (a second `WaitPid`
+                        // on the same pid would fail the not-a-child/not-
+                        // found check in `wait_current_thread` once its TCB
+                        // is gone), so reap it now -- its address space was
+                        // already released back in `exit_current_thread`.
+                        self.thread_manager.threads.remove(&target);
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Value(exit_code as i64)),
+                            cpu,
+                        );
+                        Ok(VirtAddr::new(cpu.pc + 4))
+                    }
+                    Ok(None) => {
+                        // Blocked. Return placeholder (will be overwritten by waker)
+                        syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                        cpu.pc += 4;
                         self.thread_manager.yield_thread(cpu);
                         Ok(VirtAddr::new(cpu.pc))
                     }
-                    Err(e) => {
-                        warn!("WaitPid failed: {}", e);
+                    Err(e) => {
+                        warn!("WaitPid failed: {}", e);
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                        Ok(VirtAddr::new(cpu.pc + 4))
+                    }
+                }
+            }
+            syscall::Syscall::FileWrite { fd, buf_ptr, len } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "FileWrite: No current thread".into(),
+                        ))?;
+
+                let descriptor = {
+                    let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                    match tcb.file_descriptors.get(fd as usize) {
+                        Some(Some(desc)) => (*desc, tcb.context.satp),
+                        _ => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        }
+                    }
+                };
+
+                if let (FileDescriptor::Console, satp) = descriptor {
+                    let mut buf = vec![0u8; len];
+                    copy_from_user(memory, satp, buf_ptr, &mut buf)?;
+                    uart_write(memory, &buf)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(len as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Null, _) | (FileDescriptor::Rand, _) = descriptor {
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(len as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Pipe { id }, satp) = descriptor {
+                    let mut buf = vec![0u8; len];
+                    copy_from_user(memory, satp, buf_ptr, &mut buf)?;
+                    if let Some(pipe) = self.pipes.get_mut(&id) {
+                        pipe.buffer.extend(buf.iter().copied());
+                    }
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(len as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let (host_fid, offset, flags, satp) = match descriptor {
+                    (FileDescriptor::Host { fid, offset, flags }, satp) => {
+                        (fid, offset, flags, satp)
+                    }
+                    _ => {
+                        // Disk-backed writes aren't implemented; only
+                        // the 9P passthrough supports FileWrite today.
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
+                    }
+                };
+
+                if flags & thread::tcb::O_WRONLY == 0 {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidArgument),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let mut buf = vec![0u8; len];
+                copy_from_user(memory, satp, buf_ptr, &mut buf)?;
+
+                let result = self
+                    .ninep
+                    .as_mut()
+                    .ok_or_else(|| crate::error::SyscallError::NineP("no --share mounted".into()))
+                    .and_then(|transport| {
+                        // O_APPEND always writes at end-of-file, regardless
+                        // of the descriptor's tracked offset.
+                        let write_offset = if flags & thread::tcb::O_APPEND != 0 {
+                            transport.size(host_fid)?
+                        } else {
+                            offset
+                        };
+                        transport.write(host_fid, write_offset, &buf)
+                    });
+
+                match result {
+                    Ok(written) => {
+                        let tcb = self
+                            .thread_manager
+                            .threads
+                            .get_mut(&current_handle)
+                            .unwrap();
+                        if let Some(Some(FileDescriptor::Host { offset, .. })) =
+                            tcb.file_descriptors.get_mut(fd as usize)
+                        {
+                            *offset += written as u64;
+                        }
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Value(written as i64)),
+                            cpu,
+                        );
+                    }
+                    Err(_) => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                    }
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::FileWriteV {
+                fd,
+                iov_ptr,
+                iov_count,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "FileWriteV: No current thread".into(),
+                        ))?;
+
+                let descriptor = {
+                    let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                    match tcb.file_descriptors.get(fd as usize) {
+                        Some(Some(desc)) => (*desc, tcb.context.satp),
+                        _ => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        }
+                    }
+                };
+                let satp = descriptor.1;
+
+                // Gather every iovec segment's bytes into one buffer --
+                // the same concatenated shape a single larger `FileWrite`
+                // would have received -- so the dispatch below doesn't
+                // need to know it came from more than one segment.
+                let mut iov_bytes = vec![0u8; iov_count * 8];
+                copy_from_user(memory, satp, iov_ptr, &mut iov_bytes)?;
+                let mut buf = Vec::new();
+                for raw in iov_bytes.chunks_exact(8) {
+                    let base = u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]);
+                    let seg_len = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]) as usize;
+                    let mut seg = vec![0u8; seg_len];
+                    copy_from_user(memory, satp, VirtAddr::new(base), &mut seg)?;
+                    buf.extend_from_slice(&seg);
+                }
+                let len = buf.len();
+
+                if let (FileDescriptor::Console, _) = descriptor {
+                    uart_write(memory, &buf)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(len as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Null, _) | (FileDescriptor::Rand, _) = descriptor {
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(len as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Pipe { id }, _) = descriptor {
+                    if let Some(pipe) = self.pipes.get_mut(&id) {
+                        pipe.buffer.extend(buf.iter().copied());
+                    }
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(len as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let (host_fid, offset, flags) = match descriptor {
+                    (FileDescriptor::Host { fid, offset, flags }, _) => (fid, offset, flags),
+                    _ => {
+                        // Disk-backed writes aren't implemented; only
+                        // the 9P passthrough supports FileWriteV today.
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
+                    }
+                };
+
+                if flags & thread::tcb::O_WRONLY == 0 {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidArgument),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let result = self
+                    .ninep
+                    .as_mut()
+                    .ok_or_else(|| crate::error::SyscallError::NineP("no --share mounted".into()))
+                    .and_then(|transport| {
+                        let write_offset = if flags & thread::tcb::O_APPEND != 0 {
+                            transport.size(host_fid)?
+                        } else {
+                            offset
+                        };
+                        transport.write(host_fid, write_offset, &buf)
+                    });
+
+                match result {
+                    Ok(written) => {
+                        let tcb = self
+                            .thread_manager
+                            .threads
+                            .get_mut(&current_handle)
+                            .unwrap();
+                        if let Some(Some(FileDescriptor::Host { offset, .. })) =
+                            tcb.file_descriptors.get_mut(fd as usize)
+                        {
+                            *offset += written as u64;
+                        }
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Value(written as i64)),
+                            cpu,
+                        );
+                    }
+                    Err(_) => {
                         syscall::Syscall::encode_result(
                             Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
                             cpu,
                         );
-                        Ok(VirtAddr::new(cpu.pc + 4))
                     }
                 }
+                Ok(VirtAddr::new(cpu.pc + 4))
             }
             syscall::Syscall::FileRead { fd, buf_ptr, len } => {
                 debug!("FileRead: fd={}, buf={:?}, len={}", fd, buf_ptr, len);
@@ -655,25 +2507,104 @@ impl Kernel {
                             "FileRead: No current thread".into(),
                         ))?;
 
-                let (inode_id, offset, satp) = {
+                let descriptor = {
                     let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
-                    if (fd as usize) < tcb.file_descriptors.len() {
-                        if let Some(desc) = &tcb.file_descriptors[fd as usize] {
-                            (desc.inode_id, desc.offset, tcb.context.satp)
-                        } else {
+                    match tcb.file_descriptors.get(fd as usize) {
+                        Some(Some(desc)) => (*desc, tcb.context.satp),
+                        _ => {
                             syscall::Syscall::encode_result(
                                 Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
                                 cpu,
                             );
                             return Ok(VirtAddr::new(cpu.pc + 4));
                         }
+                    }
+                };
+
+                if let (FileDescriptor::Console, satp) = descriptor {
+                    let read_buf = uart_read(memory, len)?;
+                    copy_to_user(memory, satp, &read_buf, buf_ptr)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(read_buf.len() as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Null, _) = descriptor {
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Value(0)), cpu);
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Rand, satp) = descriptor {
+                    let mut buf = vec![0u8; len];
+                    self.fill_random(&mut buf);
+                    copy_to_user(memory, satp, &buf, buf_ptr)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(buf.len() as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Pipe { id }, satp) = descriptor {
+                    let read = if let Some(pipe) = self.pipes.get_mut(&id) {
+                        let n = pipe.buffer.len().min(len);
+                        pipe.buffer.drain(..n).collect::<Vec<u8>>()
                     } else {
-                        syscall::Syscall::encode_result(
-                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
-                            cpu,
-                        );
-                        return Ok(VirtAddr::new(cpu.pc + 4));
+                        Vec::new()
+                    };
+                    copy_to_user(memory, satp, &read, buf_ptr)?;
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(read.len() as i64)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                if let (FileDescriptor::Host { fid, offset, .. }, satp) = descriptor {
+                    let mut buf = vec![0u8; len];
+                    let result = self
+                        .ninep
+                        .as_mut()
+                        .ok_or_else(|| {
+                            crate::error::SyscallError::NineP("no --share mounted".into())
+                        })
+                        .and_then(|transport| transport.read(fid, offset, &mut buf));
+
+                    match result {
+                        Ok(read) => {
+                            copy_to_user(memory, satp, &buf[..read], buf_ptr)?;
+                            let tcb = self
+                                .thread_manager
+                                .threads
+                                .get_mut(&current_handle)
+                                .unwrap();
+                            if let Some(Some(FileDescriptor::Host { offset, .. })) =
+                                tcb.file_descriptors.get_mut(fd as usize)
+                            {
+                                *offset += read as u64;
+                            }
+                            syscall::Syscall::encode_result(
+                                Ok(syscall::SyscallReturn::Value(read as i64)),
+                                cpu,
+                            );
+                        }
+                        Err(_) => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                        }
                     }
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let (inode_id, offset, satp) = match descriptor {
+                    (FileDescriptor::Disk {
+                        inode_id, offset, ..
+                    }, satp) => (inode_id, offset, satp),
+                    _ => unreachable!("every other FileDescriptor variant returned above"),
                 };
 
                 if let Some(fs) = &self.file_system {
@@ -711,56 +2642,511 @@ impl Kernel {
                             VirtAddr::new(buf_ptr.val() + total_read as u32),
                         )?;
 
-                        total_read += bytes;
-                        current_offset += bytes as u32;
-                        remaining -= bytes;
+                        total_read += bytes;
+                        current_offset += bytes as u32;
+                        remaining -= bytes;
+                    }
+
+                    debug!("FileRead: Updating offset to {}", current_offset);
+                    let tcb = self
+                        .thread_manager
+                        .threads
+                        .get_mut(&current_handle)
+                        .unwrap();
+                    if let Some(Some(FileDescriptor::Disk { offset, .. })) =
+                        tcb.file_descriptors.get_mut(fd as usize)
+                    {
+                        *offset = current_offset;
+                    }
+
+                    syscall::Syscall::encode_result(
+                        Ok(syscall::SyscallReturn::Value(total_read as i64)),
+                        cpu,
+                    );
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::FileReadV {
+                fd,
+                iov_ptr,
+                iov_count,
+            } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "FileReadV: No current thread".into(),
+                        ))?;
+
+                let descriptor = {
+                    let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                    match tcb.file_descriptors.get(fd as usize) {
+                        Some(Some(desc)) => (*desc, tcb.context.satp),
+                        _ => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        }
+                    }
+                };
+                let satp = descriptor.1;
+
+                let mut iov_bytes = vec![0u8; iov_count * 8];
+                copy_from_user(memory, satp, iov_ptr, &mut iov_bytes)?;
+                let iovs: Vec<(u32, usize)> = iov_bytes
+                    .chunks_exact(8)
+                    .map(|raw| {
+                        let base = u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]);
+                        let seg_len =
+                            u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]) as usize;
+                        (base, seg_len)
+                    })
+                    .collect();
+                let total_len: usize = iovs.iter().map(|(_, seg_len)| *seg_len).sum();
+
+                // Produce the bytes exactly as a single `FileRead` of
+                // `total_len` would, then scatter them across the iovec
+                // segments below instead of copying to one `buf_ptr`.
+                let read_buf: Vec<u8> = if let (FileDescriptor::Console, _) = descriptor {
+                    uart_read(memory, total_len)?
+                } else if let (FileDescriptor::Null, _) = descriptor {
+                    Vec::new()
+                } else if let (FileDescriptor::Rand, _) = descriptor {
+                    let mut b = vec![0u8; total_len];
+                    self.fill_random(&mut b);
+                    b
+                } else if let (FileDescriptor::Pipe { id }, _) = descriptor {
+                    if let Some(pipe) = self.pipes.get_mut(&id) {
+                        let n = pipe.buffer.len().min(total_len);
+                        pipe.buffer.drain(..n).collect()
+                    } else {
+                        Vec::new()
+                    }
+                } else if let (FileDescriptor::Host { fid, offset, .. }, _) = descriptor {
+                    let mut hbuf = vec![0u8; total_len];
+                    let result = self
+                        .ninep
+                        .as_mut()
+                        .ok_or_else(|| {
+                            crate::error::SyscallError::NineP("no --share mounted".into())
+                        })
+                        .and_then(|transport| transport.read(fid, offset, &mut hbuf));
+
+                    match result {
+                        Ok(read) => {
+                            hbuf.truncate(read);
+                            let tcb = self
+                                .thread_manager
+                                .threads
+                                .get_mut(&current_handle)
+                                .unwrap();
+                            if let Some(Some(FileDescriptor::Host { offset, .. })) =
+                                tcb.file_descriptors.get_mut(fd as usize)
+                            {
+                                *offset += read as u64;
+                            }
+                            hbuf
+                        }
+                        Err(_) => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        }
+                    }
+                } else {
+                    let (inode_id, offset) = match descriptor {
+                        (
+                            FileDescriptor::Disk {
+                                inode_id, offset, ..
+                            },
+                            _,
+                        ) => (inode_id, offset),
+                        _ => unreachable!("every other FileDescriptor variant handled above"),
+                    };
+
+                    if let Some(fs) = &self.file_system {
+                        let inode = fs.read_inode(memory, inode_id).map_err(|e| {
+                            TrapError::HandlerPanic(format!("Read Inode: {:?}", e))
+                        })?;
+
+                        let mut out = Vec::with_capacity(total_len);
+                        let mut temp_buf = [0u8; 512];
+                        let mut current_offset = offset;
+                        let mut remaining = total_len;
+
+                        while remaining > 0 {
+                            let chunk_size = remaining.min(512);
+                            let bytes = fs
+                                .read_data(
+                                    memory,
+                                    &inode,
+                                    current_offset,
+                                    &mut temp_buf[..chunk_size],
+                                )
+                                .map_err(|e| {
+                                    TrapError::HandlerPanic(format!("Read Data: {:?}", e))
+                                })?;
+                            if bytes == 0 {
+                                break;
+                            }
+                            out.extend_from_slice(&temp_buf[..bytes]);
+                            current_offset += bytes as u32;
+                            remaining -= bytes;
+                        }
+
+                        let tcb = self
+                            .thread_manager
+                            .threads
+                            .get_mut(&current_handle)
+                            .unwrap();
+                        if let Some(Some(FileDescriptor::Disk { offset, .. })) =
+                            tcb.file_descriptors.get_mut(fd as usize)
+                        {
+                            *offset = current_offset;
+                        }
+                        out
+                    } else {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
+                    }
+                };
+
+                // Scatter `read_buf` across the iovec segments in order,
+                // short-reading the tail once it runs out rather than
+                // zero-filling the remaining segments.
+                let mut cursor = 0usize;
+                for (base, seg_len) in iovs {
+                    let take = seg_len.min(read_buf.len().saturating_sub(cursor));
+                    if take == 0 {
+                        continue;
+                    }
+                    copy_to_user(
+                        memory,
+                        satp,
+                        &read_buf[cursor..cursor + take],
+                        VirtAddr::new(base),
+                    )?;
+                    cursor += take;
+                }
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Value(read_buf.len() as i64)),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+
+            syscall::Syscall::FileClose { fd } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "FileClose: No current thread".into(),
+                        ))?;
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .unwrap();
+                if (fd as usize) < tcb.file_descriptors.len() {
+                    let closed = tcb.file_descriptors[fd as usize].take();
+                    if let (Some(FileDescriptor::Host { fid, .. }), Some(transport)) =
+                        (closed, self.ninep.as_mut())
+                    {
+                        transport.clunk(fid);
+                    }
+                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                } else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                }
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Fstat { fd, stat_ptr } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "Fstat: No current thread".into(),
+                        ))?;
+
+                let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                let satp = tcb.context.satp;
+                let Some(Some(descriptor)) = tcb.file_descriptors.get(fd as usize).copied()
+                else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidArgument),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let stat = match descriptor {
+                    FileDescriptor::Disk { inode_id, .. } => {
+                        let Some(fs) = &self.file_system else {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        };
+                        let inode = fs.read_inode(memory, inode_id).map_err(|e| {
+                            TrapError::HandlerPanic(format!("Read Inode: {:?}", e))
+                        })?;
+                        ferrous_fs::Stat {
+                            inode: inode_id,
+                            mode: ferrous_fs::S_IFREG,
+                            size: inode.size,
+                            block_size: ferrous_fs::BLOCK_SIZE as u32,
+                            links: 1,
+                        }
+                    }
+                    FileDescriptor::Host { fid, .. } => {
+                        let size = self
+                            .ninep
+                            .as_mut()
+                            .and_then(|t| t.size(fid).ok())
+                            .unwrap_or(0);
+                        ferrous_fs::Stat {
+                            inode: fid,
+                            mode: ferrous_fs::S_IFREG,
+                            size: size as u32,
+                            block_size: ferrous_fs::BLOCK_SIZE as u32,
+                            links: 1,
+                        }
+                    }
+                    FileDescriptor::Pipe { id } => {
+                        // A pipe has no fixed size, but reporting however
+                        // many bytes are buffered right now is more useful
+                        // to a caller than always reporting zero.
+                        let size = self.pipes.get(&id).map(|p| p.buffer.len()).unwrap_or(0);
+                        ferrous_fs::Stat {
+                            inode: 0,
+                            mode: ferrous_fs::S_IFIFO,
+                            size: size as u32,
+                            block_size: 0,
+                            links: 1,
+                        }
+                    }
+                    FileDescriptor::Console | FileDescriptor::Null | FileDescriptor::Rand => {
+                        ferrous_fs::Stat {
+                            inode: 0,
+                            mode: ferrous_fs::S_IFCHR,
+                            size: 0,
+                            block_size: 0,
+                            links: 1,
+                        }
+                    }
+                };
+
+                let stat_bytes = unsafe {
+                    core::slice::from_raw_parts(
+                        &stat as *const ferrous_fs::Stat as *const u8,
+                        core::mem::size_of::<ferrous_fs::Stat>(),
+                    )
+                };
+                copy_to_user(memory, satp, stat_bytes, stat_ptr)?;
+
+                syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::FileSeek { fd, offset, whence } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic(
+                            "FileSeek: No current thread".into(),
+                        ))?;
+
+                let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                let Some(Some(descriptor)) = tcb.file_descriptors.get(fd as usize).copied()
+                else {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                let current_offset: i64 = match descriptor {
+                    FileDescriptor::Disk { offset, .. } => offset as i64,
+                    FileDescriptor::Host { offset, .. } => offset as i64,
+                    // Console/Null/Rand have no position to move, and a
+                    // pipe is a stream rather than random-access.
+                    FileDescriptor::Console
+                    | FileDescriptor::Null
+                    | FileDescriptor::Rand
+                    | FileDescriptor::Pipe { .. } => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
                     }
+                };
 
-                    debug!("FileRead: Updating offset to {}", current_offset);
-                    let tcb = self
-                        .thread_manager
-                        .threads
-                        .get_mut(&current_handle)
-                        .unwrap();
-                    if let Some(desc) = tcb.file_descriptors[fd as usize].as_mut() {
-                        desc.offset = current_offset;
+                let end_offset = match descriptor {
+                    FileDescriptor::Disk { inode_id, .. } => self
+                        .file_system
+                        .as_ref()
+                        .and_then(|fs| fs.read_inode(memory, inode_id).ok())
+                        .map(|inode| inode.size as i64),
+                    FileDescriptor::Host { fid, .. } => {
+                        self.ninep.as_mut().and_then(|t| t.size(fid).ok()).map(|s| s as i64)
+                    }
+                    _ => unreachable!("every other FileDescriptor variant returned above"),
+                };
+
+                let new_offset = match whence {
+                    0 => offset,
+                    1 => current_offset + offset,
+                    2 => match end_offset {
+                        Some(size) => size + offset,
+                        None => {
+                            syscall::Syscall::encode_result(
+                                Err(crate::error::SyscallError::InvalidArgument),
+                                cpu,
+                            );
+                            return Ok(VirtAddr::new(cpu.pc + 4));
+                        }
+                    },
+                    _ => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                        return Ok(VirtAddr::new(cpu.pc + 4));
                     }
+                };
 
+                if new_offset < 0 {
                     syscall::Syscall::encode_result(
-                        Ok(syscall::SyscallReturn::Value(total_read as i64)),
+                        Err(crate::error::SyscallError::InvalidArgument),
                         cpu,
                     );
-                } else {
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                }
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .unwrap();
+                match &mut tcb.file_descriptors[fd as usize] {
+                    Some(FileDescriptor::Disk { offset, .. }) => *offset = new_offset as u32,
+                    Some(FileDescriptor::Host { offset, .. }) => *offset = new_offset as u64,
+                    _ => unreachable!("already rejected above"),
+                }
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Value(new_offset)),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Dup { fd } => {
+                let current_handle =
+                    self.thread_manager
+                        .current_thread
+                        .ok_or(TrapError::HandlerPanic("Dup: No current thread".into()))?;
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .unwrap();
+
+                let Some(descriptor) = tcb.file_descriptors.get(fd as usize).copied().flatten()
+                else {
                     syscall::Syscall::encode_result(
-                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        Err(crate::error::SyscallError::InvalidArgument),
                         cpu,
                     );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                match alloc_fd_slot(&mut tcb.file_descriptors, descriptor) {
+                    Some(new_fd) => {
+                        syscall::Syscall::encode_result(
+                            Ok(syscall::SyscallReturn::Handle(new_fd as u32)),
+                            cpu,
+                        );
+                    }
+                    None => {
+                        syscall::Syscall::encode_result(
+                            Err(crate::error::SyscallError::InvalidArgument),
+                            cpu,
+                        );
+                    }
                 }
                 Ok(VirtAddr::new(cpu.pc + 4))
             }
-
-            syscall::Syscall::FileClose { fd } => {
+            syscall::Syscall::Dup2 { old_fd, new_fd } => {
                 let current_handle =
                     self.thread_manager
                         .current_thread
-                        .ok_or(TrapError::HandlerPanic(
-                            "FileClose: No current thread".into(),
-                        ))?;
+                        .ok_or(TrapError::HandlerPanic("Dup2: No current thread".into()))?;
 
                 let tcb = self
                     .thread_manager
                     .threads
                     .get_mut(&current_handle)
                     .unwrap();
-                if (fd as usize) < tcb.file_descriptors.len() {
-                    tcb.file_descriptors[fd as usize] = None;
-                    syscall::Syscall::encode_result(Ok(syscall::SyscallReturn::Success), cpu);
-                } else {
+
+                let Some(descriptor) = tcb.file_descriptors.get(old_fd as usize).copied().flatten()
+                else {
                     syscall::Syscall::encode_result(
-                        Err(crate::error::SyscallError::InvalidSyscallNumber(0)),
+                        Err(crate::error::SyscallError::InvalidArgument),
+                        cpu,
+                    );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
+                };
+
+                if new_fd as usize >= thread::tcb::MAX_FILE_DESCRIPTORS {
+                    syscall::Syscall::encode_result(
+                        Err(crate::error::SyscallError::InvalidArgument),
                         cpu,
                     );
+                    return Ok(VirtAddr::new(cpu.pc + 4));
                 }
+                if new_fd as usize >= tcb.file_descriptors.len() {
+                    tcb.file_descriptors
+                        .resize(new_fd as usize + 1, None);
+                }
+
+                let closed = tcb.file_descriptors[new_fd as usize].take();
+                if let (Some(FileDescriptor::Host { fid, .. }), Some(transport)) =
+                    (closed, self.ninep.as_mut())
+                {
+                    transport.clunk(fid);
+                }
+
+                let tcb = self
+                    .thread_manager
+                    .threads
+                    .get_mut(&current_handle)
+                    .unwrap();
+                tcb.file_descriptors[new_fd as usize] = Some(descriptor);
+
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(new_fd)),
+                    cpu,
+                );
                 Ok(VirtAddr::new(cpu.pc + 4))
             }
             syscall::Syscall::Exec {
@@ -859,165 +3245,588 @@ impl Kernel {
                     memory::create_user_address_space(memory).map_err(TrapError::HandlerPanic)?;
                 let root_ppn = satp_val & 0x003F_FFFF;
 
-                // 5. Load Segments
-                let mut max_vaddr = 0;
-                for ph in elf.program_headers.iter() {
-                    if ph.p_type == elf::program_header::PT_LOAD {
-                        let file_start = ph.p_offset as usize;
-                        let file_len = ph.p_filesz as usize;
-                        let segment_data = &file_data[file_start..(file_start + file_len)];
+                // 5. Load Segments (lazily -- reserved now, backed on first fault)
+                let (segments, max_vaddr) = reserve_elf_segments(memory, root_ppn, &elf)?;
 
-                        let vaddr_start = ph.p_vaddr as u32;
-                        let mem_len = ph.p_memsz as u32;
+                // 6. Setup Stack (eager top pages + lazy grow-down reservation below)
+                let stack_top = 0xF000_0000u32;
+                reserve_user_stack(memory, root_ppn, stack_top)?;
 
-                        let mut current_vaddr = vaddr_start;
-                        let end_vaddr = vaddr_start + mem_len;
+                // 7. Push argv/envp/auxv onto the stack, SysV-style.
+                let current_sp = self.build_initial_stack(
+                    memory,
+                    satp_val,
+                    stack_top,
+                    &args_vec,
+                    path_str.as_bytes(),
+                    &elf,
+                    &segments,
+                )?;
+                let argv_base = current_sp + 4;
 
-                        if end_vaddr > max_vaddr {
-                            max_vaddr = end_vaddr;
-                        }
+                // 8. Create Thread/Process
+                let entry_point = VirtAddr::new(elf.entry as u32);
+                let handle = self
+                    .thread_manager
+                    .create_thread(entry_point, current_sp)
+                    .map_err(TrapError::HandlerPanic)?;
 
-                        while current_vaddr < end_vaddr {
-                            let page_base = current_vaddr & !(memory::PAGE_SIZE - 1);
-                            let paddr_base = match translate_vaddr(memory, satp_val, page_base) {
-                                Ok(p) => p & !(memory::PAGE_SIZE - 1),
-                                Err(_) => {
-                                    let frame = memory::alloc_frame();
-                                    let flags = memory::PTE_R
-                                        | memory::PTE_W
-                                        | memory::PTE_U
-                                        | memory::PTE_X;
-                                    memory::map_page(memory, root_ppn, page_base, frame, flags)
-                                        .map_err(TrapError::HandlerPanic)?;
-                                    for i in 0..memory::PAGE_SIZE {
-                                        memory.write_byte(PhysAddr::new(frame + i), 0).unwrap();
-                                    }
-                                    frame
-                                }
-                            };
+                if let Some(tcb) = self.thread_manager.threads.get_mut(&handle) {
+                    tcb.context.satp = satp_val;
+                    // Set argc (a0) and argv (a1)
+                    tcb.context
+                        .write_reg(ferrous_vm::Register::new(10).unwrap(), args_len as u32);
+                    tcb.context
+                        .write_reg(ferrous_vm::Register::new(11).unwrap(), argv_base);
 
-                            let page_offset = current_vaddr & (memory::PAGE_SIZE - 1);
-                            let bytes_available_in_page = memory::PAGE_SIZE - page_offset;
-                            let bytes_to_end = end_vaddr - current_vaddr;
-                            let chunk_size = bytes_available_in_page.min(bytes_to_end);
-
-                            let segment_offset = (current_vaddr - vaddr_start) as usize;
-
-                            if segment_offset < file_len {
-                                let data_remaining = file_len - segment_offset;
-                                let copy_size = (chunk_size as usize).min(data_remaining);
-
-                                for i in 0..copy_size {
-                                    let b = segment_data[segment_offset + i];
-                                    memory
-                                        .write_byte(
-                                            PhysAddr::new(paddr_base + page_offset + i as u32),
-                                            b,
-                                        )
-                                        .map_err(|e| {
-                                            TrapError::HandlerPanic(format!("Write error: {:?}", e))
-                                        })?;
-                                }
-                            }
-                            current_vaddr += chunk_size;
-                        }
+                    // Set program break
+                    let heap_start = (max_vaddr + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+                    tcb.program_break = heap_start;
+                    tcb.segments = segments;
+                    tcb.image = file_data.clone();
+                    info!(
+                        "Exec: Loaded max_vaddr={:#x}, Heap starts at {:#x}",
+                        max_vaddr, heap_start
+                    );
+                }
+
+                info!("Exec spawned new process with handle: {:?}", handle);
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(handle.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::SpawnProcess {
+                image_ptr,
+                image_len,
+                args_ptr,
+                args_len,
+            } => {
+                info!("SpawnProcess syscall");
+                let satp = self
+                    .thread_manager
+                    .current_thread
+                    .and_then(|h| self.thread_manager.threads.get(&h))
+                    .map(|tcb| tcb.context.satp)
+                    .ok_or(TrapError::HandlerPanic("SpawnProcess: No current thread".into()))?;
+
+                // 1. Read the ELF image out of the caller's own address
+                // space, in place of `Exec`'s filesystem read.
+                let mut image_data = vec![0u8; image_len];
+                copy_from_user(memory, satp, image_ptr, &mut image_data)?;
+
+                // 1.5 Read Arguments (same `{ptr, len}` descriptor array as `Exec`)
+                let mut args_vec: Vec<Vec<u8>> = Vec::with_capacity(args_len);
+                if args_len > 0 {
+                    let mut arg_descriptors_bytes = vec![0u8; args_len * 8];
+                    copy_from_user(memory, satp, args_ptr, &mut arg_descriptors_bytes)?;
+
+                    for i in 0..args_len {
+                        let offset = i * 8;
+                        let ptr = u32::from_le_bytes(
+                            arg_descriptors_bytes[offset..offset + 4]
+                                .try_into()
+                                .unwrap(),
+                        );
+                        let len = u32::from_le_bytes(
+                            arg_descriptors_bytes[offset + 4..offset + 8]
+                                .try_into()
+                                .unwrap(),
+                        );
+
+                        let mut arg_data = vec![0u8; len as usize];
+                        copy_from_user(memory, satp, VirtAddr::new(ptr), &mut arg_data)?;
+                        args_vec.push(arg_data);
                     }
                 }
 
-                // 6. Setup Stack
+                // 2. Parse ELF
+                let elf = elf::Elf::parse(&image_data)
+                    .map_err(|e| TrapError::HandlerPanic(format!("SpawnProcess: Invalid ELF: {:?}", e)))?;
+
+                // 3. Create Address Space, isolated from the caller's own
+                let satp_val =
+                    memory::create_user_address_space(memory).map_err(TrapError::HandlerPanic)?;
+                let root_ppn = satp_val & 0x003F_FFFF;
+
+                // 4. Load Segments (lazily -- reserved now, backed on first fault)
+                let (segments, max_vaddr) = reserve_elf_segments(memory, root_ppn, &elf)?;
+
+                // 5. Setup Stack (eager top pages + lazy grow-down reservation below)
                 let stack_top = 0xF000_0000u32;
-                let stack_pages = 4;
-                for i in 0..stack_pages {
-                    let vaddr = stack_top - ((i + 1) * memory::PAGE_SIZE);
-                    let frame = memory::alloc_frame();
-                    memory::map_page(
-                        memory,
-                        root_ppn,
-                        vaddr,
-                        frame,
-                        memory::PTE_R | memory::PTE_W | memory::PTE_U,
+                reserve_user_stack(memory, root_ppn, stack_top)?;
+
+                // 6. Push argv/envp/auxv onto the stack, SysV-style. There's
+                // no path to use as `AT_EXECFN` here either, so `argv[0]`
+                // doubles for it the same way `bootstrap_process` does.
+                let execfn = args_vec.first().map(|a| a.as_slice()).unwrap_or(&[]);
+                let current_sp = self.build_initial_stack(
+                    memory, satp_val, stack_top, &args_vec, execfn, &elf, &segments,
+                )?;
+                let argv_base = current_sp + 4;
+
+                // 7. Create Thread/Process, its own `satp` rather than the
+                // caller's -- a faulting child can't corrupt the caller.
+                let entry_point = VirtAddr::new(elf.entry as u32);
+                let handle = self
+                    .thread_manager
+                    .create_thread(entry_point, current_sp)
+                    .map_err(TrapError::HandlerPanic)?;
+
+                if let Some(tcb) = self.thread_manager.threads.get_mut(&handle) {
+                    tcb.context.satp = satp_val;
+                    tcb.context
+                        .write_reg(ferrous_vm::Register::new(10).unwrap(), args_vec.len() as u32);
+                    tcb.context
+                        .write_reg(ferrous_vm::Register::new(11).unwrap(), argv_base);
+
+                    let heap_start = (max_vaddr + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+                    tcb.program_break = heap_start;
+                    tcb.segments = segments;
+                    tcb.image = image_data;
+                    // `create_thread` already copied the caller's file
+                    // descriptors by value, the same inheritance `Exec`
+                    // relies on -- only the address space needs isolating.
+                    info!(
+                        "SpawnProcess: Loaded max_vaddr={:#x}, Heap starts at {:#x}",
+                        max_vaddr, heap_start
+                    );
+                }
+
+                info!("SpawnProcess spawned new process with handle: {:?}", handle);
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(handle.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+            syscall::Syscall::Fork => {
+                let current_handle = self
+                    .thread_manager
+                    .current_thread
+                    .ok_or(TrapError::HandlerPanic("Fork: No current thread".into()))?;
+
+                let (parent_satp, parent_break, parent_mmap_top, parent_fds, parent_segments, parent_image) = {
+                    let tcb = self.thread_manager.threads.get(&current_handle).unwrap();
+                    (
+                        tcb.context.satp,
+                        tcb.program_break,
+                        tcb.mmap_top,
+                        tcb.file_descriptors.clone(),
+                        tcb.segments.clone(),
+                        tcb.image.clone(),
+                    )
+                };
+
+                let child_satp = memory::fork_address_space(memory, parent_satp)
+                    .map_err(TrapError::HandlerPanic)?;
+
+                let child_handle = self
+                    .thread_manager
+                    .fork_thread(
+                        cpu,
+                        child_satp,
+                        parent_break,
+                        parent_mmap_top,
+                        parent_fds,
+                        parent_segments,
+                        parent_image,
                     )
                     .map_err(TrapError::HandlerPanic)?;
+
+                info!("Fork: parent={:?} child={:?}", current_handle, child_handle);
+                syscall::Syscall::encode_result(
+                    Ok(syscall::SyscallReturn::Handle(child_handle.val())),
+                    cpu,
+                );
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+
+            syscall::Syscall::Sigaction { signum, handler } => {
+                let current_handle = self
+                    .thread_manager
+                    .current_thread
+                    .ok_or(TrapError::HandlerPanic("Sigaction: No current thread".into()))?;
+                let result = if signum == 0 || signum >= 32 {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                } else {
+                    let tcb = self.thread_manager.threads.get_mut(&current_handle).unwrap();
+                    let previous = tcb.signal_handlers[signum as usize];
+                    tcb.signal_handlers[signum as usize] = handler;
+                    Ok(syscall::SyscallReturn::Value(previous as i64))
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+
+            syscall::Syscall::Kill { pid, signum } => {
+                let target = crate::types::ThreadHandle::new(pid)
+                    .ok_or(TrapError::HandlerPanic("Kill: invalid pid".into()))?;
+                let result = if signum == 0 || signum >= 32 {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                } else if let Some(tcb) = self.thread_manager.threads.get_mut(&target) {
+                    tcb.pending_signals |= 1 << signum;
+                    self.thread_manager.wake_thread(target);
+                    self.thread_manager.wake_waiting_thread(target);
+                    Ok(syscall::SyscallReturn::Success)
+                } else {
+                    Err(crate::error::SyscallError::InvalidArgument)
+                };
+                syscall::Syscall::encode_result(result, cpu);
+                Ok(VirtAddr::new(cpu.pc + 4))
+            }
+
+            syscall::Syscall::Sigreturn => {
+                let current_handle = self
+                    .thread_manager
+                    .current_thread
+                    .ok_or(TrapError::HandlerPanic("Sigreturn: No current thread".into()))?;
+                let tcb = self.thread_manager.threads.get_mut(&current_handle).unwrap();
+                let saved = tcb
+                    .signal_saved_context
+                    .take()
+                    .ok_or(TrapError::HandlerPanic("Sigreturn: no signal handler running".into()))?;
+                saved.restore_to(cpu);
+                Ok(VirtAddr::new(cpu.pc))
+            }
+        }
+    }
+
+    /// Resolve a `MutexAcquire`/`MutexRelease` capability slot for `thread`,
+    /// returning the underlying mutex id if the slot holds a `Mutex`
+    /// capability with at least `required` rights, `None` if the slot is
+    /// empty or names the wrong kind of object (a real usage error the
+    /// caller reports back to the guest as a normal syscall failure), and
+    /// `Err` only if `thread` itself doesn't exist.
+    fn resolve_mutex_slot(
+        &self,
+        thread: crate::types::ThreadHandle,
+        slot: u32,
+        required: CapRights,
+    ) -> Result<Option<u32>, TrapError> {
+        let tcb = self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .ok_or(TrapError::HandlerPanic("resolve_mutex_slot: unknown thread".into()))?;
+        let slot = crate::capability::CapSlot::from_raw(slot);
+        Ok(tcb.capabilities.check(slot, required).and_then(|cap| {
+            if let CapObject::Mutex(id) = cap.object {
+                Some(id)
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Resolve a `CondvarWait`/`CondvarNotify*` capability slot for
+    /// `thread`, the same shape as `resolve_mutex_slot`.
+    fn resolve_condvar_slot(
+        &self,
+        thread: crate::types::ThreadHandle,
+        slot: u32,
+        required: CapRights,
+    ) -> Result<Option<u32>, TrapError> {
+        let tcb = self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .ok_or(TrapError::HandlerPanic("resolve_condvar_slot: unknown thread".into()))?;
+        let slot = crate::capability::CapSlot::from_raw(slot);
+        Ok(tcb.capabilities.check(slot, required).and_then(|cap| {
+            if let CapObject::Condvar(id) = cap.object {
+                Some(id)
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Resolve a `SemWait`/`SemPost`/`SemDestroy` capability slot for
+    /// `thread`, the same shape as `resolve_mutex_slot`.
+    fn resolve_semaphore_slot(
+        &self,
+        thread: crate::types::ThreadHandle,
+        slot: u32,
+        required: CapRights,
+    ) -> Result<Option<u32>, TrapError> {
+        let tcb = self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .ok_or(TrapError::HandlerPanic("resolve_semaphore_slot: unknown thread".into()))?;
+        let slot = crate::capability::CapSlot::from_raw(slot);
+        Ok(tcb.capabilities.check(slot, required).and_then(|cap| {
+            if let CapObject::Semaphore(id) = cap.object {
+                Some(id)
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Hand `mutex_id` off from `releasing_thread` to whichever waiter, if
+    /// any, should own it next (lowest scheduler level wins, mirroring
+    /// `propagate_priority_boost`), waking that thread and restoring
+    /// `releasing_thread`'s own priority. Shared by `MutexRelease` and
+    /// `CondvarWait`, which both give up a held mutex the same way.
+    fn release_mutex(&mut self, mutex_id: u32, releasing_thread: crate::types::ThreadHandle) {
+        let mutex = match self.mutexes.get_mut(&mutex_id) {
+            Some(m) => m,
+            None => return,
+        };
+        mutex.owner = None;
+        // Hand off to the highest-priority (lowest level) waiter rather
+        // than strict FIFO, so a thread we boosted ourselves up to wake
+        // for doesn't sit behind lower-priority waiters that arrived first.
+        let next_owner = if mutex.wait_queue.is_empty() {
+            None
+        } else {
+            let scheduler = &self.thread_manager.scheduler;
+            let mut best_idx = 0;
+            let mut best_level = scheduler.priority_level(mutex.wait_queue[0]);
+            for (i, &h) in mutex.wait_queue.iter().enumerate().skip(1) {
+                let level = scheduler.priority_level(h);
+                if level < best_level {
+                    best_level = level;
+                    best_idx = i;
                 }
+            }
+            mutex.wait_queue.remove(best_idx)
+        };
+        if let Some(next_owner) = next_owner {
+            mutex.owner = Some(next_owner);
+        }
 
-                // 7. Push Arguments to Stack
-                let mut current_sp = stack_top;
+        if let Some(tcb) = self.thread_manager.threads.get_mut(&releasing_thread) {
+            tcb.held_mutexes.retain(|&m| m != mutex_id);
+        }
+        self.restore_thread_priority(releasing_thread);
 
-                // 7a. Push String Data
-                let mut arg_vaddrs = Vec::with_capacity(args_len);
-                for arg_data in &args_vec {
-                    current_sp -= arg_data.len() as u32;
-                    let dest = VirtAddr::new(current_sp);
-                    copy_to_user(memory, satp_val, arg_data, dest)?;
-                    arg_vaddrs.push(current_sp);
-                }
+        if let Some(next_owner) = next_owner {
+            if let Some(tcb) = self.thread_manager.threads.get_mut(&next_owner) {
+                tcb.held_mutexes.push(mutex_id);
+            }
+            self.thread_manager.wake_thread(next_owner);
+        }
+    }
 
-                // 7b. Push Argv Array (Descriptors: ptr, len)
-                // We need to push args_len * 8 bytes
-                let argv_size = (args_len * 8) as u32;
-                current_sp -= argv_size;
-                current_sp &= !3; // Align to 4 bytes
-                let argv_base = current_sp;
+    /// Give `thread` (woken off a `Condvar`'s wait queue) ownership of
+    /// `mutex_id` if it's free, the same as a fresh, uncontended
+    /// `MutexAcquire` would; otherwise queue it on the mutex's own
+    /// `wait_queue` to reacquire the normal way once whoever holds it now
+    /// releases it.
+    fn acquire_or_enqueue_mutex(&mut self, thread: crate::types::ThreadHandle, mutex_id: u32) {
+        let mutex = match self.mutexes.get_mut(&mutex_id) {
+            Some(m) => m,
+            None => return,
+        };
+        if mutex.owner.is_none() {
+            mutex.owner = Some(thread);
+            if let Some(tcb) = self.thread_manager.threads.get_mut(&thread) {
+                tcb.held_mutexes.push(mutex_id);
+            }
+            self.thread_manager.wake_thread(thread);
+        } else {
+            mutex.wait_queue.push_back(thread);
+        }
+    }
 
-                for (i, vaddr) in arg_vaddrs.iter().enumerate() {
-                    let len = args_vec[i].len() as u32;
-                    let desc_addr = argv_base + (i * 8) as u32;
+    /// Longest owner->owner chain `propagate_priority_boost` will walk
+    /// before giving up. A well-formed acquire order always terminates on
+    /// its own (each hop either reaches an unowned mutex or a level that's
+    /// already boosted enough), but this caps the cost of a guest-induced
+    /// pathological wait-for graph at a fixed number of hops instead of
+    /// scanning `self.mutexes` without bound.
+    const MAX_INHERITANCE_DEPTH: u32 = 64;
 
-                    // Write ptr
-                    let paddr_ptr = translate_vaddr(memory, satp_val, desc_addr)?;
-                    memory
-                        .write_word(PhysAddr::new(paddr_ptr), *vaddr)
-                        .map_err(|e| {
-                            TrapError::HandlerPanic(format!("Stack write error: {:?}", e))
-                        })?;
+    /// Priority-inheritance boost: raise `owner`'s effective scheduling
+    /// priority to `waiter_level` if it's currently lower priority (a
+    /// higher numeric level), recording its pre-boost level in
+    /// `base_priority` the first time it happens so `restore_thread_priority`
+    /// can undo it later. If `owner` is itself blocked in some other
+    /// mutex's `wait_queue`, the boost continues transitively to *that*
+    /// mutex's owner, the same chain a real priority-inheritance protocol
+    /// walks to stop a long dependency chain from starving the waiter at
+    /// the far end.
+    fn propagate_priority_boost(&mut self, owner: crate::types::ThreadHandle, waiter_level: usize) {
+        let mut current = owner;
+        for _ in 0..Self::MAX_INHERITANCE_DEPTH {
+            let cur_level = self.thread_manager.scheduler.priority_level(current);
+            if waiter_level >= cur_level {
+                break;
+            }
+            if let Some(tcb) = self.thread_manager.threads.get_mut(&current) {
+                tcb.base_priority.get_or_insert(cur_level);
+                tcb.effective_priority = waiter_level;
+            }
+            self.thread_manager
+                .scheduler
+                .set_priority_level(current, waiter_level);
 
-                    // Write len
-                    let paddr_len = translate_vaddr(memory, satp_val, desc_addr + 4)?;
-                    memory
-                        .write_word(PhysAddr::new(paddr_len), len)
-                        .map_err(|e| {
-                            TrapError::HandlerPanic(format!("Stack write error: {:?}", e))
-                        })?;
-                }
+            match self.mutexes.values().find(|m| m.wait_queue.contains(&current)) {
+                Some(m) => match m.owner {
+                    Some(next) => current = next,
+                    None => break,
+                },
+                None => break,
+            }
+        }
+    }
 
-                // 7c. Align Stack to 16 bytes
-                current_sp &= !15;
+    /// Undo (or partially undo) a `propagate_priority_boost` once `thread`
+    /// releases a mutex. Recomputes the lowest level still demanded by any
+    /// waiter on a mutex `thread` still holds -- not just its recorded
+    /// `base_priority` -- so a thread holding two contended locks doesn't
+    /// drop back to its natural priority while the other lock still has a
+    /// higher-priority thread waiting on it. Clears `base_priority` only
+    /// once nothing still needs the boost.
+    fn restore_thread_priority(&mut self, thread: crate::types::ThreadHandle) {
+        let base = match self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .and_then(|tcb| tcb.base_priority)
+        {
+            Some(base) => base,
+            None => return,
+        };
+        let held = self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .map(|tcb| tcb.held_mutexes.clone())
+            .unwrap_or_default();
 
-                // 8. Create Thread/Process
-                let entry_point = VirtAddr::new(elf.entry as u32);
-                let handle = self
-                    .thread_manager
-                    .create_thread(entry_point, current_sp)
-                    .map_err(TrapError::HandlerPanic)?;
+        let mut new_level = base;
+        for id in &held {
+            if let Some(mutex) = self.mutexes.get(id) {
+                for &waiter in mutex.wait_queue.iter() {
+                    let level = self.thread_manager.scheduler.priority_level(waiter);
+                    if level < new_level {
+                        new_level = level;
+                    }
+                }
+            }
+        }
 
-                if let Some(tcb) = self.thread_manager.threads.get_mut(&handle) {
-                    tcb.context.satp = satp_val;
-                    // Set argc (a0) and argv (a1)
-                    tcb.context
-                        .write_reg(ferrous_vm::Register::new(10).unwrap(), args_len as u32);
-                    tcb.context
-                        .write_reg(ferrous_vm::Register::new(11).unwrap(), argv_base);
+        self.thread_manager
+            .scheduler
+            .set_priority_level(thread, new_level);
+        if let Some(tcb) = self.thread_manager.threads.get_mut(&thread) {
+            tcb.effective_priority = new_level;
+            tcb.base_priority = if new_level == base { None } else { Some(base) };
+        }
+    }
 
-                    // Set program break
-                    let heap_start = (max_vaddr + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
-                    tcb.program_break = heap_start;
-                    info!(
-                        "Exec: Loaded max_vaddr={:#x}, Heap starts at {:#x}",
-                        max_vaddr, heap_start
-                    );
-                }
+    /// Resolve an `EndpointSend`/`EndpointRecv` capability slot for
+    /// `thread`, the same shape as `resolve_mutex_slot`.
+    fn resolve_endpoint_slot(
+        &self,
+        thread: crate::types::ThreadHandle,
+        slot: u32,
+        required: CapRights,
+    ) -> Result<Option<u32>, TrapError> {
+        let tcb = self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .ok_or(TrapError::HandlerPanic("resolve_endpoint_slot: unknown thread".into()))?;
+        let slot = crate::capability::CapSlot::from_raw(slot);
+        Ok(tcb.capabilities.check(slot, required).and_then(|cap| {
+            if let CapObject::Endpoint(id) = cap.object {
+                Some(id)
+            } else {
+                None
+            }
+        }))
+    }
 
-                info!("Exec spawned new process with handle: {:?}", handle);
-                syscall::Syscall::encode_result(
-                    Ok(syscall::SyscallReturn::Handle(handle.val())),
-                    cpu,
-                );
-                Ok(VirtAddr::new(cpu.pc + 4))
+    /// Drop every trace of `thread` from every endpoint's queues, called
+    /// before it exits so a `Send`/`Recv` it never got to finish doesn't
+    /// leave a stale waiter or message another thread blocks on forever.
+    fn remove_endpoint_waiter(&mut self, thread: crate::types::ThreadHandle) {
+        for endpoint in self.endpoints.values_mut() {
+            endpoint
+                .waiting_receivers
+                .retain(|waiter| waiter.thread != thread);
+            endpoint
+                .pending_sends
+                .retain(|(sender, _, _)| *sender != thread);
+        }
+    }
+
+    /// Resolve a `Connect`/`SendMessage`/`ReceiveMessage`/`ReturnMemory`
+    /// capability slot for `thread`, the same shape as `resolve_endpoint_slot`.
+    fn resolve_server_slot(
+        &self,
+        thread: crate::types::ThreadHandle,
+        slot: u32,
+        required: CapRights,
+    ) -> Result<Option<u32>, TrapError> {
+        let tcb = self
+            .thread_manager
+            .threads
+            .get(&thread)
+            .ok_or(TrapError::HandlerPanic("resolve_server_slot: unknown thread".into()))?;
+        let slot = crate::capability::CapSlot::from_raw(slot);
+        Ok(tcb.capabilities.check(slot, required).and_then(|cap| {
+            if let CapObject::Server(id) = cap.object {
+                Some(id)
+            } else {
+                None
             }
+        }))
+    }
+
+    /// Drop every trace of `thread` from every server's queues, called
+    /// before it exits so a `SendMessage`/`ReceiveMessage` it never got to
+    /// finish doesn't leave a stale waiter, message, or reply another
+    /// thread blocks on forever.
+    fn remove_server_waiter(&mut self, thread: crate::types::ThreadHandle) {
+        for server in self.servers.values_mut() {
+            server
+                .waiting_receivers
+                .retain(|waiter| waiter.thread != thread);
+            server.pending.retain(|message| message.sender != thread);
+            server.awaiting_reply.retain(|sender| *sender != thread);
         }
     }
 }
 
+/// Pack a `ReceiveMessage` reply's opcode and sender handle into the 8
+/// bytes `meta_ptr` points at: opcode as the first 4 bytes, the sender's
+/// raw `ThreadHandle` as the next 4, both little-endian -- the same
+/// out-pointer idiom `RecvFrom` uses to hand back a sender address
+/// alongside its payload.
+fn message_meta(opcode: u32, sender: crate::types::ThreadHandle) -> [u8; 8] {
+    let mut meta = [0u8; 8];
+    meta[0..4].copy_from_slice(&opcode.to_le_bytes());
+    meta[4..8].copy_from_slice(&sender.val().to_le_bytes());
+    meta
+}
+
+/// Install `descriptor` into the first free (`None`) slot of `table`,
+/// extending it only if every existing slot is taken, and refusing once
+/// `MAX_FILE_DESCRIPTORS` is reached -- `FileOpen` and `Dup` both allocate
+/// through this so closed descriptors actually get reused instead of the
+/// table only ever growing.
+fn alloc_fd_slot(
+    table: &mut Vec<Option<FileDescriptor>>,
+    descriptor: FileDescriptor,
+) -> Option<usize> {
+    if let Some(idx) = table.iter().position(|slot| slot.is_none()) {
+        table[idx] = Some(descriptor);
+        return Some(idx);
+    }
+    if table.len() >= thread::tcb::MAX_FILE_DESCRIPTORS {
+        return None;
+    }
+    table.push(Some(descriptor));
+    Some(table.len() - 1)
+}
+
 impl TrapHandler for Kernel {
     fn as_any(&mut self) -> &mut dyn std::any::Any {
         self
@@ -1028,6 +3837,26 @@ impl TrapHandler for Kernel {
         cause: TrapCause,
         cpu: &mut Cpu,
         memory: &mut dyn Memory,
+    ) -> Result<VirtAddr, TrapError> {
+        let resume = self.handle_trap_inner(cause, cpu, memory)?;
+        let pc = self.deliver_pending_signal(resume.val(), cpu, memory);
+        Ok(VirtAddr::new(pc))
+    }
+}
+
+impl Kernel {
+    /// The actual trap dispatch `handle_trap` used to be, before signal
+    /// delivery needed a chokepoint that runs after *every* path back to
+    /// user mode -- `handle_syscall`'s match has its own `return Ok(...)`
+    /// on nearly every arm, so a check added directly in `handle_trap`
+    /// wouldn't see most of them. Wrapping this as a plain inherent method
+    /// and having the trait's `handle_trap` call it, then post-process the
+    /// `Ok` result, catches all of them at once instead.
+    fn handle_trap_inner(
+        &mut self,
+        cause: TrapCause,
+        cpu: &mut Cpu,
+        memory: &mut dyn Memory,
     ) -> Result<VirtAddr, TrapError> {
         // Ensure current thread is tracked (lazy init of main thread)
         self.thread_manager.ensure_current_thread(cpu);
@@ -1037,17 +3866,643 @@ impl TrapHandler for Kernel {
                 self.handle_syscall(cpu, memory)
             }
             TrapCause::TimerInterrupt => {
-                // Preemption: Yield current thread
-                self.thread_manager.yield_thread(cpu);
+                // Preemption: only rotates once the scheduler's quantum for
+                // the running thread has actually expired.
+                self.thread_manager.on_timer_tick(cpu);
+                // Drain every NAT'd host socket the user-mode networking
+                // backend is bridging and synthesize any replies into
+                // `pending_rx`, ahead of `process_rx` below so they go out
+                // in the same tick they arrived on the host side.
+                net::driver::DRIVER.lock().pump_user_net();
+                // Drain any packets the NIC has queued since the last tick
+                // into their sockets' rx_queues, same cadence as scheduler
+                // preemption rather than a dedicated polling syscall. Any
+                // `RxWaiter` a delivery just satisfied gets copied out and
+                // woken below, the same "collect ready, caller delivers"
+                // split `take_ready_recv_waiters` uses for TCP.
+                let ready_rx = net::socket::process_rx(memory);
+                for waiter in ready_rx {
+                    self.deliver_rx_waiter(memory, waiter)?;
+                }
+                // Drive the DHCP client's DISCOVER/REQUEST retries and
+                // lease renewal, same cadence as `process_rx` above.
+                net::dhcp::on_timer_tick(memory);
+                // Retransmit unacked TCP segments whose deadline has
+                // elapsed and reap expired `TimeWait` connections, same
+                // cadence as `process_rx` above.
+                net::tcp::on_timer_tick(memory, net::syscalls::local_ip());
+                // Age ARP cache entries, same cadence as `process_rx` above.
+                net::ethernet::on_timer_tick();
+                // Hand each connection whose handshake just finished on
+                // the listening side to its listener's `Accept` waiter (or
+                // queue it), mirroring `connect_local`'s `ConnectOutcome`
+                // handling in `SocketConnect` for the loopback case.
+                for ready in net::tcp::take_ready_accepts() {
+                    if let Some(waiter) =
+                        net::socket::SOCKETS.lock().deliver_or_queue(ready.listener_id, ready.accepted_id)
+                    {
+                        let waiter_satp = self
+                            .thread_manager
+                            .threads
+                            .get(&waiter.thread)
+                            .map(|tcb| tcb.context.satp)
+                            .unwrap_or(0);
+                        let peer_addr = net::SockAddrIn {
+                            family: 2, // AF_INET
+                            port: net::syscalls::peer_port(ready.accepted_id).unwrap_or(0).to_be(),
+                            addr: u32::from_ne_bytes(net::syscalls::local_ip()),
+                            zero: [0; 8],
+                        };
+                        copy_to_user(memory, waiter_satp, peer_addr.as_bytes(), waiter.addr_ptr)?;
+                        let addr_len = core::mem::size_of::<net::SockAddrIn>() as u32;
+                        copy_to_user(
+                            memory,
+                            waiter_satp,
+                            &addr_len.to_le_bytes(),
+                            waiter.addrlen_ptr,
+                        )?;
+                        if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                            tcb.context.regs[10] = ready.accepted_id;
+                        }
+                        self.thread_manager.wake_thread(waiter.thread);
+                    }
+                }
+                // Wake every thread parked in `SocketConnect` whose
+                // handshake just resolved, success or not.
+                for (waiter, success) in net::tcp::take_ready_connects() {
+                    if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                        tcb.context.regs[10] = if success { 0 } else { u32::MAX };
+                    }
+                    self.thread_manager.wake_thread(waiter.thread);
+                }
+                // Wake every thread parked in a blocking `Recv` that now
+                // has data (or an EOF) to hand back.
+                for (waiter, data) in net::tcp::take_ready_recv_waiters() {
+                    let waiter_satp = self
+                        .thread_manager
+                        .threads
+                        .get(&waiter.thread)
+                        .map(|tcb| tcb.context.satp)
+                        .unwrap_or(0);
+                    let copy_len = data.len().min(waiter.len);
+                    copy_to_user(memory, waiter_satp, &data[..copy_len], waiter.buf_ptr)?;
+                    if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                        tcb.context.regs[10] = copy_len as u32;
+                    }
+                    self.thread_manager.wake_thread(waiter.thread);
+                }
+                // Wake every thread parked in `Poll` whose fds have become
+                // ready (or whose timeout just ran out), writing each
+                // ready `PollFd`'s `revents` back in place the same way
+                // `Recv`'s immediate-ready path does above.
+                for (waiter, hits) in net::poll::take_ready() {
+                    let waiter_satp = self
+                        .thread_manager
+                        .threads
+                        .get(&waiter.thread)
+                        .map(|tcb| tcb.context.satp)
+                        .unwrap_or(0);
+                    // `waiter.fds_ptr`/array layout was captured at park
+                    // time as `(fd, events)` pairs in the caller's array
+                    // order, so the ready subset's index into that same
+                    // order tells us which `PollFd` slot to patch.
+                    for (i, &(fd, events)) in waiter.fds.iter().enumerate() {
+                        let revents = hits
+                            .iter()
+                            .find(|&&(hit_fd, _)| hit_fd == fd)
+                            .map(|&(_, revents)| revents)
+                            .unwrap_or(0);
+                        let pf = net::PollFd { fd, events, revents };
+                        let slot_ptr = VirtAddr::new(
+                            waiter.fds_ptr.val()
+                                + (i * core::mem::size_of::<net::PollFd>()) as u32,
+                        );
+                        copy_to_user(memory, waiter_satp, pf.as_bytes(), slot_ptr)?;
+                    }
+                    if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                        tcb.context.regs[10] = hits.len() as u32;
+                    }
+                    self.thread_manager.wake_thread(waiter.thread);
+                }
+                // Resolve a blocked `BlockRead` once the block device's
+                // simulated latency window closes, the same polling-on-
+                // every-tick cadence `process_rx` uses above rather than a
+                // genuine PLIC claim (nothing below the PLIC claims an IRQ
+                // yet -- see `ExternalInterrupt` below).
+                if self.block_read_waiter.is_some() && !crate::fs::block::is_busy(memory).unwrap_or(false) {
+                    let waiter = self.block_read_waiter.take().unwrap();
+                    let satp = self
+                        .thread_manager
+                        .threads
+                        .get(&waiter.thread)
+                        .map(|tcb| tcb.context.satp)
+                        .unwrap_or(0);
+                    copy_to_user(memory, satp, &waiter.buffer, waiter.buf_ptr)?;
+                    if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+                        tcb.context.regs[10] = 0; // SyscallReturn::Success
+                    }
+                    self.thread_manager.wake_thread(waiter.thread);
+                }
+                Ok(VirtAddr::new(cpu.pc))
+            }
+            TrapCause::ExternalInterrupt => {
+                // `fs::block`/`net::socket` still poll their own device
+                // registers directly on every `TimerInterrupt` rather than
+                // reacting to a claimed IRQ number here -- see
+                // `plic::claim_and_complete`'s doc comment for why this
+                // drains the PLIC unconditionally instead of dispatching
+                // per-IRQ, the same way `UartDevice` is driven today.
+                let _ = crate::plic::claim_and_complete(memory);
                 Ok(VirtAddr::new(cpu.pc))
             }
+            TrapCause::StorePageFault { addr } => self.handle_page_fault(cause, addr, true, cpu, memory),
+            TrapCause::LoadPageFault { addr } | TrapCause::InstructionPageFault { addr } => {
+                self.handle_page_fault(cause, addr, false, cpu, memory)
+            }
             _ => Err(TrapError::Unhandled(cause)),
         }
     }
+
+    /// Deliver the lowest-numbered deliverable signal against the current
+    /// thread, if any, on the way back to user mode. A no-op if a handler
+    /// is already running (`signal_saved_context` is `Some`) -- signals
+    /// stay pending rather than nesting, the same single-handler-at-a-time
+    /// simplification real kernels relax with a signal mask `sigprocmask`
+    /// manipulates but this one doesn't expose. Returns the pc execution
+    /// should actually resume at: `resume_pc` unchanged if nothing was
+    /// delivered, or the registered handler's entry point (with `a0` set
+    /// to the signal number) otherwise.
+    fn deliver_pending_signal(
+        &mut self,
+        resume_pc: u32,
+        cpu: &mut Cpu,
+        memory: &mut dyn Memory,
+    ) -> u32 {
+        let Some(handle) = self.thread_manager.current_thread else {
+            return resume_pc;
+        };
+        let Some(tcb) = self.thread_manager.threads.get_mut(&handle) else {
+            return resume_pc;
+        };
+        if tcb.signal_saved_context.is_some() {
+            return resume_pc;
+        }
+        let deliverable = tcb.pending_signals & !tcb.blocked_signals;
+        if deliverable == 0 {
+            return resume_pc;
+        }
+        let signum = deliverable.trailing_zeros();
+        tcb.pending_signals &= !(1 << signum);
+        let handler = tcb.signal_handlers[signum as usize];
+        if handler == 0 {
+            // Default disposition: terminate the thread, same
+            // "terminated by signal" convention `handle_page_fault`
+            // uses for an unhandled SIGSEGV (128 + signal number).
+            if let Some(root_ppn) = self.thread_manager.exit_current_thread(128 + signum as i32) {
+                if let Err(e) = memory::release_address_space(memory, root_ppn) {
+                    warn!("deliver_pending_signal: failed to release address space: {}", e);
+                }
+            }
+            return resume_pc;
+        }
+
+        // Not `save_from(cpu)`: `cpu.pc` is still the pre-trap address at
+        // this point (the VM's run loop only writes it after `handle_trap`
+        // returns), so the pc `Sigreturn` must restore is `resume_pc`, not
+        // `cpu.pc`.
+        let tcb = self.thread_manager.threads.get_mut(&handle).unwrap();
+        tcb.signal_saved_context = Some(thread::tcb::SavedContext {
+            pc: resume_pc,
+            regs: cpu.regs,
+            satp: cpu.satp,
+            mode: cpu.mode,
+        });
+        cpu.regs[10] = signum;
+        handler
+    }
+    /// Deliver the datagram that just woke `waiter`'s parked `RecvFrom` --
+    /// popping it back off `waiter.fd`'s `rx_queue` (guaranteed non-empty,
+    /// since the caller only gets a `RxWaiter` back from `take_rx_waiter`
+    /// once a delivery path has just pushed onto it), copying it into the
+    /// caller's buffer/address out-params the same way `RecvFrom`'s own
+    /// immediate-data branch does, and waking the parked thread. Shared by
+    /// `SendTo`'s `AF_UNIX` loopback path (synchronous) and
+    /// `TimerInterrupt`'s drain of `net::socket::process_rx`'s UDP
+    /// deliveries.
+    fn deliver_rx_waiter(
+        &mut self,
+        memory: &mut dyn Memory,
+        waiter: net::socket::RxWaiter,
+    ) -> Result<(), TrapError> {
+        let Some(packet) = net::syscalls::recv_from(waiter.fd) else {
+            return Ok(());
+        };
+        let waiter_satp = self
+            .thread_manager
+            .threads
+            .get(&waiter.thread)
+            .map(|tcb| tcb.context.satp)
+            .unwrap_or(0);
+
+        let copy_len = packet.payload.len().min(waiter.len);
+        copy_to_user(memory, waiter_satp, &packet.payload[..copy_len], waiter.buf_ptr)?;
+
+        let addr_len = if net::syscalls::is_unix(waiter.fd) {
+            let name = match packet.src {
+                net::socket::SocketAddr::Unix { name } => name,
+                net::socket::SocketAddr::Inet { .. } => [0; net::UNIX_NAME_MAX],
+            };
+            let src_addr = net::SockAddrUn {
+                family: net::AF_UNIX as u16,
+                name,
+            };
+            copy_to_user(memory, waiter_satp, src_addr.as_bytes(), waiter.src_ptr)?;
+            core::mem::size_of::<net::SockAddrUn>() as u32
+        } else {
+            let (ip, port) = match packet.src {
+                net::socket::SocketAddr::Inet { ip, port } => (ip, port),
+                net::socket::SocketAddr::Unix { .. } => ([0; 4], 0),
+            };
+            let src_addr = net::SockAddrIn {
+                family: net::AF_INET as u16,
+                port: port.to_be(),
+                addr: u32::from_ne_bytes(ip),
+                zero: [0; 8],
+            };
+            copy_to_user(memory, waiter_satp, src_addr.as_bytes(), waiter.src_ptr)?;
+            core::mem::size_of::<net::SockAddrIn>() as u32
+        };
+        copy_to_user(memory, waiter_satp, &addr_len.to_le_bytes(), waiter.src_len_ptr)?;
+
+        if let Some(tcb) = self.thread_manager.threads.get_mut(&waiter.thread) {
+            tcb.context.regs[10] = copy_len as u32;
+        }
+        self.thread_manager.wake_thread(waiter.thread);
+        Ok(())
+    }
+
+    /// Service a page fault that might resolve on its own instead of
+    /// killing the machine: a COW-protected store (`try_cow`, `StorePageFault`
+    /// only — a COW page is otherwise fully readable/executable, so only a
+    /// write ever faults on one) gets first crack at it, then a `PT_LOAD`
+    /// segment of the faulting thread's own image (backed from `reserve_
+    /// elf_segments`, enforcing each segment's own W^X permissions), then
+    /// any cause falls through to a purely anonymous `PTE_LAZY` reservation
+    /// left by `Sbrk`/`Mmap`. If none of those explain the fault it's
+    /// genuine, and only the faulting thread pays for it: it's killed with
+    /// the POSIX "terminated by SIGSEGV" status (128 + 11) instead of the
+    /// whole VM going down.
+    fn handle_page_fault(
+        &mut self,
+        cause: TrapCause,
+        addr: VirtAddr,
+        try_cow: bool,
+        cpu: &mut Cpu,
+        memory: &mut dyn Memory,
+    ) -> Result<VirtAddr, TrapError> {
+        let root_ppn = cpu.satp & 0x003F_FFFF;
+
+        if try_cow {
+            match memory::resolve_cow_fault(memory, root_ppn, addr.val()) {
+                Ok(true) => return Ok(VirtAddr::new(cpu.pc)),
+                Ok(false) => {}
+                Err(e) if e == "out of physical memory" => {
+                    return self.kill_faulting_thread(cause, "COW", addr, cpu, memory);
+                }
+                Err(e) => {
+                    return Err(TrapError::HandlerPanic(format!(
+                        "{:?}: COW resolve failed: {:?}",
+                        cause, e
+                    )))
+                }
+            }
+        }
+
+        if let Some(current) = self.thread_manager.current_thread {
+            if let Some(tcb) = self.thread_manager.threads.get(&current) {
+                if !tcb.segments.is_empty() {
+                    match memory::resolve_segment_fault(
+                        memory,
+                        root_ppn,
+                        addr.val(),
+                        try_cow,
+                        &tcb.segments,
+                        &tcb.image,
+                    ) {
+                        Ok(true) => return Ok(VirtAddr::new(cpu.pc)),
+                        Ok(false) => {}
+                        Err(e) if e == "out of physical memory" => {
+                            return self.kill_faulting_thread(
+                                cause, "segment-fault", addr, cpu, memory,
+                            );
+                        }
+                        Err(e) => {
+                            return Err(TrapError::HandlerPanic(format!(
+                                "{:?}: segment-fault resolve failed: {:?}",
+                                cause, e
+                            )))
+                        }
+                    }
+                }
+            }
+        }
+
+        match memory::resolve_lazy_fault(memory, root_ppn, addr.val()) {
+            Ok(true) => Ok(VirtAddr::new(cpu.pc)),
+            Ok(false) => self.kill_faulting_thread(cause, "fatal page fault", addr, cpu, memory),
+            // A frame-allocator exhaustion here is the guest's problem, not
+            // this emulator's: a lazily-reserved `mmap`/heap/segment page
+            // the allocator can no longer back is exactly what a real
+            // kernel answers with SIGSEGV/OOM-kill rather than a machine
+            // check, so it's killed the same way an unresolvable fault
+            // above is instead of propagating into `HandlerPanic` and
+            // taking the whole VM down with it.
+            Err(e) if e == "out of physical memory" => {
+                self.kill_faulting_thread(cause, "out of physical memory", addr, cpu, memory)
+            }
+            Err(e) => Err(TrapError::HandlerPanic(format!(
+                "{:?}: lazy-fault resolve failed: {:?}",
+                cause, e
+            ))),
+        }
+    }
+
+    /// Exit the thread that just took an unrecoverable fault (an
+    /// unresolvable page fault or a frame-allocator OOM while resolving
+    /// one) and resume whatever's runnable next, the same "kill one guest
+    /// thread, not the whole VM" outcome `resolve_lazy_fault`'s original
+    /// fatal-fault arm already had -- shared so the COW/segment-fault arms
+    /// above can answer their own OOM case identically instead of falling
+    /// through to `HandlerPanic`. `Err(TrapError::Halt)` if that was the
+    /// last runnable thread, matching the original fatal-fault arm.
+    fn kill_faulting_thread(
+        &mut self,
+        cause: TrapCause,
+        reason: &str,
+        addr: VirtAddr,
+        cpu: &mut Cpu,
+        memory: &mut dyn Memory,
+    ) -> Result<VirtAddr, TrapError> {
+        info!(
+            "{:?}: {} at {:#x}, killing thread",
+            cause,
+            reason,
+            addr.val()
+        );
+        if let Some(root_ppn) = self.thread_manager.exit_current_thread(139) {
+            memory::release_address_space(memory, root_ppn).map_err(TrapError::HandlerPanic)?;
+        }
+        self.thread_manager.yield_thread(cpu);
+        if self.thread_manager.current_thread.is_none() {
+            Err(TrapError::Halt)
+        } else {
+            Ok(VirtAddr::new(cpu.pc))
+        }
+    }
+}
+
+/// Reserve every `PT_LOAD` segment of `elf` as a lazily-backed mapping
+/// (`memory::reserve_lazy_page`) carrying the segment's own permissions
+/// translated from `ph.p_flags`, and return the `Segment` descriptors the
+/// page-fault handler needs to back them plus the highest mapped vaddr
+/// (the caller's heap start). Shared by `bootstrap_process` and
+/// `Syscall::Exec`, which otherwise duplicate everything else about
+/// setting up a fresh address space.
+fn reserve_elf_segments(
+    memory: &mut dyn Memory,
+    root_ppn: u32,
+    elf: &elf::Elf,
+) -> Result<(Vec<memory::Segment>, u32), TrapError> {
+    let mut segments = Vec::new();
+    let mut max_vaddr = 0;
+
+    for ph in elf.program_headers.iter() {
+        if ph.p_type != elf::program_header::PT_LOAD {
+            continue;
+        }
+
+        let vaddr_start = ph.p_vaddr as u32;
+        let end_vaddr = vaddr_start + ph.p_memsz as u32;
+        if end_vaddr > max_vaddr {
+            max_vaddr = end_vaddr;
+        }
+
+        let mut flags = memory::PTE_U;
+        if ph.p_flags & elf::program_header::PF_R != 0 {
+            flags |= memory::PTE_R;
+        }
+        if ph.p_flags & elf::program_header::PF_W != 0 {
+            flags |= memory::PTE_W;
+        }
+        if ph.p_flags & elf::program_header::PF_X != 0 {
+            flags |= memory::PTE_X;
+        }
+
+        segments.push(memory::Segment {
+            vaddr_start,
+            vaddr_end: end_vaddr,
+            file_offset: ph.p_offset as u32,
+            file_size: ph.p_filesz as u32,
+            flags,
+        });
+
+        let page_start = vaddr_start & !(memory::PAGE_SIZE - 1);
+        let page_end = (end_vaddr + memory::PAGE_SIZE - 1) & !(memory::PAGE_SIZE - 1);
+        let mut page = page_start;
+        while page < page_end {
+            memory::reserve_lazy_page(memory, root_ppn, page, flags)
+                .map_err(TrapError::HandlerPanic)?;
+            page += memory::PAGE_SIZE;
+        }
+    }
+
+    Ok((segments, max_vaddr))
+}
+
+/// How many pages at the very top of a fresh stack get a real frame up
+/// front, since `build_initial_stack` writes argv/envp/auxv into them
+/// directly rather than through a page-fault path that could back them
+/// lazily.
+const STACK_EAGER_PAGES: u32 = 4;
+
+/// Nominal max stack size below `stack_top`, the same role `RLIMIT_STACK`
+/// plays for a real process: a fault below this is a genuine stack
+/// overflow, not stack growth.
+const STACK_MAX_SIZE: u32 = 8 * 1024 * 1024;
+
+/// Reserve a fresh process's user stack below `stack_top`: the top
+/// `STACK_EAGER_PAGES` get backed with real frames immediately (so
+/// `build_initial_stack`'s direct writes land somewhere), and the rest of
+/// the stack's `STACK_MAX_SIZE` range below that is lazily reserved via
+/// `reserve_lazy_page`, the same `PTE_LAZY` mechanism `Sbrk`/`Mmap` use for
+/// an anonymous mapping. `handle_page_fault`'s `resolve_lazy_fault` fallback
+/// then backs a page the first time it's actually touched, so the stack
+/// grows one page at a time as deep calls/locals demand it instead of
+/// capping out at a hardcoded page count -- a fault below the reserved
+/// range still falls through to a genuine SIGSEGV, since nothing reserved
+/// it.
+fn reserve_user_stack(memory: &mut dyn Memory, root_ppn: u32, stack_top: u32) -> Result<(), TrapError> {
+    let flags = memory::PTE_R | memory::PTE_W | memory::PTE_U;
+
+    for i in 0..STACK_EAGER_PAGES {
+        let vaddr = stack_top - ((i + 1) * memory::PAGE_SIZE);
+        let frame = memory::alloc_frame()
+            .ok_or_else(|| TrapError::HandlerPanic("out of physical memory".into()))?;
+        memory::map_page(memory, root_ppn, vaddr, frame, flags, memory::PageLevel::Kb4)
+            .map_err(TrapError::HandlerPanic)?;
+    }
+
+    let stack_bottom = stack_top - STACK_MAX_SIZE;
+    let mut vaddr = stack_top - STACK_EAGER_PAGES * memory::PAGE_SIZE;
+    while vaddr > stack_bottom {
+        vaddr -= memory::PAGE_SIZE;
+        memory::reserve_lazy_page(memory, root_ppn, vaddr, flags).map_err(TrapError::HandlerPanic)?;
+    }
+
+    Ok(())
+}
+
+/// `AT_*` auxiliary-vector entry types this kernel populates, matching
+/// their standard Linux/ELF numbers so a libc's `_start` reads them the
+/// same way it would on a real kernel.
+const AT_NULL: u32 = 0;
+const AT_PHDR: u32 = 3;
+const AT_PHENT: u32 = 4;
+const AT_PHNUM: u32 = 5;
+const AT_PAGESZ: u32 = 6;
+const AT_ENTRY: u32 = 9;
+const AT_RANDOM: u32 = 25;
+const AT_EXECFN: u32 = 31;
+
+/// Resolve the vaddr of `elf`'s program-header table for `AT_PHDR`. The
+/// table isn't mapped as a segment of its own, but a well-formed executable
+/// always has it within the file range of one of its `PT_LOAD` segments, so
+/// this finds that segment and offsets from its vaddr the same way the
+/// Linux kernel's own ELF loader resolves `AT_PHDR`. Returns 0 (a provably
+/// unmapped address, same as `AT_PHDR` being absent) if no loaded segment
+/// covers it.
+fn phdr_vaddr(elf: &elf::Elf, segments: &[memory::Segment]) -> u32 {
+    let phoff = elf.header.e_phoff as u32;
+    segments
+        .iter()
+        .find(|seg| phoff >= seg.file_offset && phoff < seg.file_offset + seg.file_size)
+        .map(|seg| seg.vaddr_start + (phoff - seg.file_offset))
+        .unwrap_or(0)
+}
+
+/// Write every byte of `bytes` to the UART's transmit-holding register,
+/// shared by `ConsoleWrite` and a `FileWrite` on a `console:`-scheme
+/// descriptor so both go through the same driver code.
+fn uart_write(memory: &mut dyn Memory, bytes: &[u8]) -> Result<(), TrapError> {
+    for &byte in bytes {
+        memory
+            .write_word(
+                ferrous_vm::PhysAddr::new(UART_BASE + UART_THR_OFFSET),
+                byte as u32,
+            )
+            .map_err(|e| TrapError::HandlerPanic(format!("UART write error: {:?}", e)))?;
+    }
+    Ok(())
+}
+
+/// Read up to `len` bytes from the UART: blocks on the first byte (a
+/// read at `UART_RBR_OFFSET` triggers the device read), then drains
+/// whatever else is already buffered non-blockingly, stopping early at a
+/// newline the same way a line-buffered terminal would. Shared by
+/// `ConsoleRead` and a `FileRead` on a `console:`-scheme descriptor.
+fn uart_read(memory: &mut dyn Memory, len: usize) -> Result<Vec<u8>, TrapError> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut read_buf = Vec::new();
+
+    let val = memory
+        .read_word(ferrous_vm::PhysAddr::new(UART_BASE + UART_RBR_OFFSET))
+        .map_err(|e| TrapError::HandlerPanic(format!("UART read error: {:?}", e)))?;
+
+    if val == 0 {
+        // EOF on first byte
+        return Ok(read_buf);
+    }
+    read_buf.push(val as u8);
+
+    let limit = len.min(1024);
+    while read_buf.len() < limit {
+        let lsr = memory
+            .read_word(ferrous_vm::PhysAddr::new(UART_BASE + UART_LSR_OFFSET))
+            .map_err(|e| TrapError::HandlerPanic(format!("UART LSR read error: {:?}", e)))?;
+
+        if (lsr & 0x01) == 0 {
+            break; // No more data
+        }
+
+        let val = memory
+            .read_word(ferrous_vm::PhysAddr::new(UART_BASE + UART_RBR_OFFSET))
+            .map_err(|e| TrapError::HandlerPanic(format!("UART read error: {:?}", e)))?;
+
+        if val == 0 {
+            break; // EOF
+        }
+        read_buf.push(val as u8);
+
+        if val == 10 || val == 13 {
+            break; // Newline
+        }
+    }
+
+    Ok(read_buf)
 }
 
 // Helper functions for user memory access
-fn translate_vaddr(memory: &mut dyn Memory, satp: u32, vaddr: u32) -> Result<u32, TrapError> {
+
+/// Which kind of access `translate_vaddr` is authorizing, so it enforces
+/// the matching Sv32 permission bit instead of only checking `PTE_V`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MemAccess {
+    Load,
+    Store,
+    Fetch,
+}
+
+impl MemAccess {
+    fn required_pte_bit(self) -> u32 {
+        match self {
+            MemAccess::Load => crate::memory::PTE_R,
+            MemAccess::Store => crate::memory::PTE_W,
+            MemAccess::Fetch => crate::memory::PTE_X,
+        }
+    }
+
+    fn page_fault(self, addr: VirtAddr) -> TrapCause {
+        match self {
+            MemAccess::Load => TrapCause::LoadPageFault { addr },
+            MemAccess::Store => TrapCause::StorePageFault { addr },
+            MemAccess::Fetch => TrapCause::InstructionPageFault { addr },
+        }
+    }
+}
+
+/// Translate `vaddr` under `satp`, enforcing the Sv32 permission rules a
+/// real MMU would instead of just checking `PTE_V`: the leaf must carry
+/// the bit `access` needs, and `PTE_U` must be set when `user_mode` is
+/// true. Every caller here is the kernel reaching into a process's own
+/// memory on its behalf rather than a guest S-mode fetch, so a
+/// `user_mode: false` caller is implicitly granted the equivalent of
+/// `mstatus.SUM` — there's no separate supervisor-mode guest code in this
+/// kernel for that bit to protect against. On success, `PTE_A` (and
+/// `PTE_D` for a store) is set on the leaf, same as the hardware walker
+/// in `ferrous_vm::mmu` does. A violation comes back as `TrapError::
+/// Unhandled` carrying the `TrapCause` a real MMU would raise for it, so
+/// a caller that wants to can route it straight into the page-fault path
+/// instead of treating every failure as a fatal `HandlerPanic`.
+fn translate_vaddr(
+    memory: &mut dyn Memory,
+    satp: u32,
+    vaddr: u32,
+    access: MemAccess,
+    user_mode: bool,
+) -> Result<u32, TrapError> {
     // Check Mode (MSB of SATP)
     // If Mode is 0, Bare mode (Physical = Virtual)
     if (satp & 0x8000_0000) == 0 {
@@ -1058,6 +4513,7 @@ fn translate_vaddr(memory: &mut dyn Memory, satp: u32, vaddr: u32) -> Result<u32
     let vpn1 = (vaddr >> 22) & 0x3FF;
     let vpn0 = (vaddr >> 12) & 0x3FF;
     let offset = vaddr & 0xFFF;
+    let addr = VirtAddr::new(vaddr);
 
     let l1_pte_addr = ferrous_vm::PhysAddr::new((root_ppn << 12) + (vpn1 * 4));
     let l1_pte = memory
@@ -1065,52 +4521,147 @@ fn translate_vaddr(memory: &mut dyn Memory, satp: u32, vaddr: u32) -> Result<u32
         .map_err(|e| TrapError::HandlerPanic(format!("L1 read error: {:?}", e)))?;
 
     if (l1_pte & crate::memory::PTE_V) == 0 {
-        return Err(TrapError::HandlerPanic("Page fault (L1 invalid)".into()));
+        return Err(TrapError::Unhandled(access.page_fault(addr)));
+    }
+
+    // A leaf at the first level (R/W/X set) is a 4 MiB superpage: resolve it
+    // directly against the full 22-bit page offset instead of descending to
+    // an L0 table that doesn't exist for this mapping.
+    let is_superpage =
+        l1_pte & (crate::memory::PTE_R | crate::memory::PTE_W | crate::memory::PTE_X) != 0;
+
+    let (leaf_addr, leaf_pte, leaf_offset) = if is_superpage {
+        (l1_pte_addr, l1_pte, vaddr & 0x3F_FFFF)
+    } else {
+        let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+        let l0_pte_addr = ferrous_vm::PhysAddr::new((l0_ppn << 12) + (vpn0 * 4));
+        let l0_pte = memory
+            .read_word(l0_pte_addr)
+            .map_err(|e| TrapError::HandlerPanic(format!("L0 read error: {:?}", e)))?;
+
+        if (l0_pte & crate::memory::PTE_V) == 0 {
+            return Err(TrapError::Unhandled(access.page_fault(addr)));
+        }
+
+        (l0_pte_addr, l0_pte, offset)
+    };
+
+    if user_mode && (leaf_pte & crate::memory::PTE_U) == 0 {
+        return Err(TrapError::Unhandled(access.page_fault(addr)));
     }
 
-    let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
-    let l0_pte_addr = ferrous_vm::PhysAddr::new((l0_ppn << 12) + (vpn0 * 4));
-    let l0_pte = memory
-        .read_word(l0_pte_addr)
-        .map_err(|e| TrapError::HandlerPanic(format!("L0 read error: {:?}", e)))?;
+    if leaf_pte & access.required_pte_bit() == 0 {
+        return Err(TrapError::Unhandled(access.page_fault(addr)));
+    }
 
-    if (l0_pte & crate::memory::PTE_V) == 0 {
-        return Err(TrapError::HandlerPanic("Page fault (L0 invalid)".into()));
+    let mut updated = leaf_pte | crate::memory::PTE_A;
+    if access == MemAccess::Store {
+        updated |= crate::memory::PTE_D;
+    }
+    if updated != leaf_pte {
+        memory
+            .write_word(leaf_addr, updated)
+            .map_err(|e| TrapError::HandlerPanic(format!("leaf PTE update error: {:?}", e)))?;
     }
 
-    let ppn = (l0_pte >> 10) & 0x3F_FFFF;
-    let paddr = (ppn << 12) | offset;
+    let ppn = (leaf_pte >> 10) & 0x3F_FFFF;
+    let paddr = (ppn << 12) | leaf_offset;
     Ok(paddr)
 }
 
+/// Copy `dest.len()` bytes out of user memory starting at `src_ptr`,
+/// translating once per 4 KiB page instead of once per byte: each page's
+/// worth of the range is sliced straight out of the resolved physical
+/// frame in one `copy_from_slice`, and `translate_vaddr` only runs again
+/// once `vaddr` crosses into the next page. `translate_vaddr` rejects a
+/// page lacking `PTE_U` (or, on `copy_to_user`, `PTE_W`) before any bytes
+/// are touched, so a malicious user pointer into kernel-only pages comes
+/// back as a clean `TrapError` instead of a silent out-of-bounds write.
 fn copy_from_user(
     memory: &mut dyn Memory,
     satp: u32,
     src_ptr: VirtAddr,
     dest: &mut [u8],
 ) -> Result<(), TrapError> {
-    for (i, byte) in dest.iter_mut().enumerate() {
-        let vaddr = src_ptr.val() + i as u32;
-        let paddr = translate_vaddr(memory, satp, vaddr)?;
-        *byte = memory
-            .read_byte(ferrous_vm::PhysAddr::new(paddr))
+    let mut copied = 0;
+    while copied < dest.len() {
+        let vaddr = src_ptr.val() + copied as u32;
+        let paddr = translate_vaddr(memory, satp, vaddr, MemAccess::Load, true)?;
+        let offset_in_page = (vaddr % memory::PAGE_SIZE) as usize;
+        let chunk = (memory::PAGE_SIZE as usize - offset_in_page).min(dest.len() - copied);
+
+        let page = memory
+            .slice_mut(ferrous_vm::PhysAddr::new(paddr), chunk)
             .map_err(|e| TrapError::HandlerPanic(format!("User read error: {:?}", e)))?;
+        dest[copied..copied + chunk].copy_from_slice(page);
+
+        copied += chunk;
     }
     Ok(())
 }
 
+/// Copy `src` into user memory starting at `dest_ptr`, with the same
+/// per-page single-walk structure as `copy_from_user`.
 fn copy_to_user(
     memory: &mut dyn Memory,
     satp: u32,
     src: &[u8],
     dest_ptr: VirtAddr,
 ) -> Result<(), TrapError> {
-    for (i, byte) in src.iter().enumerate() {
-        let vaddr = dest_ptr.val() + i as u32;
-        let paddr = translate_vaddr(memory, satp, vaddr)?;
-        memory
-            .write_byte(ferrous_vm::PhysAddr::new(paddr), *byte)
+    let mut copied = 0;
+    while copied < src.len() {
+        let vaddr = dest_ptr.val() + copied as u32;
+        let paddr = translate_vaddr(memory, satp, vaddr, MemAccess::Store, true)?;
+        let offset_in_page = (vaddr % memory::PAGE_SIZE) as usize;
+        let chunk = (memory::PAGE_SIZE as usize - offset_in_page).min(src.len() - copied);
+
+        let page = memory
+            .slice_mut(ferrous_vm::PhysAddr::new(paddr), chunk)
             .map_err(|e| TrapError::HandlerPanic(format!("User write error: {:?}", e)))?;
+        page.copy_from_slice(&src[copied..copied + chunk]);
+
+        copied += chunk;
     }
     Ok(())
 }
+
+/// Translate the `len`-byte user range starting at `ptr` and return one
+/// mutable slice per page it spans, so a syscall handler (`FileRead`,
+/// `FileWrite`) can scatter/gather straight into the filesystem or pipe
+/// layer instead of staging through an intermediate byte buffer. Stops at
+/// (and reports) the first unmapped page rather than handing back a
+/// partially-translated buffer list.
+///
+/// The returned slices borrow the VM's backing RAM, not `memory` itself:
+/// each is built from a raw pointer immediately after translation so nothing
+/// here holds more than one reborrow of `memory` at a time, the same way
+/// `rCore`'s `translated_byte_buffer` this is modeled on does. Every slice
+/// still only ever covers its own disjoint page, so nothing actually
+/// aliases even though the borrow checker can't see that on its own.
+pub(crate) fn translated_byte_buffer(
+    memory: &mut dyn Memory,
+    satp: u32,
+    ptr: VirtAddr,
+    len: usize,
+) -> Result<Vec<&'static mut [u8]>, TrapError> {
+    let mut pages = Vec::new();
+    let mut offset = 0;
+    while offset < len {
+        let vaddr = ptr.val() + offset as u32;
+        let paddr = translate_vaddr(memory, satp, vaddr, MemAccess::Store, true)?;
+        let offset_in_page = (vaddr % memory::PAGE_SIZE) as usize;
+        let chunk = (memory::PAGE_SIZE as usize - offset_in_page).min(len - offset);
+
+        let raw_ptr = memory
+            .slice_mut(ferrous_vm::PhysAddr::new(paddr), chunk)
+            .map_err(|e| TrapError::HandlerPanic(format!("User buffer error: {:?}", e)))?
+            .as_mut_ptr();
+        // SAFETY: each iteration covers a disjoint page-aligned span of
+        // the VM's backing RAM, and the VM outlives every syscall handler
+        // this buffer is built for.
+        pages.push(unsafe { core::slice::from_raw_parts_mut(raw_ptr, chunk) });
+
+        offset += chunk;
+    }
+    Ok(pages)
+}
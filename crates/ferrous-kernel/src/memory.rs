@@ -1,7 +1,11 @@
 use alloc::format;
 use alloc::string::String;
+use alloc::vec::Vec;
 use ferrous_vm::{Memory, PhysAddr};
 use log::debug;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
 
 // Page Size
 pub const PAGE_SIZE: u32 = 4096;
@@ -15,33 +19,174 @@ pub const PTE_U: u32 = 1 << 4;
 pub const PTE_G: u32 = 1 << 5;
 pub const PTE_A: u32 = 1 << 6;
 pub const PTE_D: u32 = 1 << 7;
+/// Bit 8 is RSW (reserved for supervisor software use) in the Sv32 PTE
+/// format, so it's free for us to mark a leaf as a lazily-backed mapping:
+/// `PTE_V` stays clear — the MMU faults on first touch exactly like an
+/// unmapped page — but the permission bits and this marker are already in
+/// place for the page-fault handler to find and back with a real frame.
+pub const PTE_LAZY: u32 = 1 << 8;
+
+/// Base of the region `mmap` hands out anonymous mappings from, growing
+/// upward. Kept well clear of the heap/stack addresses around
+/// `0x8000_0000` so the two allocators never collide despite both being
+/// simple bump pointers.
+pub const MMAP_BASE: u32 = 0x4000_0000;
 
 // SATP Mode (SV32)
 pub const SATP_MODE_SV32: u32 = 1 << 31;
 
-// Simple Bump Allocator for Frames (Physical Memory)
-// Start after Kernel (assuming 4MB for Kernel code/data)
-static mut NEXT_FREE_FRAME: u32 = 0x8040_0000;
+// Physical Frame Allocator. Frames start after the kernel's own 4MB of
+// code/data and run to the end of whatever RAM the VM was configured
+// with; `free`/`allocated` mirror the Xous emulator's design of tracking
+// both sets explicitly rather than trusting a pointer that only ever
+// moves forward.
+const FRAME_BASE: u32 = 0x8040_0000;
+const RAM_BASE: u32 = 0x8000_0000;
+
+/// RAM size assumed until `init_frame_allocator` runs with the real
+/// configured size (matches `ferrous-cli`'s own `--memory` default), so
+/// anything that allocates a frame before that point — or a test that
+/// never calls it at all — still gets a usable pool instead of none.
+const DEFAULT_RAM_SIZE: u32 = 16 * 1024 * 1024;
+
+struct FrameAllocator {
+    free: BTreeSet<u32>,
+    allocated: BTreeSet<u32>,
+}
 
-pub fn alloc_frame() -> u32 {
-    unsafe {
-        let addr = NEXT_FREE_FRAME;
-        NEXT_FREE_FRAME += PAGE_SIZE;
-        addr
+impl FrameAllocator {
+    fn for_ram_size(ram_size: u32) -> Self {
+        let end_of_ram = RAM_BASE.saturating_add(ram_size);
+        let mut free = BTreeSet::new();
+        let mut addr = FRAME_BASE;
+        while addr < end_of_ram {
+            free.insert(addr);
+            addr += PAGE_SIZE;
+        }
+        Self {
+            free,
+            allocated: BTreeSet::new(),
+        }
     }
 }
 
-pub fn map_page(
-    memory: &mut dyn Memory,
-    root_ppn: u32,
-    vaddr: u32,
-    paddr: u32,
-    flags: u32,
-) -> Result<(), String> {
+static FRAME_ALLOCATOR: OnceLock<Mutex<FrameAllocator>> = OnceLock::new();
+
+fn frame_allocator() -> &'static Mutex<FrameAllocator> {
+    FRAME_ALLOCATOR.get_or_init(|| Mutex::new(FrameAllocator::for_ram_size(DEFAULT_RAM_SIZE)))
+}
+
+/// Size the managed frame region to `[0x8040_0000, 0x8000_0000 + ram_size)`
+/// to match the VM's actual configured RAM. Called once, before the VM
+/// starts running guest code; if something already allocated a frame from
+/// the lazily-initialized default pool by the time this runs, the existing
+/// pool is left alone rather than silently invalidating live allocations.
+pub fn init_frame_allocator(ram_size: u32) {
+    match FRAME_ALLOCATOR.get() {
+        None => {
+            let _ = FRAME_ALLOCATOR.set(Mutex::new(FrameAllocator::for_ram_size(ram_size)));
+        }
+        Some(existing) => {
+            let mut alloc = existing.lock().unwrap();
+            if alloc.allocated.is_empty() {
+                *alloc = FrameAllocator::for_ram_size(ram_size);
+            }
+        }
+    }
+}
+
+/// Hand out the lowest-addressed free frame, or `None` if the managed
+/// region is exhausted.
+pub fn alloc_frame() -> Option<u32> {
+    let mut alloc = frame_allocator().lock().unwrap();
+    let frame = *alloc.free.iter().next()?;
+    alloc.free.remove(&frame);
+    alloc.allocated.insert(frame);
+    Some(frame)
+}
+
+/// Return `paddr` to the free pool. A no-op if it wasn't actually handed
+/// out by `alloc_frame` (already free, or outside the managed region).
+pub fn free_frame(paddr: u32) {
+    let mut alloc = frame_allocator().lock().unwrap();
+    if alloc.allocated.remove(&paddr) {
+        alloc.free.insert(paddr);
+    }
+}
+
+/// Number of frames still unallocated in the managed region, for tests and
+/// for an eventual `meminfo` syscall to report available memory from.
+pub fn free_frame_count() -> usize {
+    frame_allocator().lock().unwrap().free.len()
+}
+
+/// Per-physical-frame reference counts used for copy-on-write sharing after
+/// `fork_address_space`. A frame absent from this table is implicitly
+/// exclusive (refcount 1); only frames actually shared between a parent and
+/// a child address space are tracked here.
+static FRAME_REFCOUNTS: OnceLock<Mutex<HashMap<u32, u32>>> = OnceLock::new();
+
+fn refcounts() -> &'static Mutex<HashMap<u32, u32>> {
+    FRAME_REFCOUNTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Number of address spaces currently sharing `frame`.
+pub fn frame_refcount(frame: u32) -> u32 {
+    *refcounts().lock().unwrap().get(&frame).unwrap_or(&1)
+}
+
+fn share_frame(frame: u32) {
+    let mut table = refcounts().lock().unwrap();
+    *table.entry(frame).or_insert(1) += 1;
+}
+
+/// Drop one reference to `frame`. Once the count falls back to 1 the entry
+/// is removed so the frame goes back to being implicitly exclusive.
+pub fn unshare_frame(frame: u32) -> u32 {
+    let mut table = refcounts().lock().unwrap();
+    match table.get_mut(&frame) {
+        Some(count) if *count > 1 => {
+            *count -= 1;
+            let remaining = *count;
+            if remaining <= 1 {
+                table.remove(&frame);
+            }
+            remaining
+        }
+        _ => 1,
+    }
+}
+
+/// Drop the caller's own reference to `frame` entirely, for use when an
+/// address space is torn down rather than when a COW fault splits it onto a
+/// fresh copy. If this was the last reference the frame is returned to
+/// the free pool via `free_frame` instead of staying leaked forever.
+pub fn release_frame(frame: u32) {
+    let mut table = refcounts().lock().unwrap();
+    match table.get_mut(&frame) {
+        Some(count) if *count > 1 => {
+            *count -= 1;
+            if *count <= 1 {
+                table.remove(&frame);
+            }
+        }
+        _ => {
+            table.remove(&frame);
+            drop(table);
+            free_frame(frame);
+        }
+    }
+}
+
+/// Walk `root_ppn`'s page table to `vaddr`'s L0 entry, allocating and
+/// zeroing a fresh L0 table if the L1 entry doesn't exist yet. Returns the
+/// physical address of the L0 PTE slot itself, leaving its contents up to
+/// the caller — shared by `map_page` (which fills it in eagerly) and
+/// `reserve_lazy_page` (which leaves `PTE_V` clear for demand paging).
+fn ensure_l0_pte(memory: &mut dyn Memory, root_ppn: u32, vaddr: u32) -> Result<PhysAddr, String> {
     let vpn1 = (vaddr >> 22) & 0x3FF;
     let vpn0 = (vaddr >> 12) & 0x3FF;
 
-    // L1 Page Table Access
     let l1_pte_addr = PhysAddr::new((root_ppn << 12) + (vpn1 * 4));
     let mut l1_pte = memory
         .read_word(l1_pte_addr)
@@ -49,7 +194,7 @@ pub fn map_page(
 
     if (l1_pte & PTE_V) == 0 {
         // Allocate L0 Page Table
-        let l0_table_pa = alloc_frame();
+        let l0_table_pa = alloc_frame().ok_or_else(|| "out of physical memory".to_string())?;
         // Zero out the new page table
         for i in 0..1024 {
             memory
@@ -65,25 +210,280 @@ pub fn map_page(
             .map_err(|e| format!("Failed to write L1 PTE: {:?}", e))?;
     }
 
-    // L0 Page Table Access
     let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
-    let l0_pte_addr = PhysAddr::new((l0_ppn << 12) + (vpn0 * 4));
+    Ok(PhysAddr::new((l0_ppn << 12) + (vpn0 * 4)))
+}
+
+/// Size of the leaf a `map_page` call installs: a normal 4 KiB L0 page, or
+/// an Sv32 4 MiB L1 superpage covering 1024 of them with a single PTE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageLevel {
+    Kb4,
+    Mb4,
+}
+
+/// Install a mapping at `vaddr`. `level` selects the leaf size: `Kb4` walks
+/// down to the usual L0 PTE via `ensure_l0_pte`; `Mb4` writes the leaf
+/// directly into the L1/root table slot, which both `vaddr` and `paddr` must
+/// be 4 MiB-aligned for (an Sv32 superpage's PPN0 field must be zero).
+pub fn map_page(
+    memory: &mut dyn Memory,
+    root_ppn: u32,
+    vaddr: u32,
+    paddr: u32,
+    flags: u32,
+    level: PageLevel,
+) -> Result<(), String> {
+    match level {
+        PageLevel::Kb4 => {
+            let l0_pte_addr = ensure_l0_pte(memory, root_ppn, vaddr)?;
+
+            let ppn = paddr >> 12;
+            let l0_pte = (ppn << 10) | flags | PTE_V | PTE_A | PTE_D; // Pre-set Accessed/Dirty for simplicity
+
+            memory
+                .write_word(l0_pte_addr, l0_pte)
+                .map_err(|e| format!("Failed to write L0 PTE: {:?}", e))?;
+
+            Ok(())
+        }
+        PageLevel::Mb4 => {
+            if vaddr & 0x3F_FFFF != 0 || paddr & 0x3F_FFFF != 0 {
+                return Err(format!(
+                    "4 MiB superpage mapping requires 4 MiB-aligned vaddr/paddr, got {:#x}/{:#x}",
+                    vaddr, paddr
+                ));
+            }
+
+            let vpn1 = (vaddr >> 22) & 0x3FF;
+            let l1_pte_addr = PhysAddr::new((root_ppn << 12) + (vpn1 * 4));
+            let ppn = paddr >> 12;
+            let l1_pte = (ppn << 10) | flags | PTE_V | PTE_A | PTE_D;
+
+            memory
+                .write_word(l1_pte_addr, l1_pte)
+                .map_err(|e| format!("Failed to write L1 superpage PTE: {:?}", e))?;
 
-    let ppn = paddr >> 12;
-    let l0_pte = (ppn << 10) | flags | PTE_V | PTE_A | PTE_D; // Pre-set Accessed/Dirty for simplicity
+            Ok(())
+        }
+    }
+}
 
+/// Install a lazily-backed mapping at `vaddr`: the leaf PTE records `prot`
+/// and `PTE_LAZY` but leaves `PTE_V` clear, so the MMU faults on first
+/// touch instead of this call paying for a frame nothing may ever read.
+/// `prot` should already include `PTE_U` for a user mapping, same as a
+/// `map_page` caller would pass. The actual backing happens later, in
+/// `resolve_lazy_fault`.
+pub fn reserve_lazy_page(
+    memory: &mut dyn Memory,
+    root_ppn: u32,
+    vaddr: u32,
+    prot: u32,
+) -> Result<(), String> {
+    let l0_pte_addr = ensure_l0_pte(memory, root_ppn, vaddr)?;
+    let l0_pte = prot | PTE_LAZY;
     memory
         .write_word(l0_pte_addr, l0_pte)
-        .map_err(|e| format!("Failed to write L0 PTE: {:?}", e))?;
+        .map_err(|e| format!("Failed to write lazy L0 PTE: {:?}", e))
+}
 
-    Ok(())
+/// Back a `reserve_lazy_page`d mapping with a fresh zero-filled frame,
+/// finalizing its PTE with `PTE_V | PTE_A | PTE_D` on top of the `prot`
+/// bits recorded when it was reserved. Returns `Ok(false)` if `vaddr`'s
+/// leaf PTE isn't `PTE_LAZY` — not a demand-paged mapping at all, or
+/// already backed — so the caller can tell a lazy fault apart from a
+/// genuine one.
+pub fn resolve_lazy_fault(memory: &mut dyn Memory, root_ppn: u32, vaddr: u32) -> Result<bool, String> {
+    let vpn1 = (vaddr >> 22) & 0x3FF;
+    let vpn0 = (vaddr >> 12) & 0x3FF;
+
+    let l1_pte_addr = PhysAddr::new((root_ppn << 12) + (vpn1 * 4));
+    let l1_pte = memory
+        .read_word(l1_pte_addr)
+        .map_err(|e| format!("Failed to read L1 PTE: {:?}", e))?;
+    if l1_pte & PTE_V == 0 {
+        return Ok(false);
+    }
+
+    let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+    let l0_pte_addr = PhysAddr::new((l0_ppn << 12) + (vpn0 * 4));
+    let l0_pte = memory
+        .read_word(l0_pte_addr)
+        .map_err(|e| format!("Failed to read L0 PTE: {:?}", e))?;
+    if l0_pte & PTE_LAZY == 0 {
+        return Ok(false);
+    }
+
+    let frame = alloc_frame().ok_or_else(|| "out of physical memory".to_string())?;
+    for i in 0..PAGE_SIZE {
+        memory
+            .write_byte(PhysAddr::new(frame + i), 0)
+            .map_err(|e| format!("Failed to zero lazy frame: {:?}", e))?;
+    }
+
+    let prot = l0_pte & 0x1F; // V|R|W|X|U bits recorded at reserve time (V is 0)
+    let new_pte = ((frame >> 12) << 10) | prot | PTE_V | PTE_A | PTE_D;
+    memory
+        .write_word(l0_pte_addr, new_pte)
+        .map_err(|e| format!("Failed to finalize lazy PTE: {:?}", e))?;
+
+    Ok(true)
+}
+
+/// A `PT_LOAD` segment recorded on the TCB at `bootstrap_process`/`Exec`
+/// time, so the page-fault handler can find which segment backs a lazily
+/// reserved page and with what file bytes. `flags` is already translated
+/// into `PTE_R`/`PTE_W`/`PTE_X`/`PTE_U` from the ELF `p_flags`, so the fault
+/// handler never has to see raw `PF_*` bits. `vaddr_end` and `file_size`
+/// together are what let `resolve_segment_fault` zero-fill the BSS tail of
+/// a segment whose `file_size` is shorter than its in-memory length instead
+/// of copying past the end of the backing image.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    pub vaddr_start: u32,
+    pub vaddr_end: u32,
+    pub file_offset: u32,
+    pub file_size: u32,
+    pub flags: u32,
+}
+
+/// Resolve a page fault at `vaddr` against `segments`, the `PT_LOAD`
+/// descriptors of the process that owns the address space at `root_ppn`.
+/// Returns `Ok(false)` if no segment covers `vaddr` — a purely anonymous
+/// stack/heap/mmap page, which the caller should fall back to
+/// `resolve_lazy_fault` for — or if the leaf PTE there isn't a
+/// `PTE_LAZY` reservation at all. On a hit, allocates a fresh frame,
+/// zero-fills it (covering BSS, i.e. any part of `memsz` past `filesz`),
+/// copies in whatever bytes of `image` fall within the segment's `filesz`
+/// for this page, and installs the PTE with the segment's own
+/// `flags` — enforcing W^X since a code segment's `flags` never carries
+/// `PTE_W` and a data segment's never carries `PTE_X`. `PTE_A` is set
+/// unconditionally since the fault is itself an access; `PTE_D` only if
+/// `is_write`, so a read or fetch fault doesn't mark a clean page dirty.
+pub fn resolve_segment_fault(
+    memory: &mut dyn Memory,
+    root_ppn: u32,
+    vaddr: u32,
+    is_write: bool,
+    segments: &[Segment],
+    image: &[u8],
+) -> Result<bool, String> {
+    let page_base = vaddr & !(PAGE_SIZE - 1);
+    let Some(segment) = segments
+        .iter()
+        .find(|s| page_base >= s.vaddr_start && page_base < s.vaddr_end)
+    else {
+        return Ok(false);
+    };
+
+    let vpn1 = (vaddr >> 22) & 0x3FF;
+    let vpn0 = (vaddr >> 12) & 0x3FF;
+
+    let l1_pte_addr = PhysAddr::new((root_ppn << 12) + (vpn1 * 4));
+    let l1_pte = memory
+        .read_word(l1_pte_addr)
+        .map_err(|e| format!("Failed to read L1 PTE: {:?}", e))?;
+    if l1_pte & PTE_V == 0 {
+        return Ok(false);
+    }
+
+    let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+    let l0_pte_addr = PhysAddr::new((l0_ppn << 12) + (vpn0 * 4));
+    let l0_pte = memory
+        .read_word(l0_pte_addr)
+        .map_err(|e| format!("Failed to read L0 PTE: {:?}", e))?;
+    if l0_pte & PTE_LAZY == 0 {
+        return Ok(false);
+    }
+
+    let frame = alloc_frame().ok_or_else(|| "out of physical memory".to_string())?;
+    for i in 0..PAGE_SIZE {
+        memory
+            .write_byte(PhysAddr::new(frame + i), 0)
+            .map_err(|e| format!("Failed to zero segment frame: {:?}", e))?;
+    }
+
+    let file_region_end = segment.vaddr_start + segment.file_size;
+    let copy_start = page_base.max(segment.vaddr_start);
+    let copy_end = (page_base + PAGE_SIZE).min(file_region_end);
+    if copy_start < copy_end {
+        let page_offset = copy_start - page_base;
+        let file_offset = segment.file_offset + (copy_start - segment.vaddr_start);
+        let len = (copy_end - copy_start) as usize;
+        let src = image
+            .get(file_offset as usize..file_offset as usize + len)
+            .ok_or_else(|| "segment file range out of bounds".to_string())?;
+        for (i, byte) in src.iter().enumerate() {
+            memory
+                .write_byte(PhysAddr::new(frame + page_offset + i as u32), *byte)
+                .map_err(|e| format!("Failed to copy segment byte: {:?}", e))?;
+        }
+    }
+
+    let mut new_pte = ((frame >> 12) << 10) | segment.flags | PTE_V | PTE_A;
+    if is_write {
+        new_pte |= PTE_D;
+    }
+    memory
+        .write_word(l0_pte_addr, new_pte)
+        .map_err(|e| format!("Failed to finalize segment PTE: {:?}", e))?;
+
+    Ok(true)
+}
+
+/// Clear `vaddr`'s leaf PTE in the address space rooted at `root_ppn`,
+/// returning the physical frame it pointed at so the caller can
+/// `release_frame` it — or `Ok(None)` if `vaddr` wasn't backed by a frame,
+/// which is a no-op rather than an error since a shrinking `sbrk` or a
+/// `munmap` may cover a range that was never actually faulted in. A
+/// `PTE_LAZY` reservation that was never touched has its PTE cleared too
+/// (so a later `mmap` can reuse the slot), just with no frame to free.
+/// `release_frame` rather than a bare `free_frame` matters here: the page
+/// this frame backs may still be a COW page shared with a forked sibling,
+/// in which case freeing it outright would let the allocator hand the same
+/// frame to an unrelated third mapping while the sibling still reads and
+/// writes it as its own.
+pub fn unmap_page(memory: &mut dyn Memory, root_ppn: u32, vaddr: u32) -> Result<Option<u32>, String> {
+    let vpn1 = (vaddr >> 22) & 0x3FF;
+    let vpn0 = (vaddr >> 12) & 0x3FF;
+
+    let l1_pte_addr = PhysAddr::new((root_ppn << 12) + (vpn1 * 4));
+    let l1_pte = memory
+        .read_word(l1_pte_addr)
+        .map_err(|e| format!("Failed to read L1 PTE: {:?}", e))?;
+    if (l1_pte & PTE_V) == 0 {
+        return Ok(None);
+    }
+
+    let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+    let l0_pte_addr = PhysAddr::new((l0_ppn << 12) + (vpn0 * 4));
+    let l0_pte = memory
+        .read_word(l0_pte_addr)
+        .map_err(|e| format!("Failed to read L0 PTE: {:?}", e))?;
+    if (l0_pte & PTE_V) == 0 {
+        if l0_pte & PTE_LAZY != 0 {
+            memory
+                .write_word(l0_pte_addr, 0)
+                .map_err(|e| format!("Failed to clear lazy L0 PTE: {:?}", e))?;
+        }
+        return Ok(None);
+    }
+
+    memory
+        .write_word(l0_pte_addr, 0)
+        .map_err(|e| format!("Failed to clear L0 PTE: {:?}", e))?;
+
+    let frame = (l0_pte >> 10) << 12;
+    Ok(Some(frame))
 }
 
 pub fn setup_kernel_address_space(memory: &mut dyn Memory) -> Result<u32, String> {
     debug!("Setting up Kernel Address Space...");
 
     // Allocate Root Page Table
-    let root_pa = alloc_frame();
+    let root_pa = alloc_frame()
+        .ok_or_else(|| "out of physical memory".to_string())?;
     // Zero root table
     for i in 0..1024 {
         memory
@@ -93,18 +493,17 @@ pub fn setup_kernel_address_space(memory: &mut dyn Memory) -> Result<u32, String
     let root_ppn = root_pa >> 12;
 
     // 1. Identity Map Kernel Code/Data (0x8000_0000 - 0x8040_0000)
-    // Map 4MB (1024 pages)
+    // This 4MB region is exactly one Sv32 superpage, so a single L1 leaf
+    // covers it instead of 1024 individual L0 PTEs.
     let kernel_start = 0x8000_0000;
-    for i in 0..1024 {
-        let addr = kernel_start + (i * PAGE_SIZE);
-        map_page(
-            memory,
-            root_ppn,
-            addr,
-            addr,
-            PTE_R | PTE_W | PTE_X, // RWX for simplicity
-        )?;
-    }
+    map_page(
+        memory,
+        root_ppn,
+        kernel_start,
+        kernel_start,
+        PTE_R | PTE_W | PTE_X, // RWX for simplicity
+        PageLevel::Mb4,
+    )?;
 
     // 2. Identity Map MMIO (UART at 0x1000_0000)
     // Map 1 Page
@@ -115,12 +514,20 @@ pub fn setup_kernel_address_space(memory: &mut dyn Memory) -> Result<u32, String
         uart_addr,
         uart_addr,
         PTE_R | PTE_W, // RW (No Execute)
+        PageLevel::Kb4,
     )?;
 
     // 3. Identity Map Block Device MMIO (at 0x2000_0000)
     // Map 1 Page (SimpleBlockDevice uses 0x1000 size)
     let block_addr = 0x2000_0000;
-    map_page(memory, root_ppn, block_addr, block_addr, PTE_R | PTE_W)?;
+    map_page(
+        memory,
+        root_ppn,
+        block_addr,
+        block_addr,
+        PTE_R | PTE_W,
+        PageLevel::Kb4,
+    )?;
 
     // 4. Stack Mapping for Initial Process
     // Map top 64KB of RAM (0x80FF_0000 - 0x8100_0000)
@@ -128,7 +535,7 @@ pub fn setup_kernel_address_space(memory: &mut dyn Memory) -> Result<u32, String
     let stack_start = 0x80FF_0000;
     for i in 0..16 {
         let addr = stack_start + (i * PAGE_SIZE);
-        map_page(memory, root_ppn, addr, addr, PTE_R | PTE_W)?;
+        map_page(memory, root_ppn, addr, addr, PTE_R | PTE_W, PageLevel::Kb4)?;
     }
 
     debug!(
@@ -140,9 +547,242 @@ pub fn setup_kernel_address_space(memory: &mut dyn Memory) -> Result<u32, String
     Ok(SATP_MODE_SV32 | root_ppn)
 }
 
+/// Duplicate a user address space for `Fork`, copy-on-write: every writable
+/// user leaf PTE is cloned into the child page table and downgraded to
+/// read-only in both the parent's and child's copy, with the underlying
+/// physical frame's refcount bumped so neither side frees it while the
+/// other still points at it. Non-user mappings (kernel/MMIO, installed by
+/// `create_user_address_space`) are copied verbatim since they point at
+/// shared hardware state rather than copyable RAM.
+pub fn fork_address_space(memory: &mut dyn Memory, parent_satp: u32) -> Result<u32, String> {
+    let parent_root_ppn = parent_satp & 0x003F_FFFF;
+    let child_root_pa = alloc_frame()
+        .ok_or_else(|| "out of physical memory".to_string())?;
+    for i in 0..1024 {
+        memory
+            .write_word(PhysAddr::new(child_root_pa + i * 4), 0)
+            .map_err(|e| format!("Failed to zero child root PTE: {:?}", e))?;
+    }
+    let child_root_ppn = child_root_pa >> 12;
+
+    for vpn1 in 0..1024u32 {
+        let parent_l1_addr = PhysAddr::new((parent_root_ppn << 12) + vpn1 * 4);
+        let parent_l1_pte = memory
+            .read_word(parent_l1_addr)
+            .map_err(|e| format!("Failed to read parent L1 PTE: {:?}", e))?;
+        if parent_l1_pte & PTE_V == 0 {
+            continue;
+        }
+
+        // A leaf at this level (R/W/X set) is a 4 MiB superpage -- the
+        // kernel's own identity map uses these, never a user page -- so its
+        // PPN field is the mapped frame itself, not an L0 table to walk.
+        // Copy the L1 PTE straight into the child's table, same as the non-
+        // user-page branch below does for an ordinary PTE.
+        if parent_l1_pte & (PTE_R | PTE_W | PTE_X) != 0 {
+            let child_l1_addr = PhysAddr::new((child_root_ppn << 12) + vpn1 * 4);
+            memory
+                .write_word(child_l1_addr, parent_l1_pte)
+                .map_err(|e| format!("Failed to write child superpage PTE: {:?}", e))?;
+            continue;
+        }
+
+        let parent_l0_ppn = (parent_l1_pte >> 10) & 0x3F_FFFF;
+        let child_l0_pa = alloc_frame()
+            .ok_or_else(|| "out of physical memory".to_string())?;
+        for i in 0..1024 {
+            memory
+                .write_word(PhysAddr::new(child_l0_pa + i * 4), 0)
+                .map_err(|e| format!("Failed to zero child L0 table: {:?}", e))?;
+        }
+        let child_l0_ppn = child_l0_pa >> 12;
+
+        for vpn0 in 0..1024u32 {
+            let parent_l0_addr = PhysAddr::new((parent_l0_ppn << 12) + vpn0 * 4);
+            let mut parent_l0_pte = memory
+                .read_word(parent_l0_addr)
+                .map_err(|e| format!("Failed to read parent L0 PTE: {:?}", e))?;
+            if parent_l0_pte & PTE_V == 0 {
+                if parent_l0_pte & PTE_LAZY != 0 {
+                    // An unbacked `reserve_lazy_page` reservation (segment or
+                    // sbrk/mmap page never yet touched): carry the reservation
+                    // itself over rather than dropping it, so the child still
+                    // demand-pages it on first fault instead of taking a fatal
+                    // segfault for a page its own `segments`/mappings say it
+                    // owns.
+                    let child_l0_addr = PhysAddr::new((child_l0_ppn << 12) + vpn0 * 4);
+                    memory
+                        .write_word(child_l0_addr, parent_l0_pte)
+                        .map_err(|e| format!("Failed to write child lazy PTE: {:?}", e))?;
+                }
+                continue;
+            }
+
+            let frame = (parent_l0_pte >> 10) << 12;
+            let mut child_l0_pte = parent_l0_pte;
+
+            if parent_l0_pte & PTE_U != 0 {
+                if parent_l0_pte & PTE_W != 0 {
+                    // Writable user page: downgrade both sides to
+                    // read-only so a store faults into `resolve_cow_fault`
+                    // instead of corrupting the other side's copy.
+                    parent_l0_pte &= !PTE_W;
+                    child_l0_pte &= !PTE_W;
+                    memory
+                        .write_word(parent_l0_addr, parent_l0_pte)
+                        .map_err(|e| format!("Failed to downgrade parent L0 PTE: {:?}", e))?;
+                }
+                // Every user page aliased into the child -- not just a
+                // writable one downgraded to COW above, a read-only text/
+                // rodata segment page the parent already mapped without
+                // `PTE_W` gets aliased here too -- now has two address
+                // spaces pointing at it, so `release_address_space` must
+                // never hand it back to the allocator out from under
+                // whichever side hasn't exited yet.
+                share_frame(frame);
+            }
+
+            let child_l0_addr = PhysAddr::new((child_l0_ppn << 12) + vpn0 * 4);
+            memory
+                .write_word(child_l0_addr, child_l0_pte)
+                .map_err(|e| format!("Failed to write child L0 PTE: {:?}", e))?;
+        }
+
+        let child_l1_pte = (child_l0_ppn << 10) | PTE_V;
+        let child_l1_addr = PhysAddr::new((child_root_ppn << 12) + vpn1 * 4);
+        memory
+            .write_word(child_l1_addr, child_l1_pte)
+            .map_err(|e| format!("Failed to write child L1 PTE: {:?}", e))?;
+    }
+
+    Ok(SATP_MODE_SV32 | child_root_ppn)
+}
+
+/// Tear down the address space rooted at `root_ppn`: every mapped leaf
+/// frame goes through `release_frame` (so a page still shared with a
+/// `fork_address_space` sibling just drops a reference instead of being
+/// freed out from under it), then the L0 tables and the root table
+/// themselves go straight to `free_frame`, since unlike data frames those
+/// are never shared between address spaces. A superpage leaf is skipped
+/// the same way `fork_address_space` copies it verbatim instead of walking
+/// it -- it's the kernel's own identity map, not this process's frame to
+/// free. Called once a process's last thread exits, so its frames return
+/// to the pool instead of leaking for the rest of the VM's run.
+pub fn release_address_space(memory: &mut dyn Memory, root_ppn: u32) -> Result<(), String> {
+    for vpn1 in 0..1024u32 {
+        let l1_addr = PhysAddr::new((root_ppn << 12) + vpn1 * 4);
+        let l1_pte = memory
+            .read_word(l1_addr)
+            .map_err(|e| format!("Failed to read L1 PTE: {:?}", e))?;
+        if l1_pte & PTE_V == 0 {
+            continue;
+        }
+        if l1_pte & (PTE_R | PTE_W | PTE_X) != 0 {
+            continue;
+        }
+
+        let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+        for vpn0 in 0..1024u32 {
+            let l0_addr = PhysAddr::new((l0_ppn << 12) + vpn0 * 4);
+            let l0_pte = memory
+                .read_word(l0_addr)
+                .map_err(|e| format!("Failed to read L0 PTE: {:?}", e))?;
+            if l0_pte & PTE_V == 0 {
+                continue;
+            }
+            let frame = (l0_pte >> 10) << 12;
+            release_frame(frame);
+        }
+
+        free_frame(l0_ppn << 12);
+    }
+
+    free_frame(root_ppn << 12);
+    Ok(())
+}
+
+/// Resolve a copy-on-write fault on a store to `vaddr` in the address space
+/// rooted at `root_ppn`. Returns `Ok(true)` if `vaddr` was in fact a
+/// read-only-for-COW user page and the fault was resolved — either by
+/// giving the frame back exclusively (no sibling still shares it) or by
+/// copying it onto a fresh private frame (one does) — and `Ok(false)` if
+/// `vaddr` isn't a COW page at all, meaning this is a genuine fault the
+/// caller should propagate. A COW page is told apart from an ordinary
+/// read-only mapping by `PTE_U` set with `PTE_W` clear, rather than a
+/// dedicated spare PTE bit — `fork_address_space` never produces a
+/// non-writable *kernel* user-accessible page any other way, so the two
+/// bits together are already an unambiguous marker.
+pub fn resolve_cow_fault(
+    memory: &mut dyn Memory,
+    root_ppn: u32,
+    vaddr: u32,
+) -> Result<bool, String> {
+    let vpn1 = (vaddr >> 22) & 0x3FF;
+    let vpn0 = (vaddr >> 12) & 0x3FF;
+
+    let l1_addr = PhysAddr::new((root_ppn << 12) + vpn1 * 4);
+    let l1_pte = memory
+        .read_word(l1_addr)
+        .map_err(|e| format!("Failed to read L1 PTE: {:?}", e))?;
+    if l1_pte & PTE_V == 0 {
+        return Ok(false);
+    }
+
+    let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+    let l0_addr = PhysAddr::new((l0_ppn << 12) + vpn0 * 4);
+    let l0_pte = memory
+        .read_word(l0_addr)
+        .map_err(|e| format!("Failed to read L0 PTE: {:?}", e))?;
+    if l0_pte & PTE_V == 0 || l0_pte & PTE_U == 0 || l0_pte & PTE_W != 0 {
+        // Not present, not a user page, or already writable: a real fault.
+        return Ok(false);
+    }
+
+    let frame = (l0_pte >> 10) << 12;
+    if frame_refcount(frame) > 1 {
+        let new_frame = alloc_frame()
+            .ok_or_else(|| "out of physical memory".to_string())?;
+        for i in 0..PAGE_SIZE {
+            let byte = memory
+                .read_byte(PhysAddr::new(frame + i))
+                .map_err(|e| format!("Failed to read COW source byte: {:?}", e))?;
+            memory
+                .write_byte(PhysAddr::new(new_frame + i), byte)
+                .map_err(|e| format!("Failed to write COW dest byte: {:?}", e))?;
+        }
+        unshare_frame(frame);
+        let new_pte = ((new_frame >> 12) << 10) | (l0_pte & 0x3FF) | PTE_W;
+        memory
+            .write_word(l0_addr, new_pte)
+            .map_err(|e| format!("Failed to write post-COW L0 PTE: {:?}", e))?;
+    } else {
+        memory
+            .write_word(l0_addr, l0_pte | PTE_W)
+            .map_err(|e| format!("Failed to restore write bit: {:?}", e))?;
+    }
+
+    Ok(true)
+}
+
+/// Builds a fresh user address space's root page table, mapping only the
+/// MMIO regions a process needs direct access to (UART, block device).
+///
+/// A guest-resident kernel would also map a trampoline page here: one
+/// physical page holding the trap entry/return code, mapped at the same
+/// virtual address in every address space, so that the instruction right
+/// after `satp` is written (switching into this very table) is still the
+/// one the CPU was executing. Without it, the instant a trap handler
+/// switches `satp`, its own code could vanish out from under it.
+///
+/// This kernel doesn't need one: `Kernel::handle_trap` runs as host Rust
+/// against `dyn Memory`, never as guest RISC-V code subject to the `satp`
+/// it's updating, so there's no in-guest handler whose mapping a switch
+/// could ever drop. See the comment below on why kernel code isn't mapped
+/// into user space at all, for the same reason.
 pub fn create_user_address_space(memory: &mut dyn Memory) -> Result<u32, String> {
     // Allocate Root Page Table
-    let root_pa = alloc_frame();
+    let root_pa = alloc_frame()
+        .ok_or_else(|| "out of physical memory".to_string())?;
     // Zero root table
     for i in 0..1024 {
         memory
@@ -158,12 +798,34 @@ pub fn create_user_address_space(memory: &mut dyn Memory) -> Result<u32, String>
     // Since the kernel is external (host-based), we don't need to protect kernel code in VM memory.
 
     // 2. UART
+    //
+    // `PTE_U` is set here even though every current user-mode caller only
+    // ever reaches the UART through `ConsoleRead`/`ConsoleWrite` (the
+    // kernel writing to it directly on the syscall's behalf, never through
+    // this mapping): without it, this "direct access" mapping this
+    // function's own doc comment promises would silently deny every
+    // guest-mode load/store to it instead of granting the access it
+    // claims to.
     let uart_addr = 0x1000_0000;
-    map_page(memory, root_ppn, uart_addr, uart_addr, PTE_R | PTE_W)?;
+    map_page(
+        memory,
+        root_ppn,
+        uart_addr,
+        uart_addr,
+        PTE_R | PTE_W | PTE_U,
+        PageLevel::Kb4,
+    )?;
 
-    // 3. Block Device
+    // 3. Block Device -- same `PTE_U` reasoning as the UART mapping above.
     let block_addr = 0x2000_0000;
-    map_page(memory, root_ppn, block_addr, block_addr, PTE_R | PTE_W)?;
+    map_page(
+        memory,
+        root_ppn,
+        block_addr,
+        block_addr,
+        PTE_R | PTE_W | PTE_U,
+        PageLevel::Kb4,
+    )?;
 
     // 4. REMOVED: Physical RAM identity mapping
     // User heap (sbrk) will allocate and map frames dynamically.
@@ -171,3 +833,119 @@ pub fn create_user_address_space(memory: &mut dyn Memory) -> Result<u32, String>
 
     Ok(SATP_MODE_SV32 | root_ppn)
 }
+
+/// Magic number stamped at the start of a `dump_address_space` blob, so a
+/// reader can sanity-check it before trusting the header that follows.
+pub const MINIDUMP_MAGIC: u32 = 0x4D44_4D50; // "MDMP" read big-endian; stored little-endian, so the file's first bytes are "PMDM"
+
+/// A single mapping found while walking an address space for
+/// `dump_address_space`: every valid leaf (backed or still-lazy) expands to
+/// one of these, always at 4 KiB granularity — a 4 MiB superpage leaf from
+/// `map_page`'s `PageLevel::Mb4` is split into its 1024 underlying frames so
+/// the dump format never has to special-case leaf size.
+struct DumpEntry {
+    vaddr: u32,
+    flags: u32,
+    frame: Option<u32>,
+}
+
+fn write_u32(out: &mut dyn Write, val: u32) -> Result<(), String> {
+    out.write_all(&val.to_le_bytes())
+        .map_err(|e| format!("minidump write error: {:?}", e))
+}
+
+/// Write a compact crash dump of the address space rooted at `satp`,
+/// following the same idea as FreeBSD's `minidump_machdep`: walk both Sv32
+/// levels and record only resident mappings, so the dump is proportional to
+/// the process's working set rather than the full address space. The blob
+/// is a header (`MINIDUMP_MAGIC`, `PAGE_SIZE`, root PPN, entry count),
+/// followed by one `(vaddr, flags)` descriptor per entry, followed by the
+/// packed 4 KiB body of every *backed* entry in the same ascending-vaddr
+/// order. An entry whose leaf PTE is a `PTE_LAZY` reservation that was never
+/// faulted in is listed in the header but contributes no body bytes, since
+/// there's no frame yet to copy.
+pub fn dump_address_space(
+    memory: &mut dyn Memory,
+    satp: u32,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let root_ppn = satp & 0x003F_FFFF;
+    let mut entries: Vec<DumpEntry> = Vec::new();
+
+    for vpn1 in 0..1024u32 {
+        let l1_addr = PhysAddr::new((root_ppn << 12) + vpn1 * 4);
+        let l1_pte = memory
+            .read_word(l1_addr)
+            .map_err(|e| format!("Failed to read L1 PTE: {:?}", e))?;
+        if l1_pte & PTE_V == 0 {
+            continue;
+        }
+
+        if l1_pte & (PTE_R | PTE_W | PTE_X) != 0 {
+            // 4 MiB superpage leaf.
+            let base_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+            let base_vaddr = vpn1 << 22;
+            for i in 0..1024u32 {
+                entries.push(DumpEntry {
+                    vaddr: base_vaddr + i * PAGE_SIZE,
+                    flags: l1_pte & 0x3FF,
+                    frame: Some((base_ppn + i) << 12),
+                });
+            }
+            continue;
+        }
+
+        let l0_ppn = (l1_pte >> 10) & 0x3F_FFFF;
+        for vpn0 in 0..1024u32 {
+            let l0_addr = PhysAddr::new((l0_ppn << 12) + vpn0 * 4);
+            let l0_pte = memory
+                .read_word(l0_addr)
+                .map_err(|e| format!("Failed to read L0 PTE: {:?}", e))?;
+            if l0_pte == 0 {
+                continue;
+            }
+            let vaddr = (vpn1 << 22) | (vpn0 << 12);
+            if l0_pte & PTE_V != 0 {
+                let frame = (l0_pte >> 10) << 12;
+                entries.push(DumpEntry {
+                    vaddr,
+                    flags: l0_pte & 0x3FF,
+                    frame: Some(frame),
+                });
+            } else if l0_pte & PTE_LAZY != 0 {
+                entries.push(DumpEntry {
+                    vaddr,
+                    flags: l0_pte & 0x3FF,
+                    frame: None,
+                });
+            }
+        }
+    }
+
+    entries.sort_by_key(|e| e.vaddr);
+
+    write_u32(out, MINIDUMP_MAGIC)?;
+    write_u32(out, PAGE_SIZE)?;
+    write_u32(out, root_ppn)?;
+    write_u32(out, entries.len() as u32)?;
+
+    for entry in &entries {
+        write_u32(out, entry.vaddr)?;
+        write_u32(out, entry.flags)?;
+    }
+
+    let mut page_buf = [0u8; PAGE_SIZE as usize];
+    for entry in &entries {
+        if let Some(frame) = entry.frame {
+            for i in 0..PAGE_SIZE {
+                page_buf[i as usize] = memory
+                    .read_byte(PhysAddr::new(frame + i))
+                    .map_err(|e| format!("Failed to read page body byte: {:?}", e))?;
+            }
+            out.write_all(&page_buf)
+                .map_err(|e| format!("minidump write error: {:?}", e))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,405 @@
+//! A DHCPv4 client (RFC 2131) so a guest can learn its address instead of
+//! hardcoding `syscalls::DEFAULT_IP`. Runs entirely below the socket layer,
+//! the same way `net::tcp`'s handshake drives `net::driver::DRIVER`
+//! directly rather than going through a user-visible fd: there's no
+//! process to own a DHCP lease, just this kernel's single interface.
+//!
+//! The DISCOVER/OFFER/REQUEST/ACK exchange rides on `net::udp::send_to`
+//! (ports 68/67) exactly like any other UDP datagram; `net::socket`'s
+//! `process_rx` demuxer hands replies addressed to port 68 to
+//! [`handle_reply`] instead of queuing them onto a socket, since nothing
+//! ever binds that port itself.
+
+use crate::net::udp;
+use crate::sync::spinlock::SpinLock;
+use crate::thread::NANOS_PER_TICK;
+use alloc::vec::Vec;
+use ferrous_vm::Memory;
+
+/// This kernel's interface address until a lease is acquired, and the
+/// value `local_ip`/`netmask`/`gateway` fall back to forever if DHCP never
+/// gets a reply -- the same default QEMU user-mode networking expects that
+/// `syscalls::LOCAL_IP` used to be hardcoded to everywhere.
+const DEFAULT_IP: [u8; 4] = [10, 0, 2, 15];
+const DEFAULT_NETMASK: [u8; 4] = [255, 255, 255, 0];
+const DEFAULT_GATEWAY: [u8; 4] = [10, 0, 2, 2];
+
+/// Fixed MAC this kernel's single network interface answers to, duplicated
+/// from `net::udp`/`net::tcp`'s constants of the same name rather than
+/// shared -- same as those two already duplicate it from each other.
+const LOCAL_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+pub(crate) const CLIENT_PORT: u16 = 68;
+const SERVER_PORT: u16 = 67;
+
+const BOOTREQUEST: u8 = 1;
+const HTYPE_ETHERNET: u8 = 1;
+const MAGIC_COOKIE: [u8; 4] = [0x63, 0x82, 0x53, 0x63];
+
+const OPT_PAD: u8 = 0;
+const OPT_SUBNET_MASK: u8 = 1;
+const OPT_ROUTER: u8 = 3;
+const OPT_DNS: u8 = 6;
+const OPT_REQUESTED_IP: u8 = 50;
+const OPT_LEASE_TIME: u8 = 51;
+const OPT_MESSAGE_TYPE: u8 = 53;
+const OPT_SERVER_ID: u8 = 54;
+const OPT_END: u8 = 255;
+
+const DHCPDISCOVER: u8 = 1;
+const DHCPOFFER: u8 = 2;
+const DHCPREQUEST: u8 = 3;
+const DHCPACK: u8 = 5;
+const DHCPNAK: u8 = 6;
+
+/// Ticks (at `NANOS_PER_TICK` each) between DISCOVER/REQUEST retries while
+/// nothing has answered yet, the same fixed-retry idea `net::tcp`'s
+/// `RETRANSMIT_TICKS` uses in place of an RTT estimate.
+const RETRY_TICKS: u32 = 300;
+/// Retries attempted before giving up on the current DISCOVER/REQUEST and
+/// falling back to `DEFAULT_IP` so the guest still has *an* address.
+const MAX_RETRIES: u32 = 5;
+const TICKS_PER_SECOND: u32 = (1_000_000_000 / NANOS_PER_TICK) as u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Hasn't sent a DISCOVER yet.
+    Init,
+    /// DISCOVER sent, waiting for an OFFER.
+    Selecting,
+    /// REQUEST sent in response to an OFFER, waiting for an ACK/NAK.
+    Requesting,
+    /// Holding a lease; `renew_ticks`/`rebind_ticks`/`expire_ticks` count
+    /// down to the next thing this client should do about it.
+    Bound,
+}
+
+struct Interface {
+    state: State,
+    ip: [u8; 4],
+    netmask: [u8; 4],
+    gateway: [u8; 4],
+    dns: [u8; 4],
+    xid: u32,
+    /// Set once an OFFER names the server this client is negotiating
+    /// with, so `Bound`'s RENEW unicasts (see `send_message`'s doc
+    /// comment) go back to the right place.
+    server_ip: [u8; 4],
+    retries: u32,
+    retry_ticks: u32,
+    renew_ticks: u32,
+    rebind_ticks: u32,
+    expire_ticks: u32,
+}
+
+impl Interface {
+    const fn new() -> Self {
+        Self {
+            state: State::Init,
+            ip: DEFAULT_IP,
+            netmask: DEFAULT_NETMASK,
+            gateway: DEFAULT_GATEWAY,
+            dns: [0; 4],
+            xid: 0,
+            server_ip: [0; 4],
+            retries: 0,
+            retry_ticks: 0,
+            renew_ticks: 0,
+            rebind_ticks: 0,
+            expire_ticks: 0,
+        }
+    }
+}
+
+static IFACE: SpinLock<Interface> = SpinLock::new(Interface::new());
+
+/// A crude xid generator: no RNG in this `no_std` kernel, so each call
+/// just advances a counter seeded from the interface's current one. Good
+/// enough to tell this client's own transactions apart from a stray reply
+/// to somebody else's, which is all `xid` is used for here.
+fn next_xid(previous: u32) -> u32 {
+    previous.wrapping_mul(1_103_515_245).wrapping_add(12_345)
+}
+
+/// This kernel's current IPv4 address: `DEFAULT_IP` until a lease is
+/// acquired, the leased address afterwards.
+pub fn local_ip() -> [u8; 4] {
+    IFACE.lock().ip
+}
+
+/// This kernel's current subnet mask, `DEFAULT_NETMASK` until a lease
+/// says otherwise.
+pub fn netmask() -> [u8; 4] {
+    IFACE.lock().netmask
+}
+
+/// This kernel's current DNS server, `[0, 0, 0, 0]` until a lease supplies
+/// one -- unlike `local_ip`/`netmask`/`gateway`, there's no pre-lease
+/// default worth falling back to, since a DNS server address means nothing
+/// without a lease granting network reachability to it.
+pub fn dns() -> [u8; 4] {
+    IFACE.lock().dns
+}
+
+/// This kernel's current default gateway, `DEFAULT_GATEWAY` until a lease
+/// says otherwise.
+pub fn gateway() -> [u8; 4] {
+    IFACE.lock().gateway
+}
+
+/// Called once per `TrapCause::TimerInterrupt`, same cadence as
+/// `socket::process_rx`: lazily kicks off the initial DISCOVER the first
+/// time it's called (mirroring `NetDriver::negotiate_features`'s own
+/// lazy-init-on-first-poll pattern), then drives retries and lease
+/// renewal/expiry.
+pub fn on_timer_tick(memory: &mut dyn Memory) {
+    let mut iface = IFACE.lock();
+    match iface.state {
+        State::Init => {
+            let xid = next_xid(iface.xid);
+            iface.xid = xid;
+            iface.retries = 0;
+            iface.retry_ticks = RETRY_TICKS;
+            iface.state = State::Selecting;
+            send_discover(memory, xid);
+        }
+        State::Selecting | State::Requesting => {
+            iface.retry_ticks = iface.retry_ticks.saturating_sub(1);
+            if iface.retry_ticks > 0 {
+                return;
+            }
+            iface.retries += 1;
+            if iface.retries > MAX_RETRIES {
+                // Give up and keep `DEFAULT_IP`: same fallback `Interface::new`
+                // already started with, just without retrying forever.
+                iface.state = State::Bound;
+                iface.ip = DEFAULT_IP;
+                iface.netmask = DEFAULT_NETMASK;
+                iface.gateway = DEFAULT_GATEWAY;
+                return;
+            }
+            iface.retry_ticks = RETRY_TICKS;
+            let xid = iface.xid;
+            if iface.state == State::Selecting {
+                send_discover(memory, xid);
+            } else {
+                let requested_ip = iface.ip;
+                let server_ip = iface.server_ip;
+                send_request(memory, xid, requested_ip, server_ip);
+            }
+        }
+        State::Bound => {
+            iface.expire_ticks = iface.expire_ticks.saturating_sub(1);
+            if iface.expire_ticks == 0 {
+                // Lease lapsed with no RENEW/REBIND ever acked: start over
+                // rather than keep using an address that may no longer be
+                // ours.
+                *iface = Interface::new();
+                return;
+            }
+            iface.rebind_ticks = iface.rebind_ticks.saturating_sub(1);
+            iface.renew_ticks = iface.renew_ticks.saturating_sub(1);
+            if iface.renew_ticks == 0 || iface.rebind_ticks == 0 {
+                let xid = next_xid(iface.xid);
+                iface.xid = xid;
+                let requested_ip = iface.ip;
+                let server_ip = if iface.rebind_ticks == 0 {
+                    // REBIND broadcasts rather than asking the original
+                    // lease-granting server specifically, per RFC 2131 --
+                    // a broadcast dest skips ARP and goes straight to the
+                    // Ethernet broadcast MAC (see `send_message`'s doc
+                    // comment), while a RENEW unicasts to `server_ip`.
+                    [255, 255, 255, 255]
+                } else {
+                    iface.server_ip
+                };
+                send_request(memory, xid, requested_ip, server_ip);
+                // Re-arm the renew timer so a server that's slow to ack a
+                // RENEW doesn't get asked again every single tick; a fresh
+                // ACK overwrites these before they'd matter anyway.
+                iface.renew_ticks = iface.rebind_ticks.max(1);
+            }
+        }
+    }
+}
+
+/// Handle a UDP datagram addressed to port 68 (the DHCP client port),
+/// i.e. a reply from some server. `payload` is the datagram body, already
+/// stripped of its Ethernet/IPv4/UDP headers by `socket::process_rx`.
+pub fn handle_reply(memory: &mut dyn Memory, payload: &[u8]) {
+    let Some(message) = parse_message(payload) else {
+        return;
+    };
+
+    let mut iface = IFACE.lock();
+    if message.xid != iface.xid {
+        return;
+    }
+
+    match (iface.state, message.kind) {
+        (State::Selecting, DHCPOFFER) => {
+            iface.server_ip = message.server_id.unwrap_or([0; 4]);
+            iface.state = State::Requesting;
+            iface.retries = 0;
+            iface.retry_ticks = RETRY_TICKS;
+            iface.ip = message.your_ip;
+            let xid = iface.xid;
+            let requested_ip = message.your_ip;
+            let server_ip = iface.server_ip;
+            drop(iface);
+            send_request(memory, xid, requested_ip, server_ip);
+        }
+        (State::Requesting, DHCPACK) | (State::Bound, DHCPACK) => {
+            iface.ip = message.your_ip;
+            iface.netmask = message.subnet_mask.unwrap_or(DEFAULT_NETMASK);
+            iface.gateway = message.router.unwrap_or(DEFAULT_GATEWAY);
+            iface.dns = message.dns.unwrap_or([0; 4]);
+            let lease_ticks = message
+                .lease_seconds
+                .unwrap_or(3600)
+                .saturating_mul(TICKS_PER_SECOND);
+            // RFC 2131's recommended T1/T2 defaults: renew at half the
+            // lease, rebind at 7/8ths, expire at the full lease.
+            iface.renew_ticks = lease_ticks / 2;
+            iface.rebind_ticks = lease_ticks.saturating_mul(7) / 8;
+            iface.expire_ticks = lease_ticks;
+            iface.state = State::Bound;
+        }
+        (State::Requesting, DHCPNAK) | (State::Bound, DHCPNAK) => {
+            *iface = Interface::new();
+        }
+        _ => {}
+    }
+}
+
+struct ParsedMessage {
+    xid: u32,
+    kind: u8,
+    your_ip: [u8; 4],
+    server_id: Option<[u8; 4]>,
+    subnet_mask: Option<[u8; 4]>,
+    router: Option<[u8; 4]>,
+    dns: Option<[u8; 4]>,
+    lease_seconds: Option<u32>,
+}
+
+/// BOOTP's fixed-size fields end at byte 236; the 4-byte magic cookie and
+/// then options follow immediately after.
+const FIXED_LEN: usize = 236;
+
+fn parse_message(data: &[u8]) -> Option<ParsedMessage> {
+    if data.len() < FIXED_LEN + MAGIC_COOKIE.len() {
+        return None;
+    }
+    if data[236..240] != MAGIC_COOKIE {
+        return None;
+    }
+    let xid = u32::from_be_bytes(data[4..8].try_into().unwrap());
+    let your_ip = [data[16], data[17], data[18], data[19]];
+
+    let mut kind = 0u8;
+    let mut server_id = None;
+    let mut subnet_mask = None;
+    let mut router = None;
+    let mut dns = None;
+    let mut lease_seconds = None;
+
+    let mut options = &data[240..];
+    while let Some(&code) = options.first() {
+        if code == OPT_PAD {
+            options = &options[1..];
+            continue;
+        }
+        if code == OPT_END {
+            break;
+        }
+        let Some(&len) = options.get(1) else { break };
+        let len = len as usize;
+        let Some(value) = options.get(2..2 + len) else {
+            break;
+        };
+        match code {
+            OPT_MESSAGE_TYPE if len == 1 => kind = value[0],
+            OPT_SERVER_ID if len == 4 => server_id = Some([value[0], value[1], value[2], value[3]]),
+            OPT_SUBNET_MASK if len == 4 => subnet_mask = Some([value[0], value[1], value[2], value[3]]),
+            // A reply may list several routers/DNS servers; this client
+            // only ever needs one of each, so it keeps the first.
+            OPT_ROUTER if len >= 4 => router = Some([value[0], value[1], value[2], value[3]]),
+            OPT_DNS if len >= 4 => dns = Some([value[0], value[1], value[2], value[3]]),
+            OPT_LEASE_TIME if len == 4 => {
+                lease_seconds = Some(u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+            }
+            _ => {}
+        }
+        options = &options[2 + len..];
+    }
+
+    Some(ParsedMessage {
+        xid,
+        kind,
+        your_ip,
+        server_id,
+        subnet_mask,
+        router,
+        dns,
+        lease_seconds,
+    })
+}
+
+/// Build a BOOTP/DHCP message: the fixed 236-byte header with `ciaddr`/
+/// `yiaddr` set as given, `chaddr` filled with `LOCAL_MAC`, the magic
+/// cookie, and `options` appended verbatim before the terminating
+/// `OPT_END`.
+fn build_message(xid: u32, ciaddr: [u8; 4], options: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(FIXED_LEN + MAGIC_COOKIE.len() + options.len() + 1);
+    message.push(BOOTREQUEST);
+    message.push(HTYPE_ETHERNET);
+    message.push(6); // hlen: MAC address length
+    message.push(0); // hops
+    message.extend_from_slice(&xid.to_be_bytes());
+    message.extend_from_slice(&0u16.to_be_bytes()); // secs
+    message.extend_from_slice(&0x8000u16.to_be_bytes()); // flags: broadcast
+    message.extend_from_slice(&ciaddr);
+    message.extend_from_slice(&[0; 4]); // yiaddr
+    message.extend_from_slice(&[0; 4]); // siaddr
+    message.extend_from_slice(&[0; 4]); // giaddr
+    message.extend_from_slice(&LOCAL_MAC);
+    message.extend_from_slice(&[0; 10]); // chaddr padding
+    message.extend_from_slice(&[0; 64]); // sname
+    message.extend_from_slice(&[0; 128]); // file
+    message.extend_from_slice(&MAGIC_COOKIE);
+    message.extend_from_slice(options);
+    message.push(OPT_END);
+    message
+}
+
+fn send_discover(memory: &mut dyn Memory, xid: u32) {
+    let options = [OPT_MESSAGE_TYPE, 1, DHCPDISCOVER];
+    let message = build_message(xid, [0; 4], &options);
+    send_message(memory, [0, 0, 0, 0], [255, 255, 255, 255], &message);
+}
+
+fn send_request(memory: &mut dyn Memory, xid: u32, requested_ip: [u8; 4], server_ip: [u8; 4]) {
+    let options = [
+        OPT_MESSAGE_TYPE,
+        1,
+        DHCPREQUEST,
+        OPT_REQUESTED_IP,
+        4,
+        requested_ip[0],
+        requested_ip[1],
+        requested_ip[2],
+        requested_ip[3],
+    ];
+    let message = build_message(xid, [0; 4], &options);
+    send_message(memory, [0, 0, 0, 0], server_ip, &message);
+}
+
+/// Hand a built DHCP message to `net::udp::send_to`. A `dest_ip` of
+/// `255.255.255.255` (every pre-lease DISCOVER/REQUEST) always goes out to
+/// the Ethernet broadcast MAC; a unicast `server_ip` (renewing an existing
+/// lease) resolves through `net::ethernet`'s ARP cache the same as any
+/// other outbound datagram.
+fn send_message(memory: &mut dyn Memory, src_ip: [u8; 4], dest_ip: [u8; 4], message: &[u8]) {
+    udp::send_to(memory, src_ip, CLIENT_PORT, dest_ip, SERVER_PORT, message);
+}
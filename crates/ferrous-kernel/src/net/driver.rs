@@ -1,17 +1,370 @@
+use crate::error::SnapshotError;
+use crate::net::ipv4::{self, Ipv4Header};
+use crate::net::tcp::{self, TcpHeader, FLAG_ACK, FLAG_FIN, FLAG_RST, FLAG_SYN};
+use crate::net::udp::{self, UdpHeader};
+use crate::snapshot::DeviceState;
 use crate::sync::spinlock::SpinLock;
 use ferrous_vm::{Memory, PhysAddr};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpStream, UdpSocket};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zerocopy::{AsBytes, FromBytes};
 
-// Base Address
+// Base address of the virtqueue-based NIC's guest-memory-resident queues.
 const NET_BASE: u32 = 0x3000_0000;
 
-// Register Offsets
-const REG_STATUS: u32 = 0x00;
-const REG_COMMAND: u32 = 0x04;
-const REG_LENGTH: u32 = 0x08;
-const BUFFER_OFFSET: u32 = 0x100;
+/// Number of descriptor/avail-ring/used-ring slots per queue.
+const QUEUE_SIZE: u16 = 16;
 
+/// Descriptor chain continues into `next`, mirroring virtio's
+/// `VIRTQ_DESC_F_NEXT`.
+const DESC_F_NEXT: u16 = 0x1;
+
+const DESC_ENTRY_SIZE: u32 = 16; // {addr: u64, len: u32, flags: u16, next: u16}
+const USED_ENTRY_SIZE: u32 = 8; // {id: u32, len: u32}
+
+// Each queue is three contiguous regions: a descriptor table, an avail ring
+// the driver writes, and a used ring the device writes. RX and TX each get
+// their own queue, far enough apart that a full descriptor table plus both
+// rings never overlaps the next queue's.
+const RX_DESC_BASE: u32 = NET_BASE;
+const RX_AVAIL_BASE: u32 = NET_BASE + 0x100;
+const RX_USED_BASE: u32 = NET_BASE + 0x200;
+const TX_DESC_BASE: u32 = NET_BASE + 0x1000;
+const TX_AVAIL_BASE: u32 = NET_BASE + 0x1100;
+const TX_USED_BASE: u32 = NET_BASE + 0x1200;
+
+// A small config region past both queues, for the feature-negotiation
+// handshake: the device publishes what it supports, the driver acks the
+// subset it understands, same two-register dance as virtio's
+// `VIRTIO_PCI_DEVICE_FEATURES`/`VIRTIO_PCI_GUEST_FEATURES`.
+const CONFIG_BASE: u32 = NET_BASE + 0x2000;
+const REG_FEATURES: u32 = CONFIG_BASE; // device -> driver, read-only
+const REG_FEATURES_ACK: u32 = CONFIG_BASE + 0x4; // driver -> device
+
+/// Device can skip inserting a TX checksum, leaving the frame flagged
+/// "checksum needed" for whatever performs the offload (host stack, TAP
+/// backend) instead.
+pub const FEATURE_CSUM_OFFLOAD: u32 = 1 << 0;
+/// Driver verifies RX checksums itself rather than trusting the device.
+pub const FEATURE_GUEST_CSUM: u32 = 1 << 1;
+/// RX buffers may be merged across more than one descriptor.
+pub const FEATURE_MRG_RXBUF: u32 = 1 << 2;
+
+const SUPPORTED_FEATURES: u32 = FEATURE_CSUM_OFFLOAD | FEATURE_GUEST_CSUM | FEATURE_MRG_RXBUF;
+
+fn read_word(memory: &mut dyn Memory, addr: u32) -> u32 {
+    memory.read_word(PhysAddr::new(addr)).unwrap_or(0)
+}
+
+fn write_word(memory: &mut dyn Memory, addr: u32, val: u32) {
+    let _ = memory.write_word(PhysAddr::new(addr), val);
+}
+
+/// Read the 16-bit field at `addr` out of whichever word contains it;
+/// `avail`/`used` ring slots are two bytes apart, so not every field lands
+/// on a word boundary.
+fn read_u16(memory: &mut dyn Memory, addr: u32) -> u16 {
+    let word = read_word(memory, addr & !0x3);
+    if addr & 0x2 == 0 {
+        word as u16
+    } else {
+        (word >> 16) as u16
+    }
+}
+
+fn write_u16(memory: &mut dyn Memory, addr: u32, val: u16) {
+    let word_addr = addr & !0x3;
+    let old = read_word(memory, word_addr);
+    let new = if addr & 0x2 == 0 {
+        (old & 0xFFFF_0000) | val as u32
+    } else {
+        (old & 0x0000_FFFF) | ((val as u32) << 16)
+    };
+    write_word(memory, word_addr, new);
+}
+
+fn read_u64(memory: &mut dyn Memory, addr: u32) -> u64 {
+    read_word(memory, addr) as u64 | ((read_word(memory, addr + 4) as u64) << 32)
+}
+
+/// Copy `dst.len()` bytes out of guest memory starting at `src_addr`.
+fn copy_from_guest(memory: &mut dyn Memory, src_addr: u32, dst: &mut [u8]) {
+    let len = dst.len();
+    let mut i = 0;
+    while i < len {
+        let word = read_word(memory, src_addr + i as u32);
+        let bytes = word.to_le_bytes();
+        for (j, byte) in bytes.iter().enumerate() {
+            if i + j < len {
+                dst[i + j] = *byte;
+            }
+        }
+        i += 4;
+    }
+}
+
+/// Copy all of `src` into guest memory starting at `dst_addr`.
+fn copy_to_guest(memory: &mut dyn Memory, dst_addr: u32, src: &[u8]) {
+    let len = src.len();
+    let mut i = 0;
+    while i < len {
+        let mut word_bytes = [0u8; 4];
+        for (j, byte) in word_bytes.iter_mut().enumerate() {
+            if i + j < len {
+                *byte = src[i + j];
+            }
+        }
+        write_word(memory, dst_addr + i as u32, u32::from_le_bytes(word_bytes));
+        i += 4;
+    }
+}
+
+/// One entry of a split virtqueue's descriptor table.
+struct Descriptor {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+/// A virtio-style split virtqueue, laid out directly in guest physical
+/// memory: a descriptor table of `{addr, len, flags, next}` entries, an
+/// avail ring the driver uses to hand descriptor chains to the device, and
+/// a used ring the device uses to hand them back with the byte count it
+/// actually read or wrote.
+struct VirtQueue {
+    desc_base: u32,
+    avail_base: u32,
+    used_base: u32,
+}
+
+impl VirtQueue {
+    const fn new(desc_base: u32, avail_base: u32, used_base: u32) -> Self {
+        Self {
+            desc_base,
+            avail_base,
+            used_base,
+        }
+    }
+
+    fn avail_idx(&self, memory: &mut dyn Memory) -> u16 {
+        read_u16(memory, self.avail_base + 2)
+    }
+
+    fn avail_ring_entry(&self, memory: &mut dyn Memory, slot: u16) -> u16 {
+        let index = slot % QUEUE_SIZE;
+        read_u16(memory, self.avail_base + 4 + index as u32 * 2)
+    }
+
+    fn used_idx(&self, memory: &mut dyn Memory) -> u16 {
+        read_u16(memory, self.used_base + 2)
+    }
+
+    fn set_used_idx(&self, memory: &mut dyn Memory, idx: u16) {
+        write_u16(memory, self.used_base + 2, idx);
+    }
+
+    /// Publish `desc_id`/`len` into used-ring slot `slot` (wrapped to the
+    /// queue size), without touching `used.idx` — the caller bumps that
+    /// once the entry is written.
+    fn publish_used(&self, memory: &mut dyn Memory, slot: u16, desc_id: u16, len: u32) {
+        let index = slot % QUEUE_SIZE;
+        let entry = self.used_base + 4 + index as u32 * USED_ENTRY_SIZE;
+        write_word(memory, entry, desc_id as u32);
+        write_word(memory, entry + 4, len);
+    }
+
+    fn descriptor(&self, memory: &mut dyn Memory, id: u16) -> Descriptor {
+        let base = self.desc_base + (id % QUEUE_SIZE) as u32 * DESC_ENTRY_SIZE;
+        Descriptor {
+            addr: read_u64(memory, base),
+            len: read_word(memory, base + 8),
+            flags: read_u16(memory, base + 12),
+            next: read_u16(memory, base + 14),
+        }
+    }
+
+    /// Pop the next avail-ring descriptor-chain head not yet seen,
+    /// advancing `last_seen_avail`, or `None` if the driver hasn't posted
+    /// anything new since the last call.
+    fn pop_avail(&self, memory: &mut dyn Memory, last_seen_avail: &mut u16) -> Option<u16> {
+        if *last_seen_avail == self.avail_idx(memory) {
+            return None;
+        }
+        let head = self.avail_ring_entry(memory, *last_seen_avail);
+        *last_seen_avail = last_seen_avail.wrapping_add(1);
+        Some(head)
+    }
+}
+
+/// Magic number, version, and link-layer type for the pcap file format
+/// (https://wiki.wireshark.org/Development/LibpcapFileFormat) `PcapWriter`
+/// emits, so a capture opens straight in Wireshark without any `-T`/`-F`
+/// guessing.
+const PCAP_MAGIC: u32 = 0xA1B2_C3D4;
+const PCAP_VERSION_MAJOR: u16 = 2;
+const PCAP_VERSION_MINOR: u16 = 4;
+/// `LINKTYPE_ETHERNET`: every frame `NetDriver` moves already starts with
+/// the 14-byte Ethernet II header `net::udp`/`net::tcp` build.
+const PCAP_LINKTYPE_ETHERNET: u32 = 1;
+/// Largest frame length recorded in full; longer than this kernel's NIC
+/// model ever actually produces; just matches the global header's
+/// declared snaplen.
+const PCAP_SNAPLEN: u32 = 65535;
+
+/// An opt-in sink that mirrors every frame `NetDriver::send_packet`/
+/// `read_packet` moves into a standard pcap file, so a capture can be
+/// opened in Wireshark to diagnose the UDP/TCP paths instead of reading
+/// ad-hoc `log::info!` calls.
+struct PcapWriter {
+    file: File,
+}
+
+impl PcapWriter {
+    /// Create (or truncate) `path` and write the 24-byte pcap global
+    /// header once, up front.
+    fn create(path: &Path) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+        header.extend_from_slice(&PCAP_VERSION_MAJOR.to_le_bytes());
+        header.extend_from_slice(&PCAP_VERSION_MINOR.to_le_bytes());
+        header.extend_from_slice(&0i32.to_le_bytes()); // thiszone: GMT, no correction applied
+        header.extend_from_slice(&0u32.to_le_bytes()); // sigfigs: always 0
+        header.extend_from_slice(&PCAP_SNAPLEN.to_le_bytes());
+        header.extend_from_slice(&PCAP_LINKTYPE_ETHERNET.to_le_bytes());
+        file.write_all(&header)?;
+        Ok(Self { file })
+    }
+
+    /// Append one per-packet record: a 16-byte header (capture
+    /// timestamp, captured length, original length) followed by up to
+    /// `PCAP_SNAPLEN` bytes of `frame`. The timestamp is wall-clock time
+    /// this frame crossed `NetDriver`, not guest virtual time -- there's
+    /// no guest-visible clock this could be tied to instead.
+    fn write_packet(&mut self, frame: &[u8]) -> io::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let captured_len = frame.len().min(PCAP_SNAPLEN as usize);
+
+        let mut record = Vec::with_capacity(16 + captured_len);
+        record.extend_from_slice(&(now.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&now.subsec_micros().to_le_bytes());
+        record.extend_from_slice(&(captured_len as u32).to_le_bytes());
+        record.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        record.extend_from_slice(&frame[..captured_len]);
+        self.file.write_all(&record)
+    }
+}
+
+/// A guest packet addressed to this IP is NAT'd to `127.0.0.1` on the host
+/// instead of actually reaching anywhere -- the same "10.0.2.2 is the
+/// host" alias QEMU user-mode networking uses, which `net::syscalls`'s
+/// default guest address (and `net_test`) already assume a peer lives at.
+const GATEWAY_ALIAS: [u8; 4] = [10, 0, 2, 2];
+
+/// Duplicated from `net::udp`/`net::tcp`'s constants of the same name,
+/// same as those two already duplicate it from each other -- this is the
+/// MAC/EtherType any frame this driver synthesizes needs to carry.
+const LOCAL_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+const BROADCAST_MAC: [u8; 6] = [0xFF; 6];
+const ETHERTYPE_IPV4: u16 = 0x0800;
+
+/// Largest payload one synthesized reply carries, matching `net::tcp`'s
+/// own `MAX_SEGMENT_SIZE` so a bridged TCP connection never needs this
+/// driver to fragment what it read off the host socket.
+const MAX_NAT_PAYLOAD: usize = 1400;
+
+/// A UDP NAT mapping: the guest socket that sent the original datagram,
+/// and the host socket standing in for it. `connect`ed to the translated
+/// destination so `recv` only ever returns that peer's replies, the same
+/// "one mapping per guest port" idea `net::socket::SocketTable` uses for
+/// its own ephemeral ports.
+struct UdpNat {
+    socket: UdpSocket,
+    guest_ip: [u8; 4],
+    guest_port: u16,
+    dest_port: u16,
+}
+
+/// A TCP NAT mapping: the guest's connection bridged onto a real
+/// `TcpStream` to `127.0.0.1:dest_port`. This bridge runs its own minimal
+/// TCP endpoint facing the guest -- independent of `net::tcp`'s own state
+/// machine, the same way a real user-mode-networking NAT (SLIRP) speaks
+/// its own protocol stack to proxy a connection rather than reusing the
+/// guest's -- so `net::tcp::process_segment` just sees ordinary SYN-ACK/
+/// ACK/data segments arrive from "the peer" and drives its side of the
+/// handshake exactly as it would for a real remote host.
+struct TcpNat {
+    stream: TcpStream,
+    guest_ip: [u8; 4],
+    guest_port: u16,
+    dest_port: u16,
+    /// Next sequence number this bridge will use for its next segment to
+    /// the guest.
+    send_nxt: u32,
+    /// Next guest sequence number this bridge expects (its "ack").
+    recv_nxt: u32,
+    /// Whether the guest's final handshake ACK has arrived; before that,
+    /// `pump` won't forward anything read off `stream` yet.
+    established: bool,
+    /// Set once `stream` has hit EOF and this bridge has sent its own
+    /// `FIN`, so `pump_user_net` stops polling it. The mapping itself stays
+    /// in `user_net` until a guest `RST` removes it or a new `SYN` from the
+    /// same port replaces it -- there's no guest-facing `FinWait`/`LastAck`
+    /// tracking here, just enough to avoid re-sending the `FIN`.
+    closing: bool,
+}
+
+enum NatKind {
+    Udp(UdpNat),
+    Tcp(TcpNat),
+}
+
+/// Split-virtqueue NIC model: one RX queue the device fills from incoming
+/// packets and one TX queue the device drains to send them, each backed by
+/// guest-physical descriptor/avail/used-ring regions rather than the single
+/// status/length/command register set this replaces. `rx_last_seen_avail`/
+/// `tx_last_seen_avail` are the shadow cursors that let `poll` report how
+/// many new descriptors have been posted without re-scanning the whole ring.
 pub struct NetDriver {
-    base_addr: u32,
+    rx: VirtQueue,
+    tx: VirtQueue,
+    rx_last_seen_avail: u16,
+    tx_last_seen_avail: u16,
+    /// Features this device supports; published to `REG_FEATURES` on
+    /// negotiation.
+    avail_features: u32,
+    /// Subset of `avail_features` the driver has acked. Only valid once
+    /// `negotiated` is set.
+    acked_features: u32,
+    /// Whether `negotiate_features` has run. `poll` refuses to report
+    /// packets before this, so nothing drives the queues on features the
+    /// driver hasn't had a chance to ack yet.
+    negotiated: bool,
+    /// Checksum status of the most recent `send_packet`/`read_packet`,
+    /// set from the negotiated features so a future host-side backend (the
+    /// TAP bridge, say) knows whether it still needs to compute or verify
+    /// one. Nothing consumes these yet.
+    pub tx_checksum_needed: bool,
+    pub rx_checksum_verified: bool,
+    /// `Some` once `enable_capture` has opened a pcap file; every frame
+    /// `send_packet`/`read_packet` moves afterward is mirrored into it.
+    capture: Option<PcapWriter>,
+    /// User-mode networking (SLIRP-style) NAT mappings, keyed by the
+    /// guest's local port -- one live mapping per guest socket, the same
+    /// one-id-per-socket assumption `net::socket::SocketTable` makes.
+    user_net: BTreeMap<u16, NatKind>,
+    /// Frames the user-mode networking backend has synthesized from a
+    /// host socket's reply, waiting for `poll`/`read_packet` to hand
+    /// them out -- these bypass the RX virtqueue entirely since nothing
+    /// backs it with a real device to post avail descriptors for.
+    pending_rx: VecDeque<Vec<u8>>,
 }
 
 impl Default for NetDriver {
@@ -23,87 +376,524 @@ impl Default for NetDriver {
 impl NetDriver {
     pub const fn new() -> Self {
         Self {
-            base_addr: NET_BASE,
+            rx: VirtQueue::new(RX_DESC_BASE, RX_AVAIL_BASE, RX_USED_BASE),
+            tx: VirtQueue::new(TX_DESC_BASE, TX_AVAIL_BASE, TX_USED_BASE),
+            rx_last_seen_avail: 0,
+            tx_last_seen_avail: 0,
+            avail_features: SUPPORTED_FEATURES,
+            acked_features: 0,
+            negotiated: false,
+            tx_checksum_needed: false,
+            rx_checksum_verified: false,
+            capture: None,
+            user_net: BTreeMap::new(),
+            pending_rx: VecDeque::new(),
         }
     }
 
+    /// Open `path` as a pcap capture and start mirroring every frame this
+    /// driver moves into it. Opt-in: with this never called, `send_packet`/
+    /// `read_packet` behave exactly as before.
+    pub fn enable_capture(&mut self, path: &Path) -> io::Result<()> {
+        self.capture = Some(PcapWriter::create(path)?);
+        Ok(())
+    }
+
+    /// Publish `avail_features` to `REG_FEATURES`, then read back whatever
+    /// the driver wrote to `REG_FEATURES_ACK`, clamped to the features we
+    /// actually advertised — a driver can't ack a feature we never offered.
+    /// The clamped result is written back to `REG_FEATURES_ACK` so the
+    /// driver can tell which of its requested features actually stuck.
+    pub fn negotiate_features(&mut self, memory: &mut dyn Memory) {
+        write_word(memory, REG_FEATURES, self.avail_features);
+        let requested = read_word(memory, REG_FEATURES_ACK);
+        self.acked_features = requested & self.avail_features;
+        write_word(memory, REG_FEATURES_ACK, self.acked_features);
+        self.negotiated = true;
+    }
+
+    /// Whether `feature` was both advertised and acked. Always `false`
+    /// before `negotiate_features` has run.
+    pub fn has_feature(&self, feature: u32) -> bool {
+        self.negotiated && self.acked_features & feature != 0
+    }
+
+    /// Whether `negotiate_features` has run yet.
+    pub fn is_negotiated(&self) -> bool {
+        self.negotiated
+    }
+
+    /// Number of RX descriptor chains the driver has posted since the last
+    /// `poll`/`read_packet`, or `None` if there's nothing new (including
+    /// when feature negotiation hasn't completed yet — a driver has no
+    /// business touching the queues before it acks what it supports).
+    /// A frame waiting in `pending_rx` counts as one pending packet
+    /// regardless of negotiation or the avail ring -- the user-mode
+    /// networking backend isn't a real device the driver needs to have
+    /// acked anything to hear back from.
     pub fn poll(&mut self, memory: &mut dyn Memory) -> Option<usize> {
-        // Read Status
-        let status = memory
-            .read_word(PhysAddr::new(self.base_addr + REG_STATUS))
-            .ok()?;
-        if status == 1 {
-            // Read Length
-            let len = memory
-                .read_word(PhysAddr::new(self.base_addr + REG_LENGTH))
-                .ok()?;
-            Some(len as usize)
+        if !self.pending_rx.is_empty() {
+            return Some(self.pending_rx.len());
+        }
+        if !self.negotiated {
+            return None;
+        }
+        let avail_idx = self.rx.avail_idx(memory);
+        let pending = avail_idx.wrapping_sub(self.rx_last_seen_avail) as usize;
+        if pending > 0 {
+            Some(pending)
         } else {
             None
         }
     }
 
+    /// Consume the next pending RX descriptor chain, copying the guest
+    /// memory it names into `buffer` (following `next` while `DESC_F_NEXT`
+    /// is set), then publish the chain back to the used ring with the
+    /// number of bytes copied. Returns 0 if nothing was pending. A frame
+    /// queued in `pending_rx` by the user-mode networking backend is
+    /// handed out first, ahead of whatever's posted to the RX virtqueue.
     pub fn read_packet(&mut self, memory: &mut dyn Memory, buffer: &mut [u8]) -> usize {
-        let len = memory
-            .read_word(PhysAddr::new(self.base_addr + REG_LENGTH))
-            .unwrap_or(0) as usize;
+        if let Some(frame) = self.pending_rx.pop_front() {
+            let len = frame.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&frame[..len]);
+            if let Some(capture) = &mut self.capture {
+                let _ = capture.write_packet(&buffer[..len]);
+            }
+            return len;
+        }
+        let Some(head) = self.rx.pop_avail(memory, &mut self.rx_last_seen_avail) else {
+            return 0;
+        };
 
-        // Cap at buffer length
-        let read_len = if len > buffer.len() {
-            buffer.len()
-        } else {
-            len
+        let len = read_chain(memory, &self.rx, head, buffer);
+        publish(memory, &self.rx, head, len as u32);
+        // Without GUEST_CSUM the driver expects us to have already
+        // validated the checksum; with it acked, the driver checks for
+        // itself and this is never true.
+        self.rx_checksum_verified = !self.has_feature(FEATURE_GUEST_CSUM);
+        if let Some(capture) = &mut self.capture {
+            let _ = capture.write_packet(&buffer[..len]);
+        }
+        len
+    }
+
+    /// Consume the next pending TX descriptor chain, copying `data` into
+    /// the guest memory it names (following `next` while `DESC_F_NEXT` is
+    /// set), then publish the chain back to the used ring with the number
+    /// of bytes written. Returns 0 if nothing was pending.
+    pub fn send_packet(&mut self, memory: &mut dyn Memory, data: &[u8]) -> usize {
+        let Some(head) = self.tx.pop_avail(memory, &mut self.tx_last_seen_avail) else {
+            return 0;
         };
 
-        // Read from MMIO window
-        let window_base = self.base_addr + BUFFER_OFFSET;
-        let mut i = 0;
-        while i < read_len {
-            if let Ok(word) = memory.read_word(PhysAddr::new(window_base + (i as u32))) {
-                let bytes = word.to_le_bytes();
-                for j in 0..4 {
-                    if i + j < read_len {
-                        buffer[i + j] = bytes[j];
-                    }
-                }
-            }
-            i += 4;
+        let len = write_chain(memory, &self.tx, head, data);
+        publish(memory, &self.tx, head, len as u32);
+        // With CSUM_OFFLOAD acked the driver is allowed to send frames with
+        // no checksum filled in, leaving it flagged here for whatever
+        // transmits the frame onward to fill in instead.
+        self.tx_checksum_needed = self.has_feature(FEATURE_CSUM_OFFLOAD);
+        if let Some(capture) = &mut self.capture {
+            let _ = capture.write_packet(&data[..len]);
         }
+        self.nat_outbound(&data[..len]);
+        len
+    }
 
-        // Acknowledge read (clears buffer)
-        // Command: Recv (2)
-        let _ = memory.write_word(PhysAddr::new(self.base_addr + REG_COMMAND), 2);
+    /// User-mode networking: if `frame` is a UDP or TCP segment addressed
+    /// to `GATEWAY_ALIAS`, bridge it onto a real host socket instead of
+    /// letting it vanish into the void the way every other destination
+    /// still does (there's no TAP/real interface backing this driver).
+    /// Best-effort throughout -- a parse failure or host I/O error just
+    /// means this frame doesn't get bridged, the same "drop and let the
+    /// sender's own retransmission cover it" approach `net::tcp` already
+    /// takes for anything it can't make sense of.
+    fn nat_outbound(&mut self, frame: &[u8]) {
+        if frame.len() < 14 + Ipv4Header::LEN {
+            return;
+        }
+        let eth_type = u16::from_be_bytes([frame[12], frame[13]]);
+        if eth_type != ETHERTYPE_IPV4 {
+            return;
+        }
+        let ip_offset = 14;
+        let Some(ip_header) = Ipv4Header::read_from(&frame[ip_offset..ip_offset + Ipv4Header::LEN])
+        else {
+            return;
+        };
+        if ip_header.dest_ip != GATEWAY_ALIAS {
+            return;
+        }
+        let payload_offset = ip_offset + Ipv4Header::LEN;
+        let payload = &frame[payload_offset..];
 
-        read_len
+        if ip_header.protocol == ipv4::PROTO_UDP {
+            self.nat_udp_outbound(ip_header.src_ip, payload);
+        } else if ip_header.protocol == ipv4::PROTO_TCP {
+            self.nat_tcp_outbound(ip_header.src_ip, payload);
+        }
     }
 
-    pub fn send_packet(&mut self, memory: &mut dyn Memory, data: &[u8]) {
-        let len = data.len();
-        if len > 2048 {
+    fn nat_udp_outbound(&mut self, guest_ip: [u8; 4], segment: &[u8]) {
+        if segment.len() < UdpHeader::LEN {
+            return;
+        }
+        let Some(header) = UdpHeader::read_from(&segment[..UdpHeader::LEN]) else {
+            return;
+        };
+        let guest_port = u16::from_be(header.src_port);
+        let dest_port = u16::from_be(header.dest_port);
+        let data = &segment[UdpHeader::LEN..];
+
+        if !matches!(self.user_net.get(&guest_port), Some(NatKind::Udp(nat)) if nat.dest_port == dest_port)
+        {
+            let Ok(socket) = UdpSocket::bind("0.0.0.0:0") else {
+                return;
+            };
+            let Ok(()) = socket.set_nonblocking(true) else {
+                return;
+            };
+            let dest = SocketAddrV4::new(Ipv4Addr::LOCALHOST, dest_port);
+            if socket.connect(dest).is_err() {
+                return;
+            }
+            self.user_net.insert(
+                guest_port,
+                NatKind::Udp(UdpNat {
+                    socket,
+                    guest_ip,
+                    guest_port,
+                    dest_port,
+                }),
+            );
+        }
+        if let Some(NatKind::Udp(nat)) = self.user_net.get(&guest_port) {
+            let _ = nat.socket.send(data);
+        }
+    }
+
+    fn nat_tcp_outbound(&mut self, guest_ip: [u8; 4], segment: &[u8]) {
+        if segment.len() < TcpHeader::LEN {
+            return;
+        }
+        let Some(header) = TcpHeader::read_from(&segment[..TcpHeader::LEN]) else {
+            return;
+        };
+        let guest_port = u16::from_be(header.src_port);
+        let dest_port = u16::from_be(header.dest_port);
+        let guest_seq = u32::from_be(header.seq);
+        let flags = header.flags();
+        let payload = &segment[TcpHeader::LEN..];
+
+        if flags & FLAG_RST != 0 {
+            self.user_net.remove(&guest_port);
+            return;
+        }
+
+        if flags & FLAG_SYN != 0 && flags & FLAG_ACK == 0 {
+            let dest = SocketAddrV4::new(Ipv4Addr::LOCALHOST, dest_port);
+            let Ok(stream) = TcpStream::connect_timeout(&dest.into(), Duration::from_millis(200))
+            else {
+                return;
+            };
+            let _ = stream.set_nonblocking(true);
+            let mut nat = TcpNat {
+                stream,
+                guest_ip,
+                guest_port,
+                dest_port,
+                send_nxt: 0,
+                recv_nxt: guest_seq.wrapping_add(1),
+                established: false,
+                closing: false,
+            };
+            let syn_ack = build_tcp_segment(
+                GATEWAY_ALIAS,
+                dest_port,
+                guest_ip,
+                guest_port,
+                nat.send_nxt,
+                nat.recv_nxt,
+                FLAG_SYN | FLAG_ACK,
+                &[],
+            );
+            nat.send_nxt = nat.send_nxt.wrapping_add(1);
+            self.pending_rx.push_back(syn_ack);
+            self.user_net.insert(guest_port, NatKind::Tcp(nat));
             return;
         }
 
-        // Write length
-        let _ = memory.write_word(PhysAddr::new(self.base_addr + REG_LENGTH), len as u32);
+        let Some(NatKind::Tcp(nat)) = self.user_net.get_mut(&guest_port) else {
+            return;
+        };
+        if nat.dest_port != dest_port {
+            return;
+        }
+
+        if flags & FLAG_ACK != 0 && !nat.established {
+            nat.established = true;
+        }
+        if !payload.is_empty() {
+            if nat.stream.write_all(payload).is_err() {
+                self.user_net.remove(&guest_port);
+                return;
+            }
+            nat.recv_nxt = nat.recv_nxt.wrapping_add(payload.len() as u32);
+            let ack = build_tcp_segment(
+                GATEWAY_ALIAS,
+                dest_port,
+                guest_ip,
+                guest_port,
+                nat.send_nxt,
+                nat.recv_nxt,
+                FLAG_ACK,
+                &[],
+            );
+            self.pending_rx.push_back(ack);
+        }
+        if flags & FLAG_FIN != 0 {
+            nat.recv_nxt = nat.recv_nxt.wrapping_add(1);
+            let ack = build_tcp_segment(
+                GATEWAY_ALIAS,
+                dest_port,
+                guest_ip,
+                guest_port,
+                nat.send_nxt,
+                nat.recv_nxt,
+                FLAG_ACK,
+                &[],
+            );
+            self.pending_rx.push_back(ack);
+            let _ = nat.stream.shutdown(std::net::Shutdown::Write);
+        }
+    }
 
-        // Write to MMIO window
-        let window_base = self.base_addr + BUFFER_OFFSET;
-        let mut i = 0;
-        while i < len {
-            let mut word_bytes = [0u8; 4];
-            for j in 0..4 {
-                if i + j < len {
-                    word_bytes[j] = data[i + j];
+    /// Drain every NAT'd host socket for replies and synthesize them into
+    /// `pending_rx`, called once per `TrapCause::TimerInterrupt` the same
+    /// cadence `net::dhcp::on_timer_tick`/`net::tcp::on_timer_tick` poll
+    /// at.
+    pub fn pump_user_net(&mut self) {
+        let mut dead = Vec::new();
+        for (&guest_port, nat) in self.user_net.iter_mut() {
+            match nat {
+                NatKind::Udp(nat) => {
+                    let mut buf = [0u8; MAX_NAT_PAYLOAD];
+                    match nat.socket.recv(&mut buf) {
+                        Ok(n) => {
+                            let frame = build_udp_frame(
+                                GATEWAY_ALIAS,
+                                nat.dest_port,
+                                nat.guest_ip,
+                                nat.guest_port,
+                                &buf[..n],
+                            );
+                            self.pending_rx.push_back(frame);
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                        Err(_) => dead.push(guest_port),
+                    }
+                }
+                NatKind::Tcp(nat) => {
+                    if !nat.established || nat.closing {
+                        continue;
+                    }
+                    let mut buf = [0u8; MAX_NAT_PAYLOAD];
+                    match nat.stream.read(&mut buf) {
+                        Ok(0) => {
+                            let fin = build_tcp_segment(
+                                GATEWAY_ALIAS,
+                                nat.dest_port,
+                                nat.guest_ip,
+                                nat.guest_port,
+                                nat.send_nxt,
+                                nat.recv_nxt,
+                                FLAG_FIN | FLAG_ACK,
+                                &[],
+                            );
+                            nat.send_nxt = nat.send_nxt.wrapping_add(1);
+                            nat.closing = true;
+                            self.pending_rx.push_back(fin);
+                        }
+                        Ok(n) => {
+                            let data = build_tcp_segment(
+                                GATEWAY_ALIAS,
+                                nat.dest_port,
+                                nat.guest_ip,
+                                nat.guest_port,
+                                nat.send_nxt,
+                                nat.recv_nxt,
+                                FLAG_ACK | tcp::FLAG_PSH,
+                                &buf[..n],
+                            );
+                            nat.send_nxt = nat.send_nxt.wrapping_add(n as u32);
+                            self.pending_rx.push_back(data);
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                        Err(_) => dead.push(guest_port),
+                    }
                 }
             }
-            let word = u32::from_le_bytes(word_bytes);
-            let _ = memory.write_word(PhysAddr::new(window_base + (i as u32)), word);
-            i += 4;
         }
+        for guest_port in dead {
+            self.user_net.remove(&guest_port);
+        }
+    }
+}
 
-        // Command: Send (1)
-        let _ = memory.write_word(PhysAddr::new(self.base_addr + REG_COMMAND), 1);
+/// Build an Ethernet II / IPv4 / UDP frame, the same construction
+/// `net::udp::send_to` does, for the user-mode networking backend to
+/// synthesize a reply with.
+fn build_udp_frame(
+    src_ip: [u8; 4],
+    src_port: u16,
+    dest_ip: [u8; 4],
+    dest_port: u16,
+    payload: &[u8],
+) -> Vec<u8> {
+    let udp_len = (UdpHeader::LEN + payload.len()) as u16;
+    let mut header = UdpHeader {
+        src_port: src_port.to_be(),
+        dest_port: dest_port.to_be(),
+        length: udp_len.to_be(),
+        checksum: 0,
+    };
+    header.checksum = udp::udp_checksum(&header, src_ip, dest_ip, payload).to_be();
+    let ip_header = Ipv4Header::new(udp_len, ipv4::PROTO_UDP, src_ip, dest_ip);
+    build_ethernet_frame(&ip_header, header.as_bytes(), payload)
+}
+
+/// Build an Ethernet II / IPv4 / TCP frame, the same construction
+/// `net::tcp::send_segment` does, for the user-mode networking backend's
+/// own minimal TCP endpoint to synthesize a segment with.
+#[allow(clippy::too_many_arguments)]
+fn build_tcp_segment(
+    src_ip: [u8; 4],
+    src_port: u16,
+    dest_ip: [u8; 4],
+    dest_port: u16,
+    seq: u32,
+    ack: u32,
+    flags: u8,
+    payload: &[u8],
+) -> Vec<u8> {
+    let offset_flags: u16 = (5u16 << 12) | (flags as u16 & 0x3F);
+    let mut header = TcpHeader {
+        src_port: src_port.to_be(),
+        dest_port: dest_port.to_be(),
+        seq: seq.to_be(),
+        ack: ack.to_be(),
+        offset_flags: offset_flags.to_be(),
+        window: 8192u16.to_be(),
+        checksum: 0,
+        urgent_ptr: 0,
+    };
+    header.checksum = tcp::tcp_checksum(&header, src_ip, dest_ip, payload).to_be();
+    let total_len = (TcpHeader::LEN + payload.len()) as u16;
+    let ip_header = Ipv4Header::new(total_len, ipv4::PROTO_TCP, src_ip, dest_ip);
+    build_ethernet_frame(&ip_header, header.as_bytes(), payload)
+}
+
+fn build_ethernet_frame(ip_header: &Ipv4Header, transport_header: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(14 + Ipv4Header::LEN + transport_header.len() + payload.len());
+    frame.extend_from_slice(&BROADCAST_MAC);
+    frame.extend_from_slice(&LOCAL_MAC);
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+    frame.extend_from_slice(ip_header.as_bytes());
+    frame.extend_from_slice(transport_header);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+const SAVED_STATE_LEN: usize = 4 + 4 + 1 + 2 + 2;
+
+impl DeviceState for NetDriver {
+    /// Negotiated features and both rings' shadow cursors, in that order.
+    /// The descriptor tables and rings themselves aren't included here —
+    /// they live in guest-physical memory and come back with whatever
+    /// restores that; restoring `rx_last_seen_avail` to its saved value is
+    /// what makes a packet the guest posted but we hadn't consumed yet
+    /// get re-presented by `poll` after restore instead of silently
+    /// dropped.
+    fn save(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(SAVED_STATE_LEN);
+        out.extend_from_slice(&self.avail_features.to_le_bytes());
+        out.extend_from_slice(&self.acked_features.to_le_bytes());
+        out.push(self.negotiated as u8);
+        out.extend_from_slice(&self.rx_last_seen_avail.to_le_bytes());
+        out.extend_from_slice(&self.tx_last_seen_avail.to_le_bytes());
+        out
+    }
+
+    fn restore(&mut self, data: &[u8]) -> Result<(), SnapshotError> {
+        if data.len() < SAVED_STATE_LEN {
+            return Err(SnapshotError::Truncated {
+                expected: SAVED_STATE_LEN,
+                actual: data.len(),
+            });
+        }
+        self.avail_features = u32::from_le_bytes(data[0..4].try_into().unwrap());
+        self.acked_features = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        self.negotiated = data[8] != 0;
+        self.rx_last_seen_avail = u16::from_le_bytes(data[9..11].try_into().unwrap());
+        self.tx_last_seen_avail = u16::from_le_bytes(data[11..13].try_into().unwrap());
+        Ok(())
+    }
+}
+
+/// Write `head`'s result (`len` bytes read or written) into the next used
+/// ring slot and bump `used.idx` so the ring's consumer sees it.
+fn publish(memory: &mut dyn Memory, queue: &VirtQueue, head: u16, len: u32) {
+    let used_slot = queue.used_idx(memory);
+    queue.publish_used(memory, used_slot, head, len);
+    queue.set_used_idx(memory, used_slot.wrapping_add(1));
+}
+
+/// Walk the descriptor chain starting at `head`, following `next` while
+/// `DESC_F_NEXT` is set, copying each descriptor's guest-memory region into
+/// `buffer` up to `buffer.len()` total. Stops after `QUEUE_SIZE` hops so a
+/// guest-corrupted chain can't loop forever.
+fn read_chain(memory: &mut dyn Memory, queue: &VirtQueue, head: u16, buffer: &mut [u8]) -> usize {
+    let mut done = 0usize;
+    let mut desc_id = head;
+    for _ in 0..QUEUE_SIZE {
+        if done >= buffer.len() {
+            break;
+        }
+        let desc = queue.descriptor(memory, desc_id);
+        let remaining = buffer.len() - done;
+        let chunk = (desc.len as usize).min(remaining);
+        copy_from_guest(memory, desc.addr as u32, &mut buffer[done..done + chunk]);
+        done += chunk;
+        if desc.flags & DESC_F_NEXT == 0 {
+            break;
+        }
+        desc_id = desc.next;
+    }
+    done
+}
+
+/// Walk the descriptor chain starting at `head`, following `next` while
+/// `DESC_F_NEXT` is set, copying `data` into each descriptor's guest-memory
+/// region in turn. Stops after `QUEUE_SIZE` hops so a guest-corrupted chain
+/// can't loop forever.
+fn write_chain(memory: &mut dyn Memory, queue: &VirtQueue, head: u16, data: &[u8]) -> usize {
+    let mut done = 0usize;
+    let mut desc_id = head;
+    for _ in 0..QUEUE_SIZE {
+        if done >= data.len() {
+            break;
+        }
+        let desc = queue.descriptor(memory, desc_id);
+        let remaining = data.len() - done;
+        let chunk = (desc.len as usize).min(remaining);
+        copy_to_guest(memory, desc.addr as u32, &data[done..done + chunk]);
+        done += chunk;
+        if desc.flags & DESC_F_NEXT == 0 {
+            break;
+        }
+        desc_id = desc.next;
     }
+    done
 }
 
 pub static DRIVER: SpinLock<NetDriver> = SpinLock::new(NetDriver::new());
@@ -0,0 +1,242 @@
+//! Ethernet framing and ARP address resolution, the missing piece between
+//! `net::udp`/`net::tcp` building an IPv4 payload and `net::driver::
+//! NetDriver` actually putting a frame on the wire: until now every send
+//! addressed the Ethernet broadcast MAC and hoped for the best, the "ARP
+//! resolution doesn't exist yet" simplification `net::udp::send_to`'s own
+//! doc comment used to call out.
+//!
+//! `ARP_CACHE` maps a resolved IPv4 address to its MAC and a countdown
+//! (`on_timer_tick` ages it the same way `net::tcp`'s retransmit/`TimeWait`
+//! timers count down ticks rather than wall-clock time); `PENDING` holds
+//! frames that arrived before their destination resolved, keyed by the
+//! destination IP they're waiting on.
+
+use crate::net::driver::DRIVER;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use ferrous_vm::Memory;
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
+
+/// A 14-byte Ethernet II header: destination MAC, source MAC, then a
+/// 16-bit EtherType naming what follows (`ETHERTYPE_IPV4`/`ETHERTYPE_ARP`
+/// below). Wire-order fields throughout, the same convention
+/// `net::ipv4::Ipv4Header`/`net::udp::UdpHeader` use.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct EthernetHeader {
+    pub dest_mac: [u8; 6],
+    pub src_mac: [u8; 6],
+    pub ethertype: u16, // Big Endian
+}
+
+impl EthernetHeader {
+    pub const LEN: usize = 14;
+}
+
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+
+/// A 28-byte ARP packet for Ethernet/IPv4 (`htype` 1, `ptype` 0x0800,
+/// `hlen` 6, `plen` 4) -- the only combination this kernel's single
+/// interface ever needs to speak.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct ArpPacket {
+    pub htype: u16, // Big Endian
+    pub ptype: u16, // Big Endian
+    pub hlen: u8,
+    pub plen: u8,
+    pub opcode: u16, // Big Endian
+    pub sender_mac: [u8; 6],
+    pub sender_ip: [u8; 4],
+    pub target_mac: [u8; 6],
+    pub target_ip: [u8; 4],
+}
+
+impl ArpPacket {
+    pub const LEN: usize = 28;
+}
+
+const ARP_HTYPE_ETHERNET: u16 = 1;
+const ARP_OP_REQUEST: u16 = 1;
+const ARP_OP_REPLY: u16 = 2;
+
+/// Fixed MAC this kernel's single network interface answers to, matching
+/// `net::udp`/`net::tcp`/`net::driver`'s constants of the same name
+/// (duplicated rather than shared, same as those already duplicate it
+/// from each other).
+const LOCAL_MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+const BROADCAST_MAC: [u8; 6] = [0xFF; 6];
+
+/// Ticks (at `thread::NANOS_PER_TICK` each) a resolved ARP cache entry
+/// stays valid before `on_timer_tick` reaps it and a fresh request is
+/// needed -- fixed rather than honoring whatever TTL a reply carried,
+/// since ARP has no such field to honor in the first place.
+const ARP_CACHE_TTL_TICKS: u32 = 6000;
+
+struct CacheEntry {
+    mac: [u8; 6],
+    ticks_left: u32,
+}
+
+static ARP_CACHE: crate::sync::spinlock::SpinLock<BTreeMap<[u8; 4], CacheEntry>> =
+    crate::sync::spinlock::SpinLock::new(BTreeMap::new());
+
+/// Ticks a destination IP's `PENDING` entry survives with no ARP reply
+/// before `on_timer_tick` drops it -- otherwise a peer that never answers
+/// leaves its queued frames (and the memory behind them) parked forever.
+const PENDING_TTL_TICKS: u32 = 300;
+
+/// Frames waiting on an in-flight ARP request for one destination IP, plus
+/// a countdown `on_timer_tick` ages the same way it ages `ARP_CACHE`
+/// entries. Each frame is a complete Ethernet frame with its destination
+/// MAC left as `BROADCAST_MAC`, patched in place once the real address
+/// resolves.
+struct PendingEntry {
+    ticks_left: u32,
+    frames: Vec<Vec<u8>>,
+}
+
+static PENDING: crate::sync::spinlock::SpinLock<BTreeMap<[u8; 4], PendingEntry>> =
+    crate::sync::spinlock::SpinLock::new(BTreeMap::new());
+
+/// IPv4's limited-broadcast address: never ARP-resolved, since no single
+/// host owns it -- `net::dhcp`'s pre-lease DISCOVER/REQUEST messages are
+/// addressed here and must always reach the broadcast MAC directly.
+const IPV4_BROADCAST: [u8; 4] = [255, 255, 255, 255];
+
+/// Build a complete Ethernet II frame around `ethertype`/`payload` and hand
+/// it straight to the `NetDriver`, resolving `dest_ip`'s MAC from the ARP
+/// cache first. A cache miss queues `payload` under `dest_ip` in `PENDING`
+/// and broadcasts an ARP request instead of sending anything yet --
+/// `handle_frame`'s reply path flushes it once the address is known.
+/// `IPV4_BROADCAST` skips ARP entirely and always goes out to the
+/// Ethernet broadcast MAC.
+pub fn send_ipv4(memory: &mut dyn Memory, local_ip: [u8; 4], dest_ip: [u8; 4], payload: &[u8]) {
+    let mut frame = Vec::with_capacity(EthernetHeader::LEN + payload.len());
+    frame.extend_from_slice(&BROADCAST_MAC);
+    frame.extend_from_slice(&LOCAL_MAC);
+    frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+    frame.extend_from_slice(payload);
+
+    if dest_ip == IPV4_BROADCAST {
+        DRIVER.lock().send_packet(memory, &frame);
+        return;
+    }
+
+    if let Some(mac) = resolve(dest_ip) {
+        frame[0..6].copy_from_slice(&mac);
+        DRIVER.lock().send_packet(memory, &frame);
+        return;
+    }
+
+    let first_for_ip = {
+        let mut pending = PENDING.lock();
+        let entry = pending.entry(dest_ip).or_insert_with(|| PendingEntry {
+            ticks_left: PENDING_TTL_TICKS,
+            frames: Vec::new(),
+        });
+        entry.frames.push(frame);
+        entry.frames.len() == 1
+    };
+    if first_for_ip {
+        send_arp_request(memory, local_ip, dest_ip);
+    }
+}
+
+/// Look up `ip`'s MAC, `None` if it's never been resolved or its entry has
+/// aged out.
+fn resolve(ip: [u8; 4]) -> Option<[u8; 6]> {
+    ARP_CACHE.lock().get(&ip).map(|entry| entry.mac)
+}
+
+fn send_arp_request(memory: &mut dyn Memory, local_ip: [u8; 4], target_ip: [u8; 4]) {
+    let arp = ArpPacket {
+        htype: ARP_HTYPE_ETHERNET.to_be(),
+        ptype: ETHERTYPE_IPV4.to_be(),
+        hlen: 6,
+        plen: 4,
+        opcode: ARP_OP_REQUEST.to_be(),
+        sender_mac: LOCAL_MAC,
+        sender_ip: local_ip,
+        target_mac: [0; 6],
+        target_ip,
+    };
+    let mut frame = Vec::with_capacity(EthernetHeader::LEN + ArpPacket::LEN);
+    frame.extend_from_slice(&BROADCAST_MAC);
+    frame.extend_from_slice(&LOCAL_MAC);
+    frame.extend_from_slice(&ETHERTYPE_ARP.to_be_bytes());
+    frame.extend_from_slice(arp.as_bytes());
+    DRIVER.lock().send_packet(memory, &frame);
+}
+
+/// Handle one inbound ARP frame (already stripped of its Ethernet header):
+/// learn the sender's IP/MAC mapping, answer a request for `local_ip` with
+/// a reply, and flush any frames a reply just unblocked in `PENDING`.
+pub fn handle_frame(memory: &mut dyn Memory, local_ip: [u8; 4], frame: &[u8]) {
+    if frame.len() < ArpPacket::LEN {
+        return;
+    }
+    let Some(arp) = ArpPacket::read_from(&frame[..ArpPacket::LEN]) else {
+        return;
+    };
+    let opcode = u16::from_be(arp.opcode);
+    let sender_ip = arp.sender_ip;
+    let sender_mac = arp.sender_mac;
+
+    ARP_CACHE.lock().insert(
+        sender_ip,
+        CacheEntry {
+            mac: sender_mac,
+            ticks_left: ARP_CACHE_TTL_TICKS,
+        },
+    );
+
+    if opcode == ARP_OP_REQUEST && arp.target_ip == local_ip {
+        let reply = ArpPacket {
+            htype: ARP_HTYPE_ETHERNET.to_be(),
+            ptype: ETHERTYPE_IPV4.to_be(),
+            hlen: 6,
+            plen: 4,
+            opcode: ARP_OP_REPLY.to_be(),
+            sender_mac: LOCAL_MAC,
+            sender_ip: local_ip,
+            target_mac: sender_mac,
+            target_ip: sender_ip,
+        };
+        let mut out = Vec::with_capacity(EthernetHeader::LEN + ArpPacket::LEN);
+        out.extend_from_slice(&sender_mac);
+        out.extend_from_slice(&LOCAL_MAC);
+        out.extend_from_slice(&ETHERTYPE_ARP.to_be_bytes());
+        out.extend_from_slice(reply.as_bytes());
+        DRIVER.lock().send_packet(memory, &out);
+    }
+
+    if opcode == ARP_OP_REPLY {
+        let queued = PENDING.lock().remove(&sender_ip);
+        if let Some(queued) = queued {
+            let mut driver = DRIVER.lock();
+            for mut queued_frame in queued.frames {
+                queued_frame[0..6].copy_from_slice(&sender_mac);
+                driver.send_packet(memory, &queued_frame);
+            }
+        }
+    }
+}
+
+/// Age every cache entry and every still-unresolved `PENDING` entry by one
+/// tick, reaping whatever hits zero -- called once per `TrapCause::
+/// TimerInterrupt`, the same cadence `net::tcp::on_timer_tick` ages its own
+/// retransmit/`TimeWait` counters at. Without this, a destination that
+/// never answers an ARP request would leave its queued frames parked in
+/// `PENDING` forever.
+pub fn on_timer_tick() {
+    ARP_CACHE.lock().retain(|_, entry| {
+        entry.ticks_left = entry.ticks_left.saturating_sub(1);
+        entry.ticks_left > 0
+    });
+    PENDING.lock().retain(|_, entry| {
+        entry.ticks_left = entry.ticks_left.saturating_sub(1);
+        entry.ticks_left > 0
+    });
+}
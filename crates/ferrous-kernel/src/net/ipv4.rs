@@ -0,0 +1,70 @@
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
+
+/// IP protocol number for UDP, per IANA's assigned internet protocol
+/// numbers.
+pub const PROTO_UDP: u8 = 17;
+/// IP protocol number for TCP, same registry.
+pub const PROTO_TCP: u8 = 6;
+
+/// A fixed 20-byte IPv4 header (no options). Every field wider than a byte
+/// is stored pre-converted to big-endian, i.e. already in wire order, the
+/// same convention `net::udp::UdpHeader` uses, so a caller reads a field
+/// back with `u16::from_be`/`u32::from_be` rather than a network-to-host
+/// swap happening implicitly somewhere.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct Ipv4Header {
+    pub version_ihl: u8,
+    pub dscp_ecn: u8,
+    pub total_length: u16, // Big Endian
+    pub identification: u16, // Big Endian
+    pub flags_fragment: u16, // Big Endian
+    pub ttl: u8,
+    pub protocol: u8,
+    pub checksum: u16, // Big Endian
+    pub src_ip: [u8; 4],
+    pub dest_ip: [u8; 4],
+}
+
+impl Ipv4Header {
+    pub const LEN: usize = 20;
+
+    /// Build a header for a payload of `payload_len` bytes, with the header
+    /// checksum already filled in.
+    pub fn new(payload_len: u16, protocol: u8, src_ip: [u8; 4], dest_ip: [u8; 4]) -> Self {
+        let mut header = Self {
+            version_ihl: 0x45, // Version 4, 5 32-bit words (no options)
+            dscp_ecn: 0,
+            total_length: (Self::LEN as u16 + payload_len).to_be(),
+            identification: 0,
+            flags_fragment: 0,
+            ttl: 64,
+            protocol,
+            checksum: 0,
+            src_ip,
+            dest_ip,
+        };
+        header.checksum = checksum(header.as_bytes()).to_be();
+        header
+    }
+}
+
+/// Internet checksum (RFC 1071): the ones'-complement sum of `data` as
+/// 16-bit big-endian words, with carries folded back in until the result
+/// fits 16 bits, then ones'-complemented. `data` is read as already-wire-
+/// order bytes, so this works equally for an IPv4 header and for the UDP
+/// header + pseudo-header + payload `net::udp` feeds it.
+pub(crate) fn checksum(data: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    let mut chunks = data.chunks_exact(2);
+    for chunk in &mut chunks {
+        sum += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+    }
+    if let [last] = *chunks.remainder() {
+        sum += (last as u32) << 8;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
@@ -1,8 +1,30 @@
+pub mod dhcp;
 pub mod driver;
+pub mod ethernet;
 pub mod ipv4;
+pub mod poll;
 pub mod socket;
 pub mod syscalls;
+pub mod tcp;
 pub mod udp;
+pub mod unix;
+
+/// `socket(2)` domain/type/protocol constants, matching their POSIX
+/// values so a libc-style caller's usual arguments just work.
+pub const AF_UNIX: u32 = 1;
+pub const AF_INET: u32 = 2;
+pub const SOCK_STREAM: u32 = 1;
+pub const SOCK_DGRAM: u32 = 2;
+pub const IPPROTO_TCP: u32 = 6;
+pub const IPPROTO_UDP: u32 = 17;
+
+/// `EWOULDBLOCK`'s usual POSIX errno value (11), negated to match this
+/// module's "negative `i32` is an error code" convention -- the value
+/// `Syscall::RecvFrom` hands back instead of silently returning `0` when a
+/// socket's `non_blocking` flag is set and nothing's queued yet, so a
+/// caller that just `poll`ed can tell "no data" apart from "an empty
+/// datagram arrived".
+pub const EWOULDBLOCK: i32 = -11;
 
 use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
@@ -15,6 +37,42 @@ pub struct SockAddrIn {
     pub zero: [u8; 8],
 }
 
+/// Longest name `bind`/`sendto` can address an `AF_UNIX` endpoint by --
+/// short since, unlike a real Unix domain socket, this has no filesystem
+/// path backing it, just a name in `unix::ENDPOINTS`'s in-memory table.
+pub const UNIX_NAME_MAX: usize = 30;
+
+/// `AF_UNIX`'s address type: a domain/family tag (so `Bind`/`SendTo`
+/// can tell it apart from a `SockAddrIn` read off the same `ptr`/`len`
+/// the syscall was given) plus a fixed-size name, zero-padded like
+/// `SockAddrIn::zero`. Unlike a real `sockaddr_un`, the name is an
+/// opaque key into `unix::ENDPOINTS` rather than a filesystem path --
+/// there's no VFS node behind it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct SockAddrUn {
+    pub family: u16,
+    pub name: [u8; UNIX_NAME_MAX],
+}
+
+/// `poll(2)`'s `POLLIN`/`POLLOUT` event bits -- the only two this kernel's
+/// sockets can usefully report, since nothing here ever models a peer
+/// closing with an error (`POLLERR`/`POLLHUP`) for `net::poll` to surface.
+pub const POLLIN: u32 = 0x0001;
+pub const POLLOUT: u32 = 0x0004;
+
+/// One fd/interest pair from a `Syscall::Poll` caller's array, read in
+/// place and written back with `revents` filled in -- the same
+/// caller-owns-the-buffer convention `SockAddrIn`/`SockAddrUn` already use
+/// for `Bind`/`SendTo`/`RecvFrom`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct PollFd {
+    pub fd: u32,
+    pub events: u32,
+    pub revents: u32,
+}
+
 // Common types?
 pub type MacAddress = [u8; 6];
 pub type Ipv4Address = [u8; 4];
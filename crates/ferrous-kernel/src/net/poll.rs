@@ -0,0 +1,104 @@
+//! A `poll(2)`-style multiplexer: `net::tcp::RecvWaiter`/`AcceptWaiter`
+//! already know how to park a thread against exactly one socket until it
+//! becomes ready, but nothing lets a caller watch several at once. This
+//! generalizes that single-fd block/wake pattern to an array of
+//! `(fd, events)` pairs, waking as soon as any one of them is ready or a
+//! timeout elapses first -- whichever comes first, the same race
+//! `net::tcp::ConnectWaiter`'s retry-count-vs-handshake race already runs.
+
+use super::socket::SOCKETS;
+use super::syscalls;
+use super::tcp;
+use super::{POLLIN, POLLOUT};
+use crate::sync::spinlock::SpinLock;
+use crate::types::ThreadHandle;
+use alloc::vec::Vec;
+use ferrous_vm::VirtAddr;
+
+/// Whether socket `fd` currently satisfies (a subset of) `events` without
+/// blocking. `POLLOUT` is always satisfied -- this kernel never models
+/// send-buffer backpressure, the same simplification `net::tcp::send`
+/// already makes by never blocking a caller on flow control.
+pub fn ready_events(fd: u32, events: u32) -> u32 {
+    let mut revents = 0;
+    if events & POLLIN != 0 && is_readable(fd) {
+        revents |= POLLIN;
+    }
+    if events & POLLOUT != 0 {
+        revents |= POLLOUT;
+    }
+    revents
+}
+
+fn is_readable(fd: u32) -> bool {
+    if syscalls::is_tcp(fd) {
+        tcp::has_data_or_eof(fd)
+    } else {
+        SOCKETS
+            .lock()
+            .get_socket(fd)
+            .map(|socket| !socket.rx_queue.is_empty())
+            .unwrap_or(false)
+    }
+}
+
+/// A thread parked in a blocking `Poll` against several fds at once, none
+/// of which were immediately ready. `fds` is a snapshot of the caller's
+/// array taken at park time, so re-checking it on each tick doesn't need
+/// to touch user memory until something's actually ready to report.
+pub struct PollWaiter {
+    pub thread: ThreadHandle,
+    /// Where the caller's `net::PollFd` array lives in its own address
+    /// space, so the ready subset's `revents` can be written back in
+    /// place once this waiter wakes.
+    pub fds_ptr: VirtAddr,
+    pub fds: Vec<(u32, u32)>,
+    /// Ticks left before giving up with an empty ready set, or `None` to
+    /// wait forever -- mirroring `ThreadManager::wait_current_thread`'s
+    /// `Option<u64>` timeout, but counted down here instead of through the
+    /// thread manager's own timer queue, since "ready" is a condition only
+    /// this module knows how to check.
+    pub ticks_left: Option<u64>,
+}
+
+static WAITERS: SpinLock<Vec<PollWaiter>> = SpinLock::new(Vec::new());
+
+/// Park `waiter` until `take_ready` finds at least one of its fds ready or
+/// its timeout expires.
+pub fn park(waiter: PollWaiter) {
+    WAITERS.lock().push(waiter);
+}
+
+/// Re-check every parked waiter's fds, for the timer-interrupt handler to
+/// call once per tick alongside `tcp::take_ready_recv_waiters`. Returns
+/// each waiter that's done this tick paired with its ready `(fd, revents)`
+/// pairs -- empty if it timed out rather than became ready.
+pub fn take_ready() -> Vec<(PollWaiter, Vec<(u32, u32)>)> {
+    let mut table = WAITERS.lock();
+    let mut done = Vec::new();
+    let mut still_waiting = Vec::new();
+    for mut waiter in table.drain(..) {
+        let hits: Vec<(u32, u32)> = waiter
+            .fds
+            .iter()
+            .filter_map(|&(fd, events)| {
+                let revents = ready_events(fd, events);
+                (revents != 0).then_some((fd, revents))
+            })
+            .collect();
+        if !hits.is_empty() {
+            done.push((waiter, hits));
+        } else {
+            match waiter.ticks_left {
+                Some(0) => done.push((waiter, Vec::new())),
+                Some(n) => {
+                    waiter.ticks_left = Some(n - 1);
+                    still_waiting.push(waiter);
+                }
+                None => still_waiting.push(waiter),
+            }
+        }
+    }
+    *table = still_waiting;
+    done
+}
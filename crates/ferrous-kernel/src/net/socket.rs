@@ -1,25 +1,103 @@
 use crate::net::driver::DRIVER;
 use crate::net::ipv4::Ipv4Header;
 use crate::net::udp::UdpHeader;
+use crate::net::UNIX_NAME_MAX;
 use crate::sync::spinlock::SpinLock;
+use crate::types::ThreadHandle;
 use alloc::collections::{BTreeMap, VecDeque};
 use alloc::vec::Vec;
+use ferrous_vm::VirtAddr;
 use zerocopy::FromBytes;
 
+/// Where a queued `RxPacket` came from: a real `SockAddrIn` peer reached
+/// over the IP layer, or another socket that `sendto`'d an `AF_UNIX`
+/// endpoint this one is bound to.
+pub enum SocketAddr {
+    Inet { ip: [u8; 4], port: u16 },
+    Unix { name: [u8; UNIX_NAME_MAX] },
+}
+
 pub struct RxPacket {
     pub payload: Vec<u8>,
-    pub src_ip: [u8; 4],
-    pub src_port: u16,
+    pub src: SocketAddr,
+}
+
+/// A thread parked in `Accept` against a listening socket whose
+/// `accept_backlog` was empty, recorded so a later `Connect` that accepts
+/// it can deliver the peer address straight into `addr_ptr`/`addrlen_ptr`
+/// instead of the thread having to wake up and re-issue the call.
+pub struct AcceptWaiter {
+    pub thread: ThreadHandle,
+    pub addr_ptr: VirtAddr,
+    pub addrlen_ptr: VirtAddr,
+}
+
+/// A thread parked in a blocking `RecvFrom` against a socket whose
+/// `rx_queue` was empty, recorded so whichever of `process_rx`'s UDP
+/// delivery or `send_to_unix`'s loopback delivery fills the queue next
+/// knows to wake it instead of leaving it parked until some unrelated
+/// event (or never) re-examines the socket.
+pub struct RxWaiter {
+    pub thread: ThreadHandle,
+    pub fd: u32,
+    pub buf_ptr: VirtAddr,
+    pub len: usize,
+    pub src_ptr: VirtAddr,
+    pub src_len_ptr: VirtAddr,
 }
 
 pub struct Socket {
     pub local_port: u16,
     pub rx_queue: VecDeque<RxPacket>, // Full packet data + metadata
+    /// `Some` once `Listen` marks this socket as a listener, holding the
+    /// ids of accepted connection sockets `Connect` has paired up but that
+    /// no `Accept` has claimed yet. Capped at the backlog `Listen` was
+    /// called with, the same bounded-queue idea `MAX_FILE_DESCRIPTORS`
+    /// applies to fd tables.
+    pub accept_backlog: Option<VecDeque<u32>>,
+    /// How many more entries `accept_backlog` may hold before `Connect`
+    /// refuses new peers.
+    pub backlog_cap: usize,
+    /// A thread parked in `Accept` with `accept_backlog` empty.
+    pub accept_waiter: Option<AcceptWaiter>,
+    /// A thread parked in a blocking `RecvFrom` with `rx_queue` empty.
+    pub rx_waiter: Option<RxWaiter>,
+    /// The other socket id this one is paired with, once `Connect`/`Accept`
+    /// completes a rendezvous. Loopback-only: both ids name sockets in this
+    /// same `SocketTable`, since there's no TCP wire handshake to pair with
+    /// a socket living in some other kernel instance.
+    pub peer: Option<u32>,
+    /// Whether `net::tcp` owns this id's actual connection state (real
+    /// SYN/ACK handshake, send/recv buffers) rather than the UDP
+    /// datagram/loopback-pairing behavior the rest of this struct
+    /// implements directly.
+    pub is_tcp: bool,
+    /// Whether this socket was created with `domain == AF_UNIX`, so
+    /// `Bind`/`SendTo`/`RecvFrom` route it through `net::unix`'s name
+    /// table instead of the IP layer, and `RecvFrom` knows to hand the
+    /// caller back a `SockAddrUn` rather than a `SockAddrIn`.
+    pub is_unix: bool,
+    /// The `AF_UNIX` name this socket bound, if any -- reported as the
+    /// source address on datagrams it sends so a peer's `recvfrom` can
+    /// reply to it.
+    pub unix_name: Option<[u8; UNIX_NAME_MAX]>,
+    /// When set, `RecvFrom` against an empty `rx_queue` reports
+    /// `net::EWOULDBLOCK` instead of silently returning `0` -- the normal
+    /// companion to `net::poll`'s level-triggered readiness check, so a
+    /// caller that polls first and then recvs can tell "nothing arrived"
+    /// apart from "an empty datagram arrived". `false` by default,
+    /// preserving every existing caller's current behavior.
+    pub non_blocking: bool,
 }
 
+/// First port handed out by `alloc_ephemeral_port`, matching the IANA
+/// dynamic/private port range's conventional start.
+const EPHEMERAL_BASE: u16 = 49152;
+
 pub struct SocketTable {
     sockets: BTreeMap<u32, Socket>,
     next_id: u32,
+    next_ephemeral_port: u16,
 }
 
 impl Default for SocketTable {
@@ -33,23 +111,61 @@ impl SocketTable {
         Self {
             sockets: BTreeMap::new(),
             next_id: 1,
+            next_ephemeral_port: EPHEMERAL_BASE,
         }
     }
 
-    pub fn create_socket(&mut self) -> u32 {
+    /// Hand out the next ephemeral port, wrapping back to `EPHEMERAL_BASE`
+    /// once the 16-bit port space is exhausted. Doesn't check for
+    /// collisions with a port some other socket already bound explicitly —
+    /// with only a handful of sockets ever live at once, that's a
+    /// theoretical concern rather than one worth a reuse scan here.
+    fn alloc_ephemeral_port(&mut self) -> u16 {
+        let port = self.next_ephemeral_port;
+        self.next_ephemeral_port = self.next_ephemeral_port.wrapping_add(1);
+        if self.next_ephemeral_port == 0 {
+            self.next_ephemeral_port = EPHEMERAL_BASE;
+        }
+        port
+    }
+
+    /// Create a socket, auto-assigning it an ephemeral local port so a
+    /// `send_to` before any explicit `bind` still has a return address
+    /// replies can reach (unused once `is_unix` sends it down the
+    /// `net::unix` path instead). `is_tcp` marks it as one `net::tcp`
+    /// drives instead of this table's own UDP/loopback-pairing logic.
+    pub fn create_socket(&mut self, is_tcp: bool, is_unix: bool) -> u32 {
         let id = self.next_id;
         self.next_id += 1;
+        let local_port = self.alloc_ephemeral_port();
         self.sockets.insert(
             id,
             Socket {
-                local_port: 0,
+                local_port,
                 rx_queue: VecDeque::new(),
+                accept_backlog: None,
+                backlog_cap: 0,
+                accept_waiter: None,
+                rx_waiter: None,
+                is_unix,
+                unix_name: None,
+                peer: None,
+                is_tcp,
+                non_blocking: false,
             },
         );
         id
     }
 
+    /// Bind `id` to `port`, or to a freshly allocated ephemeral port if
+    /// `port` is 0 (the same "let the kernel pick" convention a POSIX
+    /// `bind()` with port 0 uses).
     pub fn bind(&mut self, id: u32, port: u16) -> bool {
+        let port = if port == 0 {
+            self.alloc_ephemeral_port()
+        } else {
+            port
+        };
         if let Some(socket) = self.sockets.get_mut(&id) {
             socket.local_port = port;
             true
@@ -61,6 +177,66 @@ impl SocketTable {
     pub fn get_socket(&mut self, id: u32) -> Option<&mut Socket> {
         self.sockets.get_mut(&id)
     }
+
+    /// Park `waiter` against `id` for a later delivery to wake, if `id`
+    /// names a live socket with no `RxWaiter` already parked (`RecvFrom`
+    /// only ever calls this once its own check of `rx_queue` came up
+    /// empty, so a second parked waiter would mean two threads racing the
+    /// same socket -- refused the same way a second `Accept` waiter would
+    /// be).
+    pub fn park_rx_waiter(&mut self, id: u32, waiter: RxWaiter) -> bool {
+        match self.sockets.get_mut(&id) {
+            Some(socket) if socket.rx_waiter.is_none() => {
+                socket.rx_waiter = Some(waiter);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Take `id`'s parked `RxWaiter`, if any, so a delivery path that just
+    /// pushed a packet onto its `rx_queue` can wake it.
+    pub fn take_rx_waiter(&mut self, id: u32) -> Option<RxWaiter> {
+        self.sockets.get_mut(&id)?.rx_waiter.take()
+    }
+
+    /// Mark `id` as a listener with room for up to `backlog` unclaimed
+    /// connections. Returns `false` if `id` doesn't name a live socket.
+    pub fn listen(&mut self, id: u32, backlog: usize) -> bool {
+        if let Some(socket) = self.sockets.get_mut(&id) {
+            socket.accept_backlog = Some(VecDeque::new());
+            socket.backlog_cap = backlog;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Find a listening socket bound to `port`, for `Connect` to pair a new
+    /// connection with.
+    pub fn find_listener(&self, port: u16) -> Option<u32> {
+        self.sockets.iter().find_map(|(&id, socket)| {
+            (socket.local_port == port && socket.accept_backlog.is_some()).then_some(id)
+        })
+    }
+
+    /// Hand `accepted_id` straight to `listener_id`'s parked `Accept`
+    /// waiter if one exists, otherwise queue it onto the backlog -- the
+    /// same delivered-vs-queued choice `syscalls::connect_local` makes for
+    /// a loopback pairing, reused here so `net::tcp`'s real handshake can
+    /// complete without re-implementing that logic. Returns the waiter to
+    /// deliver to, or `None` if `accepted_id` was queued (or `listener_id`
+    /// doesn't name a live listener).
+    pub fn deliver_or_queue(&mut self, listener_id: u32, accepted_id: u32) -> Option<AcceptWaiter> {
+        let listener = self.sockets.get_mut(&listener_id)?;
+        if listener.accept_waiter.is_some() {
+            return listener.accept_waiter.take();
+        }
+        if let Some(backlog) = listener.accept_backlog.as_mut() {
+            backlog.push_back(accepted_id);
+        }
+        None
+    }
 }
 
 pub static SOCKETS: SpinLock<SocketTable> = SpinLock::new(SocketTable::new());
@@ -68,8 +244,15 @@ pub static SOCKETS: SpinLock<SocketTable> = SpinLock::new(SocketTable::new());
 use ferrous_vm::Memory;
 
 // Simple demuxer called by recv loop
-pub fn process_rx(memory: &mut dyn Memory) {
+//
+// Returns every `RxWaiter` a datagram delivered this call just satisfied,
+// so the caller (which has the `ThreadManager` access this module doesn't)
+// can copy the packet into its buffer and wake it, the same way
+// `net::tcp::take_ready_recv_waiters` hands its own ready waiters back to
+// `TimerInterrupt` rather than waking them itself.
+pub fn process_rx(memory: &mut dyn Memory) -> Vec<RxWaiter> {
     let mut buffer = [0u8; 2048];
+    let mut woken = Vec::new();
     // Use scoped lock or manual lock/unlock to minimize contention
 
     // We can't hold driver lock while processing if we want concurrency,
@@ -79,6 +262,9 @@ pub fn process_rx(memory: &mut dyn Memory) {
     loop {
         let len = {
             let mut driver = DRIVER.lock();
+            if !driver.is_negotiated() {
+                driver.negotiate_features(memory);
+            }
             if let Some(l) = driver.poll(memory) {
                 if l > 0 {
                     driver.read_packet(memory, &mut buffer)
@@ -97,50 +283,77 @@ pub fn process_rx(memory: &mut dyn Memory) {
         let packet = &buffer[..len];
         log::info!("Kernel: Read packet len {}", len);
 
-        // Parse (Assuming Ethernet II -> IPv4 -> UDP)
+        // Parse (Assuming Ethernet II -> IPv4 -> UDP/TCP)
         // Eth Header = 14 bytes
-        if packet.len() < 14 + 20 + 8 {
+        if packet.len() < 14 + 20 {
             continue;
         } // Min size
 
         let eth_type = u16::from_be_bytes([packet[12], packet[13]]);
-        if eth_type != 0x0800 {
+        if eth_type == crate::net::ethernet::ETHERTYPE_ARP {
+            crate::net::ethernet::handle_frame(memory, crate::net::syscalls::local_ip(), &packet[14..]);
+            continue;
+        }
+        if eth_type != crate::net::ethernet::ETHERTYPE_IPV4 {
             continue;
         } // Not IPv4
 
         let ip_offset = 14;
         let ip_header = Ipv4Header::read_from(&packet[ip_offset..ip_offset + 20]).unwrap();
+        let ip_payload_offset = ip_offset + 20; // Assuming no options
+        let ip_payload = &packet[ip_payload_offset..];
+
+        if ip_header.protocol == crate::net::ipv4::PROTO_TCP {
+            crate::net::tcp::process_segment(memory, ip_header.dest_ip, ip_header.src_ip, ip_payload);
+            continue;
+        }
 
         if ip_header.protocol != 17 {
             continue;
         } // Not UDP
 
-        let udp_offset = ip_offset + 20; // Assuming no options
-        let udp_header = UdpHeader::read_from(&packet[udp_offset..udp_offset + 8]).unwrap();
+        if ip_payload.len() < 8 {
+            continue;
+        }
+        let udp_header = UdpHeader::read_from(&ip_payload[..8]).unwrap();
 
         let dest_port = u16::from_be(udp_header.dest_port);
         let src_port = u16::from_be(udp_header.src_port);
         let src_ip = ip_header.src_ip;
-        let payload_offset = udp_offset + 8;
-        let payload = &packet[payload_offset..]; // Copy rest
+        let payload = &ip_payload[8..]; // Copy rest
+
+        // Port 68 is the DHCP client port: nothing ever binds a socket to
+        // it, so a reply there goes straight to `net::dhcp` instead of
+        // through the rx_queue lookup below.
+        if dest_port == crate::net::dhcp::CLIENT_PORT {
+            crate::net::dhcp::handle_reply(memory, payload);
+            continue;
+        }
 
         // Find matching socket
         let mut sockets = SOCKETS.lock();
         let mut found = false;
-        for socket in sockets.sockets.values_mut() {
+        let mut matched_id = None;
+        for (&id, socket) in sockets.sockets.iter_mut() {
             if socket.local_port == dest_port {
                 log::info!("Kernel: Matched socket port {}", dest_port);
                 socket.rx_queue.push_back(RxPacket {
                     payload: payload.to_vec(),
-                    src_ip,
-                    src_port,
+                    src: SocketAddr::Inet { ip: src_ip, port: src_port },
                 });
                 found = true;
+                matched_id = Some(id);
                 break;
             }
         }
         if !found {
             log::info!("Kernel: No socket for port {}", dest_port);
+        } else if let Some(id) = matched_id {
+            if let Some(waiter) = sockets.take_rx_waiter(id) {
+                woken.push(waiter);
+            }
         }
     }
+
+    woken
 }
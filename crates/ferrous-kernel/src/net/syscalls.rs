@@ -0,0 +1,267 @@
+//! Core logic behind the `Socket`/`Bind`/`SendTo`/`RecvFrom` syscalls,
+//! kept separate from `lib.rs`'s dispatch the same way `fs`/`memory` are:
+//! this module owns the socket-table/wire-format work, `lib.rs`'s
+//! `handle_syscall` owns the `copy_from_user`/`copy_to_user` glue around it.
+
+use super::dhcp;
+use super::socket::{AcceptWaiter, RxPacket, RxWaiter, SocketAddr, SOCKETS};
+use super::tcp;
+use super::udp;
+use super::unix;
+use super::UNIX_NAME_MAX;
+use alloc::vec::Vec;
+use ferrous_vm::Memory;
+
+/// This kernel's own IPv4 address: whatever `net::dhcp` currently has
+/// leased, or its fallback default (matching QEMU user-mode networking's
+/// default guest address) until a lease is acquired.
+pub fn local_ip() -> [u8; 4] {
+    dhcp::local_ip()
+}
+
+/// Create a new socket, auto-assigning it an ephemeral local port: a real
+/// `net::tcp`-backed one if `protocol` is `net::IPPROTO_TCP`, this table's
+/// own UDP/loopback-stream kind otherwise. `domain == net::AF_UNIX` marks
+/// it as one `Bind`/`SendTo`/`RecvFrom` route through `net::unix`'s name
+/// table instead of the IP layer; the ephemeral port it's still handed is
+/// simply never used.
+pub fn create_socket(domain: u32, protocol: u32) -> u32 {
+    SOCKETS
+        .lock()
+        .create_socket(protocol == super::IPPROTO_TCP, domain == super::AF_UNIX)
+}
+
+/// Whether `id` names a `net::tcp`-backed socket.
+pub fn is_tcp(id: u32) -> bool {
+    SOCKETS
+        .lock()
+        .get_socket(id)
+        .map(|socket| socket.is_tcp)
+        .unwrap_or(false)
+}
+
+/// Whether `id` names an `AF_UNIX` socket.
+pub fn is_unix(id: u32) -> bool {
+    SOCKETS
+        .lock()
+        .get_socket(id)
+        .map(|socket| socket.is_unix)
+        .unwrap_or(false)
+}
+
+/// Set or clear `id`'s `non_blocking` flag. Returns `false` if `id` doesn't
+/// name a live socket.
+pub fn set_non_blocking(id: u32, non_blocking: bool) -> bool {
+    match SOCKETS.lock().get_socket(id) {
+        Some(socket) => {
+            socket.non_blocking = non_blocking;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether `id`'s `non_blocking` flag is set. `false` if `id` isn't a live
+/// socket.
+pub fn is_non_blocking(id: u32) -> bool {
+    SOCKETS
+        .lock()
+        .get_socket(id)
+        .map(|socket| socket.non_blocking)
+        .unwrap_or(false)
+}
+
+/// Bind `id` to `port` (or to a fresh ephemeral port if `port` is 0).
+/// Returns `false` if `id` doesn't name a live socket.
+pub fn bind(id: u32, port: u16) -> bool {
+    SOCKETS.lock().bind(id, port)
+}
+
+/// Claim `name` in `net::unix::ENDPOINTS` for `id`. Returns `false` if `id`
+/// doesn't name a live socket.
+pub fn bind_unix(id: u32, name: [u8; UNIX_NAME_MAX]) -> bool {
+    let mut table = SOCKETS.lock();
+    let Some(socket) = table.get_socket(id) else {
+        return false;
+    };
+    socket.unix_name = Some(name);
+    unix::bind(name, id);
+    true
+}
+
+/// Deliver `payload` straight into the `rx_queue` of whatever socket is
+/// bound to `dest_name`, tagged with `id`'s own bound name (or an
+/// all-zero name if `id` never bound one) as the source address. Returns
+/// the destination socket's id on success (so the caller can check it for
+/// a parked `RxWaiter` to wake), or `None` if `id` isn't a live socket or
+/// nothing is bound to `dest_name`.
+pub fn send_to_unix(id: u32, dest_name: [u8; UNIX_NAME_MAX], payload: &[u8]) -> Option<u32> {
+    let mut table = SOCKETS.lock();
+    let src_name = table.get_socket(id)?.unix_name.unwrap_or([0; UNIX_NAME_MAX]);
+    let dest_id = unix::lookup(dest_name)?;
+    let dest = table.get_socket(dest_id)?;
+    dest.rx_queue.push_back(RxPacket {
+        payload: payload.to_vec(),
+        src: SocketAddr::Unix { name: src_name },
+    });
+    Some(dest_id)
+}
+
+/// Park `waiter` against `id`'s `RecvFrom`, or refuse if `id` isn't a live
+/// socket or already has a waiter parked.
+pub fn park_rx_waiter(id: u32, waiter: RxWaiter) -> bool {
+    SOCKETS.lock().park_rx_waiter(id, waiter)
+}
+
+/// Take `id`'s parked `RxWaiter`, if any, so a just-completed delivery can
+/// wake it.
+pub fn take_rx_waiter(id: u32) -> Option<RxWaiter> {
+    SOCKETS.lock().take_rx_waiter(id)
+}
+
+/// Build and transmit a UDP datagram from `id`'s local port to
+/// `dest_ip:dest_port`. Returns `false` if `id` doesn't name a live socket.
+pub fn send_to(
+    memory: &mut dyn Memory,
+    id: u32,
+    dest_ip: [u8; 4],
+    dest_port: u16,
+    payload: &[u8],
+) -> bool {
+    let local_port = match SOCKETS.lock().get_socket(id) {
+        Some(socket) => socket.local_port,
+        None => return false,
+    };
+    udp::send_to(memory, local_ip(), local_port, dest_ip, dest_port, payload);
+    true
+}
+
+/// Pop the oldest datagram queued for `id`, or `None` if `id` doesn't name a
+/// live socket or nothing has arrived yet.
+pub fn recv_from(id: u32) -> Option<RxPacket> {
+    SOCKETS
+        .lock()
+        .get_socket(id)
+        .and_then(|socket| socket.rx_queue.pop_front())
+}
+
+/// Mark `id` as listening with room for `backlog` unclaimed connections.
+/// Returns `false` if `id` doesn't name a live socket. For a TCP socket,
+/// also registers `id`'s local port with `net::tcp` so an inbound `SYN`
+/// knows which listener to spawn a connection against.
+pub fn listen(id: u32, backlog: usize) -> bool {
+    let mut table = SOCKETS.lock();
+    let Some(socket) = table.get_socket(id) else {
+        return false;
+    };
+    let (is_tcp, port) = (socket.is_tcp, socket.local_port);
+    if !table.listen(id, backlog) {
+        return false;
+    }
+    if is_tcp {
+        tcp::listen(id, port);
+    }
+    true
+}
+
+/// Begin a real TCP handshake from `id` to `dest_ip:dest_port`, parking
+/// `waiter` until the connection is `Established` or gives up. Returns
+/// `false` if `id` doesn't name a live socket.
+pub fn tcp_connect(
+    memory: &mut dyn Memory,
+    id: u32,
+    dest_ip: [u8; 4],
+    dest_port: u16,
+    waiter: tcp::ConnectWaiter,
+) -> bool {
+    let Some(local_port) = SOCKETS.lock().get_socket(id).map(|socket| socket.local_port) else {
+        return false;
+    };
+    tcp::connect(memory, id, local_port, local_ip(), dest_ip, dest_port, waiter);
+    true
+}
+
+/// Send `data` on established TCP connection `id`. `Err(())` if `id`
+/// isn't one.
+pub fn tcp_send(memory: &mut dyn Memory, id: u32, data: &[u8]) -> Result<usize, ()> {
+    tcp::send(memory, local_ip(), id, data)
+}
+
+/// Pop up to `max_len` bytes already received on TCP connection `id`.
+pub fn tcp_recv(id: u32, max_len: usize) -> Option<Vec<u8>> {
+    tcp::recv(id, max_len)
+}
+
+/// How `SocketConnect` paired `connecting_id` with a listener.
+pub enum ConnectOutcome {
+    /// A thread was already parked in `Accept`: the new connection's id and
+    /// the waiter it should be delivered to, for the caller to write the
+    /// peer address into and wake.
+    Delivered { accepted_id: u32, waiter: AcceptWaiter },
+    /// Nobody was waiting; queued onto the listener's backlog instead.
+    Queued,
+}
+
+/// Pair `connecting_id` with whatever socket is listening on `port`,
+/// loopback-style: there's no TCP wire handshake, so this just allocates a
+/// fresh accepted-connection socket and links the two ids' `peer` fields
+/// directly. Fails if `connecting_id` isn't a live socket, nothing listens
+/// on `port`, or the listener's backlog is already full with no `Accept`
+/// waiting to drain it.
+pub fn connect_local(connecting_id: u32, port: u16) -> Result<ConnectOutcome, ()> {
+    let mut table = SOCKETS.lock();
+    if table.get_socket(connecting_id).is_none() {
+        return Err(());
+    }
+    let listener_id = table.find_listener(port).ok_or(())?;
+
+    let has_waiter = table.get_socket(listener_id).unwrap().accept_waiter.is_some();
+    if !has_waiter {
+        let listener = table.get_socket(listener_id).unwrap();
+        if listener.accept_backlog.as_ref().unwrap().len() >= listener.backlog_cap {
+            return Err(());
+        }
+    }
+
+    let accepted_id = table.create_socket(false, false);
+    table.get_socket(accepted_id).unwrap().peer = Some(connecting_id);
+    table.get_socket(connecting_id).unwrap().peer = Some(accepted_id);
+
+    let listener = table.get_socket(listener_id).unwrap();
+    if let Some(waiter) = listener.accept_waiter.take() {
+        Ok(ConnectOutcome::Delivered { accepted_id, waiter })
+    } else {
+        listener.accept_backlog.as_mut().unwrap().push_back(accepted_id);
+        Ok(ConnectOutcome::Queued)
+    }
+}
+
+/// Pop the oldest unclaimed connection queued for listening socket `id`, or
+/// `None` if the backlog is empty (the caller should park an `AcceptWaiter`
+/// instead) or `id` isn't listening.
+pub fn accept_pending(id: u32) -> Option<u32> {
+    SOCKETS
+        .lock()
+        .get_socket(id)
+        .and_then(|socket| socket.accept_backlog.as_mut()?.pop_front())
+}
+
+/// Park a thread in `Accept` against listening socket `id`. Returns `false`
+/// if `id` isn't listening.
+pub fn park_accept_waiter(id: u32, waiter: AcceptWaiter) -> bool {
+    match SOCKETS.lock().get_socket(id) {
+        Some(socket) if socket.accept_backlog.is_some() => {
+            socket.accept_waiter = Some(waiter);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Local port of the peer `id` is connected to, for `Accept` to report back
+/// as the connection's address. `None` if `id` isn't connected to anything.
+pub fn peer_port(id: u32) -> Option<u16> {
+    let mut table = SOCKETS.lock();
+    let peer_id = table.get_socket(id)?.peer?;
+    table.get_socket(peer_id).map(|socket| socket.local_port)
+}
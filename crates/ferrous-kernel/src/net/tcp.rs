@@ -0,0 +1,622 @@
+//! A real TCP implementation over `net::driver`/`net::ipv4`: the state
+//! machine RFC 793 describes (`CLOSED`, `LISTEN`, `SYN_SENT`, `SYN_RCVD`,
+//! `ESTABLISHED`, `FIN_WAIT_1`/`2`, `CLOSE_WAIT`, `LAST_ACK`, `TIME_WAIT`),
+//! per-connection send/receive buffers, and timer-driven retransmission.
+//!
+//! Connections are addressed by the same socket ids `net::socket::SOCKETS`
+//! hands out -- `Listen`/`Accept`'s backlog/waiter machinery there is
+//! reused as-is for the accept side, so the only thing that changes from
+//! `syscalls::connect_local`'s loopback pairing is *how* a connection ends
+//! up in that backlog: a real SYN/SYN-ACK/ACK handshake against
+//! `process_segment` instead of an instant same-kernel link. A segment
+//! that arrives ahead of `recv_nxt` is held in `TcpConnection::reassembly`
+//! rather than dropped, and folded into `recv_buffer` once the gap in
+//! front of it fills in -- but nothing here ever sends a duplicate ACK or
+//! SACK to prod the peer into refilling that gap sooner, relying on its
+//! own retransmission timer the same "good enough for this kernel's
+//! workloads" simplification `socket.rs` already applies to its loopback
+//! pairing.
+
+use crate::net::ethernet;
+use crate::net::ipv4::{self, Ipv4Header};
+use crate::net::socket::SOCKETS;
+use crate::sync::spinlock::SpinLock;
+use crate::types::ThreadHandle;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use ferrous_vm::{Memory, VirtAddr};
+use core::sync::atomic::{AtomicU32, Ordering};
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
+
+/// A fixed 20-byte TCP header (no options), wire-order (big-endian) fields
+/// throughout -- the same convention `net::udp::UdpHeader` and
+/// `net::ipv4::Ipv4Header` use.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct TcpHeader {
+    pub src_port: u16,  // Big Endian
+    pub dest_port: u16, // Big Endian
+    pub seq: u32,       // Big Endian
+    pub ack: u32,       // Big Endian
+    /// High 4 bits: data offset in 32-bit words (always 5, no options).
+    /// Low 6 bits of the low byte: the flags below. The 6 bits in between
+    /// are the reserved/ECN bits, left zero.
+    pub offset_flags: u16, // Big Endian
+    pub window: u16,    // Big Endian
+    pub checksum: u16,  // Big Endian
+    pub urgent_ptr: u16, // Big Endian
+}
+
+impl TcpHeader {
+    pub const LEN: usize = 20;
+
+    pub fn flags(&self) -> u8 {
+        (u16::from_be(self.offset_flags) & 0x3F) as u8
+    }
+}
+
+pub const FLAG_FIN: u8 = 0x01;
+pub const FLAG_SYN: u8 = 0x02;
+pub const FLAG_RST: u8 = 0x04;
+pub const FLAG_PSH: u8 = 0x08;
+pub const FLAG_ACK: u8 = 0x10;
+
+/// Largest payload one segment carries, comfortably under a 1500-byte
+/// Ethernet MTU once the 14-byte Ethernet header, `Ipv4Header::LEN`, and
+/// `TcpHeader::LEN` are accounted for.
+const MAX_SEGMENT_SIZE: usize = 1400;
+
+/// Ticks (at `thread::NANOS_PER_TICK` each) before an unacked segment is
+/// retransmitted, and before a `TimeWait` connection is finally reaped --
+/// both fixed rather than RTT-estimated, the same simulated-latency-window
+/// idea `ferrous_vm::devices::block`'s `busy_ticks` uses instead of a real
+/// clock.
+const RETRANSMIT_TICKS: u32 = 50;
+const TIME_WAIT_TICKS: u32 = 200;
+
+/// Retransmissions attempted before giving up on a connection and tearing
+/// it down as if an RST had arrived.
+const MAX_RETRIES: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpState {
+    Listen,
+    SynSent,
+    SynRcvd,
+    Established,
+    FinWait1,
+    FinWait2,
+    CloseWait,
+    LastAck,
+    TimeWait,
+}
+
+struct UnackedSegment {
+    seq: u32,
+    data: Vec<u8>,
+    flags: u8,
+}
+
+/// A thread parked in a blocking `Recv` against a connection with nothing
+/// yet in `recv_buffer`, recorded so `on_timer_tick` can hand it data (or
+/// an EOF) as soon as some arrives instead of the thread busy-polling.
+pub struct RecvWaiter {
+    pub thread: ThreadHandle,
+    pub buf_ptr: VirtAddr,
+    pub len: usize,
+}
+
+/// A thread parked in a blocking `SocketConnect` against a `SynSent`
+/// connection, woken once the handshake resolves one way or the other.
+pub struct ConnectWaiter {
+    pub thread: ThreadHandle,
+}
+
+/// A connection whose handshake just completed from the listening side,
+/// for `Kernel`'s timer-interrupt handler to hand to `listener_id`'s
+/// `Accept` waiter (or queue onto its backlog) the same way
+/// `syscalls::connect_local`'s `ConnectOutcome` already does for loopback
+/// sockets.
+pub struct AcceptReady {
+    pub listener_id: u32,
+    pub accepted_id: u32,
+}
+
+struct TcpConnection {
+    state: TcpState,
+    local_port: u16,
+    remote_ip: [u8; 4],
+    remote_port: u16,
+    /// Oldest byte this end has sent but not yet seen acked.
+    send_una: u32,
+    /// Next sequence number this end will use.
+    send_nxt: u32,
+    /// Next sequence number expected from the peer.
+    recv_nxt: u32,
+    recv_buffer: VecDeque<u8>,
+    /// Segments that arrived ahead of `recv_nxt`, keyed by their starting
+    /// sequence number, waiting for the segment(s) filling the gap in
+    /// front of them so they can be appended to `recv_buffer` in order.
+    reassembly: BTreeMap<u32, Vec<u8>>,
+    unacked: VecDeque<UnackedSegment>,
+    retransmit_ticks: u32,
+    retries: u32,
+    /// Counts down to final teardown while in `TimeWait`; unused in every
+    /// other state.
+    time_wait_ticks: u32,
+    /// The listening socket this connection was spawned from by an
+    /// inbound SYN, so the handshake's final ACK knows whose backlog to
+    /// join.
+    listener_id: Option<u32>,
+    recv_waiter: Option<RecvWaiter>,
+    connect_waiter: Option<ConnectWaiter>,
+}
+
+impl TcpConnection {
+    fn has_data_or_eof(&self) -> bool {
+        !self.recv_buffer.is_empty() || matches!(self.state, TcpState::CloseWait | TcpState::TimeWait)
+    }
+}
+
+static CONNECTIONS: SpinLock<BTreeMap<u32, TcpConnection>> = SpinLock::new(BTreeMap::new());
+static LISTENERS: SpinLock<BTreeMap<u16, u32>> = SpinLock::new(BTreeMap::new());
+static PENDING_ACCEPTS: SpinLock<VecDeque<AcceptReady>> = SpinLock::new(VecDeque::new());
+
+/// Seeds each new connection's initial sequence number. A plain counter
+/// rather than anything cryptographic -- there's no adversary on this
+/// kernel's loopback-to-host QEMU link to defend against, only a need for
+/// two connections in flight at once to not collide.
+static NEXT_ISN: AtomicU32 = AtomicU32::new(0x1000);
+
+fn next_isn() -> u32 {
+    NEXT_ISN.fetch_add(10_000, Ordering::Relaxed)
+}
+
+/// Mark `id` (already a live `net::socket::Socket`) as listening on
+/// `port`. Paired with `net::socket::SocketTable::listen`, which still
+/// owns the accept backlog/waiter that a completed handshake delivers
+/// into.
+pub fn listen(id: u32, port: u16) {
+    LISTENERS.lock().insert(port, id);
+}
+
+/// Begin an active open: send the initial `SYN` and park `id` in
+/// `SynSent` until a `SYN-ACK` (or a give-up after `MAX_RETRIES`) resolves
+/// it.
+pub fn connect(
+    memory: &mut dyn Memory,
+    id: u32,
+    local_port: u16,
+    local_ip: [u8; 4],
+    remote_ip: [u8; 4],
+    remote_port: u16,
+    waiter: ConnectWaiter,
+) {
+    let isn = next_isn();
+    let mut conn = TcpConnection {
+        state: TcpState::SynSent,
+        local_port,
+        remote_ip,
+        remote_port,
+        send_una: isn,
+        send_nxt: isn.wrapping_add(1),
+        recv_nxt: 0,
+        recv_buffer: VecDeque::new(),
+        reassembly: BTreeMap::new(),
+        unacked: VecDeque::new(),
+        retransmit_ticks: RETRANSMIT_TICKS,
+        retries: 0,
+        time_wait_ticks: 0,
+        listener_id: None,
+        recv_waiter: None,
+        connect_waiter: Some(waiter),
+    };
+    send_segment(memory, local_ip, &conn, isn, 0, FLAG_SYN, &[]);
+    conn.unacked.push_back(UnackedSegment {
+        seq: isn,
+        data: Vec::new(),
+        flags: FLAG_SYN,
+    });
+    CONNECTIONS.lock().insert(id, conn);
+}
+
+/// Queue `data` for transmission on established connection `id`, chunked
+/// into `MAX_SEGMENT_SIZE`-sized segments, and send it immediately (no
+/// distinct "queued but not yet sent" phase -- every segment goes out as
+/// soon as `send` chunks it, same as `udp::send_to` firing immediately
+/// rather than batching). Returns `Err(())` if `id` isn't an established
+/// connection.
+pub fn send(memory: &mut dyn Memory, local_ip: [u8; 4], id: u32, data: &[u8]) -> Result<usize, ()> {
+    let mut table = CONNECTIONS.lock();
+    let conn = table.get_mut(&id).ok_or(())?;
+    if conn.state != TcpState::Established && conn.state != TcpState::CloseWait {
+        return Err(());
+    }
+
+    let mut sent = 0;
+    for chunk in data.chunks(MAX_SEGMENT_SIZE) {
+        let seq = conn.send_nxt;
+        send_segment(memory, local_ip, conn, seq, conn.recv_nxt, FLAG_ACK | FLAG_PSH, chunk);
+        conn.unacked.push_back(UnackedSegment {
+            seq,
+            data: chunk.to_vec(),
+            flags: FLAG_ACK | FLAG_PSH,
+        });
+        conn.send_nxt = conn.send_nxt.wrapping_add(chunk.len() as u32);
+        sent += chunk.len();
+    }
+    if sent > 0 {
+        conn.retransmit_ticks = RETRANSMIT_TICKS;
+    }
+    Ok(sent)
+}
+
+/// Pop up to `max_len` already-received bytes off `id`'s stream. `Some(&[])`
+/// means the peer has finished sending (connection is `CloseWait` or
+/// `TimeWait`) and no more data will ever arrive; `None` means neither --
+/// the caller should park a `RecvWaiter` and block.
+pub fn recv(id: u32, max_len: usize) -> Option<Vec<u8>> {
+    let mut table = CONNECTIONS.lock();
+    let conn = table.get_mut(&id)?;
+    if conn.recv_buffer.is_empty() {
+        return if matches!(conn.state, TcpState::CloseWait | TcpState::TimeWait) {
+            Some(Vec::new())
+        } else {
+            None
+        };
+    }
+    let n = max_len.min(conn.recv_buffer.len());
+    Some(conn.recv_buffer.drain(..n).collect())
+}
+
+/// Whether `id`'s `recv_buffer` has bytes ready to pop, or the connection
+/// has reached `CloseWait`/`TimeWait` so a `recv` would return the `Some(&[])`
+/// EOF `recv` above does -- `net::poll`'s readable check for a TCP socket.
+/// `false` if `id` isn't a live connection.
+pub fn has_data_or_eof(id: u32) -> bool {
+    CONNECTIONS.lock().get(&id).map(|conn| conn.has_data_or_eof()).unwrap_or(false)
+}
+
+/// Park a thread in a blocking `Recv` against `id`. Returns `false` if
+/// `id` doesn't name a live connection.
+pub fn park_recv_waiter(id: u32, waiter: RecvWaiter) -> bool {
+    match CONNECTIONS.lock().get_mut(&id) {
+        Some(conn) => {
+            conn.recv_waiter = Some(waiter);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Drain every connection whose parked `RecvWaiter` now has data (or EOF)
+/// available, for the timer-interrupt handler to copy into user memory
+/// and wake.
+pub fn take_ready_recv_waiters() -> Vec<(RecvWaiter, Vec<u8>)> {
+    let mut ready = Vec::new();
+    let mut table = CONNECTIONS.lock();
+    for conn in table.values_mut() {
+        if conn.recv_waiter.is_some() && conn.has_data_or_eof() {
+            let waiter = conn.recv_waiter.take().unwrap();
+            let n = conn.recv_buffer.len();
+            let data = conn.recv_buffer.drain(..n).collect();
+            ready.push((waiter, data));
+        }
+    }
+    ready
+}
+
+/// Drain every `SynSent` connection that has either reached `Established`
+/// or exhausted its retries, for the timer-interrupt handler to wake each
+/// one's `ConnectWaiter` with the outcome.
+pub fn take_ready_connects() -> Vec<(ConnectWaiter, bool)> {
+    let mut ready = Vec::new();
+    let mut table = CONNECTIONS.lock();
+    let mut dead = Vec::new();
+    for (&id, conn) in table.iter_mut() {
+        if conn.connect_waiter.is_none() {
+            continue;
+        }
+        if conn.state == TcpState::Established {
+            ready.push((conn.connect_waiter.take().unwrap(), true));
+        } else if conn.retries > MAX_RETRIES {
+            ready.push((conn.connect_waiter.take().unwrap(), false));
+            dead.push(id);
+        }
+    }
+    for id in dead {
+        table.remove(&id);
+    }
+    ready
+}
+
+/// Drain every connection a handshake finished on the listening side,
+/// ready for `net::socket::SocketTable::deliver_or_queue` to hand to an
+/// `Accept` waiter or the listener's backlog.
+pub fn take_ready_accepts() -> Vec<AcceptReady> {
+    PENDING_ACCEPTS.lock().drain(..).collect()
+}
+
+/// Retransmit any segment whose deadline has elapsed and reap connections
+/// that have sat in `TimeWait` long enough, called once per
+/// `TrapCause::TimerInterrupt` alongside `socket::process_rx`.
+pub fn on_timer_tick(memory: &mut dyn Memory, local_ip: [u8; 4]) {
+    let mut table = CONNECTIONS.lock();
+    let mut dead = Vec::new();
+    for (&id, conn) in table.iter_mut() {
+        if conn.state == TcpState::TimeWait {
+            conn.time_wait_ticks = conn.time_wait_ticks.saturating_sub(1);
+            if conn.time_wait_ticks == 0 {
+                dead.push(id);
+            }
+            continue;
+        }
+
+        if conn.unacked.is_empty() {
+            continue;
+        }
+        conn.retransmit_ticks = conn.retransmit_ticks.saturating_sub(1);
+        if conn.retransmit_ticks > 0 {
+            continue;
+        }
+        conn.retries += 1;
+        if conn.retries > MAX_RETRIES {
+            // Give up: drop the connection the way an RST from the peer
+            // would -- unless a `ConnectWaiter` is still parked on it, in
+            // which case `take_ready_connects` reports the failure to that
+            // thread and removes the connection itself.
+            if conn.connect_waiter.is_none() {
+                dead.push(id);
+            }
+            continue;
+        }
+        if let Some(oldest) = conn.unacked.front() {
+            send_segment(
+                memory,
+                local_ip,
+                conn,
+                oldest.seq,
+                conn.recv_nxt,
+                oldest.flags,
+                &oldest.data.clone(),
+            );
+        }
+        conn.retransmit_ticks = RETRANSMIT_TICKS;
+    }
+    for id in dead {
+        table.remove(&id);
+    }
+}
+
+/// Fold every buffered out-of-order segment that's now contiguous with
+/// `conn.recv_nxt` into `recv_buffer`, in sequence order -- called right
+/// after an in-order segment closes the gap a held one was waiting on.
+fn drain_reassembly(conn: &mut TcpConnection) {
+    while let Some(data) = conn.reassembly.remove(&conn.recv_nxt) {
+        conn.recv_nxt = conn.recv_nxt.wrapping_add(data.len() as u32);
+        conn.recv_buffer.extend(data);
+    }
+}
+
+/// Handle one inbound TCP segment (already stripped of its Ethernet/IPv4
+/// headers) from `src_ip`, dispatching to whichever connection matches its
+/// 4-tuple or, for a bare `SYN`, to a listener on its destination port.
+pub fn process_segment(memory: &mut dyn Memory, local_ip: [u8; 4], src_ip: [u8; 4], segment: &[u8]) {
+    if segment.len() < TcpHeader::LEN {
+        return;
+    }
+    let Some(header) = TcpHeader::read_from(&segment[..TcpHeader::LEN]) else {
+        return;
+    };
+    let payload = &segment[TcpHeader::LEN..];
+    let src_port = u16::from_be(header.src_port);
+    let dest_port = u16::from_be(header.dest_port);
+    let seq = u32::from_be(header.seq);
+    let ack = u32::from_be(header.ack);
+    let flags = header.flags();
+
+    let mut table = CONNECTIONS.lock();
+    let existing = table
+        .iter()
+        .find(|(_, c)| c.local_port == dest_port && c.remote_ip == src_ip && c.remote_port == src_port)
+        .map(|(&id, _)| id);
+
+    if let Some(id) = existing {
+        let conn = table.get_mut(&id).unwrap();
+        handle_segment_for_connection(memory, local_ip, conn, seq, ack, flags, payload, id);
+        return;
+    }
+
+    if flags & FLAG_SYN != 0 && flags & FLAG_ACK == 0 {
+        let Some(&listener_id) = LISTENERS.lock().get(&dest_port) else {
+            return;
+        };
+        let isn = next_isn();
+        let accepted_id = SOCKETS.lock().create_socket(true, false);
+        let mut conn = TcpConnection {
+            state: TcpState::SynRcvd,
+            local_port: dest_port,
+            remote_ip: src_ip,
+            remote_port: src_port,
+            send_una: isn,
+            send_nxt: isn.wrapping_add(1),
+            recv_nxt: seq.wrapping_add(1),
+            recv_buffer: VecDeque::new(),
+            reassembly: BTreeMap::new(),
+            unacked: VecDeque::new(),
+            retransmit_ticks: RETRANSMIT_TICKS,
+            retries: 0,
+            time_wait_ticks: 0,
+            listener_id: Some(listener_id),
+            recv_waiter: None,
+            connect_waiter: None,
+        };
+        send_segment(memory, local_ip, &conn, isn, conn.recv_nxt, FLAG_SYN | FLAG_ACK, &[]);
+        conn.unacked.push_back(UnackedSegment {
+            seq: isn,
+            data: Vec::new(),
+            flags: FLAG_SYN | FLAG_ACK,
+        });
+        table.insert(accepted_id, conn);
+    }
+    // Anything else with no matching connection (a stray ACK, RST, or
+    // data segment for a socket that's already gone) is simply dropped --
+    // this kernel never sends an RST of its own in response.
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_segment_for_connection(
+    memory: &mut dyn Memory,
+    local_ip: [u8; 4],
+    conn: &mut TcpConnection,
+    seq: u32,
+    ack: u32,
+    flags: u8,
+    payload: &[u8],
+    id: u32,
+) {
+    if flags & FLAG_RST != 0 {
+        conn.state = TcpState::TimeWait;
+        conn.time_wait_ticks = 1;
+        return;
+    }
+
+    // Retire any of our own segments this ACK now fully covers (sequence
+    // comparison done mod 2^32, the same wraparound-safe "is not after"
+    // check RFC 793 describes for `SND.UNA =< SEG.ACK`), and reset the
+    // retransmit clock for whatever's still outstanding.
+    if flags & FLAG_ACK != 0 {
+        while let Some(oldest) = conn.unacked.front() {
+            let covered = oldest.seq.wrapping_add(oldest.data.len().max(1) as u32);
+            if (covered.wrapping_sub(ack) as i32) <= 0 {
+                conn.send_una = ack;
+                conn.unacked.pop_front();
+            } else {
+                break;
+            }
+        }
+        conn.retransmit_ticks = RETRANSMIT_TICKS;
+        conn.retries = 0;
+    }
+
+    match conn.state {
+        TcpState::SynSent => {
+            if flags & FLAG_SYN != 0 && flags & FLAG_ACK != 0 {
+                conn.recv_nxt = seq.wrapping_add(1);
+                conn.unacked.clear();
+                conn.state = TcpState::Established;
+                send_segment(memory, local_ip, conn, conn.send_nxt, conn.recv_nxt, FLAG_ACK, &[]);
+            }
+        }
+        TcpState::SynRcvd => {
+            if flags & FLAG_ACK != 0 {
+                conn.state = TcpState::Established;
+                if let Some(listener_id) = conn.listener_id {
+                    PENDING_ACCEPTS.lock().push_back(AcceptReady {
+                        listener_id,
+                        accepted_id: id,
+                    });
+                }
+            }
+        }
+        TcpState::Established | TcpState::CloseWait => {
+            if !payload.is_empty() && seq == conn.recv_nxt {
+                conn.recv_buffer.extend(payload.iter().copied());
+                conn.recv_nxt = conn.recv_nxt.wrapping_add(payload.len() as u32);
+                drain_reassembly(conn);
+                send_segment(memory, local_ip, conn, conn.send_nxt, conn.recv_nxt, FLAG_ACK, &[]);
+            } else if !payload.is_empty() && (seq.wrapping_sub(conn.recv_nxt) as i32) > 0 {
+                conn.reassembly.entry(seq).or_insert_with(|| payload.to_vec());
+            }
+            if flags & FLAG_FIN != 0 && conn.state == TcpState::Established {
+                conn.recv_nxt = conn.recv_nxt.wrapping_add(1);
+                conn.state = TcpState::CloseWait;
+                send_segment(memory, local_ip, conn, conn.send_nxt, conn.recv_nxt, FLAG_ACK, &[]);
+            }
+        }
+        TcpState::FinWait1 => {
+            // Simultaneous close (RFC 793's `CLOSING` state, both sides
+            // `FIN`ing before either has acked the other's) isn't modeled
+            // here -- a peer `FIN` is only handled once our own is fully
+            // acked and this end has moved on to `FinWait2`.
+            if conn.unacked.is_empty() {
+                conn.state = TcpState::FinWait2;
+                if flags & FLAG_FIN != 0 {
+                    conn.recv_nxt = conn.recv_nxt.wrapping_add(1);
+                    send_segment(memory, local_ip, conn, conn.send_nxt, conn.recv_nxt, FLAG_ACK, &[]);
+                    conn.state = TcpState::TimeWait;
+                    conn.time_wait_ticks = TIME_WAIT_TICKS;
+                }
+            }
+        }
+        TcpState::FinWait2 => {
+            if flags & FLAG_FIN != 0 {
+                conn.recv_nxt = conn.recv_nxt.wrapping_add(1);
+                send_segment(memory, local_ip, conn, conn.send_nxt, conn.recv_nxt, FLAG_ACK, &[]);
+                conn.state = TcpState::TimeWait;
+                conn.time_wait_ticks = TIME_WAIT_TICKS;
+            }
+        }
+        TcpState::LastAck => {
+            if conn.unacked.is_empty() {
+                conn.state = TcpState::TimeWait;
+                conn.time_wait_ticks = 1;
+            }
+        }
+        TcpState::TimeWait | TcpState::Listen => {}
+    }
+}
+
+/// Build an IPv4 / TCP segment from `conn`'s 4-tuple and hand it to
+/// `net::ethernet::send_ipv4` for MAC resolution and transmission,
+/// mirroring `udp::send_to`.
+fn send_segment(
+    memory: &mut dyn Memory,
+    local_ip: [u8; 4],
+    conn: &TcpConnection,
+    seq: u32,
+    ack: u32,
+    flags: u8,
+    payload: &[u8],
+) {
+    let offset_flags: u16 = ((5u16) << 12) | (flags as u16 & 0x3F);
+    let mut header = TcpHeader {
+        src_port: conn.local_port.to_be(),
+        dest_port: conn.remote_port.to_be(),
+        seq: seq.to_be(),
+        ack: ack.to_be(),
+        offset_flags: offset_flags.to_be(),
+        window: 8192u16.to_be(),
+        checksum: 0,
+        urgent_ptr: 0,
+    };
+    header.checksum = tcp_checksum(&header, local_ip, conn.remote_ip, payload).to_be();
+
+    let total_len = (TcpHeader::LEN + payload.len()) as u16;
+    let ip_header = Ipv4Header::new(total_len, ipv4::PROTO_TCP, local_ip, conn.remote_ip);
+
+    let mut ip_payload = Vec::with_capacity(Ipv4Header::LEN + total_len as usize);
+    ip_payload.extend_from_slice(ip_header.as_bytes());
+    ip_payload.extend_from_slice(header.as_bytes());
+    ip_payload.extend_from_slice(payload);
+
+    ethernet::send_ipv4(memory, local_ip, conn.remote_ip, &ip_payload);
+}
+
+/// TCP checksum per RFC 793: the same `ipv4::checksum` internet-checksum
+/// algorithm `udp::udp_checksum` uses, over a pseudo-header of (src IP,
+/// dest IP, zero, protocol, TCP segment length) plus the header and
+/// payload. `pub(crate)` so `net::driver`'s user-mode NAT backend can
+/// checksum the segments it synthesizes the same way a real one would.
+pub(crate) fn tcp_checksum(header: &TcpHeader, src_ip: [u8; 4], dest_ip: [u8; 4], payload: &[u8]) -> u16 {
+    let total_len = (TcpHeader::LEN + payload.len()) as u16;
+    let mut pseudo = Vec::with_capacity(12 + TcpHeader::LEN + payload.len());
+    pseudo.extend_from_slice(&src_ip);
+    pseudo.extend_from_slice(&dest_ip);
+    pseudo.push(0);
+    pseudo.push(ipv4::PROTO_TCP);
+    pseudo.extend_from_slice(&total_len.to_be_bytes());
+    pseudo.extend_from_slice(header.as_bytes());
+    pseudo.extend_from_slice(payload);
+    ipv4::checksum(&pseudo)
+}
@@ -0,0 +1,69 @@
+use crate::net::ethernet;
+use crate::net::ipv4::{self, Ipv4Header};
+use alloc::vec::Vec;
+use ferrous_vm::Memory;
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
+
+/// An 8-byte UDP header. Like `Ipv4Header`, every field is stored
+/// pre-converted to big-endian (wire order).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
+pub struct UdpHeader {
+    pub src_port: u16,  // Big Endian
+    pub dest_port: u16, // Big Endian
+    pub length: u16,    // Big Endian
+    pub checksum: u16,  // Big Endian
+}
+
+impl UdpHeader {
+    pub const LEN: usize = 8;
+}
+
+/// Build an IPv4 / UDP datagram carrying `payload` from `src_ip:src_port`
+/// to `dest_ip:dest_port` and hand it to `net::ethernet::send_ipv4` for
+/// MAC resolution and transmission. Both checksums are computed fresh per
+/// call since the addresses and payload differ per datagram.
+pub fn send_to(
+    memory: &mut dyn Memory,
+    src_ip: [u8; 4],
+    src_port: u16,
+    dest_ip: [u8; 4],
+    dest_port: u16,
+    payload: &[u8],
+) {
+    let udp_len = (UdpHeader::LEN + payload.len()) as u16;
+    let mut udp_header = UdpHeader {
+        src_port: src_port.to_be(),
+        dest_port: dest_port.to_be(),
+        length: udp_len.to_be(),
+        checksum: 0,
+    };
+    udp_header.checksum = udp_checksum(&udp_header, src_ip, dest_ip, payload).to_be();
+
+    let ip_header = Ipv4Header::new(udp_len, ipv4::PROTO_UDP, src_ip, dest_ip);
+
+    let mut ip_payload = Vec::with_capacity(Ipv4Header::LEN + udp_len as usize);
+    ip_payload.extend_from_slice(ip_header.as_bytes());
+    ip_payload.extend_from_slice(udp_header.as_bytes());
+    ip_payload.extend_from_slice(payload);
+
+    ethernet::send_ipv4(memory, src_ip, dest_ip, &ip_payload);
+}
+
+/// UDP checksum per RFC 768: the Internet checksum of the UDP header and
+/// payload, prefixed with a pseudo-header of (src IP, dest IP, zero,
+/// protocol, UDP length) that binds the checksum to the IP addresses
+/// without transmitting them a second time. `pub(crate)` so
+/// `net::driver`'s user-mode NAT backend can checksum the replies it
+/// synthesizes the same way a real datagram would be.
+pub(crate) fn udp_checksum(header: &UdpHeader, src_ip: [u8; 4], dest_ip: [u8; 4], payload: &[u8]) -> u16 {
+    let mut pseudo = Vec::with_capacity(12 + UdpHeader::LEN + payload.len());
+    pseudo.extend_from_slice(&src_ip);
+    pseudo.extend_from_slice(&dest_ip);
+    pseudo.push(0);
+    pseudo.push(ipv4::PROTO_UDP);
+    pseudo.extend_from_slice(&header.length.to_ne_bytes());
+    pseudo.extend_from_slice(header.as_bytes());
+    pseudo.extend_from_slice(payload);
+    ipv4::checksum(&pseudo)
+}
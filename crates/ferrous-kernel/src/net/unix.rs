@@ -0,0 +1,25 @@
+//! The `AF_UNIX`-style local socket domain: instead of routing through the
+//! IP layer like `SockAddrIn`'s UDP/TCP path, `SockAddrUn` sends are
+//! resolved straight against this kernel-resident name table, the same
+//! way a host Unix domain socket resolves a path without ever touching a
+//! network interface.
+
+use super::UNIX_NAME_MAX;
+use crate::sync::spinlock::SpinLock;
+use alloc::collections::BTreeMap;
+
+/// Bound name -> socket id. A name is claimed by whichever socket last
+/// bound it; this table doesn't track unbinding on socket close, the same
+/// "small number of sockets, theoretical concern" tradeoff
+/// `SocketTable::alloc_ephemeral_port` makes about port reuse.
+pub static ENDPOINTS: SpinLock<BTreeMap<[u8; UNIX_NAME_MAX], u32>> = SpinLock::new(BTreeMap::new());
+
+/// Claim `name` for socket `id`, replacing whatever previously held it.
+pub fn bind(name: [u8; UNIX_NAME_MAX], id: u32) {
+    ENDPOINTS.lock().insert(name, id);
+}
+
+/// Look up the socket id currently bound to `name`.
+pub fn lookup(name: [u8; UNIX_NAME_MAX]) -> Option<u32> {
+    ENDPOINTS.lock().get(&name).copied()
+}
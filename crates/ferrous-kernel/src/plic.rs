@@ -0,0 +1,40 @@
+//! Kernel-side protocol for `ferrous_vm::devices::plic::InterruptController`,
+//! mirroring `fs::block`'s own `BLOCK_DEVICE_BASE`/register-offset copies
+//! rather than importing the device struct itself -- this module only ever
+//! pokes the protocol through `Memory`.
+use alloc::format;
+use alloc::string::String;
+use ferrous_vm::{Memory, PhysAddr};
+
+/// Matches `ferrous_vm::devices::plic::PLIC_BASE`.
+const PLIC_BASE: u32 = 0x0C00_0000;
+const CLAIM: u32 = 0x08;
+const COMPLETE: u32 = 0x0C;
+
+/// Claim whatever IRQ the PLIC currently has pending-and-enabled above its
+/// running priority (0 if none), then immediately complete it.
+///
+/// Drivers that want to act on a specific IRQ (today, none do -- `fs::block`
+/// and `net::socket` both still poll their own device registers directly on
+/// every `TimerInterrupt`, exactly as `ExternalInterrupt`'s own doc comment
+/// says they should) are expected to claim that IRQ for themselves through
+/// this same MMIO window rather than have this function fan a claimed IRQ
+/// out to them. What this function exists for is simpler: a `raise_irq`
+/// call latches the pending bit until something claims it, and nothing
+/// currently does for the IRQs `SimpleBlockDevice`/`SimpleNetDevice` raise
+/// -- left unclaimed, `mip.MEIP` would stay latched forever once any device
+/// ever raised one, since `InterruptController::has_deliverable_irq` only
+/// goes false once the pending bit is cleared. Draining it here on every
+/// `ExternalInterrupt` keeps that bit from wedging the CPU into retaking
+/// the same trap on every subsequent instruction.
+pub fn claim_and_complete(memory: &mut dyn Memory) -> Result<u32, String> {
+    let irq = memory
+        .read_word(PhysAddr::new(PLIC_BASE + CLAIM))
+        .map_err(|e| format!("Failed to read PLIC claim: {:?}", e))?;
+    if irq != 0 {
+        memory
+            .write_word(PhysAddr::new(PLIC_BASE + COMPLETE), irq)
+            .map_err(|e| format!("Failed to write PLIC complete: {:?}", e))?;
+    }
+    Ok(irq)
+}
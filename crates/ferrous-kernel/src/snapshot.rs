@@ -0,0 +1,19 @@
+//! Device-state save/restore, the per-device half of a VM snapshot.
+//!
+//! A device that wants to survive a snapshot/restore round trip implements
+//! [`DeviceState`] with whatever subset of its own bookkeeping is actually
+//! needed to resume correctly — not the guest-physical memory it's mapped
+//! over, since that's captured by whatever snapshots guest memory as a
+//! whole; just the device-side fields a plain memory dump wouldn't catch,
+//! like negotiated features or ring cursors.
+
+use crate::error::SnapshotError;
+
+pub trait DeviceState {
+    /// Serialize this device's state to a byte blob suitable for writing
+    /// into a `.snap` file.
+    fn save(&self) -> Vec<u8>;
+
+    /// Restore state previously produced by [`DeviceState::save`].
+    fn restore(&mut self, data: &[u8]) -> Result<(), SnapshotError>;
+}
@@ -1,9 +1,16 @@
 use crate::types::ThreadHandle;
 use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use ferrous_vm::VirtAddr;
 
 pub struct Mutex {
     pub id: u32,
     pub owner: Option<ThreadHandle>,
+    /// Threads blocked in `MutexAcquire`, in arrival order. `MutexRelease`
+    /// doesn't always hand off to the front of this queue: it picks the
+    /// highest-priority (lowest scheduler level) waiter instead, so a
+    /// thread priority-boosted the owner to wake for doesn't sit behind
+    /// lower-priority waiters that arrived first.
     pub wait_queue: VecDeque<ThreadHandle>,
 }
 
@@ -16,3 +23,160 @@ impl Mutex {
         }
     }
 }
+
+/// A condition variable meant to be waited on alongside a particular
+/// `Mutex`: `CondvarWait` atomically releases that mutex and enqueues the
+/// caller here under the same kernel-lock-held call, so a `CondvarNotify*`
+/// that runs between the release and the enqueue is impossible rather than
+/// just unlikely. Unlike `Mutex::wait_queue`, waking a waiter doesn't hand
+/// it anything -- it just re-joins the mutex's own wait queue to reacquire
+/// the lock `wait` released, the same as any other contended acquirer.
+pub struct Condvar {
+    pub id: u32,
+    /// Threads parked in `CondvarWait`, paired with the mutex each one
+    /// needs to reacquire before it can actually resume.
+    pub wait_queue: VecDeque<(ThreadHandle, u32)>,
+}
+
+impl Condvar {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            wait_queue: VecDeque::new(),
+        }
+    }
+}
+
+/// A counting semaphore: `count` can go negative, at which point its
+/// magnitude is exactly the number of threads parked in `wait_queue` --
+/// the invariant `SemWait`/`SemPost` maintain between them. Unlike
+/// `Mutex`, there's no owner and no priority inheritance, since a
+/// semaphore's count isn't "held" by any one thread the way a lock is.
+pub struct Semaphore {
+    pub id: u32,
+    pub count: i32,
+    /// Threads blocked in `SemWait`, in arrival order. Woken strictly
+    /// FIFO by `SemPost` -- unlike `Mutex::wait_queue`'s priority-ordered
+    /// hand-off, a semaphore has no owner to boost, so there's nothing to
+    /// reorder for.
+    pub wait_queue: VecDeque<ThreadHandle>,
+}
+
+impl Semaphore {
+    pub fn new(id: u32, initial: i32) -> Self {
+        Self {
+            id,
+            count: initial,
+            wait_queue: VecDeque::new(),
+        }
+    }
+}
+
+/// A byte-stream pipe opened via the `pipe:<name>` scheme, connecting
+/// every thread that opens the same name the same way two ends of a Unix
+/// named FIFO connect separate processes: `FileWrite` pushes onto the
+/// back of `buffer`, `FileRead` drains from the front. Unlike `Mutex` and
+/// `Endpoint`, there's no wait queue here -- a `FileRead` against an
+/// empty pipe just reports zero bytes read rather than blocking, the same
+/// non-blocking shape `FileRead` already has against a `Disk`/`Host`
+/// descriptor.
+#[derive(Default)]
+pub struct Pipe {
+    pub buffer: VecDeque<u8>,
+}
+
+impl Pipe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Largest message `EndpointSend` will accept in one call.
+pub const ENDPOINT_MAX_MSG: usize = 256;
+
+/// `EndpointSend`'s `transfer_fd` when the sender isn't handing over a
+/// descriptor, just a message.
+pub const NO_TRANSFER_FD: u32 = u32::MAX;
+
+/// A receiver parked in `EndpointRecv` with the endpoint's queue empty,
+/// recorded so a later `Send` can copy straight into its buffer instead of
+/// the receiver having to wake up and re-issue the call.
+pub struct EndpointWaiter {
+    pub thread: ThreadHandle,
+    pub buf_ptr: VirtAddr,
+    pub cap: usize,
+    /// Where to write the fd `Send`'s `transfer_fd` was duplicated into
+    /// (or `NO_TRANSFER_FD` if it didn't carry one) once delivery happens.
+    pub cap_out_ptr: VirtAddr,
+}
+
+/// A synchronous, rendezvous message-passing endpoint. `Send` hands its
+/// message straight to a receiver already parked in `waiting_receivers`,
+/// or else queues it in `pending_sends` and blocks the sender until some
+/// later `Recv` drains it; `Recv` is the mirror image, the same
+/// park-on-contention shape as `Mutex::wait_queue`. The queued/parked
+/// descriptor alongside each message is a snapshot of the sender's fd
+/// table entry at `Send` time (not a reference to it), the same way a
+/// `Dup`'d descriptor stands on its own -- the sender is free to `FileClose`
+/// its own copy the moment `Send` returns, delivered or not.
+pub struct Endpoint {
+    pub id: u32,
+    pub pending_sends: VecDeque<(ThreadHandle, Vec<u8>, Option<crate::thread::tcb::FileDescriptor>)>,
+    pub waiting_receivers: VecDeque<EndpointWaiter>,
+}
+
+impl Endpoint {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            pending_sends: VecDeque::new(),
+            waiting_receivers: VecDeque::new(),
+        }
+    }
+}
+
+/// Largest message `SendMessage` will accept in one call.
+pub const SERVER_MAX_MSG: usize = 256;
+
+/// One call queued on a `Server` by `SendMessage`, waiting for
+/// `ReceiveMessage` to hand it to the server thread.
+pub struct Message {
+    pub sender: ThreadHandle,
+    pub opcode: u32,
+    pub data: Vec<u8>,
+}
+
+/// A server thread parked in `ReceiveMessage` with `pending` empty, recorded
+/// so a later `SendMessage` can deliver straight into its buffers instead of
+/// it having to wake up and re-issue the call.
+pub struct ServerWaiter {
+    pub thread: ThreadHandle,
+    pub buf_ptr: VirtAddr,
+    pub cap: usize,
+    pub meta_ptr: VirtAddr,
+}
+
+/// A Xous-style server: `SendMessage` always blocks its caller, even when a
+/// `ReceiveMessage` is already parked waiting, because the call is a
+/// request that only completes once the server thread replies with
+/// `ReturnMemory`. `awaiting_reply` is the FIFO of senders blocked on that
+/// reply, in the order their calls were accepted -- `ReturnMemory` always
+/// answers the oldest one, the same single-request-in-flight assumption a
+/// Xous server's receive/process/reply loop makes.
+pub struct Server {
+    pub id: u32,
+    pub pending: VecDeque<Message>,
+    pub waiting_receivers: VecDeque<ServerWaiter>,
+    pub awaiting_reply: VecDeque<ThreadHandle>,
+}
+
+impl Server {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            pending: VecDeque::new(),
+            waiting_receivers: VecDeque::new(),
+            awaiting_reply: VecDeque::new(),
+        }
+    }
+}
@@ -1,6 +1,24 @@
 use crate::error::SyscallError;
 use ferrous_vm::{Cpu, Register, VirtAddr};
 
+/// `WaitPid`'s `timeout_nanos` of `0` means "block forever", matching its
+/// original (pre-timeout) behavior and the zero-initialized default a
+/// caller gets if it doesn't set `a1`/`a2` at all.
+pub const WAITPID_NO_TIMEOUT: u64 = 0;
+
+/// The exit-code register value `WaitPid` returns when its timeout elapses
+/// before the waited-on child exits, distinct from any real exit code
+/// `Exit`/`exit_current_thread` could produce (those are truncated `i32`s
+/// a real program controls; `-1` is reserved here the way POSIX reserves
+/// `-1` for a failed `waitpid(2)`).
+pub const WAITPID_TIMED_OUT: i32 = -1;
+
+/// `Poll`'s `timeout_ms` of `u32::MAX` means "block forever" -- unlike
+/// `WaitPid`, `0` here instead means a real `poll(2)`'s "don't block at
+/// all", so the "forever" sentinel can't also be `0` the way
+/// `WAITPID_NO_TIMEOUT` uses it.
+pub const POLL_NO_TIMEOUT: u32 = u32::MAX;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Syscall {
     // I/O
@@ -9,6 +27,18 @@ pub enum Syscall {
         buf_ptr: VirtAddr,
         len: usize,
     },
+    /// `writev`'s Linux syscall number (66): `iov_ptr` points to
+    /// `iov_count` back-to-back `{ base: VirtAddr, len: u32 }` records
+    /// (eight bytes each, both little-endian). The handler reads every
+    /// segment into one buffer and dispatches it exactly like `FileWrite`
+    /// would a single buffer that size, rather than issuing one write per
+    /// segment -- so a `Pipe`/`Host` write still lands as one contiguous
+    /// chunk instead of being interleaved with writes from elsewhere.
+    FileWriteV {
+        fd: u32,
+        iov_ptr: VirtAddr,
+        iov_count: usize,
+    },
     ConsoleRead {
         fd: u32,
         buf_ptr: VirtAddr,
@@ -27,23 +57,151 @@ pub enum Syscall {
         stack_top: u32,
     },
     ThreadYield,
+    ThreadStats {
+        handle: u32,
+    },
+    /// Block the caller for at least `nanos_lo`/`nanos_hi` (packed low/high
+    /// into one `u64`, the way a 32-bit ABI splits any 64-bit argument
+    /// across a register pair) nanoseconds, converted to a tick count via
+    /// `thread::NANOS_PER_TICK` and served by the same timer queue
+    /// `WaitPid`'s timeout uses.
+    Sleep {
+        nanos_lo: u32,
+        nanos_hi: u32,
+    },
 
     // Synchronization
     MutexCreate,
     MutexAcquire {
-        id: u32,
+        slot: u32,
     },
     MutexRelease {
-        id: u32,
+        slot: u32,
+    },
+    /// Non-blocking `MutexAcquire`: returns `Value(1)` if the mutex was free
+    /// and is now held by the caller, or `Value(0)` if it's already held
+    /// (by the caller or anyone else) -- never queues the caller or yields,
+    /// so a thread that might already own the mutex (e.g. a panic handler
+    /// printing from inside a locked section) can't deadlock itself the way
+    /// a blocking `MutexAcquire` would.
+    MutexTryAcquire {
+        slot: u32,
+    },
+    CondvarCreate,
+    /// Atomically release the mutex in `mutex_slot` and block the caller on
+    /// the condvar in `condvar_slot`, re-acquiring the mutex (blocking again
+    /// if it's contended) before returning -- the whole point being that no
+    /// `CondvarNotify*` can land in the gap between the release and the
+    /// block the way it could if a caller did those two steps itself.
+    CondvarWait {
+        condvar_slot: u32,
+        mutex_slot: u32,
+    },
+    CondvarNotifyOne {
+        slot: u32,
+    },
+    CondvarNotifyAll {
+        slot: u32,
+    },
+    /// Create a counting semaphore starting at `initial` (which may be
+    /// negative, the same as handing out that many permits already owed).
+    SemCreate {
+        initial: i32,
+    },
+    /// Decrement the semaphore's count (P); blocks if it goes negative,
+    /// the same block-and-yield shape `MutexAcquire` uses when contended.
+    SemWait {
+        slot: u32,
+    },
+    /// Increment the semaphore's count (V); if it was negative, wakes the
+    /// longest-waiting blocked thread (strictly FIFO, unlike
+    /// `MutexRelease`'s priority-ordered hand-off).
+    SemPost {
+        slot: u32,
+    },
+    /// Tear down a semaphore, waking every thread still parked in
+    /// `SemWait` with an error in `a0` rather than leaving them blocked
+    /// forever.
+    SemDestroy {
+        slot: u32,
+    },
+    EndpointCreate,
+    /// `transfer_fd` is the sender's own fd table index to duplicate into
+    /// the receiver's table at the rendezvous point, atomically with the
+    /// message, seL4-style -- or `sync::NO_TRANSFER_FD` to send a message
+    /// with no accompanying capability.
+    EndpointSend {
+        ep: u32,
+        buf_ptr: VirtAddr,
+        len: usize,
+        transfer_fd: u32,
+    },
+    /// `cap_out_ptr` is where the matching `Send`'s `transfer_fd` (already
+    /// duplicated into this thread's own fd table) is written back, or
+    /// `sync::NO_TRANSFER_FD` if that `Send` didn't carry one.
+    EndpointRecv {
+        ep: u32,
+        buf_ptr: VirtAddr,
+        cap: usize,
+        cap_out_ptr: VirtAddr,
+    },
+
+    // Xous-style server/connection IPC: unlike `Endpoint*` above (one shared
+    // id a sender and receiver both hold), a server has its own id and each
+    // caller gets back a distinct connection capability from `Connect`, and
+    // `SendMessage` always blocks for a `ReturnMemory` reply instead of
+    // returning as soon as the payload is delivered.
+    CreateServer,
+    Connect {
+        server_id: u32,
+    },
+    SendMessage {
+        conn: u32,
+        opcode: u32,
+        buf_ptr: VirtAddr,
+        len: usize,
+    },
+    ReceiveMessage {
+        server_id: u32,
+        buf_ptr: VirtAddr,
+        len: usize,
+        meta_ptr: VirtAddr,
+    },
+    ReturnMemory {
+        conn: u32,
     },
 
     // Memory
+    /// POSIX `brk`'s Linux syscall number (214) with `sbrk`'s relative-
+    /// increment calling convention instead of an absolute break address:
+    /// a libc allocator built against either interface just needs a
+    /// `sbrk`-style shim over this one.
     Sbrk {
         increment: i32,
     },
+    Mmap {
+        addr: u32,
+        len: usize,
+        prot: u32,
+        flags: u32,
+    },
+    Munmap {
+        addr: u32,
+        len: usize,
+    },
 
     // Network
-    Socket,
+    /// `domain`/`ty`/`protocol` match `socket(2)`'s arguments
+    /// (`net::AF_INET`/`net::SOCK_STREAM`/`net::IPPROTO_TCP` and so on);
+    /// only `domain` and `protocol` actually change behavior today:
+    /// `net::AF_UNIX` routes the socket through `net::unix`'s name table,
+    /// and `net::IPPROTO_TCP` selects a real `net::tcp` connection over
+    /// this module's original UDP/loopback-stream kind.
+    Socket {
+        domain: u32,
+        ty: u32,
+        protocol: u32,
+    },
     Bind {
         fd: usize,
         ptr: VirtAddr,
@@ -63,35 +221,230 @@ pub enum Syscall {
         src_ptr: VirtAddr,
         src_len_ptr: VirtAddr,
     },
+    /// Mark `fd` as a listener with room for up to `backlog` accepted
+    /// connections nobody's called `Accept` for yet.
+    Listen {
+        fd: usize,
+        backlog: usize,
+    },
+    /// Block until a peer `SocketConnect`s to a listening `fd`, then return
+    /// a fresh connected socket fd for that peer, writing its address into
+    /// `addr_ptr`/`addrlen_ptr` the same way `RecvFrom` fills `src_ptr`.
+    /// Uses the same block/yield/wake mechanism `EndpointRecv` does when
+    /// nothing's pending.
+    Accept {
+        fd: usize,
+        addr_ptr: VirtAddr,
+        addrlen_ptr: VirtAddr,
+    },
+    /// Named `SocketConnect` rather than `Connect` to avoid colliding with
+    /// the IPC `Connect { server_id }` above -- this one pairs `fd` with
+    /// whatever socket `Listen`s on the port named by `addr_ptr`'s
+    /// `sockaddr_in`. If that address is this kernel's own
+    /// `net::syscalls::local_ip()`, it's the original loopback pairing with
+    /// no wire traffic; otherwise `fd` must be a TCP socket, and this
+    /// blocks for a real `net::tcp` SYN/SYN-ACK/ACK handshake against
+    /// `addr_ptr`'s remote address instead.
+    SocketConnect {
+        fd: usize,
+        addr_ptr: VirtAddr,
+    },
+    /// Stream-socket `send(2)`: like `SendTo` minus the destination
+    /// address, since a TCP connection's 4-tuple already fixes the peer.
+    Send {
+        fd: usize,
+        buf_ptr: VirtAddr,
+        len: usize,
+    },
+    /// Stream-socket `recv(2)`: like `RecvFrom` minus the source address
+    /// it would otherwise write back. Blocks (parking a
+    /// `net::tcp::RecvWaiter`) if nothing's buffered yet and the peer
+    /// hasn't finished sending, the same block/yield/wake pattern
+    /// `Accept` uses for its backlog.
+    Recv {
+        fd: usize,
+        buf_ptr: VirtAddr,
+        len: usize,
+    },
+    /// `poll(2)`: block until any of `fds_ptr`'s `nfds` `net::PollFd`
+    /// entries is ready or `timeout_ms` elapses (`0` = check and return
+    /// immediately either way, matching a real `poll(2)`'s non-blocking
+    /// sense of a zero timeout). Writes each entry's `revents` back in
+    /// place and returns the count that became ready, `0` on timeout.
+    /// Parks a `net::poll::PollWaiter` the same block/yield/wake way
+    /// `Recv` parks a `net::tcp::RecvWaiter` if nothing's ready yet and
+    /// the timeout hasn't already elapsed.
+    Poll {
+        fds_ptr: VirtAddr,
+        nfds: usize,
+        timeout_ms: u32,
+    },
+    /// Set or clear `fd`'s `net::socket::Socket::non_blocking` flag
+    /// (`flag != 0`), the normal companion to level-triggered `Poll`: once
+    /// set, `RecvFrom` reports `net::EWOULDBLOCK` instead of silently
+    /// returning `0` when nothing's queued.
+    SetNonBlocking {
+        fd: usize,
+        flag: u32,
+    },
+
+    // Entropy
+    /// `getrandom`'s Linux syscall number (278). Fills up to `len` bytes of
+    /// `buf_ptr` from `Kernel`'s own xorshift32 generator (the same one the
+    /// `rand:` scheme reads from) and returns the count written -- always
+    /// all of `len`, since that generator never blocks on missing entropy.
+    /// `flags` is accepted for interface compatibility with a real
+    /// `getrandom(2)` (e.g. `GRND_NONBLOCK`) but otherwise ignored here.
+    GetRandom {
+        buf_ptr: VirtAddr,
+        len: usize,
+        flags: u32,
+    },
 
     // Block Device (Temporary Debug)
     BlockRead {
         sector: u32,
         buf_ptr: VirtAddr,
     },
+    /// Program the DMA engine with a guest-resident descriptor chain and
+    /// return immediately -- unlike `BlockRead`, nothing is copied to user
+    /// memory here, since each descriptor already names its own destination
+    /// and the engine writes straight there. Completion shows up in the
+    /// descriptor's `done` field and the engine's own status register, not
+    /// this syscall's return value.
+    BlockReadDma {
+        desc_ptr: VirtAddr,
+    },
+
+    // Persistent Config Store
+    /// Look up `key_ptr`/`key_len` in `fs::config`'s reserved-sector store,
+    /// copying its value into `buf_ptr`/`buf_len` (truncated if the value is
+    /// longer) and returning the value's full length, the same
+    /// longer-than-the-buffer-is-fine convention `GetRandom` uses.
+    ConfigRead {
+        key_ptr: VirtAddr,
+        key_len: usize,
+        buf_ptr: VirtAddr,
+        buf_len: usize,
+    },
+    /// Write `key_ptr`/`key_len` = `val_ptr`/`val_len`, replacing any
+    /// existing record for that key.
+    ConfigWrite {
+        key_ptr: VirtAddr,
+        key_len: usize,
+        val_ptr: VirtAddr,
+        val_len: usize,
+    },
+    /// Erase `key_ptr`/`key_len`'s record, if any.
+    ConfigRemove {
+        key_ptr: VirtAddr,
+        key_len: usize,
+    },
 
     // File System
     FileOpen {
         path_ptr: VirtAddr,
         path_len: usize,
+        flags: u32,
     },
     FileRead {
         fd: u32,
         buf_ptr: VirtAddr,
         len: usize,
     },
+    /// `readv`'s Linux syscall number (65) minus the collision with this
+    /// kernel's own `ConsoleRead` already at 65 -- this uses 67 instead.
+    /// `iov_ptr`/`iov_count` have the same layout `FileWriteV` reads;
+    /// bytes the descriptor produces are scattered across the segments in
+    /// order rather than requiring the caller to size a single buffer for
+    /// the whole read up front.
+    FileReadV {
+        fd: u32,
+        iov_ptr: VirtAddr,
+        iov_count: usize,
+    },
     FileClose {
         fd: u32,
     },
+    /// `lseek`'s Linux syscall number (62). `whence` is POSIX's
+    /// `SEEK_SET`/`SEEK_CUR`/`SEEK_END` (0/1/2); `offset` is relative to
+    /// whichever of those it names rather than always absolute. Only
+    /// `Disk`/`Host` descriptors are seekable -- `Console`/`Null`/`Rand`
+    /// have no position to move, and `Pipe` is a stream.
+    FileSeek {
+        fd: u32,
+        offset: i64,
+        whence: u32,
+    },
+    /// `fstat`'s Linux syscall number (80). Writes a `ferrous_fs::Stat`
+    /// describing `fd` to `stat_ptr` -- its size and, for a `Disk`/`Host`
+    /// descriptor, its inode number/fid, with `mode`'s type bits telling
+    /// userspace whether it opened a regular file, a `Pipe`, or one of the
+    /// character-device-shaped schemes (`Console`/`Null`/`Rand`).
+    Fstat {
+        fd: u32,
+        stat_ptr: VirtAddr,
+    },
+    Dup {
+        fd: u32,
+    },
+    /// `dup2`'s Linux syscall number (33): like `Dup`, but installs the copy
+    /// at exactly `new_fd` instead of the lowest free slot, closing whatever
+    /// was already open there first.
+    Dup2 {
+        old_fd: u32,
+        new_fd: u32,
+    },
     Exec {
         path_ptr: VirtAddr,
         path_len: usize,
         args_ptr: VirtAddr,
         args_len: usize,
     },
+    /// Like `Exec`, but the ELF image is read straight out of the caller's
+    /// own address space (`image_ptr`/`image_len`) instead of off the
+    /// filesystem by path -- for a caller that already has a binary in
+    /// memory (unpacked from an archive, received over the network) and
+    /// has nothing on disk to point `Exec` at. Builds its own fresh
+    /// address space and thread the same way `Exec` does, so the spawned
+    /// process is isolated from the caller rather than sharing its `satp`.
+    SpawnProcess {
+        image_ptr: VirtAddr,
+        image_len: usize,
+        args_ptr: VirtAddr,
+        args_len: usize,
+    },
+    /// `timeout_nanos` is `a1`/`a2` packed low/high into one `u64`, the same
+    /// register-pair convention `Sleep`'s duration uses -- `0`
+    /// (`WAITPID_NO_TIMEOUT`) blocks forever, `WaitPid`'s original behavior,
+    /// anything else caps how long the caller blocks before giving up on an
+    /// exit that never comes.
     WaitPid {
         pid: u32,
+        timeout_nanos: u64,
+    },
+    Fork,
+
+    // Signals
+    /// `rt_sigaction`'s Linux syscall number (134), pared down to just the
+    /// handler entry point `Kernel::deliver_pending_signal` jumps to --
+    /// there's no `sigaction` struct with flags/mask/restorer to parse since
+    /// user binaries here call `Sigreturn` directly rather than through a
+    /// libc-installed trampoline.
+    Sigaction {
+        signum: u32,
+        handler: u32,
+    },
+    /// `kill`'s Linux syscall number (129), minus process groups: `pid` is
+    /// always a single thread handle.
+    Kill {
+        pid: u32,
+        signum: u32,
     },
+    /// `rt_sigreturn`'s Linux syscall number (139). Takes no arguments --
+    /// the context to restore is `Kernel::deliver_pending_signal`'s own
+    /// `signal_saved_context`, not a stack frame the handler passes back.
+    Sigreturn,
 }
 
 #[derive(Debug)]
@@ -122,21 +475,62 @@ impl Syscall {
                 buf_ptr: VirtAddr::new(a1),
                 len: a2 as usize,
             }),
+            66 => Ok(Syscall::FileWriteV {
+                fd: a0,
+                iov_ptr: VirtAddr::new(a1),
+                iov_count: a2 as usize,
+            }),
+            67 => Ok(Syscall::FileReadV {
+                fd: a0,
+                iov_ptr: VirtAddr::new(a1),
+                iov_count: a2 as usize,
+            }),
             22 => Ok(Syscall::Pipe {
                 pipe_array_ptr: VirtAddr::new(a0),
             }),
             56 => Ok(Syscall::FileOpen {
                 path_ptr: VirtAddr::new(a0),
                 path_len: a1 as usize,
+                flags: a2,
             }),
             57 => Ok(Syscall::FileClose { fd: a0 }),
+            62 => Ok(Syscall::FileSeek {
+                fd: a0,
+                offset: a1 as i32 as i64,
+                whence: a2,
+            }),
+            80 => Ok(Syscall::Fstat {
+                fd: a0,
+                stat_ptr: VirtAddr::new(a1),
+            }),
+            58 => Ok(Syscall::Dup { fd: a0 }),
+            33 => Ok(Syscall::Dup2 {
+                old_fd: a0,
+                new_fd: a1,
+            }),
             59 => Ok(Syscall::Exec {
                 path_ptr: VirtAddr::new(a0),
                 path_len: a1 as usize,
                 args_ptr: VirtAddr::new(a2),
                 args_len: a3 as usize,
             }),
-            260 => Ok(Syscall::WaitPid { pid: a0 }),
+            60 => Ok(Syscall::SpawnProcess {
+                image_ptr: VirtAddr::new(a0),
+                image_len: a1 as usize,
+                args_ptr: VirtAddr::new(a2),
+                args_len: a3 as usize,
+            }),
+            260 => Ok(Syscall::WaitPid {
+                pid: a0,
+                timeout_nanos: ((a2 as u64) << 32) | a1 as u64,
+            }),
+            220 => Ok(Syscall::Fork),
+            129 => Ok(Syscall::Kill { pid: a0, signum: a1 }),
+            134 => Ok(Syscall::Sigaction {
+                signum: a0,
+                handler: a1,
+            }),
+            139 => Ok(Syscall::Sigreturn),
             63 => Ok(Syscall::FileRead {
                 fd: a0,
                 buf_ptr: VirtAddr::new(a1),
@@ -148,17 +542,95 @@ impl Syscall {
                 entry_point: VirtAddr::new(a0),
                 stack_top: a1,
             }),
+            103 => Ok(Syscall::ThreadStats { handle: a0 }),
+            104 => Ok(Syscall::Sleep {
+                nanos_lo: a0,
+                nanos_hi: a1,
+            }),
             110 => Ok(Syscall::MutexCreate),
-            111 => Ok(Syscall::MutexAcquire { id: a0 }),
-            112 => Ok(Syscall::MutexRelease { id: a0 }),
+            111 => Ok(Syscall::MutexAcquire { slot: a0 }),
+            112 => Ok(Syscall::MutexRelease { slot: a0 }),
+            125 => Ok(Syscall::MutexTryAcquire { slot: a0 }),
+            121 => Ok(Syscall::CondvarCreate),
+            122 => Ok(Syscall::CondvarWait {
+                condvar_slot: a0,
+                mutex_slot: a1,
+            }),
+            123 => Ok(Syscall::CondvarNotifyOne { slot: a0 }),
+            124 => Ok(Syscall::CondvarNotifyAll { slot: a0 }),
+            126 => Ok(Syscall::SemCreate { initial: a0 as i32 }),
+            127 => Ok(Syscall::SemWait { slot: a0 }),
+            128 => Ok(Syscall::SemPost { slot: a0 }),
+            130 => Ok(Syscall::SemDestroy { slot: a0 }),
+            113 => Ok(Syscall::EndpointCreate),
+            114 => Ok(Syscall::EndpointSend {
+                ep: a0,
+                buf_ptr: VirtAddr::new(a1),
+                len: a2 as usize,
+                transfer_fd: a3,
+            }),
+            115 => Ok(Syscall::EndpointRecv {
+                ep: a0,
+                buf_ptr: VirtAddr::new(a1),
+                cap: a2 as usize,
+                cap_out_ptr: VirtAddr::new(a3),
+            }),
+            116 => Ok(Syscall::CreateServer),
+            117 => Ok(Syscall::Connect { server_id: a0 }),
+            118 => Ok(Syscall::SendMessage {
+                conn: a0,
+                opcode: a1,
+                buf_ptr: VirtAddr::new(a2),
+                len: a3 as usize,
+            }),
+            119 => Ok(Syscall::ReceiveMessage {
+                server_id: a0,
+                buf_ptr: VirtAddr::new(a1),
+                len: a2 as usize,
+                meta_ptr: VirtAddr::new(a3),
+            }),
+            120 => Ok(Syscall::ReturnMemory { conn: a0 }),
             200 => Ok(Syscall::BlockRead {
                 sector: a0,
                 buf_ptr: VirtAddr::new(a1),
             }),
+            201 => Ok(Syscall::BlockReadDma {
+                desc_ptr: VirtAddr::new(a0),
+            }),
+            202 => Ok(Syscall::ConfigRead {
+                key_ptr: VirtAddr::new(a0),
+                key_len: a1 as usize,
+                buf_ptr: VirtAddr::new(a2),
+                buf_len: a3 as usize,
+            }),
+            203 => Ok(Syscall::ConfigWrite {
+                key_ptr: VirtAddr::new(a0),
+                key_len: a1 as usize,
+                val_ptr: VirtAddr::new(a2),
+                val_len: a3 as usize,
+            }),
+            204 => Ok(Syscall::ConfigRemove {
+                key_ptr: VirtAddr::new(a0),
+                key_len: a1 as usize,
+            }),
             214 => Ok(Syscall::Sbrk {
                 increment: a0 as i32,
             }),
-            300 => Ok(Syscall::Socket),
+            215 => Ok(Syscall::Munmap {
+                addr: a0,
+                len: a1 as usize,
+            }),
+            222 => Ok(Syscall::Mmap {
+                addr: a0,
+                len: a1 as usize,
+                prot: a2,
+                flags: a3,
+            }),
+            300 => Ok(Syscall::Socket {
+                domain: a0,
+                ty: a1,
+                protocol: a2,
+            }),
             301 => Ok(Syscall::Bind {
                 fd: a0 as usize,
                 ptr: VirtAddr::new(a1),
@@ -178,6 +650,43 @@ impl Syscall {
                 src_ptr: VirtAddr::new(a3),
                 src_len_ptr: VirtAddr::new(a4),
             }),
+            304 => Ok(Syscall::Listen {
+                fd: a0 as usize,
+                backlog: a1 as usize,
+            }),
+            305 => Ok(Syscall::Accept {
+                fd: a0 as usize,
+                addr_ptr: VirtAddr::new(a1),
+                addrlen_ptr: VirtAddr::new(a2),
+            }),
+            306 => Ok(Syscall::SocketConnect {
+                fd: a0 as usize,
+                addr_ptr: VirtAddr::new(a1),
+            }),
+            307 => Ok(Syscall::Send {
+                fd: a0 as usize,
+                buf_ptr: VirtAddr::new(a1),
+                len: a2 as usize,
+            }),
+            308 => Ok(Syscall::Recv {
+                fd: a0 as usize,
+                buf_ptr: VirtAddr::new(a1),
+                len: a2 as usize,
+            }),
+            309 => Ok(Syscall::Poll {
+                fds_ptr: VirtAddr::new(a0),
+                nfds: a1 as usize,
+                timeout_ms: a2,
+            }),
+            310 => Ok(Syscall::SetNonBlocking {
+                fd: a0 as usize,
+                flag: a1,
+            }),
+            278 => Ok(Syscall::GetRandom {
+                buf_ptr: VirtAddr::new(a0),
+                len: a1 as usize,
+                flags: a2,
+            }),
             _ => Err(SyscallError::InvalidSyscallNumber(a7)),
         }
     }
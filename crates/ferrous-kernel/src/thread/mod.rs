@@ -4,20 +4,50 @@ pub mod tcb;
 
 use crate::types::ThreadHandle;
 use alloc::boxed::Box;
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BinaryHeap};
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
-use ferrous_vm::{Cpu, PrivilegeMode, VirtAddr};
+use core::cmp::Reverse;
+use ferrous_vm::{Cpu, PrivilegeMode, Register, VirtAddr};
 use log::info;
-use scheduler::{RoundRobinScheduler, Scheduler};
-use tcb::{ThreadControlBlock, ThreadState};
+use crate::capability::CapabilityTable;
+use scheduler::{MlfqScheduler, Scheduler};
+use tcb::{FileDescriptor, ThreadControlBlock, ThreadState};
+
+/// Nominal nanoseconds per timer tick, used only to convert a `Sleep`/
+/// `WaitPid` timeout's duration into a tick count. The VM's timer interrupt
+/// actually fires every fixed number of *instructions* executed
+/// (`ferrous_vm::Config::timer_interval`), not on a wall-clock cadence, so
+/// there's no real ns-per-tick to measure here -- this is a documented
+/// approximation (roughly a 10ms tick) good enough to turn "sleep N ns"
+/// into "sleep at least one tick" without claiming real-time precision.
+pub const NANOS_PER_TICK: u64 = 10_000_000;
+
+/// What a popped timer-queue entry should do to the thread it names. A
+/// `WaitPidTimeout` entry can go stale if the waited-on child exits for
+/// real before the timeout is reached -- `wake_waitpid_timeout` checks the
+/// thread is still actually `Waiting` before touching it, so a stale entry
+/// is just a silent no-op rather than something that needs to be removed
+/// from the heap up front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TimerWake {
+    Sleep,
+    WaitPidTimeout,
+}
 
 pub struct ThreadManager {
     pub threads: BTreeMap<ThreadHandle, ThreadControlBlock>,
     pub scheduler: Box<dyn Scheduler>,
     pub current_thread: Option<ThreadHandle>,
     pub next_handle: u32,
+    /// Monotonic count of timer interrupts seen so far, advanced once per
+    /// `on_timer_tick`. The clock `timers` schedules wakeups against.
+    ticks: u64,
+    /// Pending wakeups keyed by absolute wakeup tick, min-ordered via
+    /// `Reverse` so `BinaryHeap` (normally max-first) pops the soonest
+    /// wakeup first. Drained by `wake_due_timers` every tick.
+    timers: BinaryHeap<Reverse<(u64, ThreadHandle, TimerWake)>>,
 }
 
 impl Default for ThreadManager {
@@ -28,11 +58,19 @@ impl Default for ThreadManager {
 
 impl ThreadManager {
     pub fn new() -> Self {
+        Self::with_scheduler(Box::new(MlfqScheduler::new()))
+    }
+
+    /// Build a `ThreadManager` around a caller-chosen `Scheduler`, e.g. a
+    /// plain `RoundRobinScheduler` instead of the default `MlfqScheduler`.
+    pub fn with_scheduler(scheduler: Box<dyn Scheduler>) -> Self {
         Self {
             threads: BTreeMap::new(),
-            scheduler: Box::new(RoundRobinScheduler::new()),
+            scheduler,
             current_thread: None,
             next_handle: 1,
+            ticks: 0,
+            timers: BinaryHeap::new(),
         }
     }
 
@@ -42,6 +80,7 @@ impl ThreadManager {
             let handle = ThreadHandle::new(self.next_handle).unwrap();
             self.next_handle += 1;
 
+            let quantum_remaining = self.scheduler.quantum_for(handle);
             let tcb = ThreadControlBlock {
                 handle,
                 state: ThreadState::Running,
@@ -53,8 +92,23 @@ impl ThreadManager {
                 ),
                 stack_pointer: cpu.regs[2],
                 kernel_stack: 0,
+                parent: None,
                 program_break: 0x8040_0000, // Default heap start (4MB mark)
+                mmap_top: crate::memory::MMAP_BASE,
+                mappings: Vec::new(),
+                segments: Vec::new(),
+                image: Vec::new(),
                 file_descriptors: vec![None, None, None], // Reserve stdin, stdout, stderr
+                capabilities: CapabilityTable::new(),
+                quantum_remaining,
+                cpu_ticks_total: 0,
+                held_mutexes: Vec::new(),
+                base_priority: None,
+                effective_priority: self.scheduler.priority_level(handle),
+                pending_signals: 0,
+                blocked_signals: 0,
+                signal_handlers: [0; 32],
+                signal_saved_context: None,
             };
             self.threads.insert(handle, tcb);
             self.current_thread = Some(handle);
@@ -83,20 +137,30 @@ impl ThreadManager {
         } else {
             (0, 0x8040_0000)
         };
+        let mmap_top = crate::memory::MMAP_BASE;
 
         // FORCE User Mode for new threads created via syscall
         // (If created by kernel internal logic, might be different, but for now Syscall::ThreadCreate implies User)
         let mode = PrivilegeMode::User;
 
-        // Inherit File Descriptors from parent thread
-        let file_descriptors = if let Some(current) = self.current_thread {
+        // Inherit File Descriptors and the owning process's segment/image
+        // bookkeeping from parent thread, since a `ThreadCreate` sibling
+        // shares the same address space and therefore the same lazily
+        // loaded ELF image. `bootstrap_process`/`Exec` overwrite both on
+        // the handle they get back, since they're starting a fresh process
+        // rather than a sibling thread.
+        let (file_descriptors, segments, image) = if let Some(current) = self.current_thread {
             if let Some(parent) = self.threads.get(&current) {
-                parent.file_descriptors.clone()
+                (
+                    parent.file_descriptors.clone(),
+                    parent.segments.clone(),
+                    parent.image.clone(),
+                )
             } else {
-                vec![None, None, None]
+                (vec![None, None, None], Vec::new(), Vec::new())
             }
         } else {
-            vec![None, None, None]
+            (vec![None, None, None], Vec::new(), Vec::new())
         };
 
         let tcb = ThreadControlBlock {
@@ -105,8 +169,23 @@ impl ThreadManager {
             context: tcb::SavedContext::new(entry_point, stack_top, satp, mode),
             stack_pointer: stack_top,
             kernel_stack: 0, // Assume no kernel stack switch for now (running in user mode usually)
+            parent: self.current_thread,
             program_break,
+            mmap_top,
+            mappings: Vec::new(),
+            segments,
+            image,
             file_descriptors,
+            capabilities: CapabilityTable::new(),
+            quantum_remaining: 0, // set by quantum_for when first dispatched
+            cpu_ticks_total: 0,
+            held_mutexes: Vec::new(),
+            base_priority: None,
+            effective_priority: self.scheduler.priority_level(handle),
+            pending_signals: 0,
+            blocked_signals: 0,
+            signal_handlers: [0; 32],
+            signal_saved_context: None,
         };
 
         self.threads.insert(handle, tcb);
@@ -115,7 +194,91 @@ impl ThreadManager {
         Ok(handle)
     }
 
+    /// Spawn a child thread that resumes exactly where `cpu` is (the
+    /// instruction after the `Fork` syscall), sharing `cpu`'s full register
+    /// file except for `a0`, which is forced to 0 so the child can tell
+    /// itself apart from the parent (who gets the child's handle as its own
+    /// `a0` via the normal syscall-return path). `satp` is the COW address
+    /// space already built by `memory::fork_address_space`.
+    pub fn fork_thread(
+        &mut self,
+        cpu: &Cpu,
+        satp: u32,
+        program_break: u32,
+        mmap_top: u32,
+        file_descriptors: Vec<Option<FileDescriptor>>,
+        segments: Vec<crate::memory::Segment>,
+        image: Vec<u8>,
+    ) -> Result<ThreadHandle, String> {
+        let handle = ThreadHandle::new(self.next_handle).ok_or("Out of thread handles")?;
+        self.next_handle += 1;
+
+        let mut context =
+            tcb::SavedContext::new(VirtAddr::new(cpu.pc + 4), cpu.regs[2], satp, cpu.mode);
+        context.regs = cpu.regs;
+        context.write_reg(Register::new(10).unwrap(), 0); // a0 = 0 in the child
+
+        let tcb = ThreadControlBlock {
+            handle,
+            state: ThreadState::Ready,
+            context,
+            stack_pointer: cpu.regs[2],
+            kernel_stack: 0,
+            parent: self.current_thread,
+            program_break,
+            mmap_top,
+            mappings: Vec::new(),
+            segments,
+            image,
+            file_descriptors,
+            capabilities: CapabilityTable::new(),
+            quantum_remaining: 0, // set by quantum_for when first dispatched
+            cpu_ticks_total: 0,
+            held_mutexes: Vec::new(),
+            base_priority: None,
+            effective_priority: self.scheduler.priority_level(handle),
+            pending_signals: 0,
+            blocked_signals: 0,
+            signal_handlers: [0; 32],
+            signal_saved_context: None,
+        };
+
+        self.threads.insert(handle, tcb);
+        self.scheduler.enqueue(handle);
+
+        Ok(handle)
+    }
+
+    /// Called on every timer interrupt. Charges the running thread one
+    /// tick against its `quantum_remaining` and its lifetime
+    /// `cpu_ticks_total`, and only preempts it — via the same path a
+    /// cooperative `ThreadYield` syscall takes — once that quantum hits
+    /// zero, so a configurable number of ticks elapse between rotations
+    /// instead of preempting on every single interrupt.
+    pub fn on_timer_tick(&mut self, cpu: &mut Cpu) -> bool {
+        self.ticks += 1;
+        self.wake_due_timers();
+        self.scheduler.on_tick();
+        if let Some(current) = self.current_thread {
+            if let Some(tcb) = self.threads.get_mut(&current) {
+                tcb.cpu_ticks_total += 1;
+                tcb.quantum_remaining = tcb.quantum_remaining.saturating_sub(1);
+                if tcb.quantum_remaining > 0 {
+                    return false;
+                }
+            }
+        }
+        self.switch_thread(cpu, true)
+    }
+
+    /// Voluntary preemption: a `ThreadYield` syscall, a blocking wait, or
+    /// exit. The current thread hasn't used its whole quantum, so it's
+    /// requeued as "early" rather than "expired".
     pub fn yield_thread(&mut self, cpu: &mut Cpu) -> bool {
+        self.switch_thread(cpu, false)
+    }
+
+    fn switch_thread(&mut self, cpu: &mut Cpu, quantum_expired: bool) -> bool {
         if let Some(current) = self.current_thread {
             // Save context
             if let Some(tcb) = self.threads.get_mut(&current) {
@@ -123,7 +286,11 @@ impl ThreadManager {
                 // We only set to Ready if it was Running.
                 if tcb.state == ThreadState::Running {
                     tcb.state = ThreadState::Ready;
-                    self.scheduler.enqueue(current);
+                    if quantum_expired {
+                        self.scheduler.requeue_expired(current);
+                    } else {
+                        self.scheduler.requeue_early(current);
+                    }
                 }
                 tcb.context.save_from(cpu);
             }
@@ -135,6 +302,7 @@ impl ThreadManager {
             self.current_thread = Some(next);
             if let Some(tcb) = self.threads.get_mut(&next) {
                 tcb.state = ThreadState::Running;
+                tcb.quantum_remaining = self.scheduler.quantum_for(next);
                 tcb.context.restore_to(cpu);
             }
             true
@@ -167,35 +335,64 @@ impl ThreadManager {
         }
     }
 
-    pub fn exit_current_thread(&mut self, code: i32) {
+    /// Exit `current_thread`, waking any `WaitPid`er already parked on it,
+    /// and return the `satp` root PPN of the address space the caller
+    /// should now tear down via `memory::release_address_space` -- `None`
+    /// if another still-live thread (a `ThreadCreate` sibling) shares it.
+    /// This can't do that teardown itself: it has no `&mut dyn Memory` to
+    /// walk page tables with, the same reason `memory`/`thread` stay
+    /// separate modules everywhere else in this kernel.
+    ///
+    /// Reaping the exiting thread's own TCB is a separate concern from
+    /// freeing its address space, since a future `WaitPid` may still need
+    /// to read its `exit_code` long after the frames are gone: if a waiter
+    /// was already parked, it's collecting the status right here, so the
+    /// TCB is dropped immediately; otherwise it's left `Terminated` for
+    /// `WaitPid`'s already-exited branch to collect and drop later.
+    pub fn exit_current_thread(&mut self, code: i32) -> Option<u32> {
         info!("ThreadManager: Exiting thread {:?}", self.current_thread);
-        if let Some(current) = self.current_thread {
-            // Find anyone waiting on 'current'
-            let mut to_wake = Vec::new();
-            for (handle, tcb) in self.threads.iter() {
-                if let ThreadState::Waiting { target } = tcb.state {
-                    if target == current {
-                        to_wake.push(*handle);
-                    }
-                }
-            }
+        let Some(current) = self.current_thread else {
+            return None;
+        };
 
-            // Wake them up
-            for h in to_wake {
-                if let Some(tcb) = self.threads.get_mut(&h) {
-                    tcb.state = ThreadState::Ready;
-                    // Pass exit code to waiter's A0 (register 10)
-                    tcb.context.regs[10] = code as u32;
-                    self.scheduler.enqueue(h);
+        // Find anyone waiting on 'current'
+        let mut to_wake = Vec::new();
+        for (handle, tcb) in self.threads.iter() {
+            if let ThreadState::Waiting { target } = tcb.state {
+                if target == current {
+                    to_wake.push(*handle);
                 }
             }
+        }
+        let reaped_by_waiter = !to_wake.is_empty();
 
-            if let Some(tcb) = self.threads.get_mut(&current) {
-                tcb.state = ThreadState::Terminated { exit_code: code };
+        // Wake them up
+        for h in to_wake {
+            if let Some(tcb) = self.threads.get_mut(&h) {
+                tcb.state = ThreadState::Ready;
+                // Pass exit code to waiter's A0 (register 10)
+                tcb.context.regs[10] = code as u32;
+                self.scheduler.enqueue(h);
             }
-            self.current_thread = None;
-            // Schedule next immediately handled by caller or next trap
         }
+
+        let satp = self.threads.get(&current).map(|tcb| tcb.context.satp);
+
+        if let Some(tcb) = self.threads.get_mut(&current) {
+            tcb.state = ThreadState::Terminated { exit_code: code };
+        }
+        if reaped_by_waiter {
+            self.threads.remove(&current);
+        }
+        self.current_thread = None;
+        // Schedule next immediately handled by caller or next trap
+
+        let root_ppn = satp.map(|satp| satp & 0x003F_FFFF)?;
+        let still_shared = self.threads.values().any(|tcb| {
+            tcb.context.satp & 0x003F_FFFF == root_ppn
+                && !matches!(tcb.state, ThreadState::Terminated { .. })
+        });
+        (!still_shared).then_some(root_ppn)
     }
 
     pub fn block_current_thread(&mut self) {
@@ -206,28 +403,50 @@ impl ThreadManager {
         }
     }
 
-    pub fn wait_current_thread(&mut self, target: ThreadHandle) -> Result<Option<i32>, String> {
-        // If target doesn't exist or is already terminated, return exit code if possible
-        if let Some(target_tcb) = self.threads.get(&target) {
-            if let ThreadState::Terminated { exit_code } = target_tcb.state {
-                return Ok(Some(exit_code));
-            }
-        } else {
-            // Target not found.
-            return Err("Target thread not found".into());
+    /// Block the current thread and schedule it to be woken (via
+    /// `wake_thread`, the same path `Mutex`/`Condvar` use) once at least
+    /// `ticks` timer interrupts have elapsed. `ticks == 0` still waits for
+    /// the next tick rather than returning immediately, since a sleep of
+    /// "zero more ticks" isn't meaningfully different from "one".
+    pub fn sleep_current_thread(&mut self, ticks: u64) {
+        if let Some(current) = self.current_thread {
+            self.timers
+                .push(Reverse((self.ticks + ticks.max(1), current, TimerWake::Sleep)));
+            self.block_current_thread();
         }
+    }
 
-        if let Some(current) = self.current_thread {
-            if current == target {
-                return Err("Cannot wait on self".into());
-            }
-            if let Some(tcb) = self.threads.get_mut(&current) {
-                tcb.state = ThreadState::Waiting { target };
-            }
-            Ok(None)
-        } else {
-            Err("No current thread".into())
+    pub fn wait_current_thread(
+        &mut self,
+        target: ThreadHandle,
+        timeout_ticks: Option<u64>,
+    ) -> Result<Option<i32>, String> {
+        let current = self.current_thread.ok_or("No current thread")?;
+        if current == target {
+            return Err("Cannot wait on self".into());
+        }
+
+        let target_tcb = self.threads.get(&target).ok_or("Target thread not found")?;
+        // A real waitpid(2) only ever returns a child's status; refuse to
+        // block on a pid this thread didn't create via `Fork`/`Exec`.
+        if target_tcb.parent != Some(current) {
+            return Err("Target is not a child of the calling thread".into());
+        }
+        if let ThreadState::Terminated { exit_code } = target_tcb.state {
+            return Ok(Some(exit_code));
+        }
+
+        if let Some(tcb) = self.threads.get_mut(&current) {
+            tcb.state = ThreadState::Waiting { target };
         }
+        if let Some(timeout) = timeout_ticks {
+            self.timers.push(Reverse((
+                self.ticks + timeout.max(1),
+                current,
+                TimerWake::WaitPidTimeout,
+            )));
+        }
+        Ok(None)
     }
 
     pub fn wake_thread(&mut self, handle: ThreadHandle) {
@@ -238,4 +457,50 @@ impl ThreadManager {
             }
         }
     }
+
+    /// Like `wake_thread`, but for a thread parked in `ThreadState::Waiting`
+    /// (blocked in `WaitPid`, not `MutexAcquire`/`EndpointRecv`/etc) --
+    /// `wake_thread` only matches `Blocked`, so a signal raised against a
+    /// thread sitting in `WaitPid` would otherwise never reach it until its
+    /// child actually exits.
+    pub fn wake_waiting_thread(&mut self, handle: ThreadHandle) {
+        if let Some(tcb) = self.threads.get_mut(&handle) {
+            if matches!(tcb.state, ThreadState::Waiting { .. }) {
+                tcb.state = ThreadState::Ready;
+                self.scheduler.enqueue(handle);
+            }
+        }
+    }
+
+    /// Wake `handle` from a `WaitPid` that timed out, writing
+    /// `syscall::WAITPID_TIMED_OUT` into its `a0` the same way
+    /// `exit_current_thread` writes a real exit code there for a normal
+    /// wake. A no-op if `handle` isn't still `Waiting` -- it may have
+    /// already been woken for real by its child exiting first, in which
+    /// case this timer-queue entry is simply stale.
+    fn wake_waitpid_timeout(&mut self, handle: ThreadHandle) {
+        if let Some(tcb) = self.threads.get_mut(&handle) {
+            if matches!(tcb.state, ThreadState::Waiting { .. }) {
+                tcb.state = ThreadState::Ready;
+                tcb.context.regs[10] = crate::syscall::WAITPID_TIMED_OUT as u32;
+                self.scheduler.enqueue(handle);
+            }
+        }
+    }
+
+    /// Pop every timer-queue entry whose wakeup tick has passed and wake
+    /// the thread it names. Called once per timer interrupt, right after
+    /// `self.ticks` advances.
+    fn wake_due_timers(&mut self) {
+        while let Some(&Reverse((tick, handle, kind))) = self.timers.peek() {
+            if tick > self.ticks {
+                break;
+            }
+            self.timers.pop();
+            match kind {
+                TimerWake::Sleep => self.wake_thread(handle),
+                TimerWake::WaitPidTimeout => self.wake_waitpid_timeout(handle),
+            }
+        }
+    }
 }
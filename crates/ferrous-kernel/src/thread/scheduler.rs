@@ -1,5 +1,9 @@
 use crate::types::ThreadHandle;
-use alloc::collections::VecDeque;
+use alloc::collections::{BTreeMap, VecDeque};
+
+/// Default number of timer ticks a thread gets to run before its quantum
+/// is considered expired, for schedulers that don't vary it by thread.
+const DEFAULT_QUANTUM_TICKS: u32 = 5;
 
 pub trait Scheduler: Send {
     /// Select next thread to run
@@ -11,12 +15,61 @@ pub trait Scheduler: Send {
     /// Remove thread from ready queue (e.g. if blocked or terminated)
     fn dequeue(&mut self, thread: ThreadHandle) -> bool;
 
-    /// Called on timer tick
-    fn tick(&mut self);
+    /// Timer ticks granted to `thread` the next time it's dispatched.
+    /// `ThreadManager` copies this into `ThreadControlBlock::quantum_remaining`
+    /// and counts it down once per timer interrupt. The default grants every
+    /// thread the same fixed quantum; a priority-feedback scheduler overrides
+    /// it to vary the quantum by queue level.
+    fn quantum_for(&self, thread: ThreadHandle) -> u32 {
+        let _ = thread;
+        DEFAULT_QUANTUM_TICKS
+    }
+
+    /// Re-queue a thread that ran until its quantum hit zero. The default
+    /// just re-enqueues it unchanged; a priority-feedback scheduler demotes
+    /// it to a lower-priority, longer-quantum queue.
+    fn requeue_expired(&mut self, thread: ThreadHandle) {
+        self.enqueue(thread);
+    }
+
+    /// Re-queue a thread that yielded or blocked before exhausting its
+    /// quantum. The default just re-enqueues it unchanged; a
+    /// priority-feedback scheduler promotes it back toward the
+    /// highest-priority queue to keep interactive threads responsive.
+    fn requeue_early(&mut self, thread: ThreadHandle) {
+        self.enqueue(thread);
+    }
+
+    /// `thread`'s current scheduling priority, lower being more urgent --
+    /// an `MlfqScheduler` queue level, or just `0` for any scheduler with
+    /// no notion of priority. Consulted by `sync::Mutex`'s priority
+    /// inheritance: a thread that's never been scheduled yet reads as `0`,
+    /// same as `level_of`'s own default.
+    fn priority_level(&self, thread: ThreadHandle) -> usize {
+        let _ = thread;
+        0
+    }
+
+    /// Force `thread`'s scheduling priority to `level`, moving it between
+    /// ready queues immediately if it's currently sitting in one. The
+    /// default is a no-op, since a scheduler with no notion of priority has
+    /// nothing to move. Used by `sync::Mutex`'s priority-inheritance boost
+    /// (raising a lock owner's priority to match a blocked waiter's) and by
+    /// its matching restore on release.
+    fn set_priority_level(&mut self, thread: ThreadHandle, level: usize) {
+        let _ = (thread, level);
+    }
+
+    /// Called once per timer tick regardless of which thread is running or
+    /// whether this tick preempted it, so a priority-feedback scheduler can
+    /// track elapsed time toward a periodic anti-starvation boost. The
+    /// default is a no-op; only `MlfqScheduler` overrides it.
+    fn on_tick(&mut self) {}
 }
 
 pub struct RoundRobinScheduler {
     ready_queue: VecDeque<ThreadHandle>,
+    quantum: u32,
 }
 
 impl Default for RoundRobinScheduler {
@@ -27,8 +80,15 @@ impl Default for RoundRobinScheduler {
 
 impl RoundRobinScheduler {
     pub fn new() -> Self {
+        Self::with_quantum(DEFAULT_QUANTUM_TICKS)
+    }
+
+    /// Build a scheduler that preempts the running thread every `quantum`
+    /// timer ticks instead of the default.
+    pub fn with_quantum(quantum: u32) -> Self {
         Self {
             ready_queue: VecDeque::new(),
+            quantum,
         }
     }
 }
@@ -52,8 +112,134 @@ impl Scheduler for RoundRobinScheduler {
         }
     }
 
-    fn tick(&mut self) {
-        // Round robin usually rotates on tick if time slice expired
-        // For now simple FIFO until we add preemption logic
+    fn quantum_for(&self, _thread: ThreadHandle) -> u32 {
+        self.quantum
+    }
+}
+
+/// Number of priority queues `MlfqScheduler` keeps, 0 being highest
+/// priority (shortest quantum) and `LEVEL_QUANTA.len() - 1` lowest
+/// (longest quantum).
+const LEVEL_QUANTA: [u32; 3] = [4, 8, 16];
+
+/// How many timer ticks pass between `MlfqScheduler`'s anti-starvation
+/// boosts, which move every thread back to level 0 so a run of CPU-bound
+/// threads can't permanently bury an interactive one that got demoted
+/// earlier.
+const BOOST_INTERVAL_TICKS: u32 = 100;
+
+/// Multilevel feedback queue: every thread starts in the highest-priority
+/// queue with a short quantum. A thread that runs out its full quantum is
+/// demoted one level (longer quantum, scheduled later); a thread that
+/// yields or blocks on its own before that happens is promoted back
+/// toward the top so interactive threads don't get stuck behind CPU-bound
+/// ones. `schedule` always drains the highest non-empty queue first.
+pub struct MlfqScheduler {
+    queues: [VecDeque<ThreadHandle>; LEVEL_QUANTA.len()],
+    levels: BTreeMap<ThreadHandle, usize>,
+    /// Ticks elapsed since the last anti-starvation boost; reset to 0 every
+    /// time it reaches `boost_interval_ticks`.
+    ticks_since_boost: u32,
+    /// Ticks between anti-starvation boosts, `BOOST_INTERVAL_TICKS` unless
+    /// overridden via `with_boost_interval`.
+    boost_interval_ticks: u32,
+}
+
+impl Default for MlfqScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MlfqScheduler {
+    pub fn new() -> Self {
+        Self::with_boost_interval(BOOST_INTERVAL_TICKS)
+    }
+
+    /// Build an `MlfqScheduler` that boosts every `boost_interval_ticks`
+    /// timer ticks instead of the default, the same override `RoundRobinScheduler::with_quantum`
+    /// offers for its own fixed constant.
+    pub fn with_boost_interval(boost_interval_ticks: u32) -> Self {
+        Self {
+            queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            levels: BTreeMap::new(),
+            ticks_since_boost: 0,
+            boost_interval_ticks,
+        }
+    }
+
+    fn level_of(&self, thread: ThreadHandle) -> usize {
+        self.levels.get(&thread).copied().unwrap_or(0)
+    }
+}
+
+impl Scheduler for MlfqScheduler {
+    fn schedule(&mut self) -> Option<ThreadHandle> {
+        self.queues.iter_mut().find_map(|q| q.pop_front())
+    }
+
+    fn enqueue(&mut self, thread: ThreadHandle) {
+        let level = self.level_of(thread);
+        self.levels.insert(thread, level);
+        self.queues[level].push_back(thread);
+    }
+
+    fn dequeue(&mut self, thread: ThreadHandle) -> bool {
+        for q in self.queues.iter_mut() {
+            if let Some(pos) = q.iter().position(|&h| h == thread) {
+                q.remove(pos);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn quantum_for(&self, thread: ThreadHandle) -> u32 {
+        LEVEL_QUANTA[self.level_of(thread)]
+    }
+
+    fn requeue_expired(&mut self, thread: ThreadHandle) {
+        let level = (self.level_of(thread) + 1).min(LEVEL_QUANTA.len() - 1);
+        self.levels.insert(thread, level);
+        self.queues[level].push_back(thread);
+    }
+
+    fn requeue_early(&mut self, thread: ThreadHandle) {
+        let level = self.level_of(thread).saturating_sub(1);
+        self.levels.insert(thread, level);
+        self.queues[level].push_back(thread);
+    }
+
+    fn priority_level(&self, thread: ThreadHandle) -> usize {
+        self.level_of(thread)
+    }
+
+    fn set_priority_level(&mut self, thread: ThreadHandle, level: usize) {
+        let level = level.min(LEVEL_QUANTA.len() - 1);
+        let old = self.level_of(thread);
+        if old != level {
+            if let Some(pos) = self.queues[old].iter().position(|&h| h == thread) {
+                self.queues[old].remove(pos);
+                self.queues[level].push_back(thread);
+            }
+        }
+        self.levels.insert(thread, level);
+    }
+
+    fn on_tick(&mut self) {
+        self.ticks_since_boost += 1;
+        if self.ticks_since_boost < self.boost_interval_ticks {
+            return;
+        }
+        self.ticks_since_boost = 0;
+
+        for level in 1..self.queues.len() {
+            while let Some(thread) = self.queues[level].pop_front() {
+                self.queues[0].push_back(thread);
+            }
+        }
+        for level in self.levels.values_mut() {
+            *level = 0;
+        }
     }
 }
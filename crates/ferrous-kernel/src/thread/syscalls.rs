@@ -34,6 +34,15 @@ pub fn handle_syscall(
             thread_manager.yield_thread(cpu);
             Ok(VirtAddr::new(cpu.pc))
         }
+        Syscall::ThreadStats { handle } => {
+            let ticks = crate::types::ThreadHandle::new(handle)
+                .and_then(|h| thread_manager.threads.get(&h))
+                .map(|tcb| tcb.cpu_ticks_total)
+                .unwrap_or(0);
+            debug!("ThreadStats: handle={}, cpu_ticks_total={}", handle, ticks);
+            Syscall::encode_result(Ok(SyscallReturn::Value(ticks as i64)), cpu);
+            Ok(VirtAddr::new(cpu.pc + 4))
+        }
         Syscall::Exit { code } => {
             info!("Thread/Process Exit: {}", code);
             thread_manager.exit_current_thread(code);
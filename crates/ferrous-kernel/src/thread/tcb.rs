@@ -1,3 +1,4 @@
+use crate::capability::CapabilityTable;
 use crate::types::ThreadHandle;
 use ferrous_vm::{Cpu, PrivilegeMode, VirtAddr};
 
@@ -10,20 +11,158 @@ pub enum ThreadState {
     Terminated { exit_code: i32 },
 }
 
-pub struct FileDescriptor {
-    pub inode_id: u32,
-    pub offset: u32,
+/// A single anonymous mapping handed out by `Syscall::Mmap`, tracked so
+/// `Munmap` can unmap exactly the pages it covers and so a future `Mmap`
+/// with an explicit hint can be rejected if it would overlap one.
+#[derive(Debug, Clone, Copy)]
+pub struct Vma {
+    pub base: u32,
+    pub len: u32,
     pub flags: u32,
 }
 
+impl Vma {
+    pub fn end(&self) -> u32 {
+        self.base + self.len
+    }
+
+    pub fn overlaps(&self, base: u32, len: u32) -> bool {
+        base < self.end() && self.base < base + len
+    }
+}
+
+/// `Syscall::FileOpen`'s `flags` bitmask. Close enough to POSIX's
+/// `O_RDONLY`/`O_WRONLY`/`O_APPEND` for `FileWrite` to enforce that a
+/// descriptor was actually opened for writing, and for append mode to seek
+/// to end-of-file before every write instead of wherever the descriptor's
+/// offset happens to be.
+pub const O_RDONLY: u32 = 0;
+pub const O_WRONLY: u32 = 1 << 0;
+pub const O_APPEND: u32 = 1 << 1;
+
+/// How many descriptors a single thread may hold open at once. `FileOpen`
+/// and `Dup` both fail once a thread is at this limit, the same bounded
+/// FD-table idea MOROS's `FileDescriptorTable` enforces.
+pub const MAX_FILE_DESCRIPTORS: usize = 64;
+
+/// Deliberately has no `Socket` variant: `Syscall::Socket`/`Bind`/`Connect`/
+/// `Listen`/`Accept` address a socket by the id `net::socket::SOCKETS` hands
+/// out directly, a separate namespace from this table entirely, rather than
+/// installing a slot here the way `FileOpen` does -- there's no POSIX-style
+/// unification of file and socket descriptors in this kernel.
+#[derive(Debug, Clone, Copy)]
+pub enum FileDescriptor {
+    /// A file opened from the mkfs-built disk image.
+    Disk { inode_id: u32, offset: u32, flags: u32 },
+    /// A file opened from a `--share`d host directory via the 9P
+    /// passthrough transport, keyed by its fid there.
+    Host { fid: u32, offset: u64, flags: u32 },
+    /// Opened against the `console:` scheme: `FileRead`/`FileWrite` drive
+    /// the same UART MMIO `ConsoleRead`/`ConsoleWrite` already use, just
+    /// reachable through the generic file-descriptor syscalls too.
+    Console,
+    /// Opened against the `null:` scheme: every write reports all bytes
+    /// accepted (and discarded), every read reports EOF.
+    Null,
+    /// Opened against the `pipe:<name>` scheme, `id` keying the
+    /// `Kernel::pipes` ring buffer every descriptor opened against the
+    /// same name shares.
+    Pipe { id: u32 },
+    /// Opened against the `rand:` scheme: every read fills the caller's
+    /// buffer from `Kernel`'s xorshift generator, every write reports all
+    /// bytes accepted (and discarded), the same as a real `/dev/urandom`
+    /// permits entropy-mixing writes without requiring them.
+    Rand,
+}
+
 pub struct ThreadControlBlock {
     pub handle: ThreadHandle,
     pub state: ThreadState,
     pub context: SavedContext,
     pub stack_pointer: u32,
-    pub kernel_stack: u32, // For kernel stack if needed
+    /// Always 0: a per-thread kernel stack (and the trampoline page a real
+    /// Sv32 kernel maps alongside it so a trap handler's own code stays
+    /// mapped across the SATP switch into it) only matters when the trap
+    /// handler executes as guest RISC-V instructions under the address
+    /// space it's switching into. `Kernel::handle_trap` runs as host Rust
+    /// against `dyn Memory` instead -- see `create_user_address_space`'s
+    /// comment on why kernel code isn't mapped into user space at all --
+    /// so there's no guest-side stack or trampoline for this field to hold
+    /// the address of. Kept rather than removed; every constructor in
+    /// `thread` still sets it to 0.
+    pub kernel_stack: u32,
+    /// The thread whose `Exec`/`Fork` syscall created this one, or `None`
+    /// for the bootstrap main thread. `Syscall::WaitPid` uses this to
+    /// refuse a wait on a pid that isn't the caller's own child, the same
+    /// restriction a real `waitpid(2)` enforces.
+    pub parent: Option<ThreadHandle>,
     pub program_break: u32,
+    /// Next address `mmap` hands out for an unhinted (`addr == 0`)
+    /// anonymous mapping, bumped upward from `memory::MMAP_BASE` the same
+    /// way `program_break` bumps the heap.
+    pub mmap_top: u32,
+    /// Every anonymous mapping this thread currently holds, in no
+    /// particular order. Consulted by `Mmap` to reject an explicit-address
+    /// request that would overlap one, and by `Munmap` to drop (or shrink)
+    /// the entries the unmapped range covers.
+    pub mappings: Vec<Vma>,
+    /// The `PT_LOAD` segments of the ELF image this process was started
+    /// from, recorded by `bootstrap_process`/`Exec` so a page fault on a
+    /// still-lazy code/data page can be traced back to the segment that
+    /// owns it. Empty for a thread spawned by `ThreadCreate` (it shares its
+    /// parent's address space rather than owning its own image).
+    pub segments: Vec<crate::memory::Segment>,
+    /// The raw bytes of the ELF image `segments` was built from, kept
+    /// around so a segment page fault can copy its file-backed bytes in
+    /// on demand instead of `bootstrap_process`/`Exec` having to copy the
+    /// whole image up front.
+    pub image: Vec<u8>,
     pub file_descriptors: Vec<Option<FileDescriptor>>,
+    pub capabilities: CapabilityTable,
+    /// Timer ticks left before this thread's current quantum is exhausted.
+    /// Counted down in `ThreadManager::on_timer_tick`, reloaded from
+    /// `Scheduler::quantum_for` each time the thread is dispatched.
+    pub quantum_remaining: u32,
+    /// Total timer ticks this thread has spent running, across its whole
+    /// lifetime. Never reset; exposed via `Syscall::ThreadStats` so a shell
+    /// can report per-thread CPU usage.
+    pub cpu_ticks_total: u64,
+    /// Ids of mutexes this thread currently owns (acquired via
+    /// `MutexAcquire`, not yet released). Consulted on `MutexRelease` to
+    /// recompute the priority it should still be boosted to from any
+    /// *other* contended lock it keeps holding, rather than unconditionally
+    /// dropping back to `base_priority`.
+    pub held_mutexes: Vec<u32>,
+    /// This thread's scheduler priority level before a priority-inheritance
+    /// boost raised it, or `None` if it isn't currently boosted. Set the
+    /// first time `Mutex` priority inheritance raises its effective
+    /// priority above this; restored (and cleared, once no held mutex
+    /// still needs it) by the matching `MutexRelease`.
+    pub base_priority: Option<usize>,
+    /// This thread's current scheduler priority level, after whatever
+    /// boost `sync::Mutex` priority inheritance has applied. Equal to
+    /// `base_priority` (or the scheduler's natural level, if `base_priority`
+    /// is `None`) when the thread isn't boosted.
+    pub effective_priority: usize,
+    /// Bitmask of signals `Syscall::Kill` has raised against this thread
+    /// but that haven't been delivered yet, bit `signum` per signal
+    /// (signal 0 is never set, matching POSIX's reserved signal 0).
+    /// Drained one bit at a time by `Kernel::deliver_pending_signal` on
+    /// the way back to user mode.
+    pub pending_signals: u32,
+    /// Bitmask of signals masked out from delivery, same bit layout as
+    /// `pending_signals`; a signal raised while its bit is set here stays
+    /// pending instead of being delivered.
+    pub blocked_signals: u32,
+    /// `Syscall::Sigaction`-registered handler entry point per signal
+    /// number, `0` meaning "no handler registered" -- the default
+    /// disposition (terminate the thread) applies instead.
+    pub signal_handlers: [u32; 32],
+    /// The register context interrupted to dispatch the handler currently
+    /// running, restored by `Syscall::Sigreturn`. `None` when no handler
+    /// is running, which also guards against delivering a second signal
+    /// before the first handler returns.
+    pub signal_saved_context: Option<SavedContext>,
 }
 
 #[derive(Debug, Clone, Copy)]
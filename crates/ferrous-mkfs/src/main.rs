@@ -1,7 +1,7 @@
 use clap::Parser;
 use ferrous_fs::{DirEntry, FileType, Inode, SuperBlock, BLOCK_SIZE, INODE_DIRECT_POINTERS, MAGIC};
 use std::fs::OpenOptions;
-use std::io::{Seek, SeekFrom, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -18,6 +18,278 @@ struct Cli {
     /// Force overwrite
     #[arg(short, long)]
     force: bool,
+
+    /// Host file to embed into the image's root directory, under its own
+    /// file name. May be given more than once.
+    #[arg(short, long = "add")]
+    add: Vec<PathBuf>,
+
+    /// Repack the finished image into CISO-style sparse form: a header and
+    /// per-block index table followed only by the blocks that aren't all
+    /// zero, instead of the dense `total_blocks * BLOCK_SIZE`-byte file.
+    /// Mostly-empty student disks shrink a lot; `SimpleBlockDevice` detects
+    /// the header and decodes it transparently, so nothing downstream
+    /// needs to know which form an image is in.
+    #[arg(short, long)]
+    sparse: bool,
+}
+
+/// Marks a `--sparse` image for `SimpleBlockDevice::new` to recognize: the
+/// ASCII bytes `"CISO"` read as a little-endian `u32`.
+const CISO_MAGIC: u32 = 0x4F53_4943;
+
+/// `magic + header_size + block_size + total_blocks`, each a little-endian
+/// `u32` -- the index table starts immediately after.
+const CISO_HEADER_LEN: u32 = 16;
+
+/// An index-table entry meaning "this block is all zero and was omitted".
+const CISO_ZERO_SENTINEL: u32 = 0xFFFF_FFFF;
+
+/// Repack the dense image at `path` (already fully formatted) into
+/// CISO-style sparse form in place: every all-zero block is dropped from
+/// the file entirely and recorded as `CISO_ZERO_SENTINEL` in the index
+/// table, while every other block keeps its contents but moves to a
+/// packed position in the payload area.
+fn write_sparse(path: &PathBuf) -> std::io::Result<()> {
+    let dense = std::fs::read(path)?;
+    let total_blocks = (dense.len() / BLOCK_SIZE) as u32;
+
+    let mut entries = vec![CISO_ZERO_SENTINEL; total_blocks as usize];
+    let mut payload = Vec::new();
+    let mut next_entry = 0u32;
+    for block_index in 0..total_blocks as usize {
+        let start = block_index * BLOCK_SIZE;
+        let block = &dense[start..start + BLOCK_SIZE];
+        if block.iter().any(|&b| b != 0) {
+            entries[block_index] = next_entry;
+            payload.extend_from_slice(block);
+            next_entry += 1;
+        }
+    }
+
+    let mut out = std::fs::File::create(path)?;
+    out.write_all(&CISO_MAGIC.to_le_bytes())?;
+    out.write_all(&CISO_HEADER_LEN.to_le_bytes())?;
+    out.write_all(&(BLOCK_SIZE as u32).to_le_bytes())?;
+    out.write_all(&total_blocks.to_le_bytes())?;
+    for entry in &entries {
+        out.write_all(&entry.to_le_bytes())?;
+    }
+    out.write_all(&payload)?;
+
+    println!(
+        "Sparse: {} of {} blocks stored ({} bytes, was {} bytes)",
+        next_entry,
+        total_blocks,
+        CISO_HEADER_LEN as usize + entries.len() * 4 + payload.len(),
+        dense.len()
+    );
+    Ok(())
+}
+
+/// Pointers per indirect block: each slot is a little-endian `u32`, so a
+/// `BLOCK_SIZE`-byte block holds `BLOCK_SIZE / 4` of them -- the same
+/// constant `ferrous_kernel::fs`'s `read_data`/`write_data` compute from
+/// `BLOCK_SIZE` to walk the matching tree back apart.
+const POINTERS_PER_BLOCK: u32 = (BLOCK_SIZE / 4) as u32;
+
+/// Tracks the on-disk allocator state (data and inode bitmaps, free
+/// counts) while files are embedded into a freshly formatted image, and
+/// owns the ext2-style direct/indirect/double-indirect/triple-indirect
+/// tree-building so both a regular file's contents and the root
+/// directory's own entries (just another file's worth of bytes) go
+/// through the same block-allocation path.
+struct Formatter {
+    data_bitmap: [u8; BLOCK_SIZE],
+    inode_bitmap: [u8; BLOCK_SIZE],
+    data_bitmap_block: u32,
+    inode_bitmap_block: u32,
+    data_blocks_start: u32,
+    inode_table_start: u32,
+    inodes_per_block: u32,
+    free_blocks: u32,
+    free_inodes: u32,
+}
+
+impl Formatter {
+    fn read_block(file: &mut std::fs::File, block_id: u32) -> std::io::Result<[u8; BLOCK_SIZE]> {
+        let mut buf = [0u8; BLOCK_SIZE];
+        file.seek(SeekFrom::Start((block_id as u64) * BLOCK_SIZE as u64))?;
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn write_block(
+        file: &mut std::fs::File,
+        block_id: u32,
+        buf: &[u8; BLOCK_SIZE],
+    ) -> std::io::Result<()> {
+        file.seek(SeekFrom::Start((block_id as u64) * BLOCK_SIZE as u64))?;
+        file.write_all(buf)
+    }
+
+    /// Allocate a free data block: scan `data_bitmap` for the first clear
+    /// bit (byte-at-a-time, same skip-full-bytes shape
+    /// `ferrous_kernel::fs::FileSystem::alloc_block` uses), flip it, zero
+    /// the block on disk, and decrement `free_blocks`.
+    fn alloc_block(&mut self, file: &mut std::fs::File) -> std::io::Result<u32> {
+        let free_bit = self
+            .data_bitmap
+            .iter()
+            .enumerate()
+            .find(|(_, &byte)| byte != 0xFF)
+            .and_then(|(byte_index, &byte)| {
+                (0..8u32)
+                    .find(|bit| byte & (1 << bit) == 0)
+                    .map(|bit| byte_index * 8 + bit as usize)
+            })
+            .expect("disk image ran out of free data blocks");
+
+        self.data_bitmap[free_bit / 8] |= 1 << (free_bit % 8);
+        self.free_blocks -= 1;
+
+        let block_id = self.data_blocks_start + free_bit as u32;
+        Self::write_block(file, block_id, &[0u8; BLOCK_SIZE])?;
+        Ok(block_id)
+    }
+
+    /// Allocate a free inode id the same way `alloc_block` allocates a
+    /// data block, over `inode_bitmap` instead, and persist a fresh
+    /// `Inode::new` into its table slot.
+    fn alloc_inode(
+        &mut self,
+        file: &mut std::fs::File,
+        file_type: FileType,
+    ) -> std::io::Result<Inode> {
+        let free_bit = self
+            .inode_bitmap
+            .iter()
+            .enumerate()
+            .find(|(_, &byte)| byte != 0xFF)
+            .and_then(|(byte_index, &byte)| {
+                (0..8u32)
+                    .find(|bit| byte & (1 << bit) == 0)
+                    .map(|bit| byte_index * 8 + bit as usize)
+            })
+            .expect("disk image ran out of free inodes");
+
+        self.inode_bitmap[free_bit / 8] |= 1 << (free_bit % 8);
+        self.free_inodes -= 1;
+
+        let inode = Inode::new(free_bit as u32, file_type);
+        self.write_inode(file, &inode)?;
+        Ok(inode)
+    }
+
+    /// Persist `inode` into its slot of the on-disk inode table,
+    /// read-modify-write since several inodes share a block.
+    fn write_inode(&self, file: &mut std::fs::File, inode: &Inode) -> std::io::Result<()> {
+        let inode_size = std::mem::size_of::<Inode>() as u32;
+        let block_offset = inode.id / self.inodes_per_block;
+        let index_in_block = inode.id % self.inodes_per_block;
+        let block_id = self.inode_table_start + block_offset;
+
+        let mut buf = Self::read_block(file, block_id)?;
+        let config = bincode::config::standard().with_fixed_int_encoding();
+        let start = (index_in_block * inode_size) as usize;
+        bincode::serde::encode_into_slice(inode, &mut buf[start..start + inode_size as usize], config)
+            .unwrap();
+        Self::write_block(file, block_id, &buf)
+    }
+
+    /// Return the `index`th little-endian `u32` pointer stored in
+    /// indirect block `parent_block`, allocating a fresh block and
+    /// writing it into that slot first if it's still a hole -- the
+    /// host-side mirror of `ferrous_kernel::fs::FileSystem::ensure_pointer`.
+    fn ensure_pointer(
+        &mut self,
+        file: &mut std::fs::File,
+        parent_block: u32,
+        index: u32,
+    ) -> std::io::Result<u32> {
+        let mut buf = Self::read_block(file, parent_block)?;
+        let offset = (index * 4) as usize;
+        let existing = u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap());
+        if existing != 0 {
+            return Ok(existing);
+        }
+
+        let new_block = self.alloc_block(file)?;
+        buf[offset..offset + 4].copy_from_slice(&new_block.to_le_bytes());
+        Self::write_block(file, parent_block, &buf)?;
+        Ok(new_block)
+    }
+
+    /// Write the whole of `data` into `inode`, building the full
+    /// direct/single-indirect/double-indirect/triple-indirect pointer
+    /// tree as needed -- direct pointers cover the first
+    /// `INODE_DIRECT_POINTERS` blocks, the single-indirect block the next
+    /// `POINTERS_PER_BLOCK`, the double-indirect block
+    /// `POINTERS_PER_BLOCK` single-indirect blocks after that, and the
+    /// triple-indirect block `POINTERS_PER_BLOCK` double-indirect blocks
+    /// beyond that -- the same tiering
+    /// `ferrous_kernel::fs::FileSystem::write_data` walks to read it back
+    /// apart. `inode` is assumed freshly allocated (all pointers zero),
+    /// so every block here is a brand new allocation rather than
+    /// overwriting existing data in place.
+    fn write_file_data(
+        &mut self,
+        file: &mut std::fs::File,
+        inode: &mut Inode,
+        data: &[u8],
+    ) -> std::io::Result<()> {
+        let single_indirect_cap = INODE_DIRECT_POINTERS as u32 + POINTERS_PER_BLOCK;
+        let double_indirect_cap = single_indirect_cap + POINTERS_PER_BLOCK * POINTERS_PER_BLOCK;
+
+        let total_blocks = (data.len() as u32).div_ceil(BLOCK_SIZE as u32);
+        for block_index in 0..total_blocks {
+            let block_id = if (block_index as usize) < INODE_DIRECT_POINTERS {
+                let new_block = self.alloc_block(file)?;
+                inode.direct_ptrs[block_index as usize] = new_block;
+                new_block
+            } else if block_index < single_indirect_cap {
+                if inode.indirect_ptr == 0 {
+                    inode.indirect_ptr = self.alloc_block(file)?;
+                }
+                let indirect_index = block_index - INODE_DIRECT_POINTERS as u32;
+                self.ensure_pointer(file, inode.indirect_ptr, indirect_index)?
+            } else if block_index < double_indirect_cap {
+                if inode.double_indirect_ptr == 0 {
+                    inode.double_indirect_ptr = self.alloc_block(file)?;
+                }
+                let indirect_index = block_index - single_indirect_cap;
+                let outer_index = indirect_index / POINTERS_PER_BLOCK;
+                let inner_index = indirect_index % POINTERS_PER_BLOCK;
+
+                let single_indirect_block =
+                    self.ensure_pointer(file, inode.double_indirect_ptr, outer_index)?;
+                self.ensure_pointer(file, single_indirect_block, inner_index)?
+            } else {
+                if inode.triple_indirect_ptr == 0 {
+                    inode.triple_indirect_ptr = self.alloc_block(file)?;
+                }
+                let indirect_index = block_index - double_indirect_cap;
+                let outer_index = indirect_index / (POINTERS_PER_BLOCK * POINTERS_PER_BLOCK);
+                let middle_index = (indirect_index / POINTERS_PER_BLOCK) % POINTERS_PER_BLOCK;
+                let inner_index = indirect_index % POINTERS_PER_BLOCK;
+
+                let double_indirect_block =
+                    self.ensure_pointer(file, inode.triple_indirect_ptr, outer_index)?;
+                let single_indirect_block =
+                    self.ensure_pointer(file, double_indirect_block, middle_index)?;
+                self.ensure_pointer(file, single_indirect_block, inner_index)?
+            };
+
+            let start = block_index as usize * BLOCK_SIZE;
+            let end = (start + BLOCK_SIZE).min(data.len());
+            let mut block_buf = [0u8; BLOCK_SIZE];
+            block_buf[..end - start].copy_from_slice(&data[start..end]);
+            Self::write_block(file, block_id, &block_buf)?;
+        }
+
+        inode.size = data.len() as u32;
+        self.write_inode(file, inode)
+    }
 }
 
 fn main() -> std::io::Result<()> {
@@ -64,9 +336,9 @@ fn main() -> std::io::Result<()> {
     let data_bitmap_block = 2;
     let inode_table_start = 3;
     let data_blocks_start = inode_table_start + inode_table_blocks;
-    let free_blocks = total_blocks - data_blocks_start;
+    let mut free_blocks = total_blocks - data_blocks_start;
 
-    let sb = SuperBlock {
+    let mut sb = SuperBlock {
         magic: MAGIC,
         total_blocks,
         inode_bitmap_block,
@@ -105,7 +377,7 @@ fn main() -> std::io::Result<()> {
 
     // 4. Init Inode Table
     // Create Root Inode (Inode 0) - Directory
-    let root_inode = Inode::new(0, FileType::Directory);
+    let mut root_inode = Inode::new(0, FileType::Directory);
 
     // We need to write root inode to the first slot of inode table
     file.seek(SeekFrom::Start(
@@ -122,6 +394,85 @@ fn main() -> std::io::Result<()> {
     // 5. Zero out data area (optional, slow for large disks)
     // println!("Zeroing data area...");
 
+    // 6. Embed any `--add`ed host files into the root directory, building
+    // each one's full direct/indirect/double-indirect/triple-indirect
+    // pointer tree instead of only ever filling direct_ptrs plus a single
+    // indirect_ptr, which silently capped a file at
+    // `INODE_DIRECT_POINTERS + BLOCK_SIZE / 4` blocks.
+    if !cli.add.is_empty() {
+        let mut fmt = Formatter {
+            data_bitmap: [0u8; BLOCK_SIZE],
+            inode_bitmap: {
+                let mut b = [0u8; BLOCK_SIZE];
+                b[0] = 1;
+                b
+            },
+            data_bitmap_block,
+            inode_bitmap_block,
+            data_blocks_start,
+            inode_table_start,
+            inodes_per_block,
+            free_blocks,
+            free_inodes: sb.free_inodes,
+        };
+
+        let mut dir_data = Vec::new();
+        for path in &cli.add {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_else(|| panic!("--add path {:?} has no file name", path));
+            if name.len() > 28 {
+                eprintln!("File name too long, skipping: {}", name);
+                continue;
+            }
+
+            let contents = std::fs::read(path)?;
+            let mut inode = fmt.alloc_inode(&mut file, FileType::File)?;
+            fmt.write_file_data(&mut file, &mut inode, &contents)?;
+            println!(
+                "Added {} as inode {} ({} bytes, {} blocks)",
+                name,
+                inode.id,
+                contents.len(),
+                (contents.len() as u32).div_ceil(BLOCK_SIZE as u32)
+            );
+
+            let entry = DirEntry::new(inode.id, name);
+            let entry_bytes = unsafe {
+                std::slice::from_raw_parts(
+                    &entry as *const DirEntry as *const u8,
+                    std::mem::size_of::<DirEntry>(),
+                )
+            };
+            dir_data.extend_from_slice(entry_bytes);
+        }
+
+        if !dir_data.is_empty() {
+            fmt.write_file_data(&mut file, &mut root_inode, &dir_data)?;
+        }
+
+        free_blocks = fmt.free_blocks;
+        sb.free_inodes = fmt.free_inodes;
+        sb.free_blocks = free_blocks;
+
+        // Re-publish the superblock and both bitmaps now that embedding
+        // may have allocated blocks/inodes out of them.
+        let mut sb_bytes = [0u8; BLOCK_SIZE];
+        bincode::serde::encode_into_slice(&sb, &mut sb_bytes, config).unwrap();
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&sb_bytes)?;
+        Formatter::write_block(&mut file, data_bitmap_block, &fmt.data_bitmap)?;
+        Formatter::write_block(&mut file, inode_bitmap_block, &fmt.inode_bitmap)?;
+    }
+
+    // 7. Repack as a sparse CISO-style image, now that the dense image on
+    // disk holds its final contents.
+    if cli.sparse {
+        drop(file); // release the handle `write_sparse` reopens by path
+        write_sparse(&cli.disk)?;
+    }
+
     println!("Format complete.");
 
     Ok(())
@@ -0,0 +1,239 @@
+//! A minimal GDB Remote Serial Protocol server over `VirtualMachine`'s
+//! debugger hook, built by hand against the protocol rather than the
+//! `gdbstub` crate (this tree has no package manifest to add it as a
+//! dependency to) -- just enough of RSP for `riscv32-elf-gdb` to attach
+//! over TCP and debug a guest ELF `Runtime::load_program` already loaded:
+//! `g`/`G` for the register file, `m`/`M` for memory (through
+//! `VirtualMachine::translate`, so `satp`/privilege apply), `Z0`/`z0` for
+//! software breakpoints, and `s`/`c` for step/continue.
+use ferrous_vm::{debugger::Debugger, ExitReason, Register, VirtualMachine, VmError};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+/// Blocks accepting a single `riscv32-elf-gdb` connection on
+/// `127.0.0.1:{port}`, then serves RSP packets against `vm` until the
+/// client disconnects or sends `k` (kill).
+pub fn serve(vm: &mut VirtualMachine, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!(
+        "gdbstub listening on 127.0.0.1:{port} -- connect with `target remote 127.0.0.1:{port}`"
+    );
+    let (mut stream, addr) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    println!("gdbstub: {addr} attached");
+
+    // `Z0`/`z0` need somewhere to keep breakpoints; a session that never
+    // attached a debugger before connecting gets one created here.
+    if vm.debugger().is_none() {
+        vm.set_debugger(Debugger::new());
+    }
+
+    loop {
+        let Some(packet) = read_packet(&mut stream)? else {
+            break;
+        };
+        write_ack(&mut stream)?;
+        match handle_packet(vm, &packet) {
+            Some(reply) => write_packet(&mut stream, &reply)?,
+            None => break,
+        }
+    }
+
+    println!("gdbstub: {addr} disconnected");
+    Ok(())
+}
+
+/// Reads one `$<data>#<checksum>` packet, trusting TCP's own integrity
+/// checking rather than re-validating the checksum. `None` means the
+/// connection closed.
+fn read_packet(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut byte = [0u8; 1];
+    loop {
+        if stream.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        if byte[0] == b'$' {
+            break;
+        }
+    }
+    let mut data = Vec::new();
+    loop {
+        if stream.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        if byte[0] == b'#' {
+            break;
+        }
+        data.push(byte[0]);
+    }
+    let mut checksum = [0u8; 2];
+    stream.read_exact(&mut checksum)?;
+    Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+}
+
+fn write_ack(stream: &mut TcpStream) -> io::Result<()> {
+    stream.write_all(b"+")
+}
+
+fn write_packet(stream: &mut TcpStream, data: &str) -> io::Result<()> {
+    let checksum = data.bytes().fold(0u8, |acc, b| acc.wrapping_add(b));
+    write!(stream, "${data}#{checksum:02x}")?;
+    stream.flush()
+}
+
+/// Dispatches one packet's worth of RSP, returning the reply packet body
+/// (not yet framed with `$`/`#checksum`) or `None` to close the
+/// connection. An empty string is itself a valid reply -- RSP's way of
+/// saying "command not supported".
+fn handle_packet(vm: &mut VirtualMachine, packet: &str) -> Option<String> {
+    let mut chars = packet.chars();
+    let kind = chars.next()?;
+    let rest = chars.as_str();
+
+    Some(match kind {
+        '?' => "S05".to_string(),
+        'g' => read_registers(vm),
+        'G' => {
+            write_registers(vm, rest);
+            "OK".to_string()
+        }
+        'm' => read_memory(vm, rest),
+        'M' => write_memory(vm, rest),
+        'Z' if rest.starts_with("0,") => {
+            if let Some(addr) = parse_breakpoint_addr(rest) {
+                vm.debugger_mut().unwrap().add_breakpoint(addr);
+            }
+            "OK".to_string()
+        }
+        'z' if rest.starts_with("0,") => {
+            if let Some(addr) = parse_breakpoint_addr(rest) {
+                vm.debugger_mut().unwrap().remove_breakpoint(addr);
+            }
+            "OK".to_string()
+        }
+        's' => {
+            vm.debugger_mut().unwrap().set_single_step(true);
+            resume_addr(vm, rest);
+            stop_reply(vm.run())
+        }
+        'c' => {
+            vm.debugger_mut().unwrap().set_single_step(false);
+            resume_addr(vm, rest);
+            stop_reply(vm.run())
+        }
+        'k' => return None,
+        _ => String::new(),
+    })
+}
+
+/// `s`/`c` may carry an optional resume address (`saddr`/`caddr`); when
+/// present, `cpu.pc` jumps there before execution continues.
+fn resume_addr(vm: &mut VirtualMachine, rest: &str) {
+    if let Ok(addr) = u32::from_str_radix(rest, 16) {
+        vm.cpu.pc = addr;
+    }
+}
+
+fn stop_reply(result: Result<ExitReason, VmError>) -> String {
+    match result {
+        Ok(ExitReason::Halt) => "W00".to_string(),
+        // SIGTRAP (5) for every debugger stop -- breakpoint, watchpoint,
+        // ebreak, and single-step alike; RSP has no richer per-cause stop
+        // code a plain `target remote` session needs here.
+        Ok(ExitReason::Breakpoint(_)) => "S05".to_string(),
+        Ok(ExitReason::Error(_)) | Err(_) => "E01".to_string(),
+    }
+}
+
+fn read_registers(vm: &VirtualMachine) -> String {
+    let mut out = String::with_capacity(33 * 8);
+    for i in 0..32 {
+        let reg = Register::new(i).unwrap();
+        push_hex_le(&mut out, vm.read_register(reg));
+    }
+    push_hex_le(&mut out, vm.cpu.pc);
+    out
+}
+
+fn write_registers(vm: &mut VirtualMachine, data: &str) {
+    let mut bytes = data.as_bytes().chunks(8);
+    for i in 0..32 {
+        let Some(chunk) = bytes.next() else { return };
+        let Some(val) = parse_hex_le(chunk) else {
+            continue;
+        };
+        vm.write_register(Register::new(i).unwrap(), val);
+    }
+    if let Some(chunk) = bytes.next() {
+        if let Some(val) = parse_hex_le(chunk) {
+            vm.cpu.pc = val;
+        }
+    }
+}
+
+/// `m addr,len` -> the `len` bytes at `addr` as a hex string.
+fn read_memory(vm: &mut VirtualMachine, rest: &str) -> String {
+    let Some((addr, len)) = parse_addr_len(rest) else {
+        return String::new();
+    };
+    let bytes = vm.read_virtual(addr, len);
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push_str(&format!("{byte:02x}"));
+    }
+    out
+}
+
+/// `M addr,len:XX...` -> writes the hex-encoded bytes after the `:` to
+/// `addr`, replying `OK` regardless of whether every byte landed (matching
+/// `write_virtual`'s own "stop at the first fault" contract).
+fn write_memory(vm: &mut VirtualMachine, rest: &str) -> String {
+    let Some((header, data)) = rest.split_once(':') else {
+        return "E01".to_string();
+    };
+    let Some((addr, _len)) = parse_addr_len(header) else {
+        return "E01".to_string();
+    };
+    let bytes: Option<Vec<u8>> = data
+        .as_bytes()
+        .chunks(2)
+        .map(|chunk| std::str::from_utf8(chunk).ok().and_then(|s| u8::from_str_radix(s, 16).ok()))
+        .collect();
+    match bytes {
+        Some(bytes) => {
+            vm.write_virtual(addr, &bytes);
+            "OK".to_string()
+        }
+        None => "E01".to_string(),
+    }
+}
+
+/// `0,addr,kind` (the prefix RSP always sends for `Z0`/`z0`) -> `addr`.
+fn parse_breakpoint_addr(rest: &str) -> Option<u32> {
+    let mut parts = rest.split(',');
+    parts.next()?; // the "0" breakpoint-type prefix, already matched on.
+    u32::from_str_radix(parts.next()?, 16).ok()
+}
+
+fn parse_addr_len(s: &str) -> Option<(u32, u32)> {
+    let (addr, len) = s.split_once(',')?;
+    Some((
+        u32::from_str_radix(addr, 16).ok()?,
+        u32::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+fn push_hex_le(out: &mut String, val: u32) {
+    for byte in val.to_le_bytes() {
+        out.push_str(&format!("{byte:02x}"));
+    }
+}
+
+fn parse_hex_le(chunk: &[u8]) -> Option<u32> {
+    let s = std::str::from_utf8(chunk).ok()?;
+    let mut val = [0u8; 4];
+    for i in 0..4 {
+        val[i] = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(u32::from_le_bytes(val))
+}
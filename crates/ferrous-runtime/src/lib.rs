@@ -1,9 +1,12 @@
+pub mod gdbstub;
 pub mod loader;
 
 use ferrous_kernel::Kernel;
 use ferrous_vm::{
     devices::{
-        block::{SimpleBlockDevice, BLOCK_DEVICE_BASE, BLOCK_DEVICE_SIZE},
+        block::{MemBlockDevice, SimpleBlockDevice, BLOCK_DEVICE_BASE, BLOCK_DEVICE_SIZE},
+        bootinfo::{BootInfoDevice, BOOTINFO_BASE, BOOTINFO_SIZE},
+        clint::{ClintDevice, CLINT_BASE, CLINT_SIZE},
         uart::{UartDevice, UART_BASE, UART_SIZE},
     },
     system_bus::SystemBus,
@@ -17,28 +20,73 @@ pub struct Runtime {
 }
 
 impl Runtime {
-    pub fn new(memory_size: usize, disk_image: Option<&Path>) -> Result<Self, VmError> {
-        let mut bus = SystemBus::new(memory_size);
+    pub fn new(
+        memory_size: usize,
+        disk_image: Option<&Path>,
+        initrd: Option<Vec<u8>>,
+        share_dir: Option<std::path::PathBuf>,
+        cmdline: String,
+        pcap_path: Option<&Path>,
+    ) -> Result<Self, VmError> {
+        // Size the kernel's frame allocator to the RAM we're actually
+        // handing the VM before anything (the capability pool, page
+        // tables, ...) starts drawing frames from it.
+        ferrous_kernel::memory::init_frame_allocator(memory_size as u32);
+
+        let mut bus = SystemBus::new(memory_size)?;
 
         // Add UART
-        bus.add_device(UART_BASE, UART_SIZE, Box::new(UartDevice::new()));
+        bus.add_device(UART_BASE, UART_SIZE, Box::new(UartDevice::new()))?;
+
+        // Add CLINT
+        bus.add_device(CLINT_BASE, CLINT_SIZE, Box::new(ClintDevice::new()))?;
+
+        // Mount whichever of initrd/disk was given as the block device the
+        // FS layer reads its superblock from. An initrd takes priority over
+        // `--disk` since it's the thing the kernel command line would name
+        // as the root filesystem; both map onto the same register window,
+        // so only one can be the block device at a time.
+        // The boot-info device only reports an initrd's size; a `--disk`
+        // image's size is what `SimpleBlockDevice`'s `REG_CAPACITY`
+        // register reports instead.
+        let initrd_len = initrd.as_ref().map_or(0, |image| image.len() as u32);
 
-        // Add Block Device if provided
-        if let Some(disk_path) = disk_image {
+        if let Some(image) = initrd {
+            bus.add_device(
+                BLOCK_DEVICE_BASE,
+                BLOCK_DEVICE_SIZE,
+                Box::new(MemBlockDevice::new(image)),
+            )?;
+        } else if let Some(disk_path) = disk_image {
             let block_dev = SimpleBlockDevice::new(disk_path.to_str().unwrap()).map_err(|e| {
                 VmError::Device(ferrous_vm::DeviceError::Io(format!(
                     "Failed to open disk image: {}",
                     e
                 )))
             })?;
-            bus.add_device(BLOCK_DEVICE_BASE, BLOCK_DEVICE_SIZE, Box::new(block_dev));
+            bus.add_device(BLOCK_DEVICE_BASE, BLOCK_DEVICE_SIZE, Box::new(block_dev))?;
         }
 
+        // Boot info: the kernel command line and, when an initrd was
+        // given, where it landed -- `BLOCK_DEVICE_BASE` is where the guest
+        // already reads it from as the mounted root filesystem, so that
+        // doubles as its "location" rather than copying the image a
+        // second time into a separate reserved RAM region.
+        bus.add_device(
+            BOOTINFO_BASE,
+            BOOTINFO_SIZE,
+            Box::new(BootInfoDevice::new(
+                cmdline,
+                if initrd_len > 0 { BLOCK_DEVICE_BASE } else { 0 },
+                initrd_len,
+            )),
+        )?;
+
         // Create Memory (Boxed)
         let mut memory = Box::new(bus);
 
         // Kernel::new() returns KernelError, map it?
-        let kernel = Kernel::new().map_err(|e| {
+        let kernel = Kernel::new(share_dir).map_err(|e| {
             VmError::Device(ferrous_vm::DeviceError::Io(format!(
                 "Kernel init failed: {}",
                 e
@@ -57,11 +105,28 @@ impl Runtime {
         let config = VmConfig {
             memory_size,
             timer_interval: Some(100), // Trigger interrupt every 100 instructions
+            block_cache_enabled: true,
         };
 
         let mut vm = VirtualMachine::new(config, memory, Box::new(kernel))?;
         vm.cpu.satp = satp;
 
+        // Opt-in pcap capture of every frame `net::driver::DRIVER` moves,
+        // opened alongside the disk image/initrd above rather than lazily
+        // on first packet so a capture never misses the DHCP handshake at
+        // boot.
+        if let Some(path) = pcap_path {
+            ferrous_kernel::net::driver::DRIVER
+                .lock()
+                .enable_capture(path)
+                .map_err(|e| {
+                    VmError::Device(ferrous_vm::DeviceError::Io(format!(
+                        "Failed to open pcap capture file: {}",
+                        e
+                    )))
+                })?;
+        }
+
         Ok(Self { vm })
     }
 
@@ -80,12 +145,22 @@ impl Runtime {
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         match self.vm.run() {
             Ok(ExitReason::Halt) => Ok(()),
-            Ok(ExitReason::Breakpoint) => {
-                println!("Breakpoint hit!");
+            Ok(ExitReason::Breakpoint(reason)) => {
+                println!("Debugger stop: {:?}", reason);
                 Ok(())
             }
             Ok(ExitReason::Error(e)) => Err(Box::new(e)),
             Err(e) => Err(Box::new(e)),
         }
     }
+
+    /// Runs the guest under a GDB Remote Serial Protocol server instead of
+    /// freestanding -- blocks accepting one `riscv32-elf-gdb` connection on
+    /// `127.0.0.1:{port}`, then lets that session single-step, set
+    /// breakpoints, and inspect registers/memory for as long as it stays
+    /// attached.
+    pub fn run_with_gdbstub(&mut self, port: u16) -> Result<(), Box<dyn Error>> {
+        gdbstub::serve(&mut self.vm, port)?;
+        Ok(())
+    }
 }
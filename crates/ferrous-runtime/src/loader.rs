@@ -1,4 +1,4 @@
-use ferrous_vm::{Memory, PhysAddr, VirtAddr, VirtualMachine, VmError};
+use ferrous_vm::{DeviceError, Memory, PhysAddr, VirtAddr, VirtualMachine, VmError};
 use goblin::elf;
 use std::error::Error;
 use std::fs;
@@ -6,6 +6,38 @@ use std::path::Path;
 
 pub struct ProgramLoader;
 
+/// One regular file unpacked from an initramfs by `load_initramfs`, telling
+/// the boot path where its bytes ended up so it can build the initial file
+/// tree without re-parsing the cpio stream.
+#[derive(Debug, Clone)]
+pub struct InitramfsEntry {
+    pub name: String,
+    pub addr: PhysAddr,
+    pub size: u32,
+}
+
+/// A "new ASCII" (`070701`) cpio header: six magic bytes followed by
+/// thirteen 8-hex-digit fields, all parsed as plain ASCII rather than
+/// binary the way `ferrous_fs`'s on-disk structs are -- cpio's wire format
+/// predates anything like `zerocopy` and was never meant to be `repr(C)`.
+const CPIO_MAGIC: &[u8; 6] = b"070701";
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_TRAILER_NAME: &str = "TRAILER!!!";
+
+/// Round `len` up to the next multiple of 4 -- both a cpio entry's name and
+/// its data region are padded to this alignment.
+fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn parse_hex_field(bytes: &[u8]) -> Result<u32, Box<dyn Error>> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|e| DeviceError::Io(format!("cpio header field not ASCII: {e}")))?;
+    let value = u32::from_str_radix(text, 16)
+        .map_err(|e| DeviceError::Io(format!("cpio header field {text:?} not hex: {e}")))?;
+    Ok(value)
+}
+
 impl ProgramLoader {
     pub fn load_elf(vm: &mut VirtualMachine, elf_path: &Path) -> Result<VirtAddr, Box<dyn Error>> {
         let buffer = fs::read(elf_path)?;
@@ -39,4 +71,85 @@ impl ProgramLoader {
 
         Ok(VirtAddr::new(elf.entry as u32))
     }
+
+    /// Unpack a newc-format cpio archive (the `070701` variant `gen_init_cpio`/
+    /// `dracut` produce) into `vm`'s memory starting at `base`, the same
+    /// byte-by-byte `write_byte` loop `load_elf` uses for `PT_LOAD` segments.
+    /// Each regular file's bytes land back-to-back, 4-byte aligned, so the
+    /// caller gets a realistic early-boot root filesystem without a
+    /// pre-formatted disk image. Directory entries are skipped -- there's no
+    /// in-memory tree here for them to create, just a flat manifest of
+    /// `name -> (addr, size)` the boot path can build one from.
+    pub fn load_initramfs(
+        vm: &mut VirtualMachine,
+        cpio_path: &Path,
+        base: PhysAddr,
+    ) -> Result<Vec<InitramfsEntry>, Box<dyn Error>> {
+        let archive = fs::read(cpio_path)?;
+        let mut entries = Vec::new();
+        let mut cursor = 0usize;
+        let mut write_addr = base.val();
+
+        loop {
+            if cursor + CPIO_HEADER_LEN > archive.len() {
+                return Err(DeviceError::Io(format!(
+                    "cpio archive truncated at offset {cursor}"
+                ))
+                .into());
+            }
+            let header = &archive[cursor..cursor + CPIO_HEADER_LEN];
+            if &header[0..6] != CPIO_MAGIC {
+                return Err(DeviceError::Io(format!(
+                    "bad cpio magic at offset {cursor}: {:?}",
+                    &header[0..6]
+                ))
+                .into());
+            }
+
+            let filesize = parse_hex_field(&header[54..62])? as usize;
+            let namesize = parse_hex_field(&header[94..102])? as usize;
+
+            let name_start = cursor + CPIO_HEADER_LEN;
+            let name_end = name_start + namesize;
+            if name_end > archive.len() {
+                return Err(DeviceError::Io(format!(
+                    "cpio entry name truncated at offset {name_start}"
+                ))
+                .into());
+            }
+            let name = std::str::from_utf8(&archive[name_start..name_end - 1]) // drop NUL
+                .map_err(|e| DeviceError::Io(format!("cpio entry name not ASCII: {e}")))?
+                .to_string();
+
+            let data_start = cursor + align4(CPIO_HEADER_LEN + namesize);
+            let data_end = data_start + filesize;
+            if data_end > archive.len() {
+                return Err(DeviceError::Io(format!(
+                    "cpio entry {name:?} data truncated at offset {data_start}"
+                ))
+                .into());
+            }
+
+            if name == CPIO_TRAILER_NAME {
+                break;
+            }
+
+            if filesize > 0 {
+                let data = &archive[data_start..data_end];
+                for (i, &byte) in data.iter().enumerate() {
+                    vm.memory.write_byte(PhysAddr::new(write_addr + i as u32), byte)?;
+                }
+                entries.push(InitramfsEntry {
+                    name,
+                    addr: PhysAddr::new(write_addr),
+                    size: filesize as u32,
+                });
+                write_addr += align4(filesize) as u32;
+            }
+
+            cursor = align4(data_end);
+        }
+
+        Ok(entries)
+    }
 }
@@ -0,0 +1,52 @@
+#![allow(dead_code)]
+
+use crate::syscall;
+
+/// One link in a `block_read_dma` chain: `sector` copied straight into
+/// `dest_addr`, then on to `next` (0 ends the chain). Layout (five
+/// little-endian words: `sector`, `dest_addr`, `length`, `next`, `done`)
+/// matches `ferrous_vm::devices::dma::DmaDescriptor` exactly, since the
+/// engine reads this struct's bytes straight out of guest memory rather
+/// than through any marshalling this crate does.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct DmaDescriptor {
+    pub sector: u32,
+    pub dest_addr: u32,
+    pub length: u32,
+    pub next: u32,
+    pub done: u32,
+}
+
+pub const DONE_PENDING: u32 = 0;
+pub const DONE_OK: u32 = 1;
+pub const DONE_ERROR: u32 = 2;
+
+impl DmaDescriptor {
+    /// A single-link chain reading `sector` into `dest_addr..dest_addr+length`
+    /// -- `length` can span more than one sector, so one descriptor now
+    /// covers a whole contiguous run instead of needing one link per sector.
+    /// Chain further descriptors together by setting `next` to another
+    /// descriptor's address after construction.
+    pub fn new(sector: u32, dest_addr: u32, length: u32) -> Self {
+        Self {
+            sector,
+            dest_addr,
+            length,
+            next: 0,
+            done: DONE_PENDING,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done != DONE_PENDING
+    }
+}
+
+/// Program the DMA engine with the chain rooted at `head` and return
+/// immediately; the engine runs the whole chain before the syscall comes
+/// back, so by the time this returns `head.done` (and every link it leads
+/// to) already reflects the outcome.
+pub fn block_read_dma(head: &mut DmaDescriptor) -> Result<(), u32> {
+    syscall::block_read_dma(head as *mut _ as u32)
+}
@@ -0,0 +1,173 @@
+//! A `GlobalAlloc` for userland programs, backed by `sbrk` but with actual
+//! block reuse: `shell`'s old `SbrkAllocator` only ever grew the break and
+//! its `dealloc` was a no-op, so anything long-running leaked every
+//! allocation.
+
+use crate::syscall;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+
+/// Smallest size class, as a left-shift amount: 16 bytes.
+const MIN_CLASS_SHIFT: u32 = 4;
+/// Largest size class: one page (4096 bytes). A request too big to fit
+/// any class bypasses the bins entirely (see `alloc_large`).
+const MAX_CLASS_SHIFT: u32 = 12;
+const NUM_CLASSES: usize = (MAX_CLASS_SHIFT - MIN_CLASS_SHIFT + 1) as usize;
+
+/// Intrusive singly-linked free-list node: a free block's own first bytes
+/// hold the pointer to the next free block of the same class, so the bins
+/// themselves cost nothing beyond `NUM_CLASSES` pointers.
+struct FreeNode {
+    next: *mut FreeNode,
+}
+
+/// One per live block, written immediately before the pointer `alloc`
+/// hands out so `dealloc` can recover the size class (and thus which bin
+/// to push the block back onto) from just the pointer and layout it's
+/// given -- it never learns the class any other way.
+type BlockHeader = usize;
+const HEADER_SIZE: usize = core::mem::size_of::<BlockHeader>();
+
+// Guards `BINS` the same way `CONSOLE_MUTEX_ID` guards the console in
+// `lib.rs`: a real `ferrous_user::sync::Mutex` needs a syscall to create,
+// so it can't be a `const fn` static initializer, and is instead lazily
+// created by `init()`. Until `init()` runs, allocation is assumed
+// single-threaded (true of every program before its first thread spawn).
+static mut HEAP_MUTEX_ID: u32 = 0;
+static mut BINS: [*mut FreeNode; NUM_CLASSES] = [ptr::null_mut(); NUM_CLASSES];
+
+pub(crate) fn init() {
+    unsafe {
+        HEAP_MUTEX_ID = syscall::mutex_create();
+    }
+}
+
+fn lock() {
+    unsafe {
+        if HEAP_MUTEX_ID != 0 {
+            syscall::mutex_acquire(HEAP_MUTEX_ID);
+        }
+    }
+}
+
+fn unlock() {
+    unsafe {
+        if HEAP_MUTEX_ID != 0 {
+            syscall::mutex_release(HEAP_MUTEX_ID);
+        }
+    }
+}
+
+/// Size, in bytes, of every block in `class`.
+fn class_size(class: usize) -> usize {
+    1usize << (MIN_CLASS_SHIFT as usize + class)
+}
+
+/// The smallest class whose blocks are at least `needed` bytes, or `None`
+/// if `needed` is bigger than the largest class.
+fn class_for(needed: usize) -> Option<usize> {
+    let needed = needed.max(1 << MIN_CLASS_SHIFT);
+    if needed > 1 << MAX_CLASS_SHIFT {
+        return None;
+    }
+    let shift = (usize::BITS - (needed - 1).leading_zeros()).max(MIN_CLASS_SHIFT);
+    Some((shift - MIN_CLASS_SHIFT) as usize)
+}
+
+/// Grow the break by one more `class`-sized block (plus its header),
+/// carving the new block so that the pointer past the header lands on a
+/// `class_size(class)`-aligned address -- every `Layout::align()` this
+/// class ever serves is <= `class_size(class)` and a power of two itself
+/// (`class_for` picks a class at least as large as the requested align),
+/// so that's sufficient alignment for any of them, unlike the old
+/// allocator's `current_break % align` padding, which didn't account for
+/// the header eating into the next block's alignment.
+unsafe fn refill(class: usize) -> bool {
+    let size = class_size(class);
+    let current_break = syscall::sbrk(0) as usize;
+
+    let naive_usable = current_break + HEADER_SIZE;
+    let aligned_usable = (naive_usable + size - 1) & !(size - 1);
+    let total = (aligned_usable + size) - current_break;
+
+    let base = syscall::sbrk(total as i32) as usize;
+    if base == 0 {
+        return false;
+    }
+
+    let header_ptr = (aligned_usable - HEADER_SIZE) as *mut BlockHeader;
+    header_ptr.write(class);
+
+    push_free(class, aligned_usable as *mut u8);
+    true
+}
+
+unsafe fn push_free(class: usize, ptr: *mut u8) {
+    let node = ptr as *mut FreeNode;
+    (*node).next = BINS[class];
+    BINS[class] = node;
+}
+
+unsafe fn pop_free(class: usize) -> Option<*mut u8> {
+    let node = BINS[class];
+    if node.is_null() {
+        return None;
+    }
+    BINS[class] = (*node).next;
+    Some(node as *mut u8)
+}
+
+/// A request too big for any size class: handed a dedicated `sbrk`
+/// region with no header and no bin, so it can never be reused -- the
+/// same leak the old allocator had, just confined to the rare
+/// bigger-than-a-page allocation instead of every allocation.
+unsafe fn alloc_large(layout: Layout) -> *mut u8 {
+    let align = layout.align();
+    let current_break = syscall::sbrk(0) as usize;
+    let padding = (align - (current_break % align)) % align;
+    let total = layout.size() + padding;
+
+    let base = syscall::sbrk(total as i32) as usize;
+    if base == 0 {
+        return ptr::null_mut();
+    }
+    (base + padding) as *mut u8
+}
+
+pub struct SbrkAllocator;
+
+unsafe impl GlobalAlloc for SbrkAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let needed = layout.size().max(layout.align());
+        let Some(class) = class_for(needed) else {
+            return alloc_large(layout);
+        };
+
+        lock();
+        let ptr = pop_free(class).or_else(|| {
+            if refill(class) {
+                pop_free(class)
+            } else {
+                None
+            }
+        });
+        unlock();
+
+        ptr.unwrap_or(ptr::null_mut())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let needed = layout.size().max(layout.align());
+        let Some(_) = class_for(needed) else {
+            // Allocated via `alloc_large`: nothing to push back anywhere.
+            return;
+        };
+
+        let header_ptr = (ptr as usize - HEADER_SIZE) as *const BlockHeader;
+        let class = header_ptr.read();
+
+        lock();
+        push_free(class, ptr);
+        unlock();
+    }
+}
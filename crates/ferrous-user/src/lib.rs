@@ -2,6 +2,8 @@
 
 use core::fmt;
 
+pub mod dma;
+pub mod heap;
 pub mod sync;
 
 pub mod syscall {
@@ -110,6 +112,316 @@ pub mod syscall {
         }
     }
 
+    /// Non-blocking `mutex_acquire`: `true` if `id` was free and is now
+    /// held by the caller, `false` if it's already held (by the caller or
+    /// anyone else) -- never yields, unlike `mutex_acquire`.
+    pub fn mutex_try_acquire(id: u32) -> bool {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") id,
+                in("a7") 125,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = id;
+                ret = 1;
+            }
+        }
+        ret == 1
+    }
+
+    pub fn condvar_create() -> u32 {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a7") 121,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                ret = 0;
+            }
+        }
+        ret
+    }
+
+    pub fn condvar_wait(condvar_id: u32, mutex_id: u32) {
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") condvar_id,
+                in("a1") mutex_id,
+                in("a7") 122,
+            );
+        }
+    }
+
+    pub fn condvar_notify_one(condvar_id: u32) {
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") condvar_id,
+                in("a7") 123,
+            );
+        }
+    }
+
+    pub fn condvar_notify_all(condvar_id: u32) {
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") condvar_id,
+                in("a7") 124,
+            );
+        }
+    }
+
+    /// Create a counting semaphore starting at `initial` permits (which
+    /// may be negative, the same as pre-owing that many `sem_wait`s).
+    pub fn sem_create(initial: i32) -> u32 {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") initial,
+                in("a7") 126,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = initial;
+                ret = 0;
+            }
+        }
+        ret
+    }
+
+    /// P: decrement `id`'s count, blocking if it goes negative.
+    pub fn sem_wait(id: u32) {
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") id,
+                in("a7") 127,
+            );
+        }
+    }
+
+    /// V: increment `id`'s count, waking the longest-waiting `sem_wait`
+    /// caller if the count was negative.
+    pub fn sem_post(id: u32) {
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") id,
+                in("a7") 128,
+            );
+        }
+    }
+
+    /// Tear down semaphore `id`, waking any thread still parked in
+    /// `sem_wait` with an error rather than leaving it blocked forever.
+    pub fn sem_destroy(id: u32) {
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") id,
+                in("a7") 130,
+            );
+        }
+    }
+
+    /// One `argv` entry in the array `spawn_process`/`exec` take: a
+    /// pointer/length pair into the caller's own memory, read back out by
+    /// the kernel's `copy_from_user` the same way `DmaDescriptor` is.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct ArgDescriptor {
+        pub ptr: u32,
+        pub len: u32,
+    }
+
+    /// Spawn a process from an ELF image already sitting in `image`,
+    /// isolated from the caller in its own address space -- like `exec(2)`
+    /// but for a binary that was never written to a file, just unpacked
+    /// into memory. `args` becomes the child's `argv`. Returns the new
+    /// process's thread handle.
+    pub fn spawn_process(image: &[u8], args: &[ArgDescriptor]) -> u32 {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") image.as_ptr(),
+                in("a1") image.len(),
+                in("a2") args.as_ptr(),
+                in("a3") args.len(),
+                in("a7") 60,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = (image, args);
+                ret = 0;
+            }
+        }
+        ret
+    }
+
+    /// `open(2)`-style: resolve `path` (a disk file, or one of `fs::scheme`'s
+    /// `console:`/`null:`/`rand:`/`pipe:` names) to a file descriptor in the
+    /// calling thread's own `file_descriptors` table.
+    pub fn file_open(path: &[u8], flags: u32) -> Result<u32, u32> {
+        let path_ptr = path.as_ptr();
+        let path_len = path.len();
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") path_ptr,
+                in("a1") path_len,
+                in("a2") flags,
+                in("a7") 56,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = (path_ptr, path_len, flags);
+                ret = u32::MAX;
+            }
+        }
+        if ret == u32::MAX {
+            Err(ret)
+        } else {
+            Ok(ret)
+        }
+    }
+
+    /// Read up to `buf.len()` bytes from `fd` at its current offset,
+    /// returning the number actually read (which may be less, at EOF).
+    pub fn file_read(fd: u32, buf: &mut [u8]) -> Result<usize, u32> {
+        let ptr = buf.as_mut_ptr();
+        let len = buf.len();
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") fd,
+                in("a1") ptr,
+                in("a2") len,
+                in("a7") 63,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = (fd, ptr, len);
+                ret = u32::MAX;
+            }
+        }
+        if ret == u32::MAX {
+            Err(ret)
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    /// Write `buf` to `fd` at its current offset, returning the number of
+    /// bytes actually written. `console_write` above is this same syscall
+    /// with `fd` fixed to stdout/stderr.
+    pub fn file_write(fd: u32, buf: &[u8]) -> Result<usize, u32> {
+        let ptr = buf.as_ptr();
+        let len = buf.len();
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") fd,
+                in("a1") ptr,
+                in("a2") len,
+                in("a7") 64,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = (fd, ptr, len);
+                ret = u32::MAX;
+            }
+        }
+        if ret == u32::MAX {
+            Err(ret)
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    /// `lseek(2)`: move `fd`'s offset to `offset`, interpreted relative to
+    /// `whence` (POSIX's `SEEK_SET`/`SEEK_CUR`/`SEEK_END`, 0/1/2). Only a
+    /// `Disk`/`Host` descriptor has a position to move.
+    pub fn file_seek(fd: u32, offset: i32, whence: u32) -> Result<u32, u32> {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") fd,
+                in("a1") offset,
+                in("a2") whence,
+                in("a7") 62,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = (fd, offset, whence);
+                ret = u32::MAX;
+            }
+        }
+        if ret == u32::MAX {
+            Err(ret)
+        } else {
+            Ok(ret)
+        }
+    }
+
+    /// Free `fd`'s slot in the calling thread's `file_descriptors` table.
+    pub fn file_close(fd: u32) -> Result<(), u32> {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") fd,
+                in("a7") 57,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = fd;
+                ret = 0;
+            }
+        }
+        if ret == u32::MAX {
+            Err(ret)
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn sbrk(increment: i32) -> u32 {
         let ret: u32;
         unsafe {
@@ -151,24 +463,186 @@ pub mod syscall {
             Err(ret)
         }
     }
+
+    /// Program the DMA engine with the descriptor chain at `desc_ptr` --
+    /// unlike `block_read`, there's no buffer to copy back, since each
+    /// descriptor names its own destination. The engine walks the whole
+    /// chain before this returns, so every descriptor's `done` field (see
+    /// `ferrous_user::dma`) already holds its outcome.
+    pub fn block_read_dma(desc_ptr: u32) -> Result<(), u32> {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") desc_ptr,
+                in("a7") 201,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                let _ = desc_ptr;
+                ret = 0;
+            }
+        }
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
+
+    /// Look up `key` in the kernel's persistent config store, copying its
+    /// value into `buf` (truncated if shorter than the stored value) and
+    /// returning the value's full length -- like `block_read`, but for the
+    /// durable settings area instead of a disk sector.
+    pub fn config_read(key: &[u8], buf: &mut [u8]) -> Result<usize, u32> {
+        let key_ptr = key.as_ptr();
+        let key_len = key.len();
+        let buf_ptr = buf.as_mut_ptr();
+        let buf_len = buf.len();
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") key_ptr,
+                in("a1") key_len,
+                in("a2") buf_ptr,
+                in("a3") buf_len,
+                in("a7") 202,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                ret = u32::MAX;
+            }
+        }
+        if ret == u32::MAX {
+            Err(ret)
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    /// Write `key` = `val` into the config store, replacing any existing
+    /// record for `key`.
+    pub fn config_write(key: &[u8], val: &[u8]) -> Result<(), u32> {
+        let key_ptr = key.as_ptr();
+        let key_len = key.len();
+        let val_ptr = val.as_ptr();
+        let val_len = val.len();
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") key_ptr,
+                in("a1") key_len,
+                in("a2") val_ptr,
+                in("a3") val_len,
+                in("a7") 203,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                ret = 0;
+            }
+        }
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
+
+    /// Erase `key`'s record from the config store, if any.
+    pub fn config_remove(key: &[u8]) -> Result<(), u32> {
+        let key_ptr = key.as_ptr();
+        let key_len = key.len();
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") key_ptr,
+                in("a1") key_len,
+                in("a7") 204,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                ret = 0;
+            }
+        }
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
+
+    /// `poll(2)`: block until any of `fds`'s entries (each already
+    /// populated with an `fd` and requested `events`) is ready or
+    /// `timeout_ms` elapses (`net::POLL_NO_TIMEOUT` to wait forever, `0`
+    /// to check and return immediately). Each entry's `revents` is
+    /// overwritten in place; returns the count that became ready.
+    pub fn poll(fds: &mut [u8], nfds: u32, timeout_ms: u32) -> i32 {
+        let ptr = fds.as_mut_ptr();
+        let ret: i32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") ptr,
+                in("a1") nfds,
+                in("a2") timeout_ms,
+                in("a7") 309,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                ret = 0;
+            }
+        }
+        ret
+    }
+
+    /// Set (`flag != 0`) or clear `fd`'s non-blocking flag -- the normal
+    /// companion to `poll`, so a `RecvFrom` found ready by a wait that
+    /// later races with another reader returns `net::EWOULDBLOCK` instead
+    /// of silently returning `0`.
+    pub fn set_non_blocking(fd: u32, flag: bool) -> Result<(), u32> {
+        let ret: u32;
+        unsafe {
+            #[cfg(target_arch = "riscv32")]
+            asm!(
+                "ecall",
+                in("a0") fd,
+                in("a1") flag as u32,
+                in("a7") 310,
+                lateout("a0") ret,
+            );
+            #[cfg(not(target_arch = "riscv32"))]
+            {
+                ret = 0;
+            }
+        }
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
 }
 
 pub struct Console;
 
-// We need a way to initialize this lazily or statically.
-// Since we don't have atomic/lazy_static easily in no_std without support,
-// we'll rely on a dedicated syscall to lock the console, OR
-// we expose a Mutex to the user.
-// But println! is a macro.
-// For now, let's just make console_write atomic in the kernel?
-// No, console_write IS atomic (one buffer).
-// The problem is `write_fmt` calls `write_str` multiple times.
-// We need to lock AROUND write_fmt.
-
-// Hack: Global boolean flag? No, race condition.
-// Real solution: Global Mutex initialized at start.
-// But we can't run code at start easily (pre-main).
-// We can have `ferrous_user_init()` called by `_start`.
+// `console_write` itself is atomic (one buffer per syscall), but
+// `write_fmt` calls `write_str` multiple times, so two threads'
+// `println!`s can still interleave mid-format without a lock held across
+// the whole call -- `CONSOLE_MUTEX_ID` below is that lock, created once by
+// `init()` (called from `_start`, before any thread's first `println!`).
 
 static mut CONSOLE_MUTEX_ID: u32 = 0;
 
@@ -176,6 +650,7 @@ pub fn init() {
     unsafe {
         CONSOLE_MUTEX_ID = syscall::mutex_create();
     }
+    heap::init();
 }
 
 impl fmt::Write for Console {
@@ -185,19 +660,55 @@ impl fmt::Write for Console {
     }
 }
 
+/// Holds `CONSOLE_MUTEX_ID` for the lifetime of one `_print` call and
+/// releases it on drop, so a panic unwinding out of `write_fmt` (each
+/// `write_str` call it makes could in principle panic on a malformed
+/// formatter) still releases the lock instead of leaving every other
+/// thread's `println!` blocked forever.
+struct ConsoleLock {
+    held: bool,
+}
+
+impl Drop for ConsoleLock {
+    fn drop(&mut self) {
+        if self.held {
+            unsafe {
+                syscall::mutex_release(CONSOLE_MUTEX_ID);
+            }
+        }
+    }
+}
+
 pub fn _print(args: fmt::Arguments) {
     use fmt::Write;
-    unsafe {
+    let held = unsafe {
         if CONSOLE_MUTEX_ID != 0 {
             syscall::mutex_acquire(CONSOLE_MUTEX_ID);
+            true
+        } else {
+            // Pre-`init()`: no mutex to take yet, so just write unlocked
+            // rather than dereferencing an id that was never created.
+            false
         }
-    }
-    Console.write_fmt(args).unwrap();
-    unsafe {
-        if CONSOLE_MUTEX_ID != 0 {
-            syscall::mutex_release(CONSOLE_MUTEX_ID);
-        }
-    }
+    };
+    let _guard = ConsoleLock { held };
+    let _ = Console.write_fmt(args);
+}
+
+/// Same as `_print`, but for use from a `#[panic_handler]`: it's only ever
+/// called on a thread that may already be the one holding
+/// `CONSOLE_MUTEX_ID` (e.g. it panicked while formatting its own
+/// `println!`), and `_print`'s blocking `mutex_acquire` would deadlock that
+/// thread against itself. `mutex_try_acquire` never blocks, so if the lock
+/// is unavailable for any reason -- held by this thread or another one --
+/// this just writes unlocked rather than waiting; at panic time, avoiding a
+/// deadlock matters more than clean interleaving with whatever else is
+/// printing.
+pub fn _print_panic(args: fmt::Arguments) {
+    use fmt::Write;
+    let held = unsafe { CONSOLE_MUTEX_ID != 0 && syscall::mutex_try_acquire(CONSOLE_MUTEX_ID) };
+    let _guard = ConsoleLock { held };
+    let _ = Console.write_fmt(args);
 }
 
 #[macro_export]
@@ -211,6 +722,19 @@ macro_rules! println {
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
 
+/// Panic-handler-safe counterparts of `print!`/`println!` -- see
+/// `_print_panic`.
+#[macro_export]
+macro_rules! print_panic {
+    ($($arg:tt)*) => ($crate::_print_panic(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! println_panic {
+    () => ($crate::print_panic!("\n"));
+    ($($arg:tt)*) => ($crate::print_panic!("{}\n", format_args!($($arg)*)));
+}
+
 pub fn exit(code: i32) -> ! {
     syscall::exit(code)
 }
@@ -38,9 +38,49 @@ pub fn ntohl(u: u32) -> u32 {
     u32::from_be(u)
 }
 
-/// Create a UDP socket. Returns file descriptor or error code.
-pub fn socket() -> Result<i32, i32> {
-    syscall::socket()
+/// `AF_INET`/`SOCK_STREAM`/`IPPROTO_TCP` and friends, matching
+/// `ferrous_kernel::net`'s constants of the same names.
+pub const AF_UNIX: u32 = 1;
+pub const AF_INET: u32 = 2;
+pub const SOCK_STREAM: u32 = 1;
+pub const SOCK_DGRAM: u32 = 2;
+pub const IPPROTO_TCP: u32 = 6;
+pub const IPPROTO_UDP: u32 = 17;
+
+/// Matches `ferrous_kernel::net::UNIX_NAME_MAX`.
+pub const UNIX_NAME_MAX: usize = 30;
+
+/// An `AF_UNIX` endpoint address: a name in the kernel's in-memory
+/// `net::unix::ENDPOINTS` table rather than an IP/port pair, for fast
+/// local IPC between two processes on the same VM. Mirrors
+/// `ferrous_kernel::net::SockAddrUn`'s layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SockAddrUn {
+    pub family: u16,
+    pub name: [u8; UNIX_NAME_MAX],
+}
+
+impl SockAddrUn {
+    pub fn new(name: &str) -> Self {
+        let mut buf = [0u8; UNIX_NAME_MAX];
+        let bytes = name.as_bytes();
+        let len = bytes.len().min(UNIX_NAME_MAX);
+        buf[..len].copy_from_slice(&bytes[..len]);
+        Self {
+            family: AF_UNIX as u16,
+            name: buf,
+        }
+    }
+}
+
+/// Create a socket. A `domain` of `AF_UNIX` gets a socket addressed by
+/// name through the kernel's `net::unix` table; otherwise a `protocol` of
+/// `IPPROTO_TCP` gets a real TCP connection, and anything else (including
+/// `IPPROTO_UDP`) gets this module's original UDP/loopback-stream kind.
+/// Returns file descriptor or error code.
+pub fn socket(domain: u32, ty: u32, protocol: u32) -> Result<i32, i32> {
+    syscall::socket(domain, ty, protocol)
 }
 
 /// Bind socket to address.
@@ -97,3 +137,174 @@ pub fn recvfrom(fd: i32, buf: &mut [u8]) -> Result<(usize, SockAddrIn), i32> {
         Err(ret)
     }
 }
+
+/// Bind socket to an `AF_UNIX` name.
+pub fn bind_unix(fd: i32, addr: &SockAddrUn) -> Result<(), i32> {
+    let ret = syscall::bind(
+        fd as u32,
+        addr as *const _ as *const u8,
+        core::mem::size_of::<SockAddrUn>() as u32,
+    );
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(ret)
+    }
+}
+
+/// Send data to an `AF_UNIX` name.
+pub fn sendto_unix(fd: i32, buf: &[u8], addr: &SockAddrUn) -> Result<usize, i32> {
+    let ret = syscall::sendto(
+        fd as u32,
+        buf.as_ptr(),
+        buf.len() as u32,
+        addr as *const _ as *const u8,
+        core::mem::size_of::<SockAddrUn>() as u32,
+    );
+    if ret >= 0 {
+        Ok(ret as usize)
+    } else {
+        Err(ret)
+    }
+}
+
+/// Receive data from an `AF_UNIX` socket. Returns (bytes_read, src_addr).
+pub fn recvfrom_unix(fd: i32, buf: &mut [u8]) -> Result<(usize, SockAddrUn), i32> {
+    let mut src_addr = SockAddrUn {
+        family: 0,
+        name: [0; UNIX_NAME_MAX],
+    };
+    let mut addr_len: u32 = core::mem::size_of::<SockAddrUn>() as u32;
+
+    let ret = syscall::recvfrom(
+        fd as u32,
+        buf.as_mut_ptr(),
+        buf.len() as u32,
+        &mut src_addr as *mut _ as *mut u8,
+        &mut addr_len as *mut u32,
+    );
+
+    if ret >= 0 {
+        Ok((ret as usize, src_addr))
+    } else {
+        Err(ret)
+    }
+}
+
+/// Mark a socket as listening with room for up to `backlog` accepted
+/// connections nobody's called `accept` for yet.
+pub fn listen(fd: i32, backlog: u32) -> Result<(), i32> {
+    let ret = syscall::listen(fd as u32, backlog);
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(ret)
+    }
+}
+
+/// Block until a peer connects to a listening socket, returning the new
+/// connection's fd and its address.
+pub fn accept(fd: i32) -> Result<(i32, SockAddrIn), i32> {
+    let mut peer_addr = SockAddrIn {
+        family: 0,
+        port: 0,
+        addr: 0,
+        zero: [0; 8],
+    };
+    let mut addr_len: u32 = core::mem::size_of::<SockAddrIn>() as u32;
+
+    let ret = syscall::accept(
+        fd as u32,
+        &mut peer_addr as *mut _ as *mut u8,
+        &mut addr_len as *mut u32,
+    );
+    if ret >= 0 {
+        Ok((ret, peer_addr))
+    } else {
+        Err(ret)
+    }
+}
+
+/// Connect a `SOCK_STREAM` socket to `addr`: a real TCP handshake unless
+/// `addr` names this kernel's own loopback address.
+pub fn connect(fd: i32, addr: &SockAddrIn) -> Result<(), i32> {
+    let ret = syscall::connect(fd as u32, addr as *const _ as *const u8);
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(ret)
+    }
+}
+
+/// Send on a connected `SOCK_STREAM` socket.
+pub fn send(fd: i32, buf: &[u8]) -> Result<usize, i32> {
+    let ret = syscall::send(fd as u32, buf.as_ptr(), buf.len() as u32);
+    if ret >= 0 {
+        Ok(ret as usize)
+    } else {
+        Err(ret)
+    }
+}
+
+/// Receive on a connected `SOCK_STREAM` socket. `Ok(0)` means the peer has
+/// finished sending.
+pub fn recv(fd: i32, buf: &mut [u8]) -> Result<usize, i32> {
+    let ret = syscall::recv(fd as u32, buf.as_mut_ptr(), buf.len() as u32);
+    if ret >= 0 {
+        Ok(ret as usize)
+    } else {
+        Err(ret)
+    }
+}
+
+/// `poll(2)`'s `POLLIN`/`POLLOUT` event bits, matching
+/// `ferrous_kernel::net`'s constants of the same names.
+pub const POLLIN: u32 = 0x0001;
+pub const POLLOUT: u32 = 0x0004;
+
+/// `timeout_ms` sentinel meaning "block forever", matching
+/// `ferrous_kernel::syscall::POLL_NO_TIMEOUT`.
+pub const POLL_NO_TIMEOUT: u32 = u32::MAX;
+
+/// `recvfrom`'s error code for "a non-blocking socket has nothing
+/// ready", matching `ferrous_kernel::net::EWOULDBLOCK`.
+pub const EWOULDBLOCK: i32 = -11;
+
+/// One fd/interest pair for `poll`. Mirrors `ferrous_kernel::net::PollFd`'s
+/// layout; `revents` is overwritten in place once `poll` returns.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    pub fd: u32,
+    pub events: u32,
+    pub revents: u32,
+}
+
+impl PollFd {
+    pub fn new(fd: i32, events: u32) -> Self {
+        Self { fd: fd as u32, events, revents: 0 }
+    }
+}
+
+/// Block until any of `fds`'s entries is ready or `timeout_ms` elapses
+/// (`POLL_NO_TIMEOUT` to wait forever, `0` to check and return
+/// immediately). Each entry's `revents` is overwritten in place; returns
+/// the count that became ready.
+pub fn poll(fds: &mut [PollFd], timeout_ms: u32) -> usize {
+    let nfds = fds.len() as u32;
+    let bytes = unsafe {
+        core::slice::from_raw_parts_mut(
+            fds.as_mut_ptr() as *mut u8,
+            fds.len() * core::mem::size_of::<PollFd>(),
+        )
+    };
+    syscall::poll(bytes, nfds, timeout_ms).max(0) as usize
+}
+
+/// Set (`true`) or clear (`false`) `fd`'s non-blocking flag: once set, a
+/// `recvfrom` against it with nothing ready returns `EWOULDBLOCK` instead
+/// of silently returning `0`. `recv` ignores the flag and still blocks
+/// until data arrives.
+pub fn set_non_blocking(fd: i32, non_blocking: bool) -> Result<(), i32> {
+    syscall::set_non_blocking(fd as u32, non_blocking).map_err(|e| e as i32)
+}
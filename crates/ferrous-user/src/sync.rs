@@ -24,3 +24,39 @@ impl Mutex {
         syscall::mutex_release(self.id);
     }
 }
+
+/// A futex-style condition variable, always used alongside the `Mutex` it's
+/// waited on with: `wait` releases that mutex and parks the caller in one
+/// syscall so a concurrent `notify_one`/`notify_all` can't be lost in the
+/// gap between releasing and actually going to sleep.
+pub struct Condvar {
+    id: u32,
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        let id = syscall::condvar_create();
+        Self { id }
+    }
+
+    /// Release `mutex`, block until woken by `notify_one`/`notify_all`, then
+    /// reacquire `mutex` before returning -- the caller always gets it back
+    /// held, the same contract `std::sync::Condvar::wait` has.
+    pub fn wait(&self, mutex: &Mutex) {
+        syscall::condvar_wait(self.id, mutex.id);
+    }
+
+    pub fn notify_one(&self) {
+        syscall::condvar_notify_one(self.id);
+    }
+
+    pub fn notify_all(&self) {
+        syscall::condvar_notify_all(self.id);
+    }
+}
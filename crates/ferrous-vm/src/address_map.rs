@@ -0,0 +1,85 @@
+use crate::error::MemoryError;
+use std::ops::Range;
+
+/// What a region of the address space resolves to. `SystemBus` looks one of
+/// these up for every access instead of comparing `addr.0` against a single
+/// hard-coded RAM/device boundary, so RAM, ROM, and the MMIO window can each
+/// live at whatever address their builder call places them at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressTarget {
+    Ram,
+    Rom,
+    /// The MMIO window as a whole; which individual device within it
+    /// actually handles the access is `DeviceManager`'s job, not the map's.
+    Device,
+    Controller,
+    /// The DMA engine's register window. Reached directly off `SystemBus`
+    /// like `Controller`, rather than through `DeviceManager`, since
+    /// servicing a descriptor chain needs direct access to RAM that the
+    /// uniform `Device::read`/`write` interface doesn't provide.
+    Dma,
+    /// The virtqueue-based block device's register window. Reached
+    /// directly off `SystemBus` for the same reason as `Dma`: draining a
+    /// ring needs RAM and the registered block device at once.
+    VirtioBlk,
+}
+
+#[derive(Clone)]
+struct Region {
+    range: Range<u32>,
+    target: AddressTarget,
+}
+
+/// Ordered, non-overlapping set of address regions backing `SystemBus`.
+/// Regions are kept sorted by start address so a lookup is a binary search
+/// rather than the linear `addr.0 >= 0x8000_0000` comparison it replaces.
+#[derive(Default)]
+pub struct AddressMap {
+    regions: Vec<Region>,
+}
+
+impl AddressMap {
+    pub fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+        }
+    }
+
+    /// Insert `range` as `target`, keeping `regions` sorted by start
+    /// address. Fails without modifying the map if `range` overlaps a
+    /// region already present.
+    pub fn add(&mut self, range: Range<u32>, target: AddressTarget) -> Result<(), MemoryError> {
+        let idx = self.regions.partition_point(|r| r.range.start < range.start);
+
+        if let Some(prev) = idx.checked_sub(1).and_then(|i| self.regions.get(i)) {
+            if prev.range.end > range.start {
+                return Err(MemoryError::OverlappingRegion {
+                    new: (range.start, range.end),
+                    existing: (prev.range.start, prev.range.end),
+                });
+            }
+        }
+        if let Some(next) = self.regions.get(idx) {
+            if range.end > next.range.start {
+                return Err(MemoryError::OverlappingRegion {
+                    new: (range.start, range.end),
+                    existing: (next.range.start, next.range.end),
+                });
+            }
+        }
+
+        self.regions.insert(idx, Region { range, target });
+        Ok(())
+    }
+
+    /// The target containing `addr`, plus `addr`'s offset from that
+    /// region's start, or `None` if no region claims it.
+    pub fn lookup(&self, addr: u32) -> Option<(AddressTarget, u32)> {
+        let idx = self.regions.partition_point(|r| r.range.start <= addr);
+        let region = self.regions.get(idx.checked_sub(1)?)?;
+        region
+            .range
+            .contains(&addr)
+            .then(|| (region.target, addr - region.range.start))
+    }
+}
@@ -0,0 +1,122 @@
+//! A translation-block cache sitting in front of instruction fetch,
+//! following the same motivation as moa's own block cache: `step()` was
+//! re-running `Memory::read_word` + `Instruction::decode` on literally
+//! every instruction, including the ones a tight loop re-fetches thousands
+//! of times unchanged. Caching is keyed by physical page (not virtual --
+//! that's what the bytes actually depend on) and lazily fills one slot at
+//! a time rather than eagerly decoding a whole page up front, since a page
+//! mixing code with data (or padding) would otherwise fail to decode
+//! before a single instruction from it ever ran.
+use crate::error::VmError;
+use crate::instruction::Instruction;
+use crate::memory::{Memory, PhysAddr};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+
+const PAGE_SIZE: u32 = 4096;
+const WORDS_PER_PAGE: usize = (PAGE_SIZE / 4) as usize;
+
+fn page_of(addr: u32) -> u32 {
+    addr & !(PAGE_SIZE - 1)
+}
+
+/// One physical page's worth of fetch slots, filled in on demand. Each
+/// slot holds the raw word alongside its decode so a cache hit doesn't
+/// need to recompute the word for `TrapCause::IllegalInstruction`'s
+/// diagnostic.
+struct Block {
+    slots: Box<[Option<(u32, Instruction)>]>,
+}
+
+impl Block {
+    fn new() -> Self {
+        Self {
+            slots: vec![None; WORDS_PER_PAGE].into_boxed_slice(),
+        }
+    }
+}
+
+/// Caches decoded instructions by physical page. Disabled by default --
+/// opt in with `BlockCache::new(true)` or `set_enabled` -- so the
+/// interpreter can fall back to always re-reading and re-decoding for
+/// debugging (a stale cache entry surviving a bug in an invalidation path
+/// below would otherwise be a very confusing thing to chase).
+#[derive(Default)]
+pub struct BlockCache {
+    enabled: bool,
+    pages: BTreeMap<u32, Block>,
+}
+
+impl BlockCache {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            pages: BTreeMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabling also drops everything cached so far, so re-enabling later
+    /// doesn't resume serving instructions decoded from a page that was
+    /// modified while the cache was off.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.pages.clear();
+        }
+    }
+
+    /// Fetches and decodes the instruction at physical address `pa`,
+    /// consulting (and filling) the cache when enabled. Returns the raw
+    /// word alongside the decoded `Instruction` since `step()`'s
+    /// `TrapCause::IllegalInstruction` arm wants the word for its own
+    /// diagnostic even on a cache hit.
+    pub fn fetch(
+        &mut self,
+        memory: &mut dyn Memory,
+        pa: PhysAddr,
+    ) -> Result<(u32, Instruction), VmError> {
+        if !self.enabled {
+            let word = memory.read_word(pa)?;
+            let instruction = Instruction::decode(word)?;
+            return Ok((word, instruction));
+        }
+
+        let page = page_of(pa.val());
+        let offset = ((pa.val() - page) / 4) as usize;
+        let block = self.pages.entry(page).or_insert_with(Block::new);
+
+        if let Some(cached) = block.slots[offset] {
+            return Ok(cached);
+        }
+
+        let word = memory.read_word(pa)?;
+        let instruction = Instruction::decode(word)?;
+        block.slots[offset] = Some((word, instruction));
+        Ok((word, instruction))
+    }
+
+    /// Drops the cached block containing `pa`, if any -- called on every
+    /// `Sb`/`Sh`/`Sw` so a write to a cached page is visible the next time
+    /// that page is fetched, rather than executing whatever was decoded
+    /// before the write.
+    pub fn invalidate(&mut self, pa: u32) {
+        self.pages.remove(&page_of(pa));
+    }
+
+    /// Drops every cached block. `satp` writes and page-table edits go
+    /// through this rather than tracking which physical pages are
+    /// currently mapped executable: a changed mapping can make a
+    /// previously-unreachable (and therefore possibly stale-for-a-reason,
+    /// e.g. a reused copy-on-write frame) page reachable again, and this
+    /// cache has no cheaper way to tell which entries a new root page
+    /// table would still agree with -- the same trade `mmu::Tlb::lookup`
+    /// already makes on every `satp` write.
+    pub fn invalidate_all(&mut self) {
+        self.pages.clear();
+    }
+}
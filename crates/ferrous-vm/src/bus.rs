@@ -0,0 +1,71 @@
+//! An emulator-hal-style address/error-generic bus trait, following the
+//! `BusAccess<Address, Error>` shape used by projects like `moa`'s core
+//! abstraction. `Memory` stays the concrete trait every device and the MMU
+//! actually code against (see its own doc comment for why reads take
+//! `&mut self`); `BusAccess` is a thinner, parameterized view over it so a
+//! second core implementation -- one that isn't RV32I, or a test harness
+//! core with its own address width -- could drive a bus without depending
+//! on `PhysAddr`/`MemoryError` directly.
+//!
+//! `VirtualMachine` itself is not generic over this yet: `step()`'s
+//! instruction handling is woven through this crate's concrete `Cpu`,
+//! `mmu::translate`, and `TrapHandler` (satp/mstatus-aware privilege
+//! checks, page-crossing slow paths, etc.), so making `VirtualMachine<C,
+//! B>` generic is a larger follow-up that touches every instruction arm,
+//! not something this trait alone unlocks. This lays the groundwork --
+//! a blanket impl below means every existing `Memory` implementor (devices
+//! included) already satisfies `BusAccess<PhysAddr, MemoryError>` for free.
+
+use crate::memory::Memory;
+use crate::{MemoryError, PhysAddr};
+
+/// A bus a CPU core reads and writes addressable units on, parameterized
+/// over its address and error types so a core with a different address
+/// width or error representation isn't forced to adopt this crate's.
+pub trait BusAccess<Address, Error> {
+    fn read8(&mut self, addr: Address) -> Result<u8, Error>;
+    fn write8(&mut self, addr: Address, val: u8) -> Result<(), Error>;
+
+    fn read32(&mut self, addr: Address) -> Result<u32, Error>
+    where
+        Address: Copy + core::ops::Add<u32, Output = Address>,
+    {
+        let b0 = self.read8(addr)? as u32;
+        let b1 = self.read8(addr + 1)? as u32;
+        let b2 = self.read8(addr + 2)? as u32;
+        let b3 = self.read8(addr + 3)? as u32;
+        Ok(b0 | (b1 << 8) | (b2 << 16) | (b3 << 24))
+    }
+
+    fn write32(&mut self, addr: Address, val: u32) -> Result<(), Error>
+    where
+        Address: Copy + core::ops::Add<u32, Output = Address>,
+    {
+        self.write8(addr, (val & 0xFF) as u8)?;
+        self.write8(addr + 1, ((val >> 8) & 0xFF) as u8)?;
+        self.write8(addr + 2, ((val >> 16) & 0xFF) as u8)?;
+        self.write8(addr + 3, ((val >> 24) & 0xFF) as u8)?;
+        Ok(())
+    }
+}
+
+/// Every `Memory` implementor already speaks `BusAccess<PhysAddr,
+/// MemoryError>` through its `read_byte`/`write_byte` -- existing devices
+/// and `SimpleMemory` don't need to change to participate.
+impl<M: Memory + ?Sized> BusAccess<PhysAddr, MemoryError> for M {
+    fn read8(&mut self, addr: PhysAddr) -> Result<u8, MemoryError> {
+        self.read_byte(addr)
+    }
+
+    fn write8(&mut self, addr: PhysAddr, val: u8) -> Result<(), MemoryError> {
+        self.write_byte(addr, val)
+    }
+
+    fn read32(&mut self, addr: PhysAddr) -> Result<u32, MemoryError> {
+        self.read_word(addr)
+    }
+
+    fn write32(&mut self, addr: PhysAddr, val: u32) -> Result<(), MemoryError> {
+        self.write_word(addr, val)
+    }
+}
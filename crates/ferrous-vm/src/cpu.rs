@@ -135,8 +135,105 @@ impl Register {
             Err(InvalidRegister(num))
         }
     }
+
+    /// Register number (0-31), usable as an index into `Cpu::regs`.
+    pub const fn val(&self) -> usize {
+        self.0 as usize
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 #[error("Invalid register number: {0} (must be 0-31)")]
 pub struct InvalidRegister(pub u8);
+
+/// RISC-V privilege levels (machine, supervisor, user).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    User,
+    Supervisor,
+    Machine,
+}
+
+/// The hart's native integer width (`MXLEN`/`SXLEN`/`UXLEN`, assumed equal
+/// here). This is the seam the rest of the VM hangs off to eventually run
+/// RV64: today every register, address, and page-table routine is a bare
+/// `u32`, so `Xlen` doesn't change any representation yet — it only lets
+/// code that needs to know the active width (e.g. `mmu::mask_addr`) ask the
+/// `Cpu` instead of assuming RV32. Widening `regs`/`PhysAddr`/`VirtAddr` to
+/// `u64` and extending `mmu::translate` to a 3-level Sv39 walk for
+/// `Bit64` is future work; `Bit64` is unused until that lands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Xlen {
+    Bit32,
+    Bit64,
+}
+
+impl Xlen {
+    /// Number of effective address bits under Sv32 (32) vs. Sv39 (39).
+    pub const fn addr_bits(self) -> u32 {
+        match self {
+            Xlen::Bit32 => 32,
+            Xlen::Bit64 => 39,
+        }
+    }
+}
+
+/// Architectural state of a single hart: general-purpose registers, program
+/// counter, current privilege level, the `satp` register that roots the
+/// active page table (`satp == 0` selects Bare/identity translation), the
+/// Zicsr control/status register file, the RV32F floating-point register
+/// file, and the hart's native integer width.
+#[derive(Debug, Clone, Copy)]
+pub struct Cpu {
+    pub regs: [u32; 32],
+    pub fregs: [f32; 32],
+    pub pc: u32,
+    pub mode: PrivilegeMode,
+    pub satp: u32,
+    pub csr: crate::csr::Csr,
+    pub xlen: Xlen,
+}
+
+impl Cpu {
+    /// Reset a hart into Machine mode at `entry_point` with paging disabled,
+    /// all general-purpose and floating-point registers zeroed, a freshly
+    /// reset CSR file, and RV32 (`Xlen::Bit32`) as the native width.
+    pub fn new(entry_point: u32) -> Self {
+        Self {
+            regs: [0; 32],
+            fregs: [0.0; 32],
+            pc: entry_point,
+            mode: PrivilegeMode::Machine,
+            satp: 0,
+            csr: crate::csr::Csr::new(),
+            xlen: Xlen::Bit32,
+        }
+    }
+
+    /// Read a register; x0 always reads as zero.
+    pub fn read_reg(&self, reg: Register) -> u32 {
+        if reg.val() == 0 {
+            0
+        } else {
+            self.regs[reg.val()]
+        }
+    }
+
+    /// Write a register; writes to x0 are discarded.
+    pub fn write_reg(&mut self, reg: Register, val: u32) {
+        if reg.val() != 0 {
+            self.regs[reg.val()] = val;
+        }
+    }
+
+    /// Read a floating-point register. Unlike `x0`, `f0` is an ordinary
+    /// register with no hardwired value.
+    pub fn read_freg(&self, reg: Register) -> f32 {
+        self.fregs[reg.val()]
+    }
+
+    /// Write a floating-point register.
+    pub fn write_freg(&mut self, reg: Register, val: f32) {
+        self.fregs[reg.val()] = val;
+    }
+}
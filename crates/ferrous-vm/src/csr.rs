@@ -0,0 +1,202 @@
+//! Control and Status Register file (Zicsr).
+//!
+//! Holds the Machine- and Supervisor-mode registers needed for trap setup
+//! and delivery. Supervisor views (`sstatus`, `sie`, `sip`) currently alias
+//! their Machine-mode counterparts rather than masking to the delegated
+//! subset, which is good enough until the privileged-mode split is fleshed
+//! out further.
+
+/// `mstatus`: machine status (also readable/writable as `sstatus`)
+pub const MSTATUS: u16 = 0x300;
+/// `medeleg`: machine exception delegation
+pub const MEDELEG: u16 = 0x302;
+/// `mideleg`: machine interrupt delegation
+pub const MIDELEG: u16 = 0x303;
+/// `mie`: machine interrupt enable (also readable/writable as `sie`)
+pub const MIE: u16 = 0x304;
+/// `mtvec`: machine trap vector base address
+pub const MTVEC: u16 = 0x305;
+/// `mepc`: machine exception program counter
+pub const MEPC: u16 = 0x341;
+/// `mcause`: machine trap cause
+pub const MCAUSE: u16 = 0x342;
+/// `mtval`: machine bad address/instruction
+pub const MTVAL: u16 = 0x343;
+/// `mip`: machine interrupt pending (also readable/writable as `sip`)
+pub const MIP: u16 = 0x344;
+
+/// `sstatus`: supervisor status (aliases `mstatus`)
+pub const SSTATUS: u16 = 0x100;
+/// `sie`: supervisor interrupt enable (aliases `mie`)
+pub const SIE: u16 = 0x104;
+/// `stvec`: supervisor trap vector base address
+pub const STVEC: u16 = 0x105;
+/// `sepc`: supervisor exception program counter
+pub const SEPC: u16 = 0x141;
+/// `scause`: supervisor trap cause
+pub const SCAUSE: u16 = 0x142;
+/// `stval`: supervisor bad address/instruction
+pub const STVAL: u16 = 0x143;
+/// `sip`: supervisor interrupt pending (aliases `mip`)
+pub const SIP: u16 = 0x144;
+/// `satp`: supervisor address translation and protection
+pub const SATP: u16 = 0x180;
+
+/// `fflags`: floating-point accrued exception flags (low 5 bits of `fcsr`)
+pub const FFLAGS: u16 = 0x001;
+/// `frm`: floating-point dynamic rounding mode (bits [7:5] of `fcsr`)
+pub const FRM: u16 = 0x002;
+/// `fcsr`: floating-point control and status register (`frm` + `fflags`)
+pub const FCSR: u16 = 0x003;
+
+/// Bit 1 of `mstatus`/`sstatus`: Supervisor Interrupt Enable
+pub const MSTATUS_SIE: u32 = 1 << 1;
+/// Bit 3 of `mstatus`: Machine Interrupt Enable
+pub const MSTATUS_MIE: u32 = 1 << 3;
+/// Bit 5 of `mstatus`/`sstatus`: Supervisor Previous Interrupt Enable
+pub const MSTATUS_SPIE: u32 = 1 << 5;
+/// Bit 7 of `mstatus`: Machine Previous Interrupt Enable
+pub const MSTATUS_MPIE: u32 = 1 << 7;
+/// Bit 8 of `mstatus`/`sstatus`: Supervisor Previous Privilege (0=User, 1=Supervisor)
+pub const MSTATUS_SPP: u32 = 1 << 8;
+/// Bits 12:11 of `mstatus`: Machine Previous Privilege
+pub const MSTATUS_MPP_SHIFT: u32 = 11;
+pub const MSTATUS_MPP_MASK: u32 = 0b11 << MSTATUS_MPP_SHIFT;
+
+/// Bit 7 of `mip`/`mie`: Machine Timer Interrupt Pending/Enable
+pub const MIP_MTIP: u32 = 1 << 7;
+pub const MIE_MTIE: u32 = 1 << 7;
+
+/// Bit 11 of `mip`/`mie`: Machine External Interrupt Pending/Enable, set by
+/// `SystemBus` each tick when its `InterruptController` has a deliverable
+/// IRQ rather than by a CSR instruction -- same role as `MIP_MTIP`, just
+/// sourced from the PLIC instead of the CLINT.
+pub const MIP_MEIP: u32 = 1 << 11;
+pub const MIE_MEIE: u32 = 1 << 11;
+
+/// `fflags` accrued-exception bits (low 5 bits of `fcsr`), in priority order
+/// from least to most significant: inexact, underflow, overflow,
+/// divide-by-zero, invalid operation.
+pub const FFLAG_NX: u32 = 1 << 0;
+pub const FFLAG_UF: u32 = 1 << 1;
+pub const FFLAG_OF: u32 = 1 << 2;
+pub const FFLAG_DZ: u32 = 1 << 3;
+pub const FFLAG_NV: u32 = 1 << 4;
+
+/// The CSR file for a single hart.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Csr {
+    pub mstatus: u32,
+    pub medeleg: u32,
+    pub mideleg: u32,
+    pub mie: u32,
+    pub mtvec: u32,
+    pub mepc: u32,
+    pub mcause: u32,
+    pub mtval: u32,
+    pub mip: u32,
+    pub stvec: u32,
+    pub sepc: u32,
+    pub scause: u32,
+    pub stval: u32,
+    pub satp: u32,
+
+    /// CLINT-style wall-clock counter, incremented once per retired
+    /// instruction (standing in for a real fixed-frequency timer).
+    pub mtime: u64,
+    /// CLINT-style timer compare register: once `mtime >= mtimecmp`,
+    /// `mip.MTIP` is latched until software raises `mtimecmp` again.
+    pub mtimecmp: u64,
+
+    /// Floating-point control and status: bits `[7:5]` are `frm`, bits
+    /// `[4:0]` are the accrued `fflags`.
+    pub fcsr: u32,
+}
+
+impl Csr {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance `mtime` by one tick and latch/clear `mip.MTIP` by comparing
+    /// against `mtimecmp`, using wraparound-safe arithmetic so a `mtimecmp`
+    /// that has wrapped past `mtime` is still handled correctly.
+    pub fn tick_timer(&mut self) {
+        self.mtime = self.mtime.wrapping_add(1);
+        if self.mtime.wrapping_sub(self.mtimecmp) as i64 >= 0 {
+            self.mip |= MIP_MTIP;
+        } else {
+            self.mip &= !MIP_MTIP;
+        }
+    }
+
+    /// Whether a pending machine timer interrupt should actually be taken,
+    /// per `mstatus.MIE` and `mie.MTIE` gating. This is the machine-timer
+    /// half of trap-and-resume delivery (`tick_timer` latches `mip.MTIP`
+    /// above, `Mret` restores `mstatus`/`pc` from `mepc` on the way back
+    /// out); the other half -- an external-interrupt controller that
+    /// aggregates per-device IRQs by priority instead of the net driver's
+    /// `process_rx` busy-poll -- is a PLIC-shaped device, not a CSR, and
+    /// lives in `devices::plic`; `external_interrupt_pending` below is its
+    /// CSR-side gate.
+    pub fn timer_interrupt_pending(&self) -> bool {
+        self.mip & MIP_MTIP != 0 && self.mie & MIE_MTIE != 0 && self.mstatus & MSTATUS_MIE != 0
+    }
+
+    /// The `InterruptController` counterpart to `timer_interrupt_pending`:
+    /// whether a PLIC-reported external interrupt should actually be
+    /// taken, per the same `mstatus.MIE` gating plus `mie.MEIE`.
+    pub fn external_interrupt_pending(&self) -> bool {
+        self.mip & MIP_MEIP != 0 && self.mie & MIE_MEIE != 0 && self.mstatus & MSTATUS_MIE != 0
+    }
+
+    /// Read a CSR by its 12-bit address. Unrecognized addresses read as 0
+    /// (the decoder is responsible for rejecting truly invalid CSRs).
+    pub fn read(&self, addr: u16) -> u32 {
+        match addr {
+            MSTATUS | SSTATUS => self.mstatus,
+            MEDELEG => self.medeleg,
+            MIDELEG => self.mideleg,
+            MIE | SIE => self.mie,
+            MTVEC => self.mtvec,
+            MEPC => self.mepc,
+            MCAUSE => self.mcause,
+            MTVAL => self.mtval,
+            MIP | SIP => self.mip,
+            STVEC => self.stvec,
+            SEPC => self.sepc,
+            SCAUSE => self.scause,
+            STVAL => self.stval,
+            SATP => self.satp,
+            FFLAGS => self.fcsr & 0x1F,
+            FRM => (self.fcsr >> 5) & 0x7,
+            FCSR => self.fcsr & 0xFF,
+            _ => 0,
+        }
+    }
+
+    /// Write a CSR by its 12-bit address. Unrecognized addresses are
+    /// ignored.
+    pub fn write(&mut self, addr: u16, val: u32) {
+        match addr {
+            MSTATUS | SSTATUS => self.mstatus = val,
+            MEDELEG => self.medeleg = val,
+            MIDELEG => self.mideleg = val,
+            MIE | SIE => self.mie = val,
+            MTVEC => self.mtvec = val,
+            MEPC => self.mepc = val,
+            MCAUSE => self.mcause = val,
+            MTVAL => self.mtval = val,
+            MIP | SIP => self.mip = val,
+            STVEC => self.stvec = val,
+            SEPC => self.sepc = val,
+            SCAUSE => self.scause = val,
+            STVAL => self.stval = val,
+            SATP => self.satp = val,
+            FFLAGS => self.fcsr = (self.fcsr & !0x1F) | (val & 0x1F),
+            FRM => self.fcsr = (self.fcsr & !0xE0) | ((val & 0x7) << 5),
+            FCSR => self.fcsr = val & 0xFF,
+            _ => {}
+        }
+    }
+}
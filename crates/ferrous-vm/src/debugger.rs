@@ -0,0 +1,137 @@
+//! Mirrors moa's `Debugger`: `VirtualMachine::run` consults one of these
+//! between steps (and, for watchpoints, from inside `translate`, the one
+//! place every data access already passes through) instead of always
+//! running the trap handler or looping unconditionally. Attaching one is
+//! opt-in via `VirtualMachine::set_debugger` -- with none attached, `run()`
+//! behaves exactly as it always has (`ebreak` is still an unhandled trap).
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A memory watchpoint over `addr..addr + len`, checked against every data
+/// access `VirtualMachine::translate` resolves (not instruction fetch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub addr: u32,
+    pub len: u32,
+    pub on_read: bool,
+    pub on_write: bool,
+}
+
+impl Watchpoint {
+    fn contains(&self, addr: u32) -> bool {
+        addr.wrapping_sub(self.addr) < self.len
+    }
+}
+
+/// Why `VirtualMachine::run` handed control back to its caller instead of
+/// continuing the loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// `cpu.pc` matched a registered breakpoint before the instruction
+    /// there executed.
+    Breakpoint(u32),
+    /// A watchpoint's address range was read or written.
+    Watchpoint { addr: u32, is_write: bool },
+    /// An `ebreak` instruction executed.
+    Ebreak,
+    /// Single-step mode: one instruction ran and `run()` is handing back.
+    Step,
+}
+
+/// Debugger state attached to a `VirtualMachine`: PC breakpoints, memory
+/// watchpoints, single-step mode, and a trace log -- no I/O or command
+/// parsing lives here, that's for whatever drives the VM (a CLI REPL, a
+/// GDB stub) to build on top of these primitives.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u32>,
+    watchpoints: Vec<Watchpoint>,
+    single_step: bool,
+    trace: bool,
+    trace_log: Vec<String>,
+    pending_stop: Option<StopReason>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_breakpoint(&mut self, pc: u32) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: u32) {
+        self.breakpoints.remove(&pc);
+    }
+
+    pub fn has_breakpoint(&self, pc: u32) -> bool {
+        self.breakpoints.contains(&pc)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u32> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    /// Removes every watchpoint starting at `addr` (there's normally just
+    /// one, but nothing stops a caller registering overlapping ranges).
+    pub fn remove_watchpoint(&mut self, addr: u32) {
+        self.watchpoints.retain(|w| w.addr != addr);
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn set_single_step(&mut self, enabled: bool) {
+        self.single_step = enabled;
+    }
+
+    pub fn single_step(&self) -> bool {
+        self.single_step
+    }
+
+    pub fn set_trace(&mut self, enabled: bool) {
+        self.trace = enabled;
+    }
+
+    pub fn trace(&self) -> bool {
+        self.trace
+    }
+
+    /// Appends a line to the trace log; a no-op cost check belongs to the
+    /// caller (`VirtualMachine::step` only formats a line when `trace()` is
+    /// true in the first place).
+    pub fn log_trace(&mut self, line: String) {
+        self.trace_log.push(line);
+    }
+
+    /// Drains whatever trace lines have accumulated since the last call.
+    pub fn take_trace(&mut self) -> Vec<String> {
+        core::mem::take(&mut self.trace_log)
+    }
+
+    /// Called from `VirtualMachine::translate` on every data access. Can't
+    /// return the hit directly -- `translate`'s `Result<PhysAddr,
+    /// TrapCause>` has no room for a third outcome -- so it latches a
+    /// pending stop that `run()` picks up with `take_pending_stop` once
+    /// `step()` returns.
+    pub(crate) fn check_watchpoint(&mut self, addr: u32, is_write: bool) {
+        let hit = self.watchpoints.iter().any(|w| {
+            w.contains(addr) && if is_write { w.on_write } else { w.on_read }
+        });
+        if hit {
+            self.pending_stop = Some(StopReason::Watchpoint { addr, is_write });
+        }
+    }
+
+    pub(crate) fn take_pending_stop(&mut self) -> Option<StopReason> {
+        self.pending_stop.take()
+    }
+}
@@ -10,8 +10,8 @@ pub const BLOCK_DEVICE_SIZE: u32 = 0x1000; // 4KB (enough for buffer)
 const REG_STATUS: u32 = 0x00; // Read-only: 0=Ready, 1=Busy
 const REG_COMMAND: u32 = 0x04; // Write-only: 1=Read, 2=Write
 const REG_SECTOR: u32 = 0x08; // Sector number to access
-                              // const REG_BUFFER: u32 = 0x0C; // Pointer to memory buffer (Physical Address) - Unused in PIO mode
-                              // const REG_DATA: u32 = 0x10; // Data port - Unused
+const REG_CAPACITY: u32 = 0x0C; // Read-only: total sectors the backing image holds
+                                 // const REG_DATA: u32 = 0x10; // Data port - Unused
 
 // Note: To implement DMA (Direct Memory Access), the Device needs access to System RAM.
 // However, our current Device trait structure only allows read/write to the DEVICE registers.
@@ -24,25 +24,127 @@ const REG_SECTOR: u32 = 0x08; // Sector number to access
 
 const SECTOR_SIZE: usize = 512;
 
+/// Marks a disk image as `ferrous-mkfs --sparse` output rather than a dense
+/// one-sector-per-`SECTOR_SIZE`-bytes image: the ASCII bytes `"CISO"`,
+/// read as a little-endian `u32`, the same way a real CISO tool's magic
+/// would show up in a hex dump.
+const CISO_MAGIC: u32 = 0x4F53_4943;
+
+/// `magic + header_size + block_size + total_blocks`, each a little-endian
+/// `u32` -- the index table (`total_blocks` more little-endian `u32`s)
+/// starts immediately after these 16 bytes.
+const CISO_HEADER_LEN: u64 = 16;
+
+/// An index-table entry value meaning "this block is all zero and was
+/// omitted from the file" rather than a real payload offset.
+const CISO_ZERO_SENTINEL: u32 = 0xFFFF_FFFF;
+
+/// Parsed form of a CISO-sparse image's header + index table, kept in
+/// memory for the life of the device so every sector access is one lookup
+/// plus one seek rather than a header re-read per command.
+struct SparseIndex {
+    total_blocks: u32,
+    /// Byte offset of entry 0's slot, i.e. `CISO_HEADER_LEN + total_blocks * 4`.
+    payload_base: u64,
+    /// One slot per logical block: `CISO_ZERO_SENTINEL` or the block's
+    /// index (in `SECTOR_SIZE` units) into the payload area.
+    entries: Vec<u32>,
+}
+
+impl SparseIndex {
+    /// Read and validate a CISO header + index table off the front of
+    /// `file`, returning `None` if it isn't one (a dense image, most
+    /// commonly) so the caller falls back to the dense path untouched.
+    fn parse(file: &mut File) -> std::io::Result<Option<Self>> {
+        file.seek(SeekFrom::Start(0))?;
+        let mut header = [0u8; CISO_HEADER_LEN as usize];
+        if file.read_exact(&mut header).is_err() {
+            return Ok(None);
+        }
+        let magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        if magic != CISO_MAGIC {
+            return Ok(None);
+        }
+        let header_size = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let block_size = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let total_blocks = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        if block_size as usize != SECTOR_SIZE {
+            return Ok(None);
+        }
+
+        let mut entries = vec![0u32; total_blocks as usize];
+        let mut raw = vec![0u8; total_blocks as usize * 4];
+        file.seek(SeekFrom::Start(header_size as u64))?;
+        file.read_exact(&mut raw)?;
+        for (i, entry) in entries.iter_mut().enumerate() {
+            *entry = u32::from_le_bytes(raw[i * 4..i * 4 + 4].try_into().unwrap());
+        }
+
+        Ok(Some(Self {
+            total_blocks,
+            payload_base: header_size as u64 + total_blocks as u64 * 4,
+            entries,
+        }))
+    }
+
+    /// Persist the single index slot for `sector` back to its spot in the
+    /// on-disk table, after a write gave it a fresh payload location.
+    fn write_entry(&self, file: &mut File, sector: u32) -> std::io::Result<()> {
+        let slot = CISO_HEADER_LEN + sector as u64 * 4;
+        file.seek(SeekFrom::Start(slot))?;
+        file.write_all(&self.entries[sector as usize].to_le_bytes())
+    }
+}
+
+/// One above `DmaController`'s `DMA_IRQ`, the next free line after the DMA
+/// engine's own.
+pub const BLOCK_IRQ: u32 = 9;
+
+/// `tick()` calls (each one a VM instruction, not a kernel timer tick)
+/// `REG_STATUS` reports a command as Busy before `tick()` raises the
+/// completion IRQ -- long enough that a thread blocked on the new async
+/// `BlockRead` path genuinely overlaps disk latency with another thread's
+/// work instead of the command resolving within the same instruction it
+/// was issued on.
+const COMMAND_LATENCY_TICKS: u32 = 64;
+
 pub struct SimpleBlockDevice {
     file: File,
     sector: u32,
     buffer: [u8; SECTOR_SIZE],
+    /// Ticks left before `REG_STATUS` reports the command issued by the
+    /// last `REG_COMMAND` write as retired and `tick()` raises its
+    /// completion IRQ. The `seek`/`read_exact`/`write_all` itself already
+    /// ran synchronously inside `write()`, as it always has -- `fs::block`'s
+    /// existing callers round-trip a `write_word` straight into a
+    /// `read_word` with nothing in between to drive `tick()`, so deferring
+    /// the actual I/O to `tick()` would leave them reading a buffer that
+    /// was never filled. This only simulates the busy window and IRQ a
+    /// caller that polls `REG_STATUS` or waits for the IRQ sees.
+    busy_ticks: u32,
+    /// `Some` when `path` is a `ferrous-mkfs --sparse` image rather than a
+    /// dense one, redirecting every sector read/write through the index
+    /// table instead of a direct `sector * SECTOR_SIZE` seek.
+    sparse: Option<SparseIndex>,
 }
 
 impl SimpleBlockDevice {
     pub fn new(path: &str) -> std::io::Result<Self> {
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .truncate(false)
             .open(path)?;
 
+        let sparse = SparseIndex::parse(&mut file)?;
+
         Ok(Self {
             file,
             sector: 0,
             buffer: [0; SECTOR_SIZE],
+            busy_ticks: 0,
+            sparse,
         })
     }
 }
@@ -65,8 +167,19 @@ impl Device for SimpleBlockDevice {
         }
 
         match offset {
-            REG_STATUS => Ok(0), // Always ready for now
+            REG_STATUS => Ok((self.busy_ticks > 0) as u32), // 1 = Busy, 0 = Ready
             REG_SECTOR => Ok(self.sector),
+            REG_CAPACITY => Ok(match &self.sparse {
+                // A sparse image's file length reflects only the blocks it
+                // bothered to store, not the logical disk size the guest
+                // should see.
+                Some(index) => index.total_blocks,
+                None => self
+                    .file
+                    .metadata()
+                    .map(|m| (m.len() / SECTOR_SIZE as u64) as u32)
+                    .unwrap_or(0),
+            }),
             _ => Ok(0),
         }
     }
@@ -95,19 +208,74 @@ impl Device for SimpleBlockDevice {
                 match val {
                     1 => {
                         // Read from Disk to Buffer
-                        let pos = (self.sector as u64) * (SECTOR_SIZE as u64);
-                        if self.file.seek(SeekFrom::Start(pos)).is_err() {
-                            // Only error if seek fails hard, else assume 0s or similar?
-                            // For simplicity, do nothing or log
+                        match &self.sparse {
+                            Some(index) => {
+                                let entry = index
+                                    .entries
+                                    .get(self.sector as usize)
+                                    .copied()
+                                    .unwrap_or(CISO_ZERO_SENTINEL);
+                                if entry == CISO_ZERO_SENTINEL {
+                                    self.buffer = [0; SECTOR_SIZE];
+                                } else {
+                                    let pos =
+                                        index.payload_base + entry as u64 * SECTOR_SIZE as u64;
+                                    let _ = self.file.seek(SeekFrom::Start(pos));
+                                    let _ = self.file.read_exact(&mut self.buffer);
+                                }
+                            }
+                            None => {
+                                let pos = (self.sector as u64) * (SECTOR_SIZE as u64);
+                                if self.file.seek(SeekFrom::Start(pos)).is_err() {
+                                    // Only error if seek fails hard, else assume 0s or similar?
+                                    // For simplicity, do nothing or log
+                                }
+                                let _ = self.file.read_exact(&mut self.buffer); // Ignore EOF errors (partial read)
+                            }
                         }
-                        let _ = self.file.read_exact(&mut self.buffer); // Ignore EOF errors (partial read)
+                        self.busy_ticks = COMMAND_LATENCY_TICKS;
                         Ok(())
                     }
                     2 => {
                         // Write from Buffer to Disk
-                        let pos = (self.sector as u64) * (SECTOR_SIZE as u64);
-                        let _ = self.file.seek(SeekFrom::Start(pos));
-                        let _ = self.file.write_all(&self.buffer);
+                        match &mut self.sparse {
+                            Some(index) => {
+                                let existing = index
+                                    .entries
+                                    .get(self.sector as usize)
+                                    .copied()
+                                    .unwrap_or(CISO_ZERO_SENTINEL);
+                                let entry = if existing != CISO_ZERO_SENTINEL {
+                                    existing
+                                } else {
+                                    // This block had no payload yet -- append
+                                    // one at the end of the file rather than
+                                    // shuffling every later entry down to
+                                    // keep a dense ordering.
+                                    let new_entry =
+                                        ((self.file.metadata().map(|m| m.len()).unwrap_or(0)
+                                            - index.payload_base)
+                                            / SECTOR_SIZE as u64) as u32;
+                                    if let Some(slot) =
+                                        index.entries.get_mut(self.sector as usize)
+                                    {
+                                        *slot = new_entry;
+                                    }
+                                    new_entry
+                                };
+                                let _ = index.write_entry(&mut self.file, self.sector);
+                                let pos =
+                                    index.payload_base + entry as u64 * SECTOR_SIZE as u64;
+                                let _ = self.file.seek(SeekFrom::Start(pos));
+                                let _ = self.file.write_all(&self.buffer);
+                            }
+                            None => {
+                                let pos = (self.sector as u64) * (SECTOR_SIZE as u64);
+                                let _ = self.file.seek(SeekFrom::Start(pos));
+                                let _ = self.file.write_all(&self.buffer);
+                            }
+                        }
+                        self.busy_ticks = COMMAND_LATENCY_TICKS;
                         Ok(())
                     }
                     _ => Ok(()),
@@ -121,6 +289,116 @@ impl Device for SimpleBlockDevice {
         "virtio-block-simple"
     }
 
+    fn tick(&mut self) -> Result<Option<DeviceInterrupt>, DeviceError> {
+        if self.busy_ticks > 0 {
+            self.busy_ticks -= 1;
+            if self.busy_ticks == 0 {
+                return Ok(Some(DeviceInterrupt {
+                    device_name: "virtio-block".into(),
+                    irq_number: BLOCK_IRQ,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The same PIO register protocol as `SimpleBlockDevice`, but backed by an
+/// in-memory image instead of a host file — what an initrd is mounted on.
+/// A sector read past the end of the image comes back as zeros; a sector
+/// write past the end grows the image to hold it.
+pub struct MemBlockDevice {
+    image: Vec<u8>,
+    sector: u32,
+    buffer: [u8; SECTOR_SIZE],
+}
+
+impl MemBlockDevice {
+    pub fn new(image: Vec<u8>) -> Self {
+        Self {
+            image,
+            sector: 0,
+            buffer: [0; SECTOR_SIZE],
+        }
+    }
+}
+
+impl Device for MemBlockDevice {
+    fn read(&mut self, offset: u32) -> Result<u32, DeviceError> {
+        if offset >= 0x100 && offset < 0x100 + SECTOR_SIZE as u32 {
+            let idx = (offset - 0x100) as usize;
+            if idx + 4 > SECTOR_SIZE {
+                return Err(DeviceError::InvalidOffset(offset));
+            }
+            let val = u32::from_le_bytes([
+                self.buffer[idx],
+                self.buffer[idx + 1],
+                self.buffer[idx + 2],
+                self.buffer[idx + 3],
+            ]);
+            return Ok(val);
+        }
+
+        match offset {
+            REG_STATUS => Ok(0),
+            REG_SECTOR => Ok(self.sector),
+            REG_CAPACITY => Ok((self.image.len() / SECTOR_SIZE) as u32),
+            _ => Ok(0),
+        }
+    }
+
+    fn write(&mut self, offset: u32, val: u32) -> Result<(), DeviceError> {
+        if offset >= 0x100 && offset < 0x100 + SECTOR_SIZE as u32 {
+            let idx = (offset - 0x100) as usize;
+            if idx + 4 > SECTOR_SIZE {
+                return Err(DeviceError::InvalidOffset(offset));
+            }
+            let bytes = val.to_le_bytes();
+            self.buffer[idx] = bytes[0];
+            self.buffer[idx + 1] = bytes[1];
+            self.buffer[idx + 2] = bytes[2];
+            self.buffer[idx + 3] = bytes[3];
+            return Ok(());
+        }
+
+        match offset {
+            REG_SECTOR => {
+                self.sector = val;
+                Ok(())
+            }
+            REG_COMMAND => {
+                match val {
+                    1 => {
+                        // Read from image into buffer, zero-filling past EOF.
+                        self.buffer = [0; SECTOR_SIZE];
+                        let start = self.sector as usize * SECTOR_SIZE;
+                        if start < self.image.len() {
+                            let end = (start + SECTOR_SIZE).min(self.image.len());
+                            self.buffer[..end - start].copy_from_slice(&self.image[start..end]);
+                        }
+                        Ok(())
+                    }
+                    2 => {
+                        // Write from buffer into image, growing it if needed.
+                        let start = self.sector as usize * SECTOR_SIZE;
+                        let end = start + SECTOR_SIZE;
+                        if self.image.len() < end {
+                            self.image.resize(end, 0);
+                        }
+                        self.image[start..end].copy_from_slice(&self.buffer);
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "initrd-block"
+    }
+
     fn tick(&mut self) -> Result<Option<DeviceInterrupt>, DeviceError> {
         Ok(None)
     }
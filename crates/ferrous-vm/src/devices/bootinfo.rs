@@ -0,0 +1,58 @@
+use crate::devices::{Device, DeviceInterrupt};
+use crate::error::DeviceError;
+
+pub const BOOTINFO_BASE: u32 = 0x1000_1000;
+pub const BOOTINFO_SIZE: u32 = 0x1000;
+
+// Register Offsets
+const REG_CMDLINE_LEN: u32 = 0x00; // Read-only: length of the cmdline string, in bytes
+const REG_INITRD_BASE: u32 = 0x04; // Read-only: guest physical base of the initrd image, 0 if none
+const REG_INITRD_LEN: u32 = 0x08; // Read-only: length of the initrd image in bytes, 0 if none
+const CMDLINE_DATA: u32 = 0x100; // One byte per word from here, 0 past REG_CMDLINE_LEN
+
+/// Read-only boot-info window a guest kernel queries at startup for the
+/// `--append` command line and the initrd's location, instead of either
+/// value being baked into the ELF.
+pub struct BootInfoDevice {
+    cmdline: Vec<u8>,
+    initrd_base: u32,
+    initrd_len: u32,
+}
+
+impl BootInfoDevice {
+    pub fn new(cmdline: String, initrd_base: u32, initrd_len: u32) -> Self {
+        Self {
+            cmdline: cmdline.into_bytes(),
+            initrd_base,
+            initrd_len,
+        }
+    }
+}
+
+impl Device for BootInfoDevice {
+    fn name(&self) -> &str {
+        "bootinfo"
+    }
+
+    fn read(&mut self, offset: u32) -> Result<u32, DeviceError> {
+        match offset {
+            REG_CMDLINE_LEN => Ok(self.cmdline.len() as u32),
+            REG_INITRD_BASE => Ok(self.initrd_base),
+            REG_INITRD_LEN => Ok(self.initrd_len),
+            _ if offset >= CMDLINE_DATA => {
+                let index = ((offset - CMDLINE_DATA) / 4) as usize;
+                Ok(self.cmdline.get(index).copied().unwrap_or(0) as u32)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn write(&mut self, _offset: u32, _value: u32) -> Result<(), DeviceError> {
+        // Read-only device; the guest has nothing to configure here.
+        Ok(())
+    }
+
+    fn tick(&mut self) -> Result<Option<DeviceInterrupt>, DeviceError> {
+        Ok(None)
+    }
+}
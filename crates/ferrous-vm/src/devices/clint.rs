@@ -0,0 +1,102 @@
+use crate::devices::{Device, DeviceInterrupt};
+use crate::error::DeviceError;
+
+pub const CLINT_BASE: u32 = 0x0200_0000;
+pub const CLINT_SIZE: u32 = 0x1_0000;
+
+// Register offsets, matching the SiFive CLINT layout.
+const MSIP: u32 = 0x0000; // Machine software interrupt pending (hart 0 only)
+const MTIMECMP_LO: u32 = 0x4000;
+const MTIMECMP_HI: u32 = 0x4004;
+const MTIME_LO: u32 = 0xBFF8;
+const MTIME_HI: u32 = 0xBFFC;
+
+/// IRQ number reported for a CLINT timer interrupt (machine timer, per the
+/// platform-level interrupt numbering convention).
+const TIMER_IRQ: u32 = 7;
+
+/// A memory-mapped CLINT exposing `mtime`/`mtimecmp` as bus registers,
+/// rather than the `Csr` shortcut used when the CPU models the timer
+/// internally. `mtime` advances once per `tick()`; once it reaches
+/// `mtimecmp` the device reports a timer interrupt exactly once, until
+/// software rearms it by writing a new `mtimecmp`.
+pub struct ClintDevice {
+    mtime: u64,
+    mtimecmp: u64,
+    msip: u32,
+    fired: bool,
+}
+
+impl Default for ClintDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClintDevice {
+    pub fn new() -> Self {
+        Self {
+            mtime: 0,
+            mtimecmp: u64::MAX,
+            msip: 0,
+            fired: false,
+        }
+    }
+}
+
+impl Device for ClintDevice {
+    fn name(&self) -> &str {
+        "CLINT"
+    }
+
+    fn read(&mut self, offset: u32) -> Result<u32, DeviceError> {
+        match offset {
+            MSIP => Ok(self.msip),
+            MTIMECMP_LO => Ok(self.mtimecmp as u32),
+            MTIMECMP_HI => Ok((self.mtimecmp >> 32) as u32),
+            MTIME_LO => Ok(self.mtime as u32),
+            MTIME_HI => Ok((self.mtime >> 32) as u32),
+            _ => Ok(0),
+        }
+    }
+
+    fn write(&mut self, offset: u32, value: u32) -> Result<(), DeviceError> {
+        match offset {
+            MSIP => {
+                self.msip = value & 1;
+                Ok(())
+            }
+            MTIMECMP_LO => {
+                self.mtimecmp = (self.mtimecmp & !0xFFFF_FFFF) | value as u64;
+                self.fired = false;
+                Ok(())
+            }
+            MTIMECMP_HI => {
+                self.mtimecmp = (self.mtimecmp & 0xFFFF_FFFF) | ((value as u64) << 32);
+                self.fired = false;
+                Ok(())
+            }
+            MTIME_LO => {
+                self.mtime = (self.mtime & !0xFFFF_FFFF) | value as u64;
+                Ok(())
+            }
+            MTIME_HI => {
+                self.mtime = (self.mtime & 0xFFFF_FFFF) | ((value as u64) << 32);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn tick(&mut self) -> Result<Option<DeviceInterrupt>, DeviceError> {
+        self.mtime = self.mtime.wrapping_add(1);
+        if !self.fired && self.mtime >= self.mtimecmp {
+            self.fired = true;
+            return Ok(Some(DeviceInterrupt {
+                device_name: self.name().into(),
+                irq_number: TIMER_IRQ,
+            }));
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::error::MemoryError;
+use crate::memory::{Memory, PhysAddr};
+
+/// Right after the PLIC window, matching how `PLIC_BASE`/`CLINT_BASE` are
+/// each a fixed, well-known platform address rather than something a
+/// builder places.
+pub const DMA_BASE: u32 = 0x0C00_1000;
+pub const DMA_SIZE: u32 = 0x1000;
+
+/// IRQ line the engine raises on `COMMAND` completing, one above
+/// `ClintDevice`'s `TIMER_IRQ`.
+pub const DMA_IRQ: u32 = 8;
+
+// Register offsets.
+const REG_STATUS: u32 = 0x00; // RO: see the STATUS_* constants below
+const REG_HEAD: u32 = 0x04; // R/W: phys addr of the first descriptor in the chain
+const REG_COMMAND: u32 = 0x08; // WO: 1 = walk the chain starting at `head`
+
+pub const STATUS_IDLE: u32 = 0;
+pub const STATUS_BUSY: u32 = 1;
+pub const STATUS_DONE: u32 = 2;
+pub const STATUS_ERROR: u32 = 3;
+
+/// Longest chain `SystemBus::run_dma_chain` will walk before giving up, so a
+/// descriptor whose `next` loops back on itself (or on an earlier link)
+/// can't spin the engine forever.
+pub const MAX_CHAIN_LEN: u32 = 256;
+
+/// Register file for the DMA engine's MMIO window. The engine itself has no
+/// state beyond these two registers -- a `COMMAND` write walks the chain to
+/// completion before returning, the same synchronous-to-the-triggering-write
+/// shape `SimpleBlockDevice`'s own `REG_COMMAND` already has.
+pub struct DmaController {
+    status: u32,
+    head: u32,
+}
+
+impl Default for DmaController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DmaController {
+    pub fn new() -> Self {
+        Self {
+            status: STATUS_IDLE,
+            head: 0,
+        }
+    }
+
+    pub fn read(&self, offset: u32) -> u32 {
+        match offset {
+            REG_STATUS => self.status,
+            REG_HEAD => self.head,
+            _ => 0,
+        }
+    }
+
+    /// Handle a write to the engine's own register file. A `COMMAND` write
+    /// doesn't run the chain itself -- that needs `SystemBus`'s access to
+    /// RAM and the block device, so it just reports whether `run` should be
+    /// called, leaving `self.status` as `STATUS_BUSY` until it is.
+    pub fn write(&mut self, offset: u32, value: u32) -> bool {
+        match offset {
+            REG_HEAD => {
+                self.head = value;
+                false
+            }
+            REG_COMMAND if value == 1 => {
+                self.status = STATUS_BUSY;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn head(&self) -> u32 {
+        self.head
+    }
+
+    pub fn set_status(&mut self, status: u32) {
+        self.status = status;
+    }
+}
+
+/// One scatter/gather entry in a `block_read_dma` chain: `length` bytes
+/// (spanning one or more whole sectors, up to `SystemBus::
+/// MAX_SECTORS_PER_DESCRIPTOR`) starting at `sector` read from the block
+/// device straight into `dest_addr`, then on to `next` (0 ends the chain).
+/// `done` is written back by the engine so guest code polling the
+/// descriptor directly -- rather than the engine's own `REG_STATUS`, which
+/// only reports the chain as a whole -- can tell which link it got to.
+#[derive(Debug, Clone, Copy)]
+pub struct DmaDescriptor {
+    pub sector: u32,
+    pub dest_addr: u32,
+    pub length: u32,
+    pub next: u32,
+}
+
+impl DmaDescriptor {
+    /// `sector`, `dest_addr`, `length`, `next`, `done`: five little-endian
+    /// words, in that order.
+    pub const SIZE: u32 = 20;
+
+    const DONE_OFFSET: u32 = 16;
+
+    pub const DONE_PENDING: u32 = 0;
+    pub const DONE_OK: u32 = 1;
+    pub const DONE_ERROR: u32 = 2;
+
+    /// Read one descriptor out of guest memory. `pub(crate)` rather than a
+    /// method on some owning type, since the caller driving the walk
+    /// (`SystemBus::run_dma_chain`) needs `&mut self` for RAM and the block
+    /// device at the same time it needs this -- both reached through the
+    /// same `SystemBus: Memory` impl, so they have to be sequential calls
+    /// rather than this taking a borrow of its own.
+    pub(crate) fn read_from(memory: &mut dyn Memory, addr: u32) -> Result<Self, MemoryError> {
+        Ok(Self {
+            sector: memory.read_word(PhysAddr::new(addr))?,
+            dest_addr: memory.read_word(PhysAddr::new(addr + 4))?,
+            length: memory.read_word(PhysAddr::new(addr + 8))?,
+            next: memory.read_word(PhysAddr::new(addr + 12))?,
+        })
+    }
+
+    pub(crate) fn write_done(memory: &mut dyn Memory, addr: u32, code: u32) -> Result<(), MemoryError> {
+        memory.write_word(PhysAddr::new(addr + Self::DONE_OFFSET), code)
+    }
+}
@@ -1,5 +1,11 @@
 pub mod block;
+pub mod bootinfo;
+pub mod clint;
+pub mod dma;
+pub mod plic;
+pub mod proxy;
 pub mod uart;
+pub mod virtio_blk;
 
 use crate::error::DeviceError;
 
@@ -53,26 +59,11 @@ impl DeviceManager {
         });
     }
 
-    pub fn read_word(&self, _addr: u32) -> Result<u32, DeviceError> {
-        // Need interior mutability if Device::read is &mut self.
-        // But SystemBus::read_word is &self.
-        // Option 1: Wrap Device in RefCell/Mutex.
-        // Option 2: Change Memory::read_word to &mut self (it's often stateful for devices).
-        // Let's check Memory trait.
-        // Memory::read_word is &self.
-        // This is a conflict. MMIO reads CAN have side effects (Clear on Read).
-        // So Memory trait should probably be &mut self for reads too?
-        // Or Device uses internal mutability.
-
-        // For now, let's look at `Device` trait again. It has `read(&mut self)`.
-        // So we MUST have `&mut self` to call it.
-        // But `DeviceManager::read_word` is taking `&self`.
-        Err(DeviceError::Io(
-            "Memory trait requires &self for read, but devices need mutability".into(),
-        ))
-    }
-
-    pub fn read_word_mut(&mut self, addr: u32) -> Result<u32, DeviceError> {
+    /// Routes through `Device::read`'s `&mut self`, same as `write_word`,
+    /// so a clear-on-read register (UART's `RBR`, an interrupt-status
+    /// word) can drain itself on the way out instead of needing a second
+    /// side-effecting call after an immutable peek.
+    pub fn read_word(&mut self, addr: u32) -> Result<u32, DeviceError> {
         for entry in &mut self.devices {
             if addr >= entry.base_addr && addr < entry.base_addr + entry.size {
                 return entry.device.read(addr - entry.base_addr);
@@ -89,4 +80,18 @@ impl DeviceManager {
         }
         Err(DeviceError::InvalidOffset(addr))
     }
+
+    /// Advance every attached device by one VM step, collecting whatever
+    /// interrupts they raise (e.g. a CLINT reaching `mtimecmp`). Dispatching
+    /// these into the trap pipeline is left to whichever subsystem ends up
+    /// owning interrupt routing.
+    pub fn tick_all(&mut self) -> Result<Vec<DeviceInterrupt>, DeviceError> {
+        let mut interrupts = Vec::new();
+        for entry in &mut self.devices {
+            if let Some(irq) = entry.device.tick()? {
+                interrupts.push(irq);
+            }
+        }
+        Ok(interrupts)
+    }
 }
@@ -20,20 +20,191 @@ const BUFFER_OFFSET: u32 = 0x100;
 #[cfg(not(feature = "std"))]
 pub struct SimpleNetDevice;
 
+/// What a "send" writes full frames to and a "recv" pulls them out of --
+/// either a connected `UdpSocket` tunnel (the original backend, which loses
+/// all Ethernet framing) or a host TAP interface (real L2 frames, so the
+/// guest's MAC in `REG_MAC_LOW`/`HIGH` is meaningful to whatever bridge the
+/// TAP is attached to). `SimpleNetDevice` only ever talks to this trait, not
+/// either concrete backend, so `check_rx`/the `REG_COMMAND` handler don't
+/// change based on which one it was constructed with.
 #[cfg(feature = "std")]
-pub struct SimpleNetDevice {
+trait NetBackend {
+    fn send(&mut self, frame: &[u8]) -> std::io::Result<()>;
+    /// Non-blocking: `Err(WouldBlock)` means nothing was waiting.
+    fn recv(&mut self, buf: &mut [u8]) -> std::io::Result<usize>;
+}
+
+#[cfg(feature = "std")]
+struct UdpBackend {
     socket: UdpSocket,
+}
+
+#[cfg(feature = "std")]
+impl NetBackend for UdpBackend {
+    fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.socket.send(frame).map(|_| ())
+    }
+
+    fn recv(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.socket.recv(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+struct TapBackend {
+    file: std::fs::File,
+}
+
+#[cfg(feature = "std")]
+impl NetBackend for TapBackend {
+    fn send(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        use std::io::Write;
+        self.file.write_all(frame)
+    }
+
+    fn recv(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::io::Read;
+        self.file.read(buf)
+    }
+}
+
+/// Thin FFI around the Linux TAP ioctl protocol -- a `libc`-less `extern
+/// "C"` block rather than a new crate dependency, since `ioctl`/`fcntl` are
+/// already part of every Unix host's libc this binary links against.
+#[cfg(feature = "std")]
+mod tap_ioctl {
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    const IFF_TAP: i16 = 0x0002;
+    const IFF_NO_PI: i16 = 0x1000;
+    const TUNSETIFF: u64 = 0x4004_54ca;
+    const F_SETFL: i32 = 4;
+    const O_NONBLOCK: i32 = 0x0800;
+
+    #[repr(C)]
+    struct IfReq {
+        name: [u8; 16],
+        flags: i16,
+        // `ifreq` is a union past `ifr_name`; this is more padding than the
+        // flags form needs, but it keeps the struct at least as large as
+        // the kernel expects regardless of which union arm it reads.
+        _pad: [u8; 22],
+    }
+
+    extern "C" {
+        fn ioctl(fd: i32, request: u64, ...) -> i32;
+        fn fcntl(fd: i32, cmd: i32, ...) -> i32;
+    }
+
+    /// Open `/dev/net/tun`, bind it to the host TAP interface named `name`
+    /// (created beforehand by `ip tuntap add`, same as any other TAP user),
+    /// and switch it to non-blocking reads so `TapBackend::recv` behaves
+    /// like `UdpSocket::recv` on a socket with `set_nonblocking(true)`.
+    pub fn open(name: &str) -> io::Result<std::fs::File> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/net/tun")?;
+
+        let mut req = IfReq {
+            name: [0; 16],
+            flags: IFF_TAP | IFF_NO_PI,
+            _pad: [0; 22],
+        };
+        let name_bytes = name.as_bytes();
+        let len = name_bytes.len().min(req.name.len() - 1);
+        req.name[..len].copy_from_slice(&name_bytes[..len]);
+
+        let fd = file.as_raw_fd();
+        if unsafe { ioctl(fd, TUNSETIFF, &mut req as *mut IfReq) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if unsafe { fcntl(fd, F_SETFL, O_NONBLOCK) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(file)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TapBackend {
+    fn open(name: &str) -> std::io::Result<Self> {
+        Ok(Self {
+            file: tap_ioctl::open(name)?,
+        })
+    }
+}
+
+/// Writes every frame handed to it as a libpcap record, so a capture taken
+/// through the `capture_path` given to `SimpleNetDevice::new`/`new_tap` can
+/// be opened straight in Wireshark.
+/// Flushes after each record rather than buffering, since the point of a
+/// capture is to leave a readable trace even if the guest or host crashes
+/// mid-run.
+#[cfg(feature = "std")]
+struct PcapWriter {
+    file: std::fs::File,
+}
+
+#[cfg(feature = "std")]
+impl PcapWriter {
+    const MAGIC: u32 = 0xa1b2_c3d4;
+    const LINKTYPE_ETHERNET: u32 = 1;
+
+    fn create(path: &str) -> std::io::Result<Self> {
+        use std::io::Write;
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(&Self::MAGIC.to_le_bytes())?;
+        file.write_all(&2u16.to_le_bytes())?; // version_major
+        file.write_all(&4u16.to_le_bytes())?; // version_minor
+        file.write_all(&0i32.to_le_bytes())?; // thiszone
+        file.write_all(&0u32.to_le_bytes())?; // sigfigs
+        file.write_all(&65535u32.to_le_bytes())?; // snaplen
+        file.write_all(&Self::LINKTYPE_ETHERNET.to_le_bytes())?;
+        file.flush()?;
+        Ok(Self { file })
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) {
+        use std::io::Write;
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let len = frame.len() as u32;
+        let result = (|| -> std::io::Result<()> {
+            self.file.write_all(&(now.as_secs() as u32).to_le_bytes())?;
+            self.file.write_all(&now.subsec_micros().to_le_bytes())?;
+            self.file.write_all(&len.to_le_bytes())?; // incl_len
+            self.file.write_all(&len.to_le_bytes())?; // orig_len
+            self.file.write_all(frame)?;
+            self.file.flush()
+        })();
+        if let Err(e) = result {
+            eprintln!("[VM Net] pcap write failed: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub struct SimpleNetDevice {
+    backend: Box<dyn NetBackend>,
     rx_buffer: [u8; NET_BUFFER_SIZE],
     tx_buffer: [u8; NET_BUFFER_SIZE],
     rx_packet_len: u32,
     tx_packet_len: u32,
     data_ready: bool,
     mac: [u8; 6],
+    capture: Option<PcapWriter>,
 }
 
 #[cfg(feature = "std")]
 impl SimpleNetDevice {
-    pub fn new(bind_addr: &str, remote_addr: &str) -> std::io::Result<Self> {
+    pub fn new(bind_addr: &str, remote_addr: &str, capture_path: Option<&str>) -> std::io::Result<Self> {
         let socket = UdpSocket::bind(bind_addr)?;
         socket.set_nonblocking(true)?;
 
@@ -42,24 +213,41 @@ impl SimpleNetDevice {
             socket.connect(remote_addr)?;
         }
 
+        Self::with_backend(Box::new(UdpBackend { socket }), capture_path)
+    }
+
+    /// The TAP-backed alternative to `new` -- `tap_name` is a host interface
+    /// (e.g. `"tap0"`) already created and added to a bridge/subnet, so full
+    /// Ethernet frames the guest sends actually reach it instead of being
+    /// tunneled as bare UDP payloads.
+    pub fn new_tap(tap_name: &str, capture_path: Option<&str>) -> std::io::Result<Self> {
+        Self::with_backend(Box::new(TapBackend::open(tap_name)?), capture_path)
+    }
+
+    fn with_backend(backend: Box<dyn NetBackend>, capture_path: Option<&str>) -> std::io::Result<Self> {
+        let capture = capture_path.map(PcapWriter::create).transpose()?;
         Ok(Self {
-            socket,
+            backend,
             rx_buffer: [0; NET_BUFFER_SIZE],
             tx_buffer: [0; NET_BUFFER_SIZE],
             rx_packet_len: 0,
             tx_packet_len: 0,
             data_ready: false,
             mac: [0x52, 0x54, 0x00, 0x12, 0x34, 0x56], // Standard QEMU MAC
+            capture,
         })
     }
 
     fn check_rx(&mut self) {
         if !self.data_ready {
-            match self.socket.recv(&mut self.rx_buffer) {
+            match self.backend.recv(&mut self.rx_buffer) {
                 Ok(len) => {
-                    println!("[VM Net] Received {} bytes from host socket", len);
+                    println!("[VM Net] Received {} bytes from host backend", len);
                     self.rx_packet_len = len as u32;
                     self.data_ready = true;
+                    if let Some(capture) = &mut self.capture {
+                        capture.write_frame(&self.rx_buffer[..len]);
+                    }
                 }
                 Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                     // No data
@@ -129,7 +317,10 @@ impl Device for SimpleNetDevice {
                         // Send
                         let len = self.tx_packet_len as usize;
                         if len > 0 && len <= NET_BUFFER_SIZE {
-                            let _ = self.socket.send(&self.tx_buffer[..len]);
+                            let _ = self.backend.send(&self.tx_buffer[..len]);
+                            if let Some(capture) = &mut self.capture {
+                                capture.write_frame(&self.tx_buffer[..len]);
+                            }
                         }
                         Ok(())
                     }
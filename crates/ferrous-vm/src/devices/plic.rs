@@ -0,0 +1,133 @@
+use crate::devices::{Device, DeviceInterrupt};
+use crate::error::DeviceError;
+
+/// Matches the real SiFive/RISC-V platform's conventional PLIC base, so a
+/// guest kernel written against that convention needs no changes to talk
+/// to this one.
+pub const PLIC_BASE: u32 = 0x0C00_0000;
+pub const PLIC_SIZE: u32 = 0x1000;
+
+/// Number of distinct IRQ lines the controller can track. IRQ 0 is
+/// reserved (means "no interrupt"), matching the real PLIC's convention
+/// and `ClintDevice`'s `TIMER_IRQ = 7` numbering.
+const IRQ_COUNT: usize = 32;
+
+// Register offsets.
+const ENABLE: u32 = 0x00; // R/W: bitmask of which IRQs can be claimed
+const PENDING: u32 = 0x04; // RO: bitmask of IRQs currently asserted
+const CLAIM: u32 = 0x08; // RO: claim the highest-priority pending+enabled IRQ above the running priority
+const COMPLETE: u32 = 0x0C; // WO: IRQ number being completed (EOI)
+const PRIORITY_BASE: u32 = 0x100; // one word per IRQ, PRIORITY_BASE + irq*4
+
+/// A GIC/PLIC-style interrupt distributor: other devices raise a numbered
+/// IRQ line through `raise_irq` (fed by `DeviceManager::tick_all`'s
+/// `DeviceInterrupt` reports), and the running kernel claims and
+/// completes them through this device's MMIO window. Priority nesting
+/// works like a real PLIC's priority threshold, but modeled as an
+/// explicit stack of running priorities so a claimed IRQ's priority is
+/// exactly what `COMPLETE` needs to pop to restore the previous threshold.
+pub struct InterruptController {
+    enabled: u32,
+    pending: u32,
+    priority: [u8; IRQ_COUNT],
+    /// Priorities of IRQs currently claimed-but-not-completed, outermost
+    /// first. The running priority is the stack's top (0, i.e. "nothing
+    /// masked", when empty), so only a strictly higher-priority IRQ can
+    /// preempt whatever is currently being serviced.
+    running: Vec<u8>,
+}
+
+impl Default for InterruptController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterruptController {
+    pub fn new() -> Self {
+        Self {
+            enabled: 0,
+            pending: 0,
+            priority: [1; IRQ_COUNT],
+            running: Vec::new(),
+        }
+    }
+
+    fn running_priority(&self) -> u8 {
+        *self.running.last().unwrap_or(&0)
+    }
+
+    /// Mark `irq` pending. A no-op for an out-of-range or reserved (0) IRQ
+    /// number, the same "ignore rather than fault" treatment `write` gives
+    /// an unrecognized register.
+    pub fn raise_irq(&mut self, irq: u32) {
+        if irq == 0 || irq as usize >= IRQ_COUNT {
+            return;
+        }
+        self.pending |= 1 << irq;
+    }
+
+    /// Whether a pending, enabled IRQ outranks whatever's currently
+    /// running -- the question `SystemBus::pending_interrupt` asks once
+    /// per instruction to decide whether to take `TrapCause::ExternalInterrupt`.
+    pub fn has_deliverable_irq(&self) -> bool {
+        self.highest_claimable_irq().is_some()
+    }
+
+    fn highest_claimable_irq(&self) -> Option<u32> {
+        let threshold = self.running_priority();
+        (1..IRQ_COUNT as u32)
+            .filter(|&irq| self.pending & (1 << irq) != 0 && self.enabled & (1 << irq) != 0)
+            .filter(|&irq| self.priority[irq as usize] > threshold)
+            .max_by_key(|&irq| self.priority[irq as usize])
+    }
+}
+
+impl Device for InterruptController {
+    fn name(&self) -> &str {
+        "PLIC0"
+    }
+
+    fn read(&mut self, offset: u32) -> Result<u32, DeviceError> {
+        match offset {
+            ENABLE => Ok(self.enabled),
+            PENDING => Ok(self.pending),
+            CLAIM => match self.highest_claimable_irq() {
+                Some(irq) => {
+                    self.pending &= !(1 << irq);
+                    self.running.push(self.priority[irq as usize]);
+                    Ok(irq)
+                }
+                None => Ok(0),
+            },
+            _ if (PRIORITY_BASE..PRIORITY_BASE + IRQ_COUNT as u32 * 4).contains(&offset) => {
+                let irq = (offset - PRIORITY_BASE) / 4;
+                Ok(self.priority[irq as usize] as u32)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn write(&mut self, offset: u32, value: u32) -> Result<(), DeviceError> {
+        match offset {
+            ENABLE => self.enabled = value,
+            COMPLETE => {
+                // Completing an IRQ that was never claimed (or completing
+                // twice) just finds nothing to pop; the running stack
+                // doesn't care which IRQ number was named, only that
+                // something is being un-nested.
+                self.running.pop();
+            }
+            _ if (PRIORITY_BASE..PRIORITY_BASE + IRQ_COUNT as u32 * 4).contains(&offset) => {
+                let irq = (offset - PRIORITY_BASE) / 4;
+                self.priority[irq as usize] = value as u8;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) -> Result<Option<DeviceInterrupt>, DeviceError> {
+        Ok(None)
+    }
+}
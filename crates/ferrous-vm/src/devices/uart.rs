@@ -1,7 +1,9 @@
-use crate::devices::Device;
+use crate::devices::{Device, DeviceInterrupt};
 use crate::error::DeviceError;
 use std::collections::VecDeque;
 use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
 pub const UART_BASE: u32 = 0x1000_0000;
 pub const UART_SIZE: u32 = 0x100;
@@ -9,10 +11,29 @@ pub const UART_SIZE: u32 = 0x100;
 // Registers offsets
 pub const RBR: u32 = 0x00; // Receiver Buffer Register (Read Only)
 pub const THR: u32 = 0x00; // Transmitter Holding Register (Write Only)
+pub const IER: u32 = 0x01; // Interrupt Enable Register
 pub const LSR: u32 = 0x05; // Line Status Register
 
+/// IER bit 0: raise an interrupt whenever `RBR` has a byte available,
+/// mirroring the real 16550's "Received Data Available" enable bit.
+const IER_RX_AVAILABLE: u32 = 1 << 0;
+
+/// IRQ number reported for a UART interrupt, matching the QEMU `virt`
+/// platform's conventional UART0 line the same way `PLIC_BASE`/`TIMER_IRQ`
+/// match its PLIC/CLINT numbering.
+const UART_IRQ: u32 = 10;
+
+/// A 16550-style UART, but with `RBR` fed by a background thread instead of
+/// blocking `step()` on `io::stdin().read()`. The reader thread owns no
+/// VM state beyond `input_buffer`, so it can block on stdin forever
+/// without stalling the guest; `read`/`tick` only ever take the buffer's
+/// lock for as long as a `VecDeque` operation takes.
 pub struct UartDevice {
-    input_buffer: VecDeque<u8>,
+    input_buffer: Arc<Mutex<VecDeque<u8>>>,
+    /// Interrupt Enable Register -- only bit 0 (`IER_RX_AVAILABLE`) means
+    /// anything today, but it's stored as the guest wrote it so a readback
+    /// sees exactly what was set.
+    ier: u32,
 }
 
 impl Default for UartDevice {
@@ -23,8 +44,24 @@ impl Default for UartDevice {
 
 impl UartDevice {
     pub fn new() -> Self {
+        let input_buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let reader_buffer = Arc::clone(&input_buffer);
+        thread::spawn(move || {
+            let mut chunk = [0u8; 256];
+            loop {
+                match io::stdin().read(&mut chunk) {
+                    Ok(0) => break, // EOF: nothing more will ever arrive
+                    Ok(n) => {
+                        let mut buffer = reader_buffer.lock().unwrap();
+                        buffer.extend(&chunk[..n]);
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
         Self {
-            input_buffer: VecDeque::new(),
+            input_buffer,
+            ier: 0,
         }
     }
 }
@@ -37,28 +74,13 @@ impl Device for UartDevice {
     fn read(&mut self, offset: u32) -> Result<u32, DeviceError> {
         match offset {
             RBR => {
-                if self.input_buffer.is_empty() {
-                    // This is a blocking read simulation.
-                    // In a real emulator, we might poll or use a separate thread.
-                    // For this simple OS, blocking the VM until input arrives is acceptable.
-                    let mut buffer = [0; 256];
-                    match io::stdin().read(&mut buffer) {
-                        Ok(0) => return Ok(0), // EOF
-                        Ok(n) => {
-                            for byte in buffer.iter().take(n) {
-                                self.input_buffer.push_back(*byte);
-                            }
-                        }
-                        Err(e) => return Err(DeviceError::Io(e.to_string())),
-                    }
-                }
-
-                if let Some(byte) = self.input_buffer.pop_front() {
+                let mut buffer = self.input_buffer.lock().unwrap();
+                if let Some(byte) = buffer.pop_front() {
                     // Handle CRLF normalization (Windows/Terminal artifact)
                     if byte == 13 {
-                        if let Some(&next) = self.input_buffer.front() {
+                        if let Some(&next) = buffer.front() {
                             if next == 10 {
-                                self.input_buffer.pop_front();
+                                buffer.pop_front();
                             }
                         }
                         Ok(10) // Return newline
@@ -69,9 +91,14 @@ impl Device for UartDevice {
                     Ok(0)
                 }
             }
+            IER => Ok(self.ier),
             LSR => {
                 // Bit 0: Data Ready (DR)
-                let dr = if !self.input_buffer.is_empty() { 1 } else { 0 };
+                let dr = if !self.input_buffer.lock().unwrap().is_empty() {
+                    1
+                } else {
+                    0
+                };
                 // Bit 5: Transmitter Holding Register Empty (THRE) - always 1 (ready)
                 let thre = 1 << 5;
                 Ok(dr | thre)
@@ -90,11 +117,23 @@ impl Device for UartDevice {
                     .map_err(|e| DeviceError::Io(e.to_string()))?;
                 Ok(())
             }
+            IER => {
+                self.ier = value & 0xFF;
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
 
-    fn tick(&mut self) -> Result<Option<crate::devices::DeviceInterrupt>, DeviceError> {
-        Ok(None)
+    fn tick(&mut self) -> Result<Option<DeviceInterrupt>, DeviceError> {
+        let has_data = !self.input_buffer.lock().unwrap().is_empty();
+        if has_data && self.ier & IER_RX_AVAILABLE != 0 {
+            Ok(Some(DeviceInterrupt {
+                device_name: self.name().into(),
+                irq_number: UART_IRQ,
+            }))
+        } else {
+            Ok(None)
+        }
     }
 }
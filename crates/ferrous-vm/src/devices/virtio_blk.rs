@@ -0,0 +1,183 @@
+use crate::error::MemoryError;
+
+/// Right after the DMA engine's window, the next fixed platform device --
+/// same "well-known address, not something a builder places" treatment as
+/// `PLIC_BASE`/`DMA_BASE`.
+pub const VIRTIO_BLK_BASE: u32 = 0x0C00_2000;
+pub const VIRTIO_BLK_SIZE: u32 = 0x1000;
+
+/// IRQ line raised once a notified queue has been drained, the next line
+/// after `SimpleBlockDevice`'s `BLOCK_IRQ` (9) and `UartDevice`'s
+/// `UART_IRQ` (10).
+pub const VIRTIO_BLK_IRQ: u32 = 11;
+
+/// Descriptor/ring slot count. Matches `ferrous_kernel::net::driver`'s own
+/// `QUEUE_SIZE` -- there's only one virtqueue layout convention in this
+/// codebase, so the block side reuses it rather than picking a new number.
+pub const QUEUE_SIZE: u16 = 16;
+
+/// `{addr: u64, len: u32, flags: u16, next: u16}`, 16 bytes/entry.
+pub const DESC_ENTRY_SIZE: u32 = 16;
+/// `{id: u32, len: u32}`, 8 bytes/entry.
+pub const USED_ENTRY_SIZE: u32 = 8;
+
+pub const DESC_F_NEXT: u16 = 1 << 0;
+
+/// Request header `type` field: device writes the data buffers.
+pub const REQ_TYPE_IN: u32 = 0;
+/// Request header `type` field: device reads the data buffers.
+pub const REQ_TYPE_OUT: u32 = 1;
+
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_IOERR: u8 = 1;
+pub const STATUS_UNSUPP: u8 = 2;
+
+// Register offsets.
+const REG_QUEUE_DESC: u32 = 0x00; // R/W: guest phys addr of the descriptor table
+const REG_QUEUE_AVAIL: u32 = 0x04; // R/W: guest phys addr of the avail ring
+const REG_QUEUE_USED: u32 = 0x08; // R/W: guest phys addr of the used ring
+const REG_QUEUE_NOTIFY: u32 = 0x0C; // WO: any value -- service what's newly posted
+const REG_QUEUE_SIZE: u32 = 0x10; // RO: `QUEUE_SIZE`
+
+/// Device-config space, same split real virtio-mmio uses between its
+/// transport registers and the device-specific config block.
+const CONFIG_BASE: u32 = 0x100;
+const CONFIG_CAPACITY_LOW: u32 = CONFIG_BASE; // RO: backing sectors, low 32 bits
+const CONFIG_CAPACITY_HIGH: u32 = CONFIG_BASE + 0x04; // RO: backing sectors, high 32 bits
+const CONFIG_WRITEBACK: u32 = CONFIG_BASE + 0x08; // R/W: 0 = writethrough, 1 = writeback
+
+/// Register file for the virtqueue-based block device's MMIO window. Like
+/// `DmaController`, this holds only the ring addresses and the one
+/// driver-writable config field -- walking the queue needs `ram` and the
+/// registered `SimpleBlockDevice`/`MemBlockDevice` at the same time, which
+/// only `SystemBus` has both of, so `REG_QUEUE_NOTIFY` just reports that a
+/// drain should happen rather than doing it here.
+pub struct VirtioBlockController {
+    desc_base: u32,
+    avail_base: u32,
+    used_base: u32,
+    /// Next avail-ring slot not yet consumed, wrapping mod `QUEUE_SIZE`.
+    last_seen_avail: u16,
+    /// The only R/W config field; `SystemBus` doesn't act on it today
+    /// (every write already lands on disk immediately), but it's stored as
+    /// the guest wrote it so a readback sees exactly what was set, same as
+    /// `UartDevice::ier`.
+    writeback: u32,
+}
+
+impl Default for VirtioBlockController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtioBlockController {
+    pub fn new() -> Self {
+        Self {
+            desc_base: 0,
+            avail_base: 0,
+            used_base: 0,
+            last_seen_avail: 0,
+            writeback: 0,
+        }
+    }
+
+    pub fn desc_base(&self) -> u32 {
+        self.desc_base
+    }
+
+    pub fn avail_base(&self) -> u32 {
+        self.avail_base
+    }
+
+    pub fn used_base(&self) -> u32 {
+        self.used_base
+    }
+
+    pub fn writeback(&self) -> u32 {
+        self.writeback
+    }
+
+    pub fn last_seen_avail(&self) -> u16 {
+        self.last_seen_avail
+    }
+
+    /// Marks one more avail-ring slot consumed, returning the slot index it
+    /// just advanced past (the one `SystemBus` should read the descriptor
+    /// head out of).
+    pub fn advance_last_seen_avail(&mut self) -> u16 {
+        let slot = self.last_seen_avail;
+        self.last_seen_avail = self.last_seen_avail.wrapping_add(1);
+        slot
+    }
+
+    /// Registers the engine answers without RAM/device access.
+    pub fn read(&self, offset: u32) -> u32 {
+        match offset {
+            REG_QUEUE_DESC => self.desc_base,
+            REG_QUEUE_AVAIL => self.avail_base,
+            REG_QUEUE_USED => self.used_base,
+            REG_QUEUE_SIZE => QUEUE_SIZE as u32,
+            CONFIG_WRITEBACK => self.writeback,
+            _ => 0,
+        }
+    }
+
+    /// Handle a write to the engine's own register file. Returns whether
+    /// `SystemBus` should now drain the queue (`REG_QUEUE_NOTIFY`); the
+    /// RO config capacity fields aren't handled here since only `SystemBus`
+    /// can answer them (it alone has the registered block device's size).
+    pub fn write(&mut self, offset: u32, value: u32) -> Result<bool, MemoryError> {
+        match offset {
+            REG_QUEUE_DESC => {
+                self.desc_base = value;
+                Ok(false)
+            }
+            REG_QUEUE_AVAIL => {
+                self.avail_base = value;
+                Ok(false)
+            }
+            REG_QUEUE_USED => {
+                self.used_base = value;
+                Ok(false)
+            }
+            REG_QUEUE_NOTIFY => Ok(true),
+            CONFIG_WRITEBACK => {
+                self.writeback = value;
+                Ok(false)
+            }
+            o if o == CONFIG_CAPACITY_LOW || o == CONFIG_CAPACITY_HIGH => {
+                Err(MemoryError::ReadOnly(o))
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Recognizes the `CONFIG_CAPACITY_LOW`/`_HIGH` offsets, which
+/// `VirtioBlockController::read` itself reads as 0, so `SystemBus` (which
+/// alone knows the backing device's sector count) can answer those two
+/// offsets before handing every other offset to the register file.
+pub fn is_capacity_low(offset: u32) -> bool {
+    offset == CONFIG_CAPACITY_LOW
+}
+
+pub fn is_capacity_high(offset: u32) -> bool {
+    offset == CONFIG_CAPACITY_HIGH
+}
+
+/// One descriptor-table entry: `addr` is truncated to 32 bits on read since
+/// no RV32 guest's buffers live past `u32::MAX` anyway.
+#[derive(Debug, Clone, Copy)]
+pub struct Descriptor {
+    pub addr: u32,
+    pub len: u32,
+    pub flags: u16,
+    pub next: u16,
+}
+
+impl Descriptor {
+    pub fn has_next(&self) -> bool {
+        self.flags & DESC_F_NEXT != 0
+    }
+}
@@ -24,6 +24,18 @@ pub enum MemoryError {
     ReadOnly(u32),
     Device(DeviceError),
     Misaligned { addr: u32, alignment: u32 },
+    /// No region in the `AddressMap` claims this address -- distinct from
+    /// `OutOfBounds`, which is a region claiming the address but rejecting
+    /// the particular offset/length.
+    Unmapped(u32),
+    /// Returned by `AddressMap::add` (and anything building on it, like
+    /// `SystemBusBuilder`) when a new region's range overlaps one already
+    /// present, instead of silently letting the later region shadow part of
+    /// the earlier one.
+    OverlappingRegion {
+        new: (u32, u32),
+        existing: (u32, u32),
+    },
 }
 
 impl fmt::Display for MemoryError {
@@ -39,6 +51,12 @@ impl fmt::Display for MemoryError {
                     addr, alignment
                 )
             }
+            MemoryError::Unmapped(addr) => write!(f, "unmapped address: {:#x}", addr),
+            MemoryError::OverlappingRegion { new, existing } => write!(
+                f,
+                "address region {:#x}..{:#x} overlaps existing region {:#x}..{:#x}",
+                new.0, new.1, existing.0, existing.1
+            ),
         }
     }
 }
@@ -96,6 +114,7 @@ pub enum VmError {
     InvalidInstruction(u32),
     RegisterIndex(u32),
     Decode(DecodeError),
+    Device(DeviceError),
 }
 
 impl fmt::Display for VmError {
@@ -106,6 +125,7 @@ impl fmt::Display for VmError {
             VmError::InvalidInstruction(inst) => write!(f, "invalid instruction: {:#x}", inst),
             VmError::RegisterIndex(idx) => write!(f, "invalid register index: {}", idx),
             VmError::Decode(e) => write!(f, "decode error: {}", e),
+            VmError::Device(e) => write!(f, "device error: {}", e),
         }
     }
 }
@@ -129,3 +149,9 @@ impl From<DecodeError> for VmError {
         VmError::Decode(e)
     }
 }
+
+impl From<DeviceError> for VmError {
+    fn from(e: DeviceError) -> Self {
+        VmError::Device(e)
+    }
+}
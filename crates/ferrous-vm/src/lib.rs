@@ -3,14 +3,23 @@
 extern crate alloc;
 
 use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 
+pub mod address_map;
+pub mod block_cache;
+pub mod bus;
 pub mod cpu;
+pub mod csr;
+pub mod debugger;
 pub mod devices;
 pub mod error;
 pub mod instruction;
 pub mod memory;
 pub mod mmu;
 pub mod system_bus;
+pub mod time;
 pub mod trap;
 
 pub use cpu::*;
@@ -22,7 +31,17 @@ pub use trap::*;
 
 pub struct VmConfig {
     pub memory_size: usize,
+    /// If set, `mtimecmp` is seeded to this many ticks past `mtime` at
+    /// startup and automatically rearmed by the same interval every time it
+    /// fires, giving a free-running periodic timer without guest software
+    /// having to touch `mtimecmp` itself.
     pub timer_interval: Option<u64>,
+    /// Whether `step()` fetches through `block_cache::BlockCache` instead
+    /// of always re-reading and re-decoding the instruction word. Exposed
+    /// as a config flag (rather than always on) so the interpreter can
+    /// fall back to the slow, always-correct-by-construction path while
+    /// debugging a suspected cache-invalidation bug.
+    pub block_cache_enabled: bool,
 }
 
 pub struct VirtualMachine {
@@ -31,13 +50,18 @@ pub struct VirtualMachine {
     pub trap_handler: Box<dyn TrapHandler>,
     pub config: VmConfig,
     pub instruction_count: u64,
-    pub next_timer_interrupt: u64,
+    tlb: mmu::Tlb,
+    debugger: Option<debugger::Debugger>,
+    block_cache: block_cache::BlockCache,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ExitReason {
     Halt,
-    Breakpoint,
+    /// `run()` handed control back for a debugger to inspect or continue
+    /// from -- only reachable with a `Debugger` attached via
+    /// `VirtualMachine::set_debugger`; see `debugger::StopReason` for why.
+    Breakpoint(debugger::StopReason),
     Error(VmError),
 }
 
@@ -52,27 +76,176 @@ use mmu::AccessType;
 
 impl VirtualMachine {
     fn translate(&mut self, addr: VirtAddr, access: AccessType) -> Result<PhysAddr, TrapCause> {
+        // Watchpoints are data-access only (not instruction fetch), and
+        // latched on the debugger rather than returned here -- this
+        // function's `Result<PhysAddr, TrapCause>` has no room for a third
+        // outcome, so `run()` picks the hit back up via
+        // `Debugger::take_pending_stop` once `step()` returns.
+        if access != AccessType::Execute {
+            if let Some(debugger) = &mut self.debugger {
+                debugger.check_watchpoint(addr.val(), access == AccessType::Write);
+            }
+        }
         mmu::translate(
             addr,
             access,
             self.cpu.satp,
             self.cpu.mode,
+            self.cpu.csr.mstatus,
             self.memory.as_mut(),
+            &mut self.tlb,
         )
     }
 
+    /// Drops the whole block cache on a `satp` write: a new root page table
+    /// can make a different (and possibly stale-for-a-reason) set of pages
+    /// executable, and the cache has no cheaper way to tell which of its
+    /// entries the new mapping would still agree with -- the same call
+    /// `mmu::Tlb::lookup` makes on every `satp` write.
+    fn invalidate_block_cache_on_csr_write(&mut self, csr: u16) {
+        if csr == csr::SATP {
+            self.block_cache.invalidate_all();
+        }
+    }
+
+    /// Attaches a debugger: `run()` starts consulting it for breakpoints,
+    /// watchpoints, single-step mode, and tracing. `ebreak` and PC
+    /// breakpoints are only intercepted while one is attached -- with none
+    /// set, both behave exactly as before this existed.
+    pub fn set_debugger(&mut self, debugger: debugger::Debugger) {
+        self.debugger = Some(debugger);
+    }
+
+    pub fn clear_debugger(&mut self) {
+        self.debugger = None;
+    }
+
+    pub fn debugger(&self) -> Option<&debugger::Debugger> {
+        self.debugger.as_ref()
+    }
+
+    pub fn debugger_mut(&mut self) -> Option<&mut debugger::Debugger> {
+        self.debugger.as_mut()
+    }
+
+    /// Reads a general-purpose register -- a thin wrapper over `Cpu::read_reg`
+    /// so a debugger front-end doesn't need to reach into `vm.cpu` itself.
+    pub fn read_register(&self, reg: Register) -> u32 {
+        self.cpu.read_reg(reg)
+    }
+
+    pub fn write_register(&mut self, reg: Register, val: u32) {
+        self.cpu.write_reg(reg, val)
+    }
+
+    /// Reads `len` bytes starting at the physical address `addr`, stopping
+    /// early (returning whatever was read so far) on the first access
+    /// error rather than failing the whole dump -- a debugger inspecting
+    /// an address range that runs off the end of mapped memory still wants
+    /// to see what came before it.
+    pub fn dump_memory(&mut self, addr: PhysAddr, len: u32) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            match self.memory.read_byte(addr + i) {
+                Ok(byte) => bytes.push(byte),
+                Err(_) => break,
+            }
+        }
+        bytes
+    }
+
+    /// Reads `len` bytes starting at the virtual address `vaddr`, going
+    /// through `translate` (so it honors `satp` and the current privilege
+    /// mode) rather than `dump_memory`'s raw physical access -- for a
+    /// debugger front-end inspecting guest state by the addresses guest
+    /// symbols actually use. Stops early, same "return what we got"
+    /// contract as `dump_memory`, on the first page fault.
+    pub fn read_virtual(&mut self, vaddr: u32, len: u32) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            let addr = vaddr.wrapping_add(i);
+            match self
+                .translate(VirtAddr::new(addr), AccessType::Read)
+                .ok()
+                .and_then(|pa| self.memory.read_byte(pa).ok())
+            {
+                Some(byte) => bytes.push(byte),
+                None => break,
+            }
+        }
+        bytes
+    }
+
+    /// Writes `data` to the virtual address `vaddr` through `translate`,
+    /// returning how many bytes actually landed before the first page
+    /// fault (if any) cut the write short.
+    pub fn write_virtual(&mut self, vaddr: u32, data: &[u8]) -> u32 {
+        let mut written = 0;
+        for (i, &byte) in data.iter().enumerate() {
+            let addr = vaddr.wrapping_add(i as u32);
+            let wrote = self
+                .translate(VirtAddr::new(addr), AccessType::Write)
+                .ok()
+                .map(|pa| self.memory.write_byte(pa, byte).is_ok())
+                .unwrap_or(false);
+            if !wrote {
+                break;
+            }
+            written += 1;
+        }
+        written
+    }
+
+    /// Disassembles `count` instructions starting at the virtual address
+    /// `pc`, returning each one's address alongside its rendered assembly
+    /// (or `None` if the word there couldn't be translated or decoded).
+    /// RISC-V's fixed 4-byte instruction width means "around PC" -- callers
+    /// wanting context before `pc` too just pass an earlier starting
+    /// address (`pc - 4 * n`).
+    pub fn disassemble_around(&mut self, pc: u32, count: u32) -> Vec<(u32, Option<String>)> {
+        let mut lines = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let addr = pc.wrapping_add(i * 4);
+            let rendered = self
+                .translate(VirtAddr::new(addr), AccessType::Execute)
+                .ok()
+                .and_then(|pa| self.memory.read_word(pa).ok())
+                .and_then(|word| Instruction::decode(word).ok())
+                .map(|instruction| Self::render_instruction(instruction));
+            lines.push((addr, rendered));
+        }
+        lines
+    }
+
+    #[cfg(feature = "disasm")]
+    fn render_instruction(instruction: Instruction) -> String {
+        instruction.disassemble()
+    }
+
+    #[cfg(not(feature = "disasm"))]
+    fn render_instruction(instruction: Instruction) -> String {
+        format!("{:?}", instruction)
+    }
+
     pub fn new(
         config: VmConfig,
         memory: Box<dyn Memory>,
         trap_handler: Box<dyn TrapHandler>,
     ) -> Result<Self, VmError> {
+        let mut cpu = Cpu::new(0x8000_0000); // Standard entry point
+        if let Some(interval) = config.timer_interval {
+            cpu.csr.mtimecmp = interval;
+        }
+        let block_cache = block_cache::BlockCache::new(config.block_cache_enabled);
         Ok(Self {
-            cpu: Cpu::new(0x8000_0000), // Standard entry point
+            cpu,
             memory,
             trap_handler,
             instruction_count: 0,
-            next_timer_interrupt: config.timer_interval.unwrap_or(u64::MAX),
             config,
+            tlb: mmu::Tlb::new(),
+            debugger: None,
+            block_cache,
         })
     }
 
@@ -87,31 +260,85 @@ impl VirtualMachine {
 
     pub fn run(&mut self) -> Result<ExitReason, VmError> {
         loop {
-            // Check for timer interrupt
-            if let Some(interval) = self.config.timer_interval {
-                if self.instruction_count >= self.next_timer_interrupt {
-                    self.next_timer_interrupt += interval;
-                    let result = self.trap_handler.handle_trap(
-                        TrapCause::TimerInterrupt,
-                        &mut self.cpu,
-                        self.memory.as_mut(),
-                    );
-                    match result {
-                        Ok(resume_addr) => self.cpu.pc = resume_addr.val(),
-                        Err(TrapError::Halt) => return Ok(ExitReason::Halt),
-                        Err(e) => return Err(VmError::Trap(e)),
-                    }
+            // Advance the CLINT-style clock and latch mip.MTIP if mtimecmp
+            // has been reached, then take the trap only if mstatus.MIE and
+            // mie.MTIE both allow it.
+            self.cpu.csr.tick_timer();
+            if self.cpu.csr.timer_interrupt_pending() {
+                if let Some(interval) = self.config.timer_interval {
+                    self.cpu.csr.mtimecmp = self.cpu.csr.mtimecmp.wrapping_add(interval);
+                }
+                let result = self.trap_handler.handle_trap(
+                    TrapCause::TimerInterrupt,
+                    &mut self.cpu,
+                    self.memory.as_mut(),
+                );
+                match result {
+                    Ok(resume_addr) => self.cpu.pc = resume_addr.val(),
+                    Err(TrapError::Halt) => return Ok(ExitReason::Halt),
+                    Err(e) => return Err(VmError::Trap(e)),
+                }
+            }
+
+            // Poll devices for newly raised IRQs and latch mip.MEIP the
+            // same way tick_timer latches mip.MTIP above, so external
+            // interrupts go through the identical mstatus/mie gating.
+            self.memory.tick_devices()?;
+            if self.memory.pending_interrupt() {
+                self.cpu.csr.mip |= csr::MIP_MEIP;
+            } else {
+                self.cpu.csr.mip &= !csr::MIP_MEIP;
+            }
+            if self.cpu.csr.external_interrupt_pending() {
+                let result = self.trap_handler.handle_trap(
+                    TrapCause::ExternalInterrupt,
+                    &mut self.cpu,
+                    self.memory.as_mut(),
+                );
+                match result {
+                    Ok(resume_addr) => self.cpu.pc = resume_addr.val(),
+                    Err(TrapError::Halt) => return Ok(ExitReason::Halt),
+                    Err(e) => return Err(VmError::Trap(e)),
+                }
+            }
+
+            // A breakpoint hit here means `cpu.pc` is sitting right where
+            // it was left after the previous stop -- resuming past it is
+            // the caller's job (step once first, or remove and re-add the
+            // breakpoint around a single `step()`), the same contract a
+            // GDB stub's own breakpoint handling relies on.
+            if let Some(debugger) = &self.debugger {
+                if debugger.has_breakpoint(self.cpu.pc) {
+                    return Ok(ExitReason::Breakpoint(debugger::StopReason::Breakpoint(
+                        self.cpu.pc,
+                    )));
                 }
             }
 
             let step_result = self.step();
             self.instruction_count += 1;
 
+            if let Some(debugger) = &mut self.debugger {
+                if let Some(reason) = debugger.take_pending_stop() {
+                    return Ok(ExitReason::Breakpoint(reason));
+                }
+            }
+
             match step_result {
                 Ok(StepResult::Continue) => {
+                    if self.debugger.as_ref().map_or(false, |d| d.single_step()) {
+                        return Ok(ExitReason::Breakpoint(debugger::StopReason::Step));
+                    }
                     continue;
                 }
                 Ok(StepResult::Exit(reason)) => return Ok(reason),
+                Ok(StepResult::Trap(TrapCause::Breakpoint)) if self.debugger.is_some() => {
+                    // `ebreak` drops into the debugger instead of the trap
+                    // handler -- which has no `TrapCause::Breakpoint` arm
+                    // of its own and would otherwise just fail the VM with
+                    // `TrapError::Unhandled`.
+                    return Ok(ExitReason::Breakpoint(debugger::StopReason::Ebreak));
+                }
                 Ok(StepResult::Trap(cause)) => {
                     let result =
                         self.trap_handler
@@ -121,6 +348,9 @@ impl VirtualMachine {
                         Err(TrapError::Halt) => return Ok(ExitReason::Halt),
                         Err(e) => return Err(VmError::Trap(e)),
                     }
+                    if self.debugger.as_ref().map_or(false, |d| d.single_step()) {
+                        return Ok(ExitReason::Breakpoint(debugger::StopReason::Step));
+                    }
                 }
                 Err(e) => return Err(e),
             }
@@ -136,17 +366,33 @@ impl VirtualMachine {
             Err(e) => return Ok(StepResult::Trap(e)),
         };
 
-        let instruction_word = self.memory.read_word(pc_phys).map_err(VmError::Memory)?;
-        let instruction = Instruction::decode(instruction_word)?;
+        let (instruction_word, instruction) =
+            self.block_cache.fetch(self.memory.as_mut(), pc_phys)?;
+
+        // Only snapshot the register file when trace mode is actually on --
+        // this only covers instructions that retire normally (reach the
+        // `Ok(StepResult::Continue)` below), not ones that trap, since
+        // those are already visible as a distinct `ExitReason`/trap cause.
+        let trace_regs_before = self
+            .debugger
+            .as_ref()
+            .filter(|d| d.trace())
+            .map(|_| self.cpu.regs);
 
         self.cpu.pc += 4;
 
-        // Helper macro for data translation
+        // Helper macro for data translation. Rewinds the PC back to the
+        // faulting instruction (it was sped past during fetch, above) so a
+        // handler that resolves the fault in place, e.g. a copy-on-write
+        // store fault, resumes by re-executing the very access that faulted.
         macro_rules! translate_data {
             ($addr:expr, $access:expr) => {
                 match self.translate(VirtAddr::new($addr), $access) {
                     Ok(pa) => pa,
-                    Err(e) => return Ok(StepResult::Trap(e)),
+                    Err(e) => {
+                        self.cpu.pc = pc_val;
+                        return Ok(StepResult::Trap(e));
+                    }
                 }
             };
         }
@@ -275,6 +521,7 @@ impl VirtualMachine {
                 let phys = translate_data!(addr, AccessType::Write);
                 let val = self.cpu.read_reg(rs2) as u8;
                 self.memory.write_byte(phys, val).map_err(VmError::Memory)?;
+                self.block_cache.invalidate(phys.val());
             }
             Instruction::Sh { rs1, rs2, offset } => {
                 let addr = self.cpu.read_reg(rs1).wrapping_add(offset as u32);
@@ -289,6 +536,8 @@ impl VirtualMachine {
                     self.memory
                         .write_byte(phys2, (val >> 8) as u8)
                         .map_err(VmError::Memory)?;
+                    self.block_cache.invalidate(phys.val());
+                    self.block_cache.invalidate(phys2.val());
                 } else {
                     self.memory
                         .write_byte(phys, val as u8)
@@ -296,6 +545,7 @@ impl VirtualMachine {
                     self.memory
                         .write_byte(phys + 1, (val >> 8) as u8)
                         .map_err(VmError::Memory)?;
+                    self.block_cache.invalidate(phys.val());
                 }
             }
             Instruction::Sw { rs1, rs2, offset } => {
@@ -310,9 +560,11 @@ impl VirtualMachine {
                         self.memory
                             .write_byte(pa, bytes[i as usize])
                             .map_err(VmError::Memory)?;
+                        self.block_cache.invalidate(pa.val());
                     }
                 } else {
                     self.memory.write_word(phys, val).map_err(VmError::Memory)?;
+                    self.block_cache.invalidate(phys.val());
                 }
             }
             Instruction::Addi { rd, rs1, imm } => {
@@ -410,6 +662,362 @@ impl VirtualMachine {
                 self.cpu
                     .write_reg(rd, self.cpu.read_reg(rs1) & self.cpu.read_reg(rs2));
             }
+            Instruction::Fence { .. } => {
+                // Single-hart interpreter: all memory/IO ordering is already
+                // sequential, so FENCE is a no-op.
+            }
+            Instruction::Mul { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1) as i64;
+                let b = self.cpu.read_reg(rs2) as i64;
+                self.cpu.write_reg(rd, a.wrapping_mul(b) as u32);
+            }
+            Instruction::Mulh { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1) as i32 as i64;
+                let b = self.cpu.read_reg(rs2) as i32 as i64;
+                self.cpu.write_reg(rd, ((a * b) >> 32) as u32);
+            }
+            Instruction::Mulhsu { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1) as i32 as i64;
+                let b = self.cpu.read_reg(rs2) as i64; // zero-extended
+                self.cpu.write_reg(rd, ((a * b) >> 32) as u32);
+            }
+            Instruction::Mulhu { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1) as u64;
+                let b = self.cpu.read_reg(rs2) as u64;
+                self.cpu.write_reg(rd, ((a * b) >> 32) as u32);
+            }
+            // Division by zero and the signed MIN/-1 overflow both land on
+            // a fixed result rather than a trap -- RV32M defines both so
+            // guest code compiled against it never has to special-case
+            // them, the same way a real hart wouldn't fault on them either.
+            Instruction::Div { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1) as i32;
+                let b = self.cpu.read_reg(rs2) as i32;
+                let val = if b == 0 {
+                    -1
+                } else if a == i32::MIN && b == -1 {
+                    i32::MIN
+                } else {
+                    a.wrapping_div(b)
+                };
+                self.cpu.write_reg(rd, val as u32);
+            }
+            // Unsigned division has no MIN/-1 case to special-case --
+            // division by zero alone yields all-ones per spec.
+            Instruction::Divu { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1);
+                let b = self.cpu.read_reg(rs2);
+                let val = if b == 0 { u32::MAX } else { a.wrapping_div(b) };
+                self.cpu.write_reg(rd, val);
+            }
+            // `rem`'s fixed results mirror `div`'s: the dividend itself on
+            // divide-by-zero, zero on the MIN/-1 overflow case.
+            Instruction::Rem { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1) as i32;
+                let b = self.cpu.read_reg(rs2) as i32;
+                let val = if b == 0 {
+                    a
+                } else if a == i32::MIN && b == -1 {
+                    0
+                } else {
+                    a.wrapping_rem(b)
+                };
+                self.cpu.write_reg(rd, val as u32);
+            }
+            Instruction::Remu { rd, rs1, rs2 } => {
+                let a = self.cpu.read_reg(rs1);
+                let b = self.cpu.read_reg(rs2);
+                let val = if b == 0 { a } else { a.wrapping_rem(b) };
+                self.cpu.write_reg(rd, val);
+            }
+            Instruction::Csrrw { rd, csr, rs1 } => {
+                let old = self.cpu.csr.read(csr);
+                let new = self.cpu.read_reg(rs1);
+                self.cpu.csr.write(csr, new);
+                self.invalidate_block_cache_on_csr_write(csr);
+                self.cpu.write_reg(rd, old);
+            }
+            Instruction::Csrrs { rd, csr, rs1 } => {
+                let old = self.cpu.csr.read(csr);
+                if rs1 != Register::ZERO {
+                    self.cpu.csr.write(csr, old | self.cpu.read_reg(rs1));
+                    self.invalidate_block_cache_on_csr_write(csr);
+                }
+                self.cpu.write_reg(rd, old);
+            }
+            Instruction::Csrrc { rd, csr, rs1 } => {
+                let old = self.cpu.csr.read(csr);
+                if rs1 != Register::ZERO {
+                    self.cpu.csr.write(csr, old & !self.cpu.read_reg(rs1));
+                    self.invalidate_block_cache_on_csr_write(csr);
+                }
+                self.cpu.write_reg(rd, old);
+            }
+            Instruction::Csrrwi { rd, csr, imm } => {
+                let old = self.cpu.csr.read(csr);
+                self.cpu.csr.write(csr, imm);
+                self.invalidate_block_cache_on_csr_write(csr);
+                self.cpu.write_reg(rd, old);
+            }
+            Instruction::Csrrsi { rd, csr, imm } => {
+                let old = self.cpu.csr.read(csr);
+                if imm != 0 {
+                    self.cpu.csr.write(csr, old | imm);
+                    self.invalidate_block_cache_on_csr_write(csr);
+                }
+                self.cpu.write_reg(rd, old);
+            }
+            Instruction::Csrrci { rd, csr, imm } => {
+                let old = self.cpu.csr.read(csr);
+                if imm != 0 {
+                    self.cpu.csr.write(csr, old & !imm);
+                    self.invalidate_block_cache_on_csr_write(csr);
+                }
+                self.cpu.write_reg(rd, old);
+            }
+            Instruction::Mret => {
+                let mstatus = self.cpu.csr.mstatus;
+                let mpp = (mstatus & csr::MSTATUS_MPP_MASK) >> csr::MSTATUS_MPP_SHIFT;
+                let mpie = (mstatus & csr::MSTATUS_MPIE) != 0;
+
+                let mut new_status = mstatus & !csr::MSTATUS_MPP_MASK & !csr::MSTATUS_MIE;
+                if mpie {
+                    new_status |= csr::MSTATUS_MIE;
+                }
+                new_status |= csr::MSTATUS_MPIE; // MPIE is set to 1 per spec
+                self.cpu.csr.mstatus = new_status;
+
+                self.cpu.mode = match mpp {
+                    0b11 => PrivilegeMode::Machine,
+                    0b01 => PrivilegeMode::Supervisor,
+                    _ => PrivilegeMode::User,
+                };
+                self.cpu.pc = self.cpu.csr.mepc;
+            }
+            Instruction::Sret => {
+                let mstatus = self.cpu.csr.mstatus;
+                let spp = (mstatus & csr::MSTATUS_SPP) != 0;
+                let spie = (mstatus & csr::MSTATUS_SPIE) != 0;
+
+                let mut new_status = mstatus & !csr::MSTATUS_SPP & !csr::MSTATUS_SIE;
+                if spie {
+                    new_status |= csr::MSTATUS_SIE;
+                }
+                new_status |= csr::MSTATUS_SPIE; // SPIE is set to 1 per spec
+                self.cpu.csr.mstatus = new_status;
+
+                self.cpu.mode = if spp {
+                    PrivilegeMode::Supervisor
+                } else {
+                    PrivilegeMode::User
+                };
+                self.cpu.pc = self.cpu.csr.sepc;
+            }
+            Instruction::Wfi => {
+                // A no-op: `run()` checks for pending timer and external
+                // interrupts before every instruction anyway, so there is
+                // nothing to idle on.
+            }
+            Instruction::SfenceVma { rs1, rs2 } => {
+                let vaddr = (rs1 != Register::ZERO).then(|| self.cpu.read_reg(rs1));
+                let asid = (rs2 != Register::ZERO).then(|| self.cpu.read_reg(rs2));
+                self.tlb.flush(vaddr, asid);
+                // Like the TLB, the block cache has no way to tell which
+                // decoded blocks a page-table edit invalidated, so any
+                // `sfence.vma` drops the whole cache rather than just the
+                // entries `vaddr`/`asid` would select.
+                self.block_cache.invalidate_all();
+            }
+            Instruction::Flw { rd, rs1, offset } => {
+                let addr = self.cpu.read_reg(rs1).wrapping_add(offset as u32);
+                let phys = translate_data!(addr, AccessType::Read);
+                let bits = self.memory.read_word(phys).map_err(VmError::Memory)?;
+                self.cpu.write_freg(rd, f32::from_bits(bits));
+            }
+            Instruction::Fsw { rs1, rs2, offset } => {
+                let addr = self.cpu.read_reg(rs1).wrapping_add(offset as u32);
+                let phys = translate_data!(addr, AccessType::Write);
+                let bits = self.cpu.read_freg(rs2).to_bits();
+                self.memory.write_word(phys, bits).map_err(VmError::Memory)?;
+            }
+            Instruction::FaddS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                let result = a + b;
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FsubS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                let result = a - b;
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FmulS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                let result = a * b;
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FdivS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                let result = a / b;
+                if b == 0.0 && a != 0.0 && !a.is_nan() {
+                    self.cpu.csr.fcsr |= csr::FFLAG_DZ;
+                }
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FsgnjS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                self.cpu.write_freg(rd, a.copysign(b));
+            }
+            Instruction::FsgnjnS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                self.cpu.write_freg(rd, a.copysign(-b));
+            }
+            Instruction::FsgnjxS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                let sign = (a.to_bits() ^ b.to_bits()) & 0x8000_0000;
+                self.cpu
+                    .write_freg(rd, f32::from_bits((a.to_bits() & 0x7FFF_FFFF) | sign));
+            }
+            Instruction::FeqS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                if is_signaling_nan(a) || is_signaling_nan(b) {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                self.cpu.write_reg(rd, (a == b) as u32);
+            }
+            Instruction::FltS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                if a.is_nan() || b.is_nan() {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                self.cpu.write_reg(rd, (a < b) as u32);
+            }
+            Instruction::FleS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                if a.is_nan() || b.is_nan() {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                self.cpu.write_reg(rd, (a <= b) as u32);
+            }
+            Instruction::FcvtWS { rd, rs1 } => {
+                let a = self.cpu.read_freg(rs1);
+                let result = if a.is_nan() { i32::MAX } else { a as i32 };
+                if a.is_nan() || !(-2147483648.0f32..2147483648.0f32).contains(&a) {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                self.cpu.write_reg(rd, result as u32);
+            }
+            Instruction::FcvtWuS { rd, rs1 } => {
+                let a = self.cpu.read_freg(rs1);
+                let result = if a.is_nan() { u32::MAX } else { a as u32 };
+                if a.is_nan() || !(0.0f32..4294967296.0f32).contains(&a) {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                self.cpu.write_reg(rd, result);
+            }
+            Instruction::FcvtSW { rd, rs1 } => {
+                let a = self.cpu.read_reg(rs1) as i32;
+                self.cpu.write_freg(rd, a as f32);
+            }
+            Instruction::FcvtSWu { rd, rs1 } => {
+                let a = self.cpu.read_reg(rs1);
+                self.cpu.write_freg(rd, a as f32);
+            }
+            Instruction::FsqrtS { rd, rs1 } => {
+                let a = self.cpu.read_freg(rs1);
+                let result = a.sqrt();
+                if a < 0.0 || (a.is_nan() && !result.is_nan()) {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FminS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                if is_signaling_nan(a) || is_signaling_nan(b) {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                let result = match (a.is_nan(), b.is_nan()) {
+                    (true, true) => f32::NAN,
+                    (true, false) => b,
+                    (false, true) => a,
+                    (false, false) => a.min(b),
+                };
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FmaxS { rd, rs1, rs2 } => {
+                let a = self.cpu.read_freg(rs1);
+                let b = self.cpu.read_freg(rs2);
+                if is_signaling_nan(a) || is_signaling_nan(b) {
+                    self.cpu.csr.fcsr |= csr::FFLAG_NV;
+                }
+                let result = match (a.is_nan(), b.is_nan()) {
+                    (true, true) => f32::NAN,
+                    (true, false) => b,
+                    (false, true) => a,
+                    (false, false) => a.max(b),
+                };
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FmvXW { rd, rs1 } => {
+                self.cpu.write_reg(rd, self.cpu.read_freg(rs1).to_bits());
+            }
+            Instruction::FmvWX { rd, rs1 } => {
+                self.cpu.write_freg(rd, f32::from_bits(self.cpu.read_reg(rs1)));
+            }
+            Instruction::FmaddS { rd, rs1, rs2, rs3 } => {
+                let (a, b, c) = (
+                    self.cpu.read_freg(rs1),
+                    self.cpu.read_freg(rs2),
+                    self.cpu.read_freg(rs3),
+                );
+                let result = a.mul_add(b, c);
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FmsubS { rd, rs1, rs2, rs3 } => {
+                let (a, b, c) = (
+                    self.cpu.read_freg(rs1),
+                    self.cpu.read_freg(rs2),
+                    self.cpu.read_freg(rs3),
+                );
+                let result = a.mul_add(b, -c);
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FnmsubS { rd, rs1, rs2, rs3 } => {
+                let (a, b, c) = (
+                    self.cpu.read_freg(rs1),
+                    self.cpu.read_freg(rs2),
+                    self.cpu.read_freg(rs3),
+                );
+                let result = -(a.mul_add(b, -c));
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
+            Instruction::FnmaddS { rd, rs1, rs2, rs3 } => {
+                let (a, b, c) = (
+                    self.cpu.read_freg(rs1),
+                    self.cpu.read_freg(rs2),
+                    self.cpu.read_freg(rs3),
+                );
+                let result = -(a.mul_add(b, c));
+                accumulate_fp_flags(&mut self.cpu.csr, a, b, result);
+                self.cpu.write_freg(rd, result);
+            }
             Instruction::Ecall => {
                 self.cpu.pc = pc_val; // Rewind PC for trap handler
                 let cause = match self.cpu.mode {
@@ -423,8 +1031,65 @@ impl VirtualMachine {
                 self.cpu.pc = pc_val;
                 return Ok(StepResult::Trap(TrapCause::Breakpoint));
             }
+            // Only the RV32A variants have no arm above. `decode` never
+            // produces them (it has no arm for their opcode), so only a
+            // hand-built `Instruction` lands here.
+            _ => {
+                self.cpu.pc = pc_val;
+                return Ok(StepResult::Trap(TrapCause::IllegalInstruction {
+                    instruction: instruction_word,
+                }));
+            }
+        }
+
+        if let Some(regs_before) = trace_regs_before {
+            if let Some(debugger) = &mut self.debugger {
+                debugger.log_trace(Self::format_trace_line(
+                    pc_val,
+                    instruction,
+                    &regs_before,
+                    &self.cpu.regs,
+                ));
+            }
         }
 
         Ok(StepResult::Continue)
     }
+
+    fn format_trace_line(
+        pc: u32,
+        instruction: Instruction,
+        before: &[u32; 32],
+        after: &[u32; 32],
+    ) -> String {
+        let mut line = format!("{:#010x}: {}", pc, Self::render_instruction(instruction));
+        for (i, (&b, &a)) in before.iter().zip(after.iter()).enumerate() {
+            if b != a {
+                line.push_str(&format!("  x{}: {:#010x} -> {:#010x}", i, b, a));
+            }
+        }
+        line
+    }
+}
+
+/// Accrue `fflags` for a software-evaluated single-precision op: invalid if
+/// the result is NaN, overflow if it went infinite from finite inputs.
+/// Underflow and inexact aren't tracked since doing so precisely would
+/// require a real soft-float implementation rather than native `f32` ops.
+fn accumulate_fp_flags(csr: &mut csr::Csr, a: f32, b: f32, result: f32) {
+    if result.is_nan() {
+        csr.fcsr |= csr::FFLAG_NV;
+    } else if result.is_infinite() && a.is_finite() && b.is_finite() {
+        csr.fcsr |= csr::FFLAG_OF;
+    }
+}
+
+/// Whether `v` is a signaling NaN (quiet NaNs have the MSB of the mantissa
+/// set; signaling NaNs don't). `FEQ.S` only raises `FFLAG_NV` for signaling
+/// NaNs, unlike `FLT.S`/`FLE.S` which raise it for any NaN operand.
+fn is_signaling_nan(v: f32) -> bool {
+    let bits = v.to_bits();
+    let exponent_all_ones = (bits >> 23) & 0xFF == 0xFF;
+    let mantissa = bits & 0x7F_FFFF;
+    exponent_all_ones && mantissa != 0 && mantissa & 0x40_0000 == 0
 }
@@ -92,11 +92,16 @@ pub struct PhysPageNum(pub u32);
 pub struct VirtPageNum(pub u32);
 
 /// Memory Access trait
+///
+/// Reads take `&mut self` alongside writes: a flat RAM backing store never
+/// needs it, but a bus-routed MMIO device can (a UART RX register drains a
+/// queue on read, a CLINT latches state), so the trait has to accommodate
+/// the side-effecting case uniformly.
 pub trait Memory {
-    fn read_byte(&self, addr: PhysAddr) -> Result<u8, crate::error::MemoryError>;
+    fn read_byte(&mut self, addr: PhysAddr) -> Result<u8, crate::error::MemoryError>;
     fn write_byte(&mut self, addr: PhysAddr, val: u8) -> Result<(), crate::error::MemoryError>;
 
-    fn read_word(&self, addr: PhysAddr) -> Result<u32, crate::error::MemoryError> {
+    fn read_word(&mut self, addr: PhysAddr) -> Result<u32, crate::error::MemoryError> {
         let b0 = self.read_byte(addr)? as u32;
         let b1 = self.read_byte(addr + 1)? as u32;
         let b2 = self.read_byte(addr + 2)? as u32;
@@ -111,6 +116,31 @@ pub trait Memory {
         self.write_byte(addr + 3, ((val >> 24) & 0xFF) as u8)?;
         Ok(())
     }
+
+    /// Borrow `len` contiguous bytes starting at `addr` as a single mutable
+    /// slice into the real backing store, for a caller copying many bytes
+    /// at once instead of one `read_byte`/`write_byte` call per byte. Only
+    /// meaningful for flat RAM: an address inside an MMIO device's range
+    /// has no byte array to slice into, so an implementor backed by one
+    /// reports `MemoryError::OutOfBounds` for it the same as any other
+    /// address it can't satisfy.
+    fn slice_mut(&mut self, addr: PhysAddr, len: usize) -> Result<&mut [u8], crate::error::MemoryError>;
+
+    /// Advance per-tick device logic (polling a receive queue, etc.) and
+    /// route any interrupts devices raise this tick into an interrupt
+    /// controller, if one exists. Default no-op: a bare `SimpleMemory` (or
+    /// any other implementor with no devices behind it) has nothing to
+    /// tick.
+    fn tick_devices(&mut self) -> Result<(), crate::error::MemoryError> {
+        Ok(())
+    }
+
+    /// Whether an interrupt controller behind this `Memory`, if any, has a
+    /// pending and enabled IRQ the CPU should be told about this tick, via
+    /// `mip.MEIP`. Default `false` for implementors with no controller.
+    fn pending_interrupt(&self) -> bool {
+        false
+    }
 }
 
 pub struct SimpleMemory {
@@ -120,9 +150,16 @@ pub struct SimpleMemory {
 
 impl SimpleMemory {
     pub fn new(size: usize) -> Self {
+        Self::with_base(0x8000_0000, size)
+    }
+
+    /// Like `new`, but at an arbitrary base rather than the historical
+    /// `0x8000_0000` -- lets `SystemBusBuilder` place RAM wherever its
+    /// caller's address map says it should live.
+    pub fn with_base(base_addr: u32, size: usize) -> Self {
         Self {
             data: vec![0; size],
-            base_addr: 0x8000_0000,
+            base_addr,
         }
     }
 
@@ -141,7 +178,7 @@ impl SimpleMemory {
 }
 
 impl Memory for SimpleMemory {
-    fn read_byte(&self, addr: PhysAddr) -> Result<u8, crate::error::MemoryError> {
+    fn read_byte(&mut self, addr: PhysAddr) -> Result<u8, crate::error::MemoryError> {
         if addr.0 >= self.base_addr {
             let offset = (addr.0 - self.base_addr) as usize;
             if offset < self.data.len() {
@@ -161,4 +198,16 @@ impl Memory for SimpleMemory {
         }
         Err(crate::error::MemoryError::OutOfBounds(addr.0))
     }
+
+    fn slice_mut(&mut self, addr: PhysAddr, len: usize) -> Result<&mut [u8], crate::error::MemoryError> {
+        if addr.0 < self.base_addr {
+            return Err(crate::error::MemoryError::OutOfBounds(addr.0));
+        }
+        let start = (addr.0 - self.base_addr) as usize;
+        let end = start + len;
+        if end > self.data.len() {
+            return Err(crate::error::MemoryError::OutOfBounds(end as u32));
+        }
+        Ok(&mut self.data[start..end])
+    }
 }
@@ -0,0 +1,284 @@
+use crate::cpu::{PrivilegeMode, Xlen};
+use crate::memory::{Memory, PhysAddr, VirtAddr};
+use crate::trap::TrapCause;
+use alloc::collections::BTreeMap;
+
+/// Trim `addr` to the effective address width of `xlen` (32 bits under Sv32,
+/// 39 under Sv39), clearing any bits a real hart would never produce.
+/// `translate` doesn't call this yet — today's walker is Sv32-only and every
+/// `VirtAddr` is already a bare `u32`, so there's nothing above bit 31 to
+/// trim — but it's the one place that future Sv39 support needs to change
+/// to stop treating every address as 32 bits wide.
+pub fn mask_addr(addr: u64, xlen: Xlen) -> u64 {
+    let bits = xlen.addr_bits();
+    if bits >= 64 {
+        addr
+    } else {
+        addr & ((1u64 << bits) - 1)
+    }
+}
+
+/// The kind of access being made to memory, used to select which permission
+/// bits a page table entry must grant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Sv32 page table entry bits.
+pub const PTE_V: u32 = 1 << 0;
+pub const PTE_R: u32 = 1 << 1;
+pub const PTE_W: u32 = 1 << 2;
+pub const PTE_X: u32 = 1 << 3;
+pub const PTE_U: u32 = 1 << 4;
+pub const PTE_G: u32 = 1 << 5;
+pub const PTE_A: u32 = 1 << 6;
+pub const PTE_D: u32 = 1 << 7;
+
+/// `mstatus` bit 18: Supervisor User Memory access — lets S-mode read/write
+/// pages marked `PTE_U`.
+pub const MSTATUS_SUM: u32 = 1 << 18;
+/// `mstatus` bit 19: Make eXecutable Readable — lets a load target an
+/// execute-only (`PTE_X`, `!PTE_R`) page.
+pub const MSTATUS_MXR: u32 = 1 << 19;
+
+/// A resolved `vpn -> ppn` mapping cached by the software TLB, along with
+/// the leaf PTE's flag bits and its physical address so a later write can
+/// set `PTE_D` without re-walking the page table.
+#[derive(Debug, Clone, Copy)]
+struct TlbEntry {
+    leaf_addr: PhysAddr,
+    ppn: u32,
+    flags: u32,
+}
+
+/// Software translation cache sitting in front of the Sv32 page-table
+/// walker. Entries are keyed by `(asid, vpn)`, mirroring the hardware TLB's
+/// tagging scheme, so switching `satp`'s ASID field doesn't require
+/// invalidating mappings that belong to a different address space. A plain
+/// `satp` write (new root PPN, same or different ASID) is detected by
+/// comparing against the `satp` seen on the previous lookup and flushes
+/// everything, since we have no cheaper way to tell which entries the new
+/// root page table would still agree with.
+#[derive(Debug, Clone, Default)]
+pub struct Tlb {
+    satp: u32,
+    entries: BTreeMap<(u32, u32), TlbEntry>,
+}
+
+impl Tlb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flush the whole TLB, as on `sfence.vma x0, x0`.
+    pub fn flush_all(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Flush the entries selected by `sfence.vma`'s `rs1`/`rs2` operands:
+    /// an omitted (zero) operand means "all" for that axis.
+    pub fn flush(&mut self, vaddr: Option<u32>, asid: Option<u32>) {
+        match (vaddr, asid) {
+            (None, None) => self.flush_all(),
+            (Some(vaddr), None) => {
+                let vpn = vaddr >> 12;
+                self.entries.retain(|&(_, v), _| v != vpn);
+            }
+            (None, Some(asid)) => {
+                self.entries.retain(|&(a, _), _| a != asid);
+            }
+            (Some(vaddr), Some(asid)) => {
+                let vpn = vaddr >> 12;
+                self.entries.remove(&(asid, vpn));
+            }
+        }
+    }
+
+    fn lookup(&mut self, satp: u32, asid: u32, vpn: u32) -> Option<TlbEntry> {
+        if satp != self.satp {
+            self.satp = satp;
+            self.entries.clear();
+            return None;
+        }
+        self.entries.get(&(asid, vpn)).copied()
+    }
+
+    fn insert(&mut self, asid: u32, vpn: u32, entry: TlbEntry) {
+        self.entries.insert((asid, vpn), entry);
+    }
+}
+
+/// A PTE with `PTE_W` set but `PTE_R` clear is reserved by the Sv32 spec for
+/// future use and must fault if encountered mid-walk.
+fn is_reserved_encoding(pte: u32) -> bool {
+    pte & PTE_W != 0 && pte & PTE_R == 0
+}
+
+/// Translate a virtual address to a physical address.
+///
+/// `satp` follows the Sv32 encoding: bit 31 selects the translation mode
+/// (0 = Bare, 1 = Sv32), bits `[30:22]` hold the ASID, and bits `[21:0]`
+/// hold the physical page number of the root page table. Machine mode and
+/// Bare `satp` both bypass translation entirely (identity mapping);
+/// Supervisor/User mode under Sv32 first consults `tlb` and, on a miss,
+/// walks the two-level page table, honoring `mstatus.SUM`/`mstatus.MXR` and
+/// writing back `PTE_A` (and `PTE_D` on writes) to the leaf PTE once the
+/// access is permitted, per the privileged spec's hardware A/D update rule.
+/// A successful walk is cached in `tlb` so the next access to the same page
+/// skips straight to the leaf PPN.
+pub fn translate(
+    addr: VirtAddr,
+    access: AccessType,
+    satp: u32,
+    mode: PrivilegeMode,
+    mstatus: u32,
+    memory: &mut dyn Memory,
+    tlb: &mut Tlb,
+) -> Result<PhysAddr, TrapCause> {
+    if satp & 0x8000_0000 == 0 || mode == PrivilegeMode::Machine {
+        return Ok(PhysAddr::new(addr.val()));
+    }
+
+    let sum = mstatus & MSTATUS_SUM != 0;
+    let mxr = mstatus & MSTATUS_MXR != 0;
+
+    let vaddr = addr.val();
+    let vpn1 = (vaddr >> 22) & 0x3FF;
+    let vpn0 = (vaddr >> 12) & 0x3FF;
+    let offset = vaddr & 0xFFF;
+    let vpn = vaddr >> 12;
+    let asid = (satp >> 22) & 0x1FF;
+
+    if let Some(mut entry) = tlb.lookup(satp, asid, vpn) {
+        check_permissions(entry.flags, access, mode, sum, mxr, addr)?;
+        if access == AccessType::Write && entry.flags & PTE_D == 0 {
+            entry.flags |= PTE_D;
+            memory
+                .write_word(entry.leaf_addr, entry.flags)
+                .map_err(|_| access_fault(access, addr))?;
+            tlb.insert(asid, vpn, entry);
+        }
+        return Ok(PhysAddr::new((entry.ppn << 12) | offset));
+    }
+
+    let root_ppn = satp & 0x003F_FFFF;
+    let pte1_addr = PhysAddr::new((root_ppn << 12) + vpn1 * 4);
+    let pte1 = read_pte(memory, pte1_addr, access, addr)?;
+    if pte1 & PTE_V == 0 || is_reserved_encoding(pte1) {
+        return Err(page_fault(access, addr));
+    }
+
+    let is_superpage = pte1 & (PTE_R | PTE_X) != 0;
+    let (leaf_addr, leaf_pte) = if is_superpage {
+        // A leaf at the first level is a 4 MiB superpage; its PPN0 field
+        // must be zero (i.e. it maps on a 4 MiB boundary), else it's a
+        // misaligned superpage and faults.
+        if (pte1 >> 10) & 0x3FF != 0 {
+            return Err(page_fault(access, addr));
+        }
+        (pte1_addr, pte1)
+    } else {
+        let ppn0 = (pte1 >> 10) & 0x3F_FFFF;
+        let pte0_addr = PhysAddr::new((ppn0 << 12) + vpn0 * 4);
+        let pte0 = read_pte(memory, pte0_addr, access, addr)?;
+        if pte0 & PTE_V == 0 || is_reserved_encoding(pte0) {
+            return Err(page_fault(access, addr));
+        }
+        (pte0_addr, pte0)
+    };
+
+    check_permissions(leaf_pte, access, mode, sum, mxr, addr)?;
+
+    let mut updated = leaf_pte;
+    let mut needs_writeback = false;
+    if leaf_pte & PTE_A == 0 {
+        updated |= PTE_A;
+        needs_writeback = true;
+    }
+    if access == AccessType::Write && leaf_pte & PTE_D == 0 {
+        updated |= PTE_D;
+        needs_writeback = true;
+    }
+    if needs_writeback {
+        memory
+            .write_word(leaf_addr, updated)
+            .map_err(|_| access_fault(access, addr))?;
+    }
+
+    // For a 4 MiB superpage leaf, `leaf_pte`'s PPN0 field is zero (checked
+    // above) and only carries PPN1; fold in `vpn0` here so the cached PPN
+    // already points at the right 4 KiB frame within the megapage and the
+    // plain `(ppn << 12) | offset` below is correct for both leaf sizes.
+    let ppn = if is_superpage {
+        ((leaf_pte >> 10) & 0x3F_FFFF) | vpn0
+    } else {
+        (leaf_pte >> 10) & 0x3F_FFFF
+    };
+    tlb.insert(
+        asid,
+        vpn,
+        TlbEntry {
+            leaf_addr,
+            ppn,
+            flags: updated,
+        },
+    );
+    Ok(PhysAddr::new((ppn << 12) | offset))
+}
+
+fn read_pte(
+    memory: &mut dyn Memory,
+    addr: PhysAddr,
+    access: AccessType,
+    fault_addr: VirtAddr,
+) -> Result<u32, TrapCause> {
+    memory
+        .read_word(addr)
+        .map_err(|_| access_fault(access, fault_addr))
+}
+
+fn check_permissions(
+    pte: u32,
+    access: AccessType,
+    mode: PrivilegeMode,
+    sum: bool,
+    mxr: bool,
+    addr: VirtAddr,
+) -> Result<(), TrapCause> {
+    let user_page = pte & PTE_U != 0;
+    if mode == PrivilegeMode::User && !user_page {
+        return Err(page_fault(access, addr));
+    }
+    if mode == PrivilegeMode::Supervisor && user_page && !sum {
+        return Err(page_fault(access, addr));
+    }
+
+    let permitted = match access {
+        AccessType::Execute => pte & PTE_X != 0,
+        AccessType::Read => pte & PTE_R != 0 || (mxr && pte & PTE_X != 0),
+        AccessType::Write => pte & PTE_W != 0,
+    };
+    if !permitted {
+        return Err(page_fault(access, addr));
+    }
+    Ok(())
+}
+
+fn page_fault(access: AccessType, addr: VirtAddr) -> TrapCause {
+    match access {
+        AccessType::Execute => TrapCause::InstructionPageFault { addr },
+        AccessType::Read => TrapCause::LoadPageFault { addr },
+        AccessType::Write => TrapCause::StorePageFault { addr },
+    }
+}
+
+fn access_fault(access: AccessType, addr: VirtAddr) -> TrapCause {
+    match access {
+        AccessType::Execute => TrapCause::InstructionAccessFault { addr },
+        AccessType::Read => TrapCause::LoadAccessFault { addr },
+        AccessType::Write => TrapCause::StoreAccessFault { addr },
+    }
+}
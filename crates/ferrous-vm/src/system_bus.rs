@@ -1,69 +1,613 @@
+use crate::address_map::{AddressMap, AddressTarget};
+use crate::devices::block::BLOCK_DEVICE_BASE;
+use crate::devices::dma::{
+    DmaController, DmaDescriptor, DMA_BASE, DMA_IRQ, DMA_SIZE, MAX_CHAIN_LEN, STATUS_DONE,
+    STATUS_ERROR,
+};
+use crate::devices::plic::{InterruptController, PLIC_BASE, PLIC_SIZE};
+use crate::devices::virtio_blk::{
+    self, Descriptor, VirtioBlockController, DESC_ENTRY_SIZE, QUEUE_SIZE, REQ_TYPE_IN,
+    REQ_TYPE_OUT, STATUS_IOERR, STATUS_OK, STATUS_UNSUPP, USED_ENTRY_SIZE, VIRTIO_BLK_BASE,
+    VIRTIO_BLK_IRQ, VIRTIO_BLK_SIZE,
+};
 use crate::devices::{Device, DeviceManager};
 use crate::error::MemoryError;
 use crate::memory::{Memory, PhysAddr, SimpleMemory};
+use std::ops::Range;
 
+// `SimpleBlockDevice`/`MemBlockDevice`'s register offsets are private to
+// `devices::block` (its own MMIO consumer, `fs::block::read_sector`, mirrors
+// them locally rather than importing them too), so the DMA engine's
+// device-to-RAM copy mirrors them here as well.
+const BLOCK_REG_COMMAND: u32 = 0x04;
+const BLOCK_REG_SECTOR: u32 = 0x08;
+const BLOCK_REG_CAPACITY: u32 = 0x0C;
+const BLOCK_BUFFER_START: u32 = 0x100;
+
+/// Base `SystemBus::new` places RAM at, matching the historical
+/// `addr.0 >= 0x8000_0000` split this address map replaces.
+const DEFAULT_RAM_BASE: u32 = 0x8000_0000;
+
+/// Places RAM, an optional ROM image, and the MMIO window into an
+/// `AddressMap` before handing back a `SystemBus`, so none of those three
+/// are pinned to a fixed address the way they used to be -- and so two of
+/// them landing on the same range is a build-time error instead of one
+/// silently shadowing the other.
+pub struct SystemBusBuilder {
+    ram: Option<(u32, usize)>,
+    rom: Option<(u32, Vec<u8>)>,
+    mmio_window: Range<u32>,
+}
+
+impl Default for SystemBusBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemBusBuilder {
+    pub fn new() -> Self {
+        Self {
+            ram: None,
+            rom: None,
+            mmio_window: 0..DEFAULT_RAM_BASE,
+        }
+    }
+
+    pub fn ram(mut self, base: u32, size: usize) -> Self {
+        self.ram = Some((base, size));
+        self
+    }
+
+    pub fn rom(mut self, base: u32, image: Vec<u8>) -> Self {
+        self.rom = Some((base, image));
+        self
+    }
+
+    pub fn mmio_window(mut self, range: Range<u32>) -> Self {
+        self.mmio_window = range;
+        self
+    }
+
+    pub fn build(self) -> Result<SystemBus, MemoryError> {
+        let mut map = AddressMap::new();
+
+        let (ram_base, ram_size) = self.ram.unwrap_or((DEFAULT_RAM_BASE, 0));
+        map.add(ram_base..ram_base + ram_size as u32, AddressTarget::Ram)?;
+
+        let rom = match self.rom {
+            Some((rom_base, image)) => {
+                let end = rom_base + image.len() as u32;
+                map.add(rom_base..end, AddressTarget::Rom)?;
+                Some((rom_base, image))
+            }
+            None => None,
+        };
+
+        // The fixed PLIC/DMA/virtio-blk windows sit back to back inside the
+        // default MMIO window, so the window is mapped around them.
+        let fixed = PLIC_BASE..VIRTIO_BLK_BASE + VIRTIO_BLK_SIZE;
+        let window = &self.mmio_window;
+        for piece in [
+            window.start..window.end.min(fixed.start),
+            window.start.max(fixed.end)..window.end,
+        ] {
+            if !piece.is_empty() {
+                map.add(piece, AddressTarget::Device)?;
+            }
+        }
+        map.add(PLIC_BASE..PLIC_BASE + PLIC_SIZE, AddressTarget::Controller)?;
+        map.add(DMA_BASE..DMA_BASE + DMA_SIZE, AddressTarget::Dma)?;
+        map.add(
+            VIRTIO_BLK_BASE..VIRTIO_BLK_BASE + VIRTIO_BLK_SIZE,
+            AddressTarget::VirtioBlk,
+        )?;
+
+        Ok(SystemBus {
+            ram: SimpleMemory::with_base(ram_base, ram_size),
+            rom,
+            controller: InterruptController::new(),
+            dma: DmaController::new(),
+            virtio_blk: VirtioBlockController::new(),
+            devices: DeviceManager::new(),
+            map,
+            mmio_window: self.mmio_window,
+        })
+    }
+}
+
+/// A `Memory` implementor that routes each access through an `AddressMap`
+/// to flat RAM, a read-only ROM image, the `InterruptController` (reached
+/// directly rather than through `DeviceManager`'s uniform `Box<dyn Device>`
+/// interface, since `tick_devices`/`pending_interrupt` need it specifically),
+/// or whichever registered `Device` claims the address within the MMIO
+/// window, so guest code can talk to a UART, CLINT, or block device as
+/// ordinary loads/stores instead of everything being backing-store bytes.
 pub struct SystemBus {
     ram: SimpleMemory,
+    rom: Option<(u32, Vec<u8>)>,
+    controller: InterruptController,
+    /// The DMA engine's register file. Reached directly like `controller`,
+    /// not through `devices`, since servicing a descriptor chain needs `ram`
+    /// and `devices` (the block device it copies from) at the same time.
+    dma: DmaController,
+    /// The virtqueue-based block device's register file, reached directly
+    /// for the same reason as `dma`: draining a ring needs `ram` and
+    /// `devices` at once.
+    virtio_blk: VirtioBlockController,
     devices: DeviceManager,
+    map: AddressMap,
+    mmio_window: Range<u32>,
 }
 
 impl SystemBus {
-    pub fn new(memory_size: usize) -> Self {
-        Self {
-            ram: SimpleMemory::new(memory_size),
-            devices: DeviceManager::new(),
-        }
+    /// Convenience constructor for the layout this address map replaced:
+    /// RAM at `0x8000_0000`, the MMIO window below it. Equivalent to
+    /// `SystemBusBuilder::new().ram(0x8000_0000, memory_size).build()`.
+    pub fn new(memory_size: usize) -> Result<Self, MemoryError> {
+        SystemBusBuilder::new()
+            .ram(DEFAULT_RAM_BASE, memory_size)
+            .build()
     }
 
-    pub fn add_device(&mut self, base_addr: u32, size: u32, device: Box<dyn Device>) {
+    /// Register a device at `base_addr..base_addr + size`. Fails if that
+    /// range falls outside the MMIO window the bus was built with.
+    pub fn add_device(
+        &mut self,
+        base_addr: u32,
+        size: u32,
+        device: Box<dyn Device>,
+    ) -> Result<(), MemoryError> {
+        let end = base_addr + size;
+        if base_addr < self.mmio_window.start || end > self.mmio_window.end {
+            return Err(MemoryError::OutOfBounds(base_addr));
+        }
         self.devices.add_device(base_addr, size, device);
+        Ok(())
     }
 
     pub fn load_program(&mut self, addr: PhysAddr, data: &[u8]) -> Result<(), MemoryError> {
         self.ram.load(addr, data)
     }
+
+    /// Longest run of sectors a single DMA descriptor can cover, so a
+    /// malformed/huge `length` can't turn one link into an unbounded loop.
+    const MAX_SECTORS_PER_DESCRIPTOR: u32 = 128;
+
+    /// Read through the registered block device straight into
+    /// `dest_addr..dest_addr+length`, the transfer a single DMA descriptor
+    /// describes -- `length` spanning more than one sector's worth just
+    /// means this link re-triggers the block device's own sector-read
+    /// command each time the copy crosses a 512-byte boundary, so one
+    /// descriptor can move a whole run of contiguous sectors instead of
+    /// needing one chained link per sector. `length` must be a multiple of
+    /// 4 (the device buffer is only addressable a word at a time) and span
+    /// at most `MAX_SECTORS_PER_DESCRIPTOR` sectors; `dest_addr..dest_addr+
+    /// length` must land entirely within the mapped RAM region, so a
+    /// malformed or out-of-bounds descriptor errors out here instead of
+    /// aliasing into a device register window or past the end of RAM.
+    fn dma_copy_sectors(&mut self, sector: u32, dest_addr: u32, length: u32) -> Result<(), MemoryError> {
+        if length == 0 || length % 4 != 0 {
+            return Err(MemoryError::OutOfBounds(dest_addr));
+        }
+        if length.div_ceil(512) > Self::MAX_SECTORS_PER_DESCRIPTOR {
+            return Err(MemoryError::OutOfBounds(dest_addr));
+        }
+        let in_ram = |addr: u32| matches!(self.map.lookup(addr), Some((AddressTarget::Ram, _)));
+        if !in_ram(dest_addr) || !in_ram(dest_addr + length - 1) {
+            return Err(MemoryError::OutOfBounds(dest_addr));
+        }
+
+        let mut loaded_sector = None;
+        for i in (0..length).step_by(4) {
+            let this_sector = sector + i / 512;
+            if loaded_sector != Some(this_sector) {
+                self.devices
+                    .write_word(BLOCK_DEVICE_BASE + BLOCK_REG_SECTOR, this_sector)?;
+                self.devices
+                    .write_word(BLOCK_DEVICE_BASE + BLOCK_REG_COMMAND, 1)?;
+                loaded_sector = Some(this_sector);
+            }
+            let word = self
+                .devices
+                .read_word(BLOCK_DEVICE_BASE + BLOCK_BUFFER_START + (i % 512))?;
+            self.ram.write_word(PhysAddr::new(dest_addr + i), word)?;
+        }
+        Ok(())
+    }
+
+    /// Walk the descriptor chain rooted at `self.dma`'s `head` register,
+    /// copying each link's run of sectors and writing each descriptor's
+    /// `done` flag as it's serviced, until `next` is 0, a descriptor is malformed,
+    /// or the chain runs past `MAX_CHAIN_LEN` links -- the latter two both
+    /// leave the engine in `STATUS_ERROR` rather than spinning or
+    /// panicking. Always raises `DMA_IRQ` on the way out, success or not,
+    /// so guest code waiting on the interrupt rather than polling
+    /// `REG_STATUS` still hears about a failed chain.
+    fn run_dma_chain(&mut self) {
+        let mut addr = self.dma.head();
+        let mut steps = 0;
+        let mut status = STATUS_DONE;
+
+        while addr != 0 {
+            if steps >= MAX_CHAIN_LEN {
+                status = STATUS_ERROR;
+                break;
+            }
+            steps += 1;
+
+            let desc = match DmaDescriptor::read_from(self, addr) {
+                Ok(desc) => desc,
+                Err(_) => {
+                    status = STATUS_ERROR;
+                    break;
+                }
+            };
+
+            let done_code = if desc.length == 0 {
+                // A no-op link, not malformed -- nothing to copy.
+                DmaDescriptor::DONE_OK
+            } else if self
+                .dma_copy_sectors(desc.sector, desc.dest_addr, desc.length)
+                .is_ok()
+            {
+                DmaDescriptor::DONE_OK
+            } else {
+                DmaDescriptor::DONE_ERROR
+            };
+
+            if DmaDescriptor::write_done(self, addr, done_code).is_err() || done_code == DmaDescriptor::DONE_ERROR
+            {
+                status = STATUS_ERROR;
+                break;
+            }
+            addr = desc.next;
+        }
+
+        self.dma.set_status(status);
+        self.controller.raise_irq(DMA_IRQ);
+    }
+
+    /// Total sectors the registered block device holds, or 0 if none is
+    /// registered -- the virtqueue device's only way to learn the backing
+    /// image's size, since it has no file handle of its own (see
+    /// `dma_copy_sectors`, which reuses the same registered device rather
+    /// than opening a second one).
+    fn virtio_blk_capacity_sectors(&mut self) -> u64 {
+        self.devices
+            .read_word(BLOCK_DEVICE_BASE + BLOCK_REG_CAPACITY)
+            .map(|sectors| sectors as u64)
+            .unwrap_or(0)
+    }
+
+    fn virtio_blk_read(&mut self, offset: u32) -> u32 {
+        if virtio_blk::is_capacity_low(offset) {
+            return self.virtio_blk_capacity_sectors() as u32;
+        }
+        if virtio_blk::is_capacity_high(offset) {
+            return (self.virtio_blk_capacity_sectors() >> 32) as u32;
+        }
+        self.virtio_blk.read(offset)
+    }
+
+    /// Reads descriptor-table entry `id`: `{addr: u64 (truncated to u32),
+    /// len: u32, flags: u16, next: u16}`, 16 bytes starting at `desc_base +
+    /// id * DESC_ENTRY_SIZE`.
+    fn virtio_blk_descriptor(&mut self, id: u16) -> Result<Descriptor, MemoryError> {
+        let base = self.virtio_blk.desc_base() + id as u32 * DESC_ENTRY_SIZE;
+        let addr = self.read_word(PhysAddr::new(base))?;
+        let len = self.read_word(PhysAddr::new(base + 8))?;
+        let flags_and_next = self.read_word(PhysAddr::new(base + 12))?;
+        Ok(Descriptor {
+            addr,
+            len,
+            flags: flags_and_next as u16,
+            next: (flags_and_next >> 16) as u16,
+        })
+    }
+
+    /// Reads one 16-bit avail-ring entry: `ring[slot % QUEUE_SIZE]`, 2 bytes
+    /// per slot starting 4 bytes into the ring (past `flags`/`idx`).
+    fn virtio_blk_avail_entry(&mut self, slot: u16) -> Result<u16, MemoryError> {
+        let index = slot % QUEUE_SIZE;
+        let addr = self.virtio_blk.avail_base() + 4 + index as u32 * 2;
+        let word = self.read_word(PhysAddr::new(addr & !0x3))?;
+        let shift = (addr % 4) * 8;
+        Ok(((word >> shift) & 0xFFFF) as u16)
+    }
+
+    /// Reads the avail ring's `idx` field (offset 2, a 16-bit field packed
+    /// into the same word as `flags`).
+    fn virtio_blk_avail_idx(&mut self) -> Result<u16, MemoryError> {
+        let word = self.read_word(PhysAddr::new(self.virtio_blk.avail_base()))?;
+        Ok((word >> 16) as u16)
+    }
+
+    /// Reads the used ring's `idx` field, same packing as the avail ring's.
+    fn virtio_blk_used_idx(&mut self) -> Result<u16, MemoryError> {
+        let word = self.read_word(PhysAddr::new(self.virtio_blk.used_base()))?;
+        Ok((word >> 16) as u16)
+    }
+
+    /// Writes `{id, len}` into the used ring's next free slot and bumps
+    /// `idx`, same layout `ferrous_kernel::net::driver::VirtQueue` already
+    /// assumes on the guest side of an analogous ring.
+    fn virtio_blk_push_used(&mut self, head: u16, len: u32) -> Result<(), MemoryError> {
+        let idx = self.virtio_blk_used_idx()?;
+        let slot = idx % QUEUE_SIZE;
+        let entry = self.virtio_blk.used_base() + 4 + slot as u32 * USED_ENTRY_SIZE;
+        self.write_word(PhysAddr::new(entry), head as u32)?;
+        self.write_word(PhysAddr::new(entry + 4), len)?;
+        let used_idx_addr = self.virtio_blk.used_base();
+        let flags_and_idx = self.read_word(PhysAddr::new(used_idx_addr))?;
+        let merged = (flags_and_idx & 0xFFFF) | ((idx.wrapping_add(1) as u32) << 16);
+        self.write_word(PhysAddr::new(used_idx_addr), merged)
+    }
+
+    /// Copies `buf.len` bytes starting at guest address `buf.addr` out of
+    /// (`write_to_guest`) or into the registered block device at `sector`,
+    /// the same one-word-at-a-time PIO round trip `dma_copy_sectors` uses
+    /// to bridge RAM and the block device's register window. `len` must be
+    /// a sector-sized multiple of 4, same restriction `dma_copy_sectors`
+    /// places on its own descriptors' `length` field.
+    fn virtio_blk_transfer(
+        &mut self,
+        sector: u64,
+        buf: &Descriptor,
+        write_to_guest: bool,
+    ) -> Result<(), MemoryError> {
+        if buf.len == 0 || buf.len % 4 != 0 {
+            return Err(MemoryError::OutOfBounds(buf.addr));
+        }
+        for i in (0..buf.len).step_by(512) {
+            let this_sector = sector + (i / 512) as u64;
+            self.devices
+                .write_word(BLOCK_DEVICE_BASE + BLOCK_REG_SECTOR, this_sector as u32)?;
+            if write_to_guest {
+                self.devices
+                    .write_word(BLOCK_DEVICE_BASE + BLOCK_REG_COMMAND, 1)?;
+            }
+            let chunk_len = (buf.len - i).min(512);
+            for j in (0..chunk_len).step_by(4) {
+                if write_to_guest {
+                    let word = self
+                        .devices
+                        .read_word(BLOCK_DEVICE_BASE + BLOCK_BUFFER_START + j)?;
+                    self.write_word(PhysAddr::new(buf.addr + i + j), word)?;
+                } else {
+                    let word = self.read_word(PhysAddr::new(buf.addr + i + j))?;
+                    self.devices
+                        .write_word(BLOCK_DEVICE_BASE + BLOCK_BUFFER_START + j, word)?;
+                }
+            }
+            if !write_to_guest {
+                self.devices
+                    .write_word(BLOCK_DEVICE_BASE + BLOCK_REG_COMMAND, 2)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Walks the descriptor chain rooted at `head`: a 16-byte header
+    /// (`{type, reserved, sector}`), one or more data buffers, and a
+    /// trailing 1-byte status descriptor the device writes OK/IOERR/UNSUPP
+    /// into. Returns the bytes moved, for the used-ring entry's `len`.
+    fn virtio_blk_service_request(&mut self, head: u16) -> Result<u32, MemoryError> {
+        let header = self.virtio_blk_descriptor(head)?;
+        let req_type = self.read_word(PhysAddr::new(header.addr))?;
+        let sector_lo = self.read_word(PhysAddr::new(header.addr + 8))?;
+        let sector_hi = self.read_word(PhysAddr::new(header.addr + 12))?;
+        let sector = sector_lo as u64 | ((sector_hi as u64) << 32);
+
+        let mut chain = Vec::new();
+        let mut cursor = header;
+        while cursor.has_next() {
+            cursor = self.virtio_blk_descriptor(cursor.next)?;
+            chain.push(cursor);
+        }
+        let Some(status_desc) = chain.pop() else {
+            return Ok(0); // malformed chain: no status descriptor to report into
+        };
+
+        let mut bytes_moved = 0u32;
+        let mut status = STATUS_OK;
+        if req_type != REQ_TYPE_IN && req_type != REQ_TYPE_OUT {
+            status = STATUS_UNSUPP;
+        } else {
+            let write_to_guest = req_type == REQ_TYPE_IN;
+            for buf in &chain {
+                if self.virtio_blk_transfer(sector, buf, write_to_guest).is_err() {
+                    status = STATUS_IOERR;
+                    break;
+                }
+                bytes_moved += buf.len;
+            }
+        }
+
+        self.write_byte(PhysAddr::new(status_desc.addr), status)?;
+        Ok(bytes_moved)
+    }
+
+    /// Drains every request the driver posted since the last
+    /// `REG_QUEUE_NOTIFY`, raising `VIRTIO_BLK_IRQ` once if anything was
+    /// serviced -- a spurious notify with nothing new posted stays silent,
+    /// mirroring `NetDriver::poll` returning `None` on an empty queue.
+    fn run_virtio_blk_queue(&mut self) -> Result<(), MemoryError> {
+        let mut serviced = false;
+        while self.virtio_blk.last_seen_avail() != self.virtio_blk_avail_idx()? {
+            let slot = self.virtio_blk.advance_last_seen_avail();
+            let head = self.virtio_blk_avail_entry(slot)?;
+            let len = self.virtio_blk_service_request(head).unwrap_or(0);
+            self.virtio_blk_push_used(head, len)?;
+            serviced = true;
+        }
+        if serviced {
+            self.controller.raise_irq(VIRTIO_BLK_IRQ);
+        }
+        Ok(())
+    }
 }
 
 impl Memory for SystemBus {
     fn read_byte(&mut self, addr: PhysAddr) -> Result<u8, MemoryError> {
-        if addr.0 >= 0x8000_0000 {
-            self.ram.read_byte(addr)
-        } else {
-            let word = self.devices.read_word_mut(addr.0)?;
-            let shift = (addr.0 % 4) * 8;
-            Ok(((word >> shift) & 0xFF) as u8)
+        match self.map.lookup(addr.0) {
+            Some((AddressTarget::Ram, _)) => self.ram.read_byte(addr),
+            Some((AddressTarget::Rom, offset)) => rom_byte(&self.rom, offset),
+            Some((AddressTarget::Device, _)) => {
+                let word = self.devices.read_word(addr.0)?;
+                let shift = (addr.0 % 4) * 8;
+                Ok(((word >> shift) & 0xFF) as u8)
+            }
+            Some((AddressTarget::Controller, _)) => {
+                let word = self.controller.read(addr.0 - PLIC_BASE)?;
+                let shift = (addr.0 % 4) * 8;
+                Ok(((word >> shift) & 0xFF) as u8)
+            }
+            Some((AddressTarget::Dma, _)) => {
+                let word = self.dma.read(addr.0 - DMA_BASE);
+                let shift = (addr.0 % 4) * 8;
+                Ok(((word >> shift) & 0xFF) as u8)
+            }
+            Some((AddressTarget::VirtioBlk, _)) => {
+                let word = self.virtio_blk_read(addr.0 - VIRTIO_BLK_BASE);
+                let shift = (addr.0 % 4) * 8;
+                Ok(((word >> shift) & 0xFF) as u8)
+            }
+            None => Err(MemoryError::Unmapped(addr.0)),
         }
     }
 
+    /// `Device`/`Controller` registers only understand whole-word reads and
+    /// writes, so a sub-word write there is a read-modify-write: read the
+    /// word the target byte lives in, splice `val` in at the right shift,
+    /// and write the merged word back, instead of writing `val as u32` over
+    /// the other three bytes (`Sb`/`Sh` in `VirtualMachine::step` compose
+    /// half-word stores out of two of these, so this is all they need).
     fn write_byte(&mut self, addr: PhysAddr, val: u8) -> Result<(), MemoryError> {
-        if addr.0 >= 0x8000_0000 {
-            self.ram.write_byte(addr, val)
-        } else {
-            if addr.0 % 4 != 0 {
-                return Err(MemoryError::Misaligned {
-                    addr: addr.0,
-                    alignment: 4,
-                });
+        match self.map.lookup(addr.0) {
+            Some((AddressTarget::Ram, _)) => self.ram.write_byte(addr, val),
+            Some((AddressTarget::Rom, _)) => Err(MemoryError::ReadOnly(addr.0)),
+            Some((AddressTarget::Device, _)) => {
+                let word_addr = addr.0 & !0x3;
+                let shift = (addr.0 % 4) * 8;
+                let word = self.devices.read_word(word_addr)?;
+                let merged = (word & !(0xFFu32 << shift)) | ((val as u32) << shift);
+                self.devices.write_word(word_addr, merged)?;
+                Ok(())
             }
-
-            self.devices.write_word(addr.0, val as u32)?;
-            Ok(())
+            Some((AddressTarget::Controller, _)) => {
+                let word_addr = addr.0 & !0x3;
+                let shift = (addr.0 % 4) * 8;
+                let word = self.controller.read(word_addr - PLIC_BASE)?;
+                let merged = (word & !(0xFFu32 << shift)) | ((val as u32) << shift);
+                self.controller.write(word_addr - PLIC_BASE, merged)?;
+                Ok(())
+            }
+            Some((AddressTarget::Dma, _)) => {
+                let word_addr = addr.0 & !0x3;
+                let shift = (addr.0 % 4) * 8;
+                let word = self.dma.read(word_addr - DMA_BASE);
+                let merged = (word & !(0xFFu32 << shift)) | ((val as u32) << shift);
+                self.write_word(PhysAddr::new(word_addr), merged)
+            }
+            Some((AddressTarget::VirtioBlk, _)) => {
+                let word_addr = addr.0 & !0x3;
+                let shift = (addr.0 % 4) * 8;
+                let word = self.virtio_blk_read(word_addr - VIRTIO_BLK_BASE);
+                let merged = (word & !(0xFFu32 << shift)) | ((val as u32) << shift);
+                self.write_word(PhysAddr::new(word_addr), merged)
+            }
+            None => Err(MemoryError::Unmapped(addr.0)),
         }
     }
 
     fn read_word(&mut self, addr: PhysAddr) -> Result<u32, MemoryError> {
-        if addr.0 >= 0x8000_0000 {
-            self.ram.read_word(addr)
-        } else {
-            self.devices.read_word_mut(addr.0).map_err(Into::into)
+        match self.map.lookup(addr.0) {
+            Some((AddressTarget::Ram, _)) => self.ram.read_word(addr),
+            Some((AddressTarget::Rom, offset)) => rom_word(&self.rom, offset),
+            Some((AddressTarget::Device, _)) => self.devices.read_word(addr.0).map_err(Into::into),
+            Some((AddressTarget::Controller, _)) => self
+                .controller
+                .read(addr.0 - PLIC_BASE)
+                .map_err(Into::into),
+            Some((AddressTarget::Dma, _)) => Ok(self.dma.read(addr.0 - DMA_BASE)),
+            Some((AddressTarget::VirtioBlk, _)) => Ok(self.virtio_blk_read(addr.0 - VIRTIO_BLK_BASE)),
+            None => Err(MemoryError::Unmapped(addr.0)),
         }
     }
 
+    /// A `COMMAND` write runs the whole descriptor chain synchronously
+    /// before this returns, the same as `SimpleBlockDevice`'s own
+    /// `REG_COMMAND` -- `DmaController::write` only flips the register file
+    /// to `STATUS_BUSY` and reports whether to actually walk the chain,
+    /// since doing that needs `ram`/`devices`, which the register file
+    /// itself doesn't have access to.
     fn write_word(&mut self, addr: PhysAddr, val: u32) -> Result<(), MemoryError> {
-        if addr.0 >= 0x8000_0000 {
-            self.ram.write_word(addr, val)
-        } else {
-            self.devices.write_word(addr.0, val).map_err(Into::into)
+        match self.map.lookup(addr.0) {
+            Some((AddressTarget::Ram, _)) => self.ram.write_word(addr, val),
+            Some((AddressTarget::Rom, _)) => Err(MemoryError::ReadOnly(addr.0)),
+            Some((AddressTarget::Device, _)) => {
+                self.devices.write_word(addr.0, val).map_err(Into::into)
+            }
+            Some((AddressTarget::Controller, _)) => self
+                .controller
+                .write(addr.0 - PLIC_BASE, val)
+                .map_err(Into::into),
+            Some((AddressTarget::Dma, _)) => {
+                if self.dma.write(addr.0 - DMA_BASE, val) {
+                    self.run_dma_chain();
+                }
+                Ok(())
+            }
+            Some((AddressTarget::VirtioBlk, _)) => {
+                if self.virtio_blk.write(addr.0 - VIRTIO_BLK_BASE, val)? {
+                    self.run_virtio_blk_queue()?;
+                }
+                Ok(())
+            }
+            None => Err(MemoryError::Unmapped(addr.0)),
+        }
+    }
+
+    fn slice_mut(&mut self, addr: PhysAddr, len: usize) -> Result<&mut [u8], MemoryError> {
+        match self.map.lookup(addr.0) {
+            Some((AddressTarget::Ram, _)) => self.ram.slice_mut(addr, len),
+            Some(_) => Err(MemoryError::OutOfBounds(addr.0)),
+            None => Err(MemoryError::Unmapped(addr.0)),
+        }
+    }
+
+    /// Poll every registered device and feed any `DeviceInterrupt`s it
+    /// reports into the `InterruptController` as raised IRQ lines -- the
+    /// "shared handle" devices use to signal the controller is this
+    /// per-tick aggregation step rather than a shared mutable reference
+    /// each device would otherwise need to hold onto.
+    fn tick_devices(&mut self) -> Result<(), MemoryError> {
+        for irq in self.devices.tick_all()? {
+            self.controller.raise_irq(irq.irq_number);
         }
+        Ok(())
+    }
+
+    fn pending_interrupt(&self) -> bool {
+        self.controller.has_deliverable_irq()
     }
 }
+
+fn rom_byte(rom: &Option<(u32, Vec<u8>)>, offset: u32) -> Result<u8, MemoryError> {
+    let (base, data) = rom.as_ref().expect("Rom target without a ROM image");
+    data.get(offset as usize)
+        .copied()
+        .ok_or(MemoryError::OutOfBounds(base + offset))
+}
+
+fn rom_word(rom: &Option<(u32, Vec<u8>)>, offset: u32) -> Result<u32, MemoryError> {
+    let (base, data) = rom.as_ref().expect("Rom target without a ROM image");
+    let start = offset as usize;
+    let bytes: [u8; 4] = data
+        .get(start..start + 4)
+        .ok_or(MemoryError::OutOfBounds(base + offset))?
+        .try_into()
+        .unwrap();
+    Ok(u32::from_le_bytes(bytes))
+}
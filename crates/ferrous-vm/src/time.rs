@@ -1,13 +1,55 @@
 use core::time::Duration;
 
-/// Simulated time instant (monotonic)
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+const FEMTOS_PER_NANO: u64 = 1_000_000;
+
+/// A monotonic simulated timestamp, in femtoseconds -- the fixed-point
+/// representation the femtos/fugit model (as used in `moa`) favors over a
+/// floating-point clock so device scheduling stays exact regardless of how
+/// fast or slow the simulated clock runs.
+///
+/// Only the type and its `Duration` conversions live here so far. Actually
+/// driving a `now: SimulatedInstant` from per-instruction cycle costs, and
+/// giving `Device` a `poll(&mut self, now)`/`next_event(&self)` pair to
+/// schedule off of it instead of `tick()`'s once-per-instruction call, is a
+/// larger follow-up that touches every device and `VirtualMachine::run`'s
+/// loop -- this lands the type those would build on without changing how
+/// `run()` currently paces itself off `instruction_count`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Default)]
 pub struct SimulatedInstant {
-    ticks: u64,
+    femtos: u64,
 }
 
 impl SimulatedInstant {
-    // pub fn elapsed_since(&self, earlier: SimulatedInstant) -> Duration { ... }
-    // pub fn duration_since(&self, earlier: SimulatedInstant) -> Duration { ... }
-}
+    pub const ZERO: SimulatedInstant = SimulatedInstant { femtos: 0 };
+
+    pub fn from_femtos(femtos: u64) -> Self {
+        Self { femtos }
+    }
+
+    pub fn as_femtos(&self) -> u64 {
+        self.femtos
+    }
 
+    pub fn checked_add(&self, femtos: u64) -> Self {
+        Self {
+            femtos: self.femtos.saturating_add(femtos),
+        }
+    }
+
+    /// How long after `earlier` this instant is -- saturates to zero rather
+    /// than panicking if `earlier` is actually later, the same
+    /// saturating-not-panicking convention `std::time::Instant::duration_since`
+    /// documents for a clock that briefly went backwards.
+    pub fn duration_since(&self, earlier: SimulatedInstant) -> Duration {
+        let femtos = self.femtos.saturating_sub(earlier.femtos);
+        Duration::from_nanos(femtos / FEMTOS_PER_NANO)
+    }
+
+    /// Time elapsed from this instant until `later` -- the same quantity as
+    /// `later.duration_since(self)`, just with the earlier instant as the
+    /// receiver for call sites that read better that way (e.g. a device
+    /// asking `self.last_event.elapsed_since(now)`).
+    pub fn elapsed_since(&self, later: SimulatedInstant) -> Duration {
+        later.duration_since(*self)
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use xshell::{cmd, Shell};
+
+pub const TARGET: &str = "riscv32i-unknown-none-elf";
+pub const MODE: &str = "release";
+
+/// The user programs `build_user`/`make_fs`/`Test` all build and boot, in
+/// the order `cargo build` sees them.
+pub const USER_PROGRAMS: &[&str] = &[
+    "hello-world",
+    "shell",
+    "threads",
+    "sbrk",
+    "disk-read",
+    "net_test",
+];
+
+/// Where `cargo build --target {TARGET} --release` drops user-program
+/// binaries.
+pub fn user_out_dir() -> PathBuf {
+    PathBuf::from(format!("target/{}/{}", TARGET, MODE))
+}
+
+/// Run an `xshell` command, echoing its full command line to stderr first
+/// when `verbose`. Every build/VM step should route through this (or
+/// [`echo_command`] for the handful of steps built on
+/// `std::process::Command` instead) so `--verbose` gives one consistent,
+/// greppable trace -- as opposed to cargo's own build output, which stays
+/// as noisy as ever and is never toggled by this flag.
+pub fn exec(cmd: xshell::Cmd, verbose: bool) -> Result<()> {
+    if verbose {
+        eprintln!("+ {}", cmd);
+    }
+    cmd.run().map_err(Into::into)
+}
+
+/// The [`exec`] of the `std::process::Command` call sites (interactive
+/// `shell`/`net_test` runs, the UDP echo server, `Test`'s per-binary VM
+/// boots) that can't go through `xshell`'s `Cmd`.
+pub fn echo_command(cmd: &std::process::Command, verbose: bool) {
+    if verbose {
+        let args: Vec<_> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        eprintln!(
+            "+ {} {}",
+            cmd.get_program().to_string_lossy(),
+            args.join(" ")
+        );
+    }
+}
+
+/// Build the host tools (VM, CLI, mkfs).
+pub fn build_host(sh: &Shell, verbose: bool) -> Result<()> {
+    exec(
+        cmd!(sh, "cargo build -p ferrous-cli -p ferrous-mkfs"),
+        verbose,
+    )
+}
+
+/// Build every user program (shell, examples) for RISC-V.
+pub fn build_user(sh: &Shell, verbose: bool) -> Result<()> {
+    let mut cmd = cmd!(sh, "cargo build --release --target {TARGET}");
+    for pkg in USER_PROGRAMS {
+        cmd = cmd.arg("-p").arg(pkg);
+    }
+    exec(cmd, verbose)
+}
+
+/// Build user programs, then assemble a disk image at `disk` with `inodes`
+/// inodes from them plus a sample text file.
+pub fn make_fs(sh: &Shell, verbose: bool, disk: &Path, inodes: u32) -> Result<()> {
+    build_user(sh, verbose)?;
+
+    println!("Creating hello.txt...");
+    sh.write_file("hello.txt", "Hello from Ferrous File System!\n")?;
+
+    println!("Building disk image...");
+    let out_path = user_out_dir();
+    let mut paths: Vec<PathBuf> = USER_PROGRAMS.iter().map(|bin| out_path.join(bin)).collect();
+    paths.push(PathBuf::from("hello.txt"));
+
+    let mut cmd = cmd!(
+        sh,
+        "cargo run -p ferrous-mkfs -- --disk {disk} --force --inodes {inodes}"
+    );
+    for path in paths {
+        cmd = cmd.arg(path);
+    }
+    exec(cmd, verbose)?;
+
+    sh.remove_path("hello.txt")?;
+    Ok(())
+}
@@ -1,7 +1,42 @@
+mod tap;
+
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::{Path, PathBuf};
+use tap::TapDevice;
 use xshell::{cmd, Shell};
+use xtask::{build_host, build_user, echo_command, exec, make_fs, user_out_dir, USER_PROGRAMS};
+
+/// VM sizing/disk options shared by every subcommand that builds or boots a
+/// disk image, so `--mem`/`--inodes`/`--disk` mean the same thing (and
+/// forward to the same place: `ferrous-mkfs` and `ferrous-cli run`) no
+/// matter which subcommand you pass them to.
+#[derive(Args, Clone)]
+struct VmOptions {
+    /// VM memory size in MiB, forwarded to `ferrous-cli run --memory`
+    #[arg(long, default_value_t = 16)]
+    mem: usize,
+    /// Number of inodes in the disk image, forwarded to `ferrous-mkfs --inodes`
+    #[arg(long, default_value_t = 128)]
+    inodes: u32,
+    /// Path to the disk image. Commands that require one default to
+    /// `disk.img` when this is omitted; commands that don't (`RunHello`,
+    /// `RunNet`) only mount a disk if this is set.
+    #[arg(long)]
+    disk: Option<PathBuf>,
+}
+
+impl VmOptions {
+    fn mem_bytes(&self) -> usize {
+        self.mem * 1024 * 1024
+    }
+
+    fn disk_or_default(&self) -> PathBuf {
+        self.disk
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("disk.img"))
+    }
+}
 
 #[derive(Parser)]
 #[command(name = "xtask")]
@@ -9,6 +44,9 @@ use xshell::{cmd, Shell};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Echo every build/VM command line to stderr before running it
+    #[arg(short, long, global = true)]
+    verbose: bool,
 }
 
 #[derive(Subcommand)]
@@ -18,21 +56,133 @@ enum Commands {
     /// Build user programs (shell, examples) for RISC-V
     BuildUser,
     /// Run simple hello-world example
-    RunHello,
+    RunHello {
+        #[command(flatten)]
+        vm: VmOptions,
+    },
     /// Create disk image (disk.img) with shell and examples
-    Fs,
+    Fs {
+        #[command(flatten)]
+        vm: VmOptions,
+    },
     /// Run the interactive shell (requires disk image)
     RunShell {
         /// Start the UDP echo server in the background
         #[arg(long)]
         with_net: bool,
+        #[command(flatten)]
+        vm: VmOptions,
     },
     /// Run network test (launches UDP echo server + VM)
-    RunNet,
+    RunNet {
+        /// Bridge the guest's networking through a host TAP interface
+        /// (e.g. "tap0") instead of the UDP echo loopback. Requires Linux
+        /// and `CAP_NET_ADMIN`; falls back to the UDP echo server if the
+        /// interface can't be opened.
+        #[arg(long)]
+        tap: Option<String>,
+        #[command(flatten)]
+        vm: VmOptions,
+    },
+    /// Build every user program, boot each one in the VM in turn, and
+    /// report which ones passed
+    Test {
+        /// Only run test binaries whose name contains this substring
+        #[arg(long)]
+        filter: Option<String>,
+        /// Kill a test's VM and record it as timed out if it runs longer
+        /// than this many seconds
+        #[arg(long, default_value_t = 20)]
+        timeout: u64,
+        #[command(flatten)]
+        vm: VmOptions,
+    },
     /// Clean build artifacts
     Clean,
 }
 
+/// The line a test binary prints to stdout right before its successful
+/// `exit(0)`, the other half of the `Error:` convention: a test is only
+/// `Passed` if it printed this *and* neither printed an `Error:` line nor
+/// exited non-zero.
+const TEST_PASS_MARKER: &str = "TEST PASS";
+
+enum TestOutcome {
+    Passed,
+    Failed(String),
+    Timeout,
+}
+
+impl std::fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestOutcome::Passed => write!(f, "PASS"),
+            TestOutcome::Failed(reason) => write!(f, "FAIL ({})", reason),
+            TestOutcome::Timeout => write!(f, "TIMEOUT"),
+        }
+    }
+}
+
+/// Boot `binary` under `ferrous-cli run` with piped output, wait up to
+/// `timeout_secs` for it to exit, and judge the run by the same
+/// `Error:`/success-marker/exit-status convention the summary table reports.
+fn run_test_binary(
+    binary: &Path,
+    timeout_secs: u64,
+    verbose: bool,
+    disk: &Path,
+    mem_bytes: usize,
+) -> TestOutcome {
+    let mut command = std::process::Command::new("cargo");
+    command
+        .args(["run", "-p", "ferrous-cli", "--", "run"])
+        .arg(binary)
+        .arg("--disk")
+        .arg(disk)
+        .arg("--memory")
+        .arg(mem_bytes.to_string())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    echo_command(&command, verbose);
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => return TestOutcome::Failed(format!("failed to spawn: {}", e)),
+    };
+    let pid = child.id();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(child.wait_with_output());
+    });
+
+    match rx.recv_timeout(std::time::Duration::from_secs(timeout_secs)) {
+        Ok(Ok(output)) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if let Some(line) = stdout.lines().find(|line| line.starts_with("Error:")) {
+                return TestOutcome::Failed(line.to_string());
+            }
+            if !output.status.success() {
+                return TestOutcome::Failed(format!("exited with {}", output.status));
+            }
+            if !stdout.lines().any(|line| line == TEST_PASS_MARKER) {
+                return TestOutcome::Failed(format!("missing \"{}\" marker", TEST_PASS_MARKER));
+            }
+            TestOutcome::Passed
+        }
+        Ok(Err(e)) => TestOutcome::Failed(format!("wait failed: {}", e)),
+        Err(_) => {
+            // The VM is stuck (or just slower than `timeout_secs`); kill it
+            // rather than leaving the harness blocked on this one test.
+            let _ = std::process::Command::new("kill")
+                .arg("-9")
+                .arg(pid.to_string())
+                .status();
+            TestOutcome::Timeout
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     let sh = Shell::new()?;
@@ -45,85 +195,33 @@ fn main() -> Result<()> {
         .unwrap();
     sh.change_dir(project_root);
 
-    let target = "riscv32i-unknown-none-elf";
-    let mode = "release";
-    let out_dir = format!("target/{}/{}", target, mode);
-    let out_path = Path::new(&out_dir);
+    let out_path = user_out_dir();
 
     match cli.command {
         Commands::BuildHost => {
-            cmd!(sh, "cargo build -p ferrous-cli -p ferrous-mkfs").run()?;
+            build_host(&sh, cli.verbose)?;
         }
         Commands::BuildUser => {
-            let flags = vec!["--release", "--target", target];
-            let packages = vec![
-                "hello-world",
-                "shell",
-                "echo",
-                "threads",
-                "sbrk",
-                "file-read",
-                "disk-read",
-                "net_test",
-            ];
-
-            // Construct the cargo build command
-            // We use .args() to pass dynamic lists of arguments
-            let mut cmd = cmd!(sh, "cargo build");
-            for flag in &flags {
-                cmd = cmd.arg(flag);
-            }
-            for pkg in packages {
-                cmd = cmd.arg("-p").arg(pkg);
-            }
-            cmd.run()?;
+            build_user(&sh, cli.verbose)?;
         }
-        Commands::RunHello => {
-            // Build user programs first
-            run_xtask(&sh, &["build-user"])?;
+        Commands::RunHello { vm } => {
+            build_user(&sh, cli.verbose)?;
 
             let binary = out_path.join("hello-world");
-            cmd!(sh, "cargo run -p ferrous-cli -- run {binary}").run()?;
-        }
-        Commands::Fs => {
-            // Build user programs first
-            run_xtask(&sh, &["build-user"])?;
-
-            println!("Creating hello.txt...");
-            sh.write_file("hello.txt", "Hello from Ferrous File System!\n")?;
-
-            println!("Building disk image...");
-            let binaries = vec![
-                "shell",
-                "echo",
-                "threads",
-                "sbrk",
-                "hello-world",
-                "file-read",
-                "disk-read",
-                "net_test",
-            ];
-
-            let mut paths = Vec::new();
-            for bin in binaries {
-                paths.push(out_path.join(bin));
-            }
-            paths.push(PathBuf::from("hello.txt"));
-
-            let mut cmd = cmd!(
-                sh,
-                "cargo run -p ferrous-mkfs -- --disk disk.img --force --inodes 128"
-            );
-            for path in paths {
-                cmd = cmd.arg(path);
+            let mut cmd = cmd!(sh, "cargo run -p ferrous-cli -- run {binary}")
+                .arg("--memory")
+                .arg(vm.mem_bytes().to_string());
+            if let Some(disk) = &vm.disk {
+                cmd = cmd.arg("--disk").arg(disk);
             }
-            cmd.run()?;
-
-            sh.remove_path("hello.txt")?;
+            exec(cmd, cli.verbose)?;
+        }
+        Commands::Fs { vm } => {
+            make_fs(&sh, cli.verbose, &vm.disk_or_default(), vm.inodes)?;
         }
-        Commands::RunShell { with_net } => {
+        Commands::RunShell { with_net, vm } => {
             // Create FS first (which builds user programs)
-            run_xtask(&sh, &["fs"])?;
+            make_fs(&sh, cli.verbose, &vm.disk_or_default(), vm.inodes)?;
 
             // Start UDP Echo Server if requested
             let mut server = None;
@@ -132,7 +230,7 @@ fn main() -> Result<()> {
 
                 // Build the tool first
                 println!("Building UDP Echo Server...");
-                cmd!(sh, "cargo build -p udp-echo --release").run()?;
+                exec(cmd!(sh, "cargo build -p udp-echo --release"), cli.verbose)?;
 
                 let tool_path = if cfg!(windows) {
                     "target/release/udp-echo.exe"
@@ -141,11 +239,10 @@ fn main() -> Result<()> {
                 };
 
                 println!("Starting UDP Echo Server...");
-                let s = Command::new(tool_path)
-                    .stdout(Stdio::inherit())
-                    .stderr(Stdio::inherit())
-                    .spawn()?;
-                server = Some(s);
+                let mut server_cmd = Command::new(tool_path);
+                server_cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+                echo_command(&server_cmd, cli.verbose);
+                server = Some(server_cmd.spawn()?);
                 // Give it a moment to start
                 std::thread::sleep(std::time::Duration::from_millis(500));
             }
@@ -155,15 +252,19 @@ fn main() -> Result<()> {
             // Use std::process::Command directly to ensure stdin is properly inherited
             // for the interactive shell session. xshell can sometimes cause issues
             // with interactive input on some platforms.
-            let status = std::process::Command::new("cargo")
+            let mut shell_cmd = std::process::Command::new("cargo");
+            shell_cmd
                 .args(["run", "-p", "ferrous-cli", "--", "run"])
                 .arg(shell_bin)
                 .arg("--disk")
-                .arg("disk.img")
+                .arg(vm.disk_or_default())
+                .arg("--memory")
+                .arg(vm.mem_bytes().to_string())
                 .stdin(std::process::Stdio::inherit())
                 .stdout(std::process::Stdio::inherit())
-                .stderr(std::process::Stdio::inherit())
-                .status()?;
+                .stderr(std::process::Stdio::inherit());
+            echo_command(&shell_cmd, cli.verbose);
+            let status = shell_cmd.status()?;
 
             // Kill server if running
             if let Some(mut s) = server {
@@ -174,52 +275,221 @@ fn main() -> Result<()> {
                 return Err(anyhow::anyhow!("VM execution failed"));
             }
         }
-        Commands::RunNet => {
+        Commands::RunNet { tap, vm } => {
             // Build user programs first
-            run_xtask(&sh, &["build-user"])?;
+            build_user(&sh, cli.verbose)?;
+
+            use std::process::{Child, Command, Stdio};
 
-            // Start UDP Echo Server in background
-            use std::process::{Command, Stdio};
+            // `ferrous-cli run` launches the VM as its own OS process, so
+            // neither host backend below can reach into the guest kernel's
+            // `NetDriver` directly — both only bridge frames/datagrams on
+            // the host side of whatever transport the guest program talks
+            // over. A TAP interface is a strict upgrade over the UDP
+            // loopback in that it moves real Ethernet frames instead of a
+            // single hardcoded socket, but wiring it straight into the
+            // in-process virtqueue state would need the VM to run in this
+            // same process — a larger change than this flag is scoped to.
+            let mut udp_server: Option<Child> = None;
+            let mut tap_device: Option<TapDevice> = None;
+
+            if let Some(ifname) = &tap {
+                match TapDevice::open(ifname) {
+                    Ok(dev) => {
+                        println!("Bridging through TAP interface {:?}", ifname);
+                        tap_device = Some(dev);
+                    }
+                    Err(e) => {
+                        println!(
+                            "Could not open TAP interface {:?} ({}), falling back to UDP echo",
+                            ifname, e
+                        );
+                    }
+                }
+            }
 
-            // Build the tool first
-            println!("Building UDP Echo Server...");
-            cmd!(sh, "cargo build -p udp-echo --release").run()?;
+            // Lines the UDP echo server's reader thread pulls the
+            // `ECHO_BOUNCED:<payload>` marker out of, one entry per
+            // datagram it actually echoed back -- only populated when
+            // we're using the loopback server, not the TAP bridge.
+            let bounced: std::sync::Arc<std::sync::Mutex<Vec<String>>> = Default::default();
 
-            let tool_path = if cfg!(windows) {
-                "target/release/udp-echo.exe"
+            if let Some(mut dev) = tap_device {
+                // Echo every frame straight back out the interface, the
+                // same loopback role the UDP echo server plays below, just
+                // at the raw Ethernet layer a TAP device exchanges frames at.
+                std::thread::spawn(move || {
+                    let mut frame = [0u8; 2048];
+                    loop {
+                        match dev.read_frame(&mut frame) {
+                            Ok(0) => continue,
+                            Ok(len) => {
+                                let _ = dev.write_frame(&frame[..len]);
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                });
             } else {
-                "target/release/udp-echo"
-            };
+                println!("Building UDP Echo Server...");
+                exec(cmd!(sh, "cargo build -p udp-echo --release"), cli.verbose)?;
 
-            println!("Starting UDP Echo Server...");
-            let mut server = Command::new(tool_path)
-                .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit())
-                .spawn()?;
+                let tool_path = if cfg!(windows) {
+                    "target/release/udp-echo.exe"
+                } else {
+                    "target/release/udp-echo"
+                };
+
+                println!("Starting UDP Echo Server...");
+                let mut server_cmd = Command::new(tool_path);
+                server_cmd.stdout(Stdio::piped()).stderr(Stdio::inherit());
+                echo_command(&server_cmd, cli.verbose);
+                let mut child = server_cmd.spawn()?;
+                let server_stdout = child.stdout.take().expect("piped stdout");
+                udp_server = Some(child);
 
-            // Give it a moment to start
-            std::thread::sleep(std::time::Duration::from_millis(500));
+                // Record every datagram the server actually bounced so we
+                // can diff it against what the guest claims to have sent,
+                // instead of trusting the VM's exit status alone.
+                let bounced_for_reader = bounced.clone();
+                std::thread::spawn(move || {
+                    use std::io::BufRead;
+                    for line in std::io::BufReader::new(server_stdout).lines().flatten() {
+                        println!("[udp-echo] {}", line);
+                        if let Some(payload) = line.strip_prefix("ECHO_BOUNCED:") {
+                            bounced_for_reader.lock().unwrap().push(payload.to_string());
+                        }
+                    }
+                });
 
-            // Run VM with net_test
+                // Give it a moment to start
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+
+            // Run VM with net_test, capturing its stdout so we can pull out
+            // the `NET_TEST_SENT:`/`NET_TEST_RECV:` markers once it exits.
             let binary = out_path.join("net_test");
             println!("Running VM with net_test...");
-            let status = Command::new("cargo")
+            let mut net_cmd = Command::new("cargo");
+            net_cmd
                 .args(["run", "-p", "ferrous-cli", "--", "run"])
                 .arg(binary)
-                // Add network flag if ferrous-cli supports it, or it might be default?
-                // Assuming default or transparent
-                .status();
+                .arg("--memory")
+                .arg(vm.mem_bytes().to_string())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::inherit());
+            if let Some(disk) = &vm.disk {
+                net_cmd.arg("--disk").arg(disk);
+            }
+            echo_command(&net_cmd, cli.verbose);
+            let output = net_cmd.output();
 
-            // Kill server
-            let _ = server.kill();
+            let result = (|| -> Result<()> {
+                let output = output.map_err(|e| anyhow::anyhow!("VM execution failed: {}", e))?;
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                print!("{}", stdout);
 
-            match status {
-                Ok(s) if s.success() => println!("VM finished successfully"),
-                _ => return Err(anyhow::anyhow!("VM execution failed")),
+                if !output.status.success() {
+                    return Err(anyhow::anyhow!("VM execution failed: {}", output.status));
+                }
+
+                let sent: Vec<&str> = stdout
+                    .lines()
+                    .filter_map(|l| l.strip_prefix("NET_TEST_SENT:"))
+                    .collect();
+                let received: Vec<&str> = stdout
+                    .lines()
+                    .filter_map(|l| l.strip_prefix("NET_TEST_RECV:"))
+                    .collect();
+
+                // The UDP-echo path is the only one we can verify a true
+                // round trip on; the TAP bridge has no reader thread to
+                // diff against.
+                if udp_server.is_some() {
+                    const TRAFFIC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+                    let deadline = std::time::Instant::now() + TRAFFIC_TIMEOUT;
+                    loop {
+                        if bounced.lock().unwrap().len() >= sent.len() {
+                            break;
+                        }
+                        if std::time::Instant::now() >= deadline {
+                            return Err(anyhow::anyhow!(
+                                "UDP echo server produced no traffic within {:?}; guest sent {} packet(s) but none were bounced back",
+                                TRAFFIC_TIMEOUT,
+                                sent.len()
+                            ));
+                        }
+                        std::thread::sleep(std::time::Duration::from_millis(50));
+                    }
+
+                    let bounced = bounced.lock().unwrap();
+                    for payload in &sent {
+                        if !bounced.iter().any(|b| b == payload) {
+                            return Err(anyhow::anyhow!(
+                                "echo server never bounced back sent payload {:?}",
+                                payload
+                            ));
+                        }
+                        if !received.contains(payload) {
+                            return Err(anyhow::anyhow!(
+                                "guest never received its own payload {:?} back",
+                                payload
+                            ));
+                        }
+                    }
+                }
+
+                println!("VM finished successfully, round trip verified");
+                Ok(())
+            })();
+
+            // Kill the server on both the success and failure paths above --
+            // nothing past this point should ever leave it running.
+            if let Some(mut s) = udp_server {
+                let _ = s.kill();
+            }
+
+            result?;
+        }
+        Commands::Test {
+            filter,
+            timeout,
+            vm,
+        } => {
+            // Create FS first (which builds user programs)
+            let disk = vm.disk_or_default();
+            make_fs(&sh, cli.verbose, &disk, vm.inodes)?;
+
+            let mut results = Vec::new();
+            for name in USER_PROGRAMS {
+                if let Some(substr) = &filter {
+                    if !name.contains(substr.as_str()) {
+                        continue;
+                    }
+                }
+
+                println!("Running {}...", name);
+                let binary = out_path.join(name);
+                let outcome = run_test_binary(&binary, timeout, cli.verbose, &disk, vm.mem_bytes());
+                results.push((name, outcome));
+            }
+
+            println!();
+            println!("{:<16} RESULT", "TEST");
+            let mut any_failed = false;
+            for (name, outcome) in &results {
+                if !matches!(outcome, TestOutcome::Passed) {
+                    any_failed = true;
+                }
+                println!("{:<16} {}", name, outcome);
+            }
+
+            if any_failed {
+                return Err(anyhow::anyhow!("one or more tests failed"));
             }
         }
         Commands::Clean => {
-            cmd!(sh, "cargo clean").run()?;
+            exec(cmd!(sh, "cargo clean"), cli.verbose)?;
             if sh.path_exists("disk.img") {
                 sh.remove_path("disk.img")?;
             }
@@ -228,11 +498,3 @@ fn main() -> Result<()> {
 
     Ok(())
 }
-
-// Helper to run recursive xtask commands
-fn run_xtask(sh: &Shell, args: &[&str]) -> Result<()> {
-    // We can just call the binary recursively, or refactor to call functions.
-    // Calling binary is simpler for ensuring clean environment.
-    cmd!(sh, "cargo xtask").args(args).run()?;
-    Ok(())
-}
@@ -0,0 +1,117 @@
+//! A minimal Linux TAP interface opener, used by `RunNet --tap` to give the
+//! guest real L2 connectivity instead of the UDP echo stub. Raw `libc`
+//! types/ioctl numbers are declared inline rather than pulling in a crate,
+//! matching how the rest of the host-facing I/O in this tree (the block
+//! device, the UDP echo tool) sticks to plain `std`.
+
+use std::fs::File;
+use std::io;
+use std::os::unix::io::FromRawFd;
+
+#[cfg(target_os = "linux")]
+mod sys {
+    pub const IFNAMSIZ: usize = 16;
+    pub const IFF_TAP: i16 = 0x0002;
+    pub const IFF_NO_PI: i16 = 0x1000;
+    // `_IOW('T', 202, int)`, as defined in <linux/if_tun.h>.
+    pub const TUNSETIFF: u64 = 0x4004_54ca;
+
+    #[repr(C)]
+    pub struct IfReq {
+        pub ifr_name: [libc_char; IFNAMSIZ],
+        pub ifr_flags: i16,
+        pub _padding: [u8; 22],
+    }
+
+    // Avoid depending on the `libc` crate for a single typedef.
+    #[allow(non_camel_case_types)]
+    pub type libc_char = std::os::raw::c_char;
+
+    extern "C" {
+        pub fn open(path: *const std::os::raw::c_char, flags: i32) -> i32;
+        pub fn ioctl(fd: i32, request: u64, ...) -> i32;
+    }
+
+    pub const O_RDWR: i32 = 0x0002;
+}
+
+/// An open `/dev/net/tun` handle bound to a TAP interface, exchanging raw
+/// Ethernet frames with whatever virtual switch/bridge the host has `ifname`
+/// attached to.
+pub struct TapDevice {
+    file: File,
+}
+
+impl TapDevice {
+    /// Open (and, if needed, create) the TAP interface named `ifname`.
+    /// Requires `CAP_NET_ADMIN` (typically root) and a Linux host; callers
+    /// should fall back to another backend if this returns `Err`.
+    #[cfg(target_os = "linux")]
+    pub fn open(ifname: &str) -> io::Result<Self> {
+        use std::ffi::CString;
+
+        if ifname.len() >= sys::IFNAMSIZ {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "interface name too long",
+            ));
+        }
+
+        let dev_path = CString::new("/dev/net/tun").unwrap();
+        // SAFETY: `dev_path` is a valid NUL-terminated C string for the
+        // duration of the call; `open` is the standard POSIX syscall.
+        let fd = unsafe { sys::open(dev_path.as_ptr(), sys::O_RDWR) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut req: sys::IfReq = unsafe { std::mem::zeroed() };
+        for (dst, src) in req.ifr_name.iter_mut().zip(ifname.bytes()) {
+            *dst = src as std::os::raw::c_char;
+        }
+        req.ifr_flags = sys::IFF_TAP | sys::IFF_NO_PI;
+
+        // SAFETY: `fd` was just opened above and `req` is a validly
+        // initialized `ifreq` the kernel's `TUNSETIFF` handler expects.
+        let rc = unsafe { sys::ioctl(fd, sys::TUNSETIFF, &mut req as *mut sys::IfReq) };
+        if rc < 0 {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc_close(fd);
+            }
+            return Err(err);
+        }
+
+        // SAFETY: `fd` is a valid, newly-created file descriptor we now own
+        // exclusively.
+        let file = unsafe { File::from_raw_fd(fd) };
+        Ok(Self { file })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn open(_ifname: &str) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TAP devices are only supported on Linux",
+        ))
+    }
+
+    /// Read one raw Ethernet frame (with `IFF_NO_PI`, no extra packet-info
+    /// header) into `buf`, returning its length.
+    pub fn read_frame(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        use std::io::Read;
+        self.file.read(buf)
+    }
+
+    /// Write one raw Ethernet frame out to the interface.
+    pub fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+        self.file.write_all(frame)
+    }
+}
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    #[link_name = "close"]
+    fn libc_close(fd: i32) -> i32;
+}
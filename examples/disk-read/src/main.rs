@@ -37,6 +37,7 @@ pub extern "C" fn _start() -> ! {
         }
     }
     println!();
+    println!("TEST PASS");
 
     exit(0);
 }
@@ -9,6 +9,7 @@ use ferrous_user::{exit, println};
 pub extern "C" fn _start() -> ! {
     println!("Hello from Ferrous!");
     println!("Iteration 1 Complete.");
+    println!("TEST PASS");
 
     exit(0)
 }
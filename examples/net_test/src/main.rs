@@ -15,7 +15,7 @@ pub extern "C" fn _start() -> ! {
     println!("Net Test: Starting...");
 
     // 1. Create Socket
-    let fd = match net::socket() {
+    let fd = match net::socket(net::AF_INET, net::SOCK_DGRAM, net::IPPROTO_UDP) {
         Ok(fd) => {
             println!("Socket created: {}", fd);
             fd
@@ -50,10 +50,18 @@ pub extern "C" fn _start() -> ! {
     let dest_addr = net::SockAddrIn::new(5555, dest_ip);
     let msg = b"Hello from Ferrous Multitasking!";
 
+    let msg_str = core::str::from_utf8(msg).unwrap_or("<invalid utf8>");
+
     let mut counter = 0;
     loop {
         match net::sendto(fd, msg, &dest_addr) {
-            Ok(len) => println!("[Sender] Sent packet {} ({} bytes)", counter, len),
+            Ok(len) => {
+                println!("[Sender] Sent packet {} ({} bytes)", counter, len);
+                // Machine-readable marker so the host-side test harness can
+                // diff what we sent against what the echo server bounced
+                // back, instead of just trusting this process's exit code.
+                println!("NET_TEST_SENT:{}", msg_str);
+            }
             Err(e) => println!("[Sender] Send failed: {}", e),
         }
         counter += 1;
@@ -88,6 +96,7 @@ extern "C" fn receiver_thread() {
                     "[Receiver] Got {} bytes from {:x}:{:x}: {}",
                     len, src.addr, src.port, received
                 );
+                println!("NET_TEST_RECV:{}", received);
             }
             Err(-1) => {
                 yield_now();
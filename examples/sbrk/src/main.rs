@@ -40,6 +40,7 @@ pub extern "C" fn _start() -> ! {
             println!("Error: Memory read mismatch!");
         } else {
             println!("Memory check passed!");
+            println!("TEST PASS");
         }
     }
 
@@ -6,39 +6,11 @@ extern crate ferrous_fs;
 extern crate ferrous_user;
 
 use alloc::vec::Vec;
-use core::alloc::{GlobalAlloc, Layout};
 use ferrous_fs::DirEntry;
+use ferrous_user::heap::SbrkAllocator;
 use ferrous_user::syscall;
 use ferrous_user::{print, println};
 
-struct SbrkAllocator;
-
-unsafe impl GlobalAlloc for SbrkAllocator {
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        let size = layout.size();
-        let align = layout.align();
-
-        // Get current break
-        let current_break = syscall::sbrk(0) as usize;
-
-        // Calculate required alignment padding
-        let padding = (align - (current_break % align)) % align;
-        let total_size = size + padding;
-
-        // Allocate
-        let start = syscall::sbrk(total_size as i32) as usize;
-        if start == 0 {
-            return core::ptr::null_mut();
-        }
-
-        (start + padding) as *mut u8
-    }
-
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
-        // No-op
-    }
-}
-
 #[global_allocator]
 static ALLOCATOR: SbrkAllocator = SbrkAllocator;
 
@@ -21,6 +21,7 @@ pub extern "C" fn _start() -> ! {
         yield_now();
     }
     println!("Thread 1: Loop end");
+    println!("TEST PASS");
 
     exit(0)
 }
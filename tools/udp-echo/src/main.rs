@@ -18,6 +18,10 @@ fn main() -> std::io::Result<()> {
                 print!("Received {} bytes from {}: ", amt, src);
                 if let Ok(s) = std::str::from_utf8(msg) {
                     println!("{:?}", s);
+                    // Machine-readable marker so `cargo xtask run-net` can
+                    // confirm a round trip instead of just trusting the VM's
+                    // exit status: one line per bounced datagram.
+                    println!("ECHO_BOUNCED:{}", s);
                 } else {
                     println!("{:?}", msg);
                 }